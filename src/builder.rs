@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+use truetype::Tag;
+
+use crate::{Font, Result, Table};
+
+/// A builder of a font.
+#[derive(Clone, Debug)]
+pub struct Builder {
+    version: Tag,
+    tables: BTreeMap<Tag, Vec<u8>>,
+}
+
+impl Builder {
+    /// Create a builder given the format of the font, such as `OTTO` or `[0, 1, 0, 0]`.
+    #[inline]
+    pub fn new(version: Tag) -> Self {
+        Self {
+            version,
+            tables: Default::default(),
+        }
+    }
+
+    /// Create a builder containing all tables of a font.
+    pub fn from_font<T: crate::tape::Read>(font: &Font, tape: &mut T) -> Result<Self> {
+        let mut builder = Self::new(font.offsets.header.version.into());
+        for record in font.offsets.records.iter() {
+            tape.jump(record.offset as u64)?;
+            let data = tape.take_bytes(record.size as usize)?;
+            builder.tables.insert(record.tag, data);
+        }
+        Ok(builder)
+    }
+
+    /// Return the format of the font.
+    #[inline]
+    pub fn version(&self) -> Tag {
+        self.version
+    }
+
+    /// Add a table, replacing any table with the same tag.
+    pub fn add<T: Table + crate::value::Write>(&mut self, table: &T) -> Result<&mut Self> {
+        let mut data = vec![];
+        crate::tape::Write::give(&mut data, table)?;
+        Ok(self.add_bytes(T::tag(), data))
+    }
+
+    /// Add a table given its content, replacing any table with the same tag.
+    #[inline]
+    pub fn add_bytes(&mut self, tag: Tag, data: Vec<u8>) -> &mut Self {
+        self.tables.insert(tag, data);
+        self
+    }
+
+    /// Return the content of a table if present.
+    #[inline]
+    pub fn get(&self, tag: Tag) -> Option<&[u8]> {
+        self.tables.get(&tag).map(|data| &data[..])
+    }
+
+    /// Remove a table and return its content if present.
+    #[inline]
+    pub fn remove(&mut self, tag: Tag) -> Option<Vec<u8>> {
+        self.tables.remove(&tag)
+    }
+
+    /// Iterate over the tags of the tables in ascending order.
+    #[inline]
+    pub fn tags(&self) -> impl Iterator<Item = Tag> + '_ {
+        self.tables.keys().cloned()
+    }
+
+    /// Write the font.
+    #[inline]
+    pub fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        crate::File::write(tape, std::slice::from_ref(self))
+    }
+
+    pub(crate) fn tables(&self) -> impl Iterator<Item = (Tag, &[u8])> + '_ {
+        self.tables.iter().map(|(tag, data)| (*tag, &data[..]))
+    }
+}
@@ -0,0 +1,53 @@
+use truetype::Tag;
+
+table! {
+    /// A font collection.
+    pub Collection { // TTCHeader
+        tag           (Tag) = { Tag(*b"ttcf") }, // ttcTag
+
+        major_version (u16) |_, tape| { // majorVersion
+            let value = tape.take()?;
+            match value {
+                1 | 2 => {}
+                value => raise!("found an unknown version of the font collection ({value})"),
+            }
+            Ok(value)
+        },
+
+        minor_version (u16), // minorVersion
+        font_count    (u32), // numFonts
+
+        font_offsets (Vec<u32>) |this, tape| { // tableDirectoryOffsets
+            tape.take_given(this.font_count as usize)
+        },
+
+        signature_tag (u32) |this, tape| { // dsigTag
+            if this.major_version < 2 {
+                return Ok(0);
+            }
+            tape.take()
+        },
+
+        signature_size (u32) |this, tape| { // dsigLength
+            if this.major_version < 2 {
+                return Ok(0);
+            }
+            tape.take()
+        },
+
+        signature_offset (u32) |this, tape| { // dsigOffset
+            if this.major_version < 2 {
+                return Ok(0);
+            }
+            tape.take()
+        },
+    }
+}
+
+impl Collection {
+    /// Check if the collection has a digital signature.
+    #[inline]
+    pub fn has_signature(&self) -> bool {
+        self.signature_tag == u32::from(Tag(*b"DSIG")) && self.signature_size > 0
+    }
+}
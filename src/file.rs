@@ -1,7 +1,11 @@
+use std::io::Cursor;
+
+use truetype::tables::offsets::{Header, Offsets, Record};
+use truetype::tables::FontHeader;
 use truetype::Tag;
 
-use crate::tape::Read;
-use crate::{Font, Result};
+use crate::tape::{Read, Write};
+use crate::{Builder, Collection, Font, Result};
 
 /// A file.
 pub struct File {
@@ -10,15 +14,181 @@ pub struct File {
 }
 
 impl File {
+    /// Read the collection header if the file is a font collection.
+    ///
+    /// The tape is expected to be at the beginning of the file, and its position is restored
+    /// afterwards.
+    pub fn collection<T: crate::tape::Read>(tape: &mut T) -> Result<Option<Collection>> {
+        let position = tape.position()?;
+        let collection = match &Read::peek::<Tag>(tape)?.0 {
+            b"ttcf" => Some(tape.take::<Collection>()?),
+            _ => None,
+        };
+        Read::jump(tape, position)?;
+        Ok(collection)
+    }
+
     /// Read a file.
     pub fn read<T: crate::tape::Read>(tape: &mut T) -> Result<File> {
-        if &Read::peek::<Tag>(tape)?.0 == b"ttcf" {
-            raise!("found a TrueType collection, which is not supported yet");
+        match &Read::peek::<Tag>(tape)?.0 {
+            b"ttcf" => {}
+            b"wOFF" | b"wOF2" => raise!("found a web font, which should be decoded first"),
+            _ => {
+                return Ok(File {
+                    fonts: vec![Font::read(tape)?],
+                })
+            }
         }
-        Ok(File {
-            fonts: vec![Font::read(tape)?],
-        })
+        let position = tape.position()?;
+        let collection = tape.take::<Collection>()?;
+        let fonts =
+            jump_take!(@unwrap tape, position, collection.font_count, collection.font_offsets);
+        Ok(File { fonts })
+    }
+
+    /// Read a file in any of the supported formats.
+    ///
+    /// The content is loaded into memory, and web fonts are decoded. The fonts refer to the
+    /// returned tape, which should be used for reading tables.
+    pub fn decode<T: crate::tape::Read>(tape: &mut T) -> Result<(File, Cursor<Vec<u8>>)> {
+        let mut tape = match &Read::peek::<Tag>(tape)?.0 {
+            b"wOFF" => tape.take::<crate::woff::File>()?.decode()?,
+            b"wOF2" => tape.take::<crate::woff2::File>()?.decode()?,
+            _ => {
+                let mut data = vec![];
+                std::io::Read::read_to_end(tape, &mut data)?;
+                Cursor::new(data)
+            }
+        };
+        Ok((File::read(&mut tape)?, tape))
+    }
+
+    /// Write a file.
+    ///
+    /// A single font is written as is, and several fonts are written as a font collection in
+    /// which identical tables are shared.
+    pub fn write<T: crate::tape::Write>(tape: &mut T, fonts: &[Builder]) -> Result<()> {
+        if fonts.is_empty() {
+            raise!("found no fonts to write");
+        }
+        let mut tables: Vec<(Tag, &[u8])> = vec![];
+        let mut indices = Vec::with_capacity(fonts.len());
+        for font in fonts.iter() {
+            let mut font_indices = vec![];
+            for table in font.tables() {
+                match tables.iter().position(|other| *other == table) {
+                    Some(i) => font_indices.push(i),
+                    _ => {
+                        font_indices.push(tables.len());
+                        tables.push(table);
+                    }
+                }
+            }
+            indices.push((font.version(), font_indices));
+        }
+        let collection = if fonts.len() > 1 { Some((1, 0)) } else { None };
+        tape.give_bytes(&assemble(&indices, &tables, collection)?)
     }
 }
 
 dereference! { File::fonts => [Font] }
+
+pub(crate) fn assemble(
+    fonts: &[(Tag, Vec<usize>)],
+    tables: &[(Tag, &[u8])],
+    collection: Option<(u16, u16)>,
+) -> Result<Vec<u8>> {
+    let mut offset = match collection {
+        Some((major_version, _)) if major_version > 1 => 12 + 4 * fonts.len() + 12,
+        Some(_) => 12 + 4 * fonts.len(),
+        _ => 0,
+    } as u32;
+    let mut font_offsets = Vec::with_capacity(fonts.len());
+    for (_, indices) in fonts.iter() {
+        font_offsets.push(offset);
+        offset += 12 + 16 * indices.len() as u32;
+    }
+    let mut table_offsets = Vec::with_capacity(tables.len());
+    for (_, data) in tables.iter() {
+        table_offsets.push(offset);
+        offset += (data.len() as u32 + 3) & !3;
+    }
+    let mut tape = Vec::with_capacity(offset as usize);
+    if let Some((major_version, minor_version)) = collection {
+        tape.give(&Tag(*b"ttcf"))?;
+        tape.give(&major_version)?;
+        tape.give(&minor_version)?;
+        tape.give(&(fonts.len() as u32))?;
+        tape.give(&font_offsets[..])?;
+        if major_version > 1 {
+            tape.give(&[0u32; 3][..])?;
+        }
+    }
+    for (version, indices) in fonts.iter() {
+        let table_count = indices.len() as u32;
+        let (search_range, entry_selector) = match table_count.checked_ilog2() {
+            Some(value) => (16 * (1 << value), value),
+            _ => (0, 0),
+        };
+        let (table_count, search_range, entry_selector, range_shift) = match (
+            u16::try_from(table_count),
+            u16::try_from(search_range),
+            u16::try_from(entry_selector),
+            u16::try_from(16 * table_count - search_range),
+        ) {
+            (Ok(table_count), Ok(search_range), Ok(entry_selector), Ok(range_shift)) => {
+                (table_count, search_range, entry_selector, range_shift)
+            }
+            _ => raise!("found too many tables in a font ({table_count})"),
+        };
+        let mut records = indices
+            .iter()
+            .map(|&i| Record {
+                tag: tables[i].0,
+                checksum: checksum(tables[i].0, tables[i].1),
+                offset: table_offsets[i],
+                size: tables[i].1.len() as u32,
+            })
+            .collect::<Vec<_>>();
+        records.sort_by_key(|record| record.tag);
+        tape.give(&Offsets {
+            header: Header {
+                version: (*version).into(),
+                table_count,
+                search_range,
+                entry_selector,
+                range_shift,
+            },
+            records,
+        })?;
+    }
+    for (_, data) in tables.iter() {
+        tape.give_bytes(data)?;
+        tape.give_bytes(&[0; 3][..(4 - data.len() % 4) % 4])?;
+    }
+    if collection.is_none() && fonts.len() == 1 {
+        if let Some(i) = tables.iter().position(|(tag, _)| tag == b"head") {
+            let offset = table_offsets[i] as usize + 8;
+            if tables[i].1.len() >= 12 {
+                tape[offset..(offset + 4)].copy_from_slice(&[0; 4]);
+                let value =
+                    FontHeader::CHECKSUM_ADJUSTMENT.wrapping_sub(checksum(Tag([0; 4]), &tape));
+                tape[offset..(offset + 4)].copy_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+    Ok(tape)
+}
+
+pub(crate) fn checksum(tag: Tag, data: &[u8]) -> u32 {
+    let head = tag == b"head";
+    data.chunks(4)
+        .enumerate()
+        .filter(|(i, _)| !head || *i != 2)
+        .map(|(_, chunk)| {
+            let mut value = [0; 4];
+            value[..chunk.len()].copy_from_slice(chunk);
+            u32::from_be_bytes(value)
+        })
+        .fold(0u32, |total, value| total.wrapping_add(value))
+}
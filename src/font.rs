@@ -1,7 +1,9 @@
 use truetype::tables::offsets::Offsets;
 
+use crate::options::Repair;
 use crate::tape::Read;
-use crate::{Result, Table};
+use crate::trace::trace;
+use crate::{ReadOptions, Result, Segment, Table};
 
 /// A font.
 pub struct Font {
@@ -27,7 +29,17 @@ impl Font {
     }
 
     /// Jump to the position of the table.
+    #[inline]
     pub fn position<T, U>(&self, tape: &mut T) -> Result<Option<()>>
+    where
+        T: crate::tape::Read,
+        U: Table,
+    {
+        self.position_with::<T, U>(tape, &mut ReadOptions::strict())
+    }
+
+    /// Jump to the position of the table given reading options.
+    pub fn position_with<T, U>(&self, tape: &mut T, options: &mut ReadOptions) -> Result<Option<()>>
     where
         T: crate::tape::Read,
         U: Table,
@@ -35,8 +47,12 @@ impl Font {
         let tag = U::tag();
         for record in &self.offsets.records {
             if record.tag == tag {
-                #[cfg(not(feature = "ignore-invalid-checksums"))]
-                if record.checksum != record.checksum(tape)? {
+                if record.checksum != record.checksum(tape)?
+                    && !options.repair(
+                        options.ignore_invalid_checksums,
+                        Repair::InvalidChecksum(record.tag),
+                    )
+                {
                     raise!("found a malformed font table with {:?}", record.tag);
                 }
                 Read::jump(tape, record.offset as u64)?;
@@ -54,7 +70,28 @@ impl Font {
         U: Table + crate::value::Read,
     {
         self.position::<T, U>(tape)?
-            .map(|_| tape.take::<U>())
+            .map(|_| trace(tape, Segment::Table(U::tag()), |tape| tape.take::<U>()))
+            .transpose()
+    }
+
+    /// Read a table given reading options.
+    ///
+    /// The options are passed on to the table, which makes them apply to nested tables as well.
+    pub fn take_with<'l, T, U>(
+        &self,
+        tape: &mut T,
+        options: &'l mut ReadOptions,
+    ) -> Result<Option<U>>
+    where
+        T: crate::tape::Read,
+        U: Table + crate::walue::Read<'l, Parameter = &'l mut ReadOptions>,
+    {
+        self.position_with::<T, U>(tape, options)?
+            .map(|_| {
+                trace(tape, Segment::Table(U::tag()), |tape| {
+                    tape.take_given::<U>(options)
+                })
+            })
             .transpose()
     }
 
@@ -65,7 +102,11 @@ impl Font {
         U: Table + crate::walue::Read<'l>,
     {
         self.position::<T, U>(tape)?
-            .map(|_| tape.take_given::<U>(parameter))
+            .map(|_| {
+                trace(tape, Segment::Table(U::tag()), |tape| {
+                    tape.take_given::<U>(parameter)
+                })
+            })
             .transpose()
     }
 }
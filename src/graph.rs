@@ -0,0 +1,321 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::Result;
+
+/// A type that can be written as an object with offsets to other objects.
+pub(crate) trait Write {
+    /// Write the value and return the index of the resulting object.
+    fn write(&self, graph: &mut Graph) -> Result<usize>;
+}
+
+/// A graph of objects referring to each other via offsets.
+#[derive(Default)]
+pub(crate) struct Graph {
+    objects: Vec<Object>,
+    indices: HashMap<Object, usize>,
+}
+
+/// An object in a graph.
+#[derive(Clone, Default, Eq, Hash, PartialEq)]
+pub(crate) struct Object {
+    data: Vec<u8>,
+    links: Vec<Link>,
+}
+
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+struct Link {
+    position: usize,
+    size: usize,
+    target: usize,
+}
+
+impl Graph {
+    /// Add an object unless an identical one already exists and return its index.
+    pub fn add(&mut self, object: Object) -> usize {
+        if let Some(&index) = self.indices.get(&object) {
+            return index;
+        }
+        let index = self.objects.len();
+        self.indices.insert(object.clone(), index);
+        self.objects.push(object);
+        index
+    }
+
+    /// Write a value and return the index of the resulting object.
+    #[inline]
+    pub fn give<T: Write + ?Sized>(&mut self, value: &T) -> Result<usize> {
+        value.write(self)
+    }
+
+    /// Write values and return the indices of the resulting objects.
+    #[inline]
+    pub fn give_all<T: Write>(&mut self, values: &[T]) -> Result<Vec<usize>> {
+        values.iter().map(|value| value.write(self)).collect()
+    }
+
+    /// Write a value if present and return the index of the resulting object.
+    #[inline]
+    pub fn give_maybe<T: Write>(&mut self, value: &Option<T>) -> Result<Option<usize>> {
+        value.as_ref().map(|value| value.write(self)).transpose()
+    }
+
+    /// Add an object and return its index without sharing.
+    pub fn add_unique(&mut self, object: Object) -> usize {
+        self.objects.push(object);
+        self.objects.len() - 1
+    }
+
+    /// Return an object.
+    #[inline]
+    pub fn get(&self, index: usize) -> &Object {
+        &self.objects[index]
+    }
+
+    /// Return an object for modification.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> &mut Object {
+        &mut self.objects[index]
+    }
+
+    /// Iterate over the objects reachable from an object including itself.
+    pub fn descendants(&self, index: usize) -> impl Iterator<Item = usize> {
+        let mut seen = vec![false; self.objects.len()];
+        let mut stack = vec![index];
+        let mut indices = vec![];
+        while let Some(index) = stack.pop() {
+            if std::mem::replace(&mut seen[index], true) {
+                continue;
+            }
+            indices.push(index);
+            stack.extend(self.objects[index].links.iter().map(|link| link.target));
+        }
+        indices.into_iter()
+    }
+
+    /// Serialize the objects reachable from the root.
+    ///
+    /// The objects are laid out so that 16-bit offsets are as short as possible. Overflowing
+    /// offsets to shared objects are resolved by duplicating the objects; other overflows are
+    /// passed to the resolver, which should modify the graph and return `true` or give up by
+    /// returning `false`.
+    pub fn serialize<F>(&mut self, root: usize, mut resolve: F) -> Result<Vec<u8>>
+    where
+        F: FnMut(&mut Graph, usize, usize) -> Result<bool>,
+    {
+        loop {
+            let order = self.sort(root);
+            let mut positions = vec![0; self.objects.len()];
+            let mut size = 0;
+            for &index in order.iter() {
+                positions[index] = size;
+                size += self.objects[index].data.len();
+            }
+            let overflow = order.iter().find_map(|&index| {
+                self.objects[index]
+                    .links
+                    .iter()
+                    .find(|link| {
+                        let offset = positions[link.target] - positions[index];
+                        (link.size == 2 && offset > 0xFFFF) || offset > 0xFFFF_FFFF
+                    })
+                    .map(|link| (index, link.target))
+            });
+            match overflow {
+                Some((parent, child)) => {
+                    if self.count_parents(&order, child) > 1 {
+                        self.duplicate(parent, child);
+                    } else if !resolve(self, parent, child)? {
+                        raise!("found an offset overflow that cannot be resolved");
+                    }
+                }
+                _ => {
+                    let mut data = Vec::with_capacity(size);
+                    for &index in order.iter() {
+                        let object = &self.objects[index];
+                        let start = data.len();
+                        data.extend_from_slice(&object.data);
+                        for link in object.links.iter() {
+                            let offset = (positions[link.target] - positions[index]) as u32;
+                            let position = start + link.position;
+                            let bytes = offset.to_be_bytes();
+                            data[position..(position + link.size)]
+                                .copy_from_slice(&bytes[(4 - link.size)..]);
+                        }
+                    }
+                    return Ok(data);
+                }
+            }
+        }
+    }
+
+    fn count_parents(&self, order: &[usize], index: usize) -> usize {
+        order
+            .iter()
+            .filter(|&&other| {
+                let links = &self.objects[other].links;
+                links.iter().any(|link| link.target == index)
+            })
+            .count()
+    }
+
+    fn duplicate(&mut self, parent: usize, child: usize) {
+        let index = self.add_unique(self.objects[child].clone());
+        for link in self.objects[parent].links.iter_mut() {
+            if link.target == child {
+                link.target = index;
+            }
+        }
+    }
+
+    // Objects reachable via 16-bit offsets form spaces, which are laid out one after another
+    // starting with the one of the root, and each space is laid out depth first.
+    fn sort(&self, root: usize) -> Vec<usize> {
+        let count = self.objects.len();
+        let mut degrees = vec![0; count];
+        let mut spaces = vec![usize::MAX; count];
+        let mut space_count = 1;
+        let mut pending = vec![];
+        let mut stack = vec![root];
+        spaces[root] = 0;
+        let mut next = 0;
+        loop {
+            while let Some(index) = stack.pop() {
+                for link in self.objects[index].links.iter() {
+                    degrees[link.target] += 1;
+                    if spaces[link.target] != usize::MAX {
+                        continue;
+                    }
+                    if link.size == 2 {
+                        spaces[link.target] = spaces[index];
+                        stack.push(link.target);
+                    } else {
+                        pending.push(link.target);
+                    }
+                }
+            }
+            while next < pending.len() && spaces[pending[next]] != usize::MAX {
+                next += 1;
+            }
+            if next == pending.len() {
+                break;
+            }
+            spaces[pending[next]] = space_count;
+            space_count += 1;
+            stack.push(pending[next]);
+        }
+        let mut order = vec![];
+        let mut time = 0;
+        let mut heap = BinaryHeap::new();
+        heap.push((Reverse(0), time, root));
+        while let Some((_, _, index)) = heap.pop() {
+            order.push(index);
+            for link in self.objects[index].links.iter().rev() {
+                degrees[link.target] -= 1;
+                if degrees[link.target] == 0 {
+                    time += 1;
+                    heap.push((Reverse(spaces[link.target]), time, link.target));
+                }
+            }
+        }
+        order
+    }
+}
+
+impl Object {
+    /// Write a 16-bit offset to an object.
+    #[inline]
+    pub fn link(&mut self, target: usize) {
+        self.links.push(Link {
+            position: self.data.len(),
+            size: 2,
+            target,
+        });
+        self.data.extend_from_slice(&[0; 2]);
+    }
+
+    /// Write a 16-bit offset to an object if present or a null offset otherwise.
+    #[inline]
+    pub fn link_maybe(&mut self, target: Option<usize>) {
+        match target {
+            Some(target) => self.link(target),
+            _ => self.data.extend_from_slice(&[0; 2]),
+        }
+    }
+
+    /// Write a 32-bit offset to an object.
+    #[inline]
+    pub fn link_long(&mut self, target: usize) {
+        self.links.push(Link {
+            position: self.data.len(),
+            size: 4,
+            target,
+        });
+        self.data.extend_from_slice(&[0; 4]);
+    }
+
+    /// Write a 32-bit offset to an object if present or a null offset otherwise.
+    #[inline]
+    pub fn link_long_maybe(&mut self, target: Option<usize>) {
+        match target {
+            Some(target) => self.link_long(target),
+            _ => self.data.extend_from_slice(&[0; 4]),
+        }
+    }
+
+    /// Write a count, which should fit into 16 bits.
+    pub fn give_count(&mut self, value: usize) -> Result<()> {
+        if value > u16::MAX as usize {
+            raise!("found too many elements to write ({value})");
+        }
+        crate::tape::Write::give(self, &(value as u16))
+    }
+
+    /// Return the content.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Return the content for modification.
+    #[inline]
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Iterate over the indices of the objects referred to.
+    #[inline]
+    pub fn targets(&self) -> impl Iterator<Item = usize> + '_ {
+        self.links.iter().map(|link| link.target)
+    }
+
+    /// Redirect the offsets referring to an object to another one.
+    pub fn redirect(&mut self, from: usize, to: usize) {
+        for link in self.links.iter_mut() {
+            if link.target == from {
+                link.target = to;
+            }
+        }
+    }
+}
+
+impl std::io::Write for Object {
+    #[inline]
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.data.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<U: crate::value::Write + ?Sized> Write for U {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let mut object = Object::default();
+        crate::tape::Write::give(&mut object, self)?;
+        Ok(graph.add(object))
+    }
+}
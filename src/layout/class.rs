@@ -2,6 +2,7 @@
 
 use truetype::GlyphID;
 
+use crate::layout::coverage::{find, sorted, Range};
 use crate::Result;
 
 /// A class.
@@ -14,6 +15,7 @@ pub enum Class {
 }
 
 table! {
+    @write
     /// A class in format 1.
     pub Class1 { // ClassDefFormat1
         format         (u16    ), // classFormat
@@ -27,6 +29,7 @@ table! {
 }
 
 table! {
+    @write
     /// A class in format 2.
     pub Class2 { // ClassDefFormat2
         format       (u16), // classFormat
@@ -39,6 +42,7 @@ table! {
 }
 
 table! {
+    @write
     /// A class record.
     #[derive(Copy)]
     pub Record { // ClassRangeRecord
@@ -48,6 +52,53 @@ table! {
     }
 }
 
+impl Class {
+    /// Return the class of a glyph.
+    ///
+    /// Glyphs not assigned explicitly belong to class 0. In case of unsorted or overlapping
+    /// entries, the first entry covering the glyph is taken.
+    pub fn class_of(&self, glyph_id: GlyphID) -> u16 {
+        match self {
+            Class::Format1(table) => glyph_id
+                .checked_sub(table.start_glyph_id)
+                .and_then(|index| table.indices.get(index as usize))
+                .cloned()
+                .unwrap_or(0),
+            Class::Format2(table) => find(&table.records, glyph_id)
+                .map(|record| record.index)
+                .unwrap_or(0),
+        }
+    }
+
+    /// Iterate over the glyphs assigned explicitly to a class in ascending order.
+    ///
+    /// For class 0, only the glyphs assigned to it explicitly are yielded.
+    pub fn glyphs(&self, class: u16) -> impl Iterator<Item = GlyphID> + '_ {
+        let mut glyph_ids = match self {
+            Class::Format1(table) => table
+                .indices
+                .iter()
+                .enumerate()
+                .filter(|(_, &index)| index == class)
+                .filter_map(|(i, _)| u16::try_from(table.start_glyph_id as usize + i).ok())
+                .collect::<Vec<_>>(),
+            Class::Format2(table) => {
+                let sorted = sorted(&table.records);
+                table
+                    .records
+                    .iter()
+                    .filter(|record| record.index == class)
+                    .flat_map(|record| record.start_glyph_id..=record.end_glyph_id)
+                    .filter(|&glyph_id| sorted || self.class_of(glyph_id) == class)
+                    .collect::<Vec<_>>()
+            }
+        };
+        glyph_ids.sort();
+        glyph_ids.dedup();
+        glyph_ids.into_iter()
+    }
+}
+
 impl Default for Class {
     #[inline]
     fn default() -> Self {
@@ -64,3 +115,24 @@ impl crate::value::Read for Class {
         })
     }
 }
+
+impl crate::value::Write for Class {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        match self {
+            Class::Format1(table) => tape.give(table),
+            Class::Format2(table) => tape.give(table),
+        }
+    }
+}
+
+impl Range for Record {
+    #[inline]
+    fn start(&self) -> GlyphID {
+        self.start_glyph_id
+    }
+
+    #[inline]
+    fn end(&self) -> GlyphID {
+        self.end_glyph_id
+    }
+}
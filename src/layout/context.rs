@@ -2,7 +2,10 @@
 
 use truetype::GlyphID;
 
+use crate::graph::{Graph, Object};
 use crate::layout::{Class, Coverage};
+use crate::tape::Write;
+use crate::trace::trace;
 use crate::Result;
 
 /// A contextual lookup.
@@ -29,11 +32,13 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         records (Vec<Records>) |this, tape, position| {
-            jump_take!(tape, position, this.record_count, this.record_offsets)
+            trace(tape, "records", |tape| {
+                jump_take!(tape, position, this.record_count, this.record_offsets)
+            })
         },
     }
 }
@@ -52,15 +57,17 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         class (Class) |this, tape, position| {
-            jump_take!(tape, position, this.class_offset)
+            trace(tape, "class", |tape| jump_take!(tape, position, this.class_offset))
         },
 
         records (Vec<Option<ClassRecords>>) |this, tape, position| {
-            jump_take_maybe!(tape, position, this.record_count, this.record_offsets)
+            trace(tape, "records", |tape| {
+                jump_take_maybe!(tape, position, this.record_count, this.record_offsets)
+            })
         },
     }
 }
@@ -82,7 +89,9 @@ table! {
         },
 
         coverages (Vec<Coverage>) |this, tape, position| {
-            jump_take!(tape, position, this.glyph_count, this.coverage_offsets)
+            trace(tape, "coverages", |tape| {
+                jump_take!(tape, position, this.glyph_count, this.coverage_offsets)
+            })
         },
     }
 }
@@ -111,11 +120,13 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         records (Vec<ChainedRecords>) |this, tape, position| {
-            jump_take!(tape, position, this.record_count, this.record_offsets)
+            trace(tape, "records", |tape| {
+                jump_take!(tape, position, this.record_count, this.record_offsets)
+            })
         },
     }
 }
@@ -136,23 +147,29 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         backward_class (Class) |this, tape, position| {
-            jump_take!(tape, position, this.backward_class_offset)
+            trace(tape, "backward_class", |tape| {
+                jump_take!(tape, position, this.backward_class_offset)
+            })
         },
 
         class (Class) |this, tape, position| {
-            jump_take!(tape, position, this.class_offset)
+            trace(tape, "class", |tape| jump_take!(tape, position, this.class_offset))
         },
 
         forward_class (Class) |this, tape, position| {
-            jump_take!(tape, position, this.forward_class_offset)
+            trace(tape, "forward_class", |tape| {
+                jump_take!(tape, position, this.forward_class_offset)
+            })
         },
 
         records (Vec<Option<ChainedClassRecords>>) |this, tape, position| {
-            jump_take_maybe!(tape, position, this.record_count, this.record_offsets)
+            trace(tape, "records", |tape| {
+                jump_take_maybe!(tape, position, this.record_count, this.record_offsets)
+            })
         },
     }
 }
@@ -187,20 +204,27 @@ table! {
         },
 
         backward_coverages (Vec<Coverage>) |this, tape, position| {
-            jump_take!(tape, position, this.backward_glyph_count, this.backward_coverage_offsets)
+            trace(tape, "backward_coverages", |tape| {
+                jump_take!(tape, position, this.backward_glyph_count, this.backward_coverage_offsets)
+            })
         },
 
         coverages (Vec<Coverage>) |this, tape, position| {
-            jump_take!(tape, position, this.glyph_count, this.coverage_offsets)
+            trace(tape, "coverages", |tape| {
+                jump_take!(tape, position, this.glyph_count, this.coverage_offsets)
+            })
         },
 
         forward_coverages (Vec<Coverage>) |this, tape, position| {
-            jump_take!(tape, position, this.forward_glyph_count, this.forward_coverage_offsets)
+            trace(tape, "forward_coverages", |tape| {
+                jump_take!(tape, position, this.forward_glyph_count, this.forward_coverage_offsets)
+            })
         },
     }
 }
 
 table! {
+    @write
     /// A context record.
     pub Record { // SequenceRule
         glyph_count  (u16), // glyphCount
@@ -230,12 +254,13 @@ table! {
         },
 
         records (Vec<Record>) |this, tape, position| {
-            jump_take!(tape, position, this.count, this.offsets)
+            trace(tape, "records", |tape| jump_take!(tape, position, this.count, this.offsets))
         },
     }
 }
 
 table! {
+    @write
     /// A class context record.
     pub ClassRecord { // ClassSequenceRule
         glyph_count  (u16), // glyphCount
@@ -265,12 +290,13 @@ table! {
         },
 
         records (Vec<ClassRecord>) |this, tape, position| {
-            jump_take!(tape, position, this.count, this.offsets)
+            trace(tape, "records", |tape| jump_take!(tape, position, this.count, this.offsets))
         },
     }
 }
 
 table! {
+    @write
     /// A chained context record.
     pub ChainedRecord { // ChainedSequenceRule
         backward_glyph_count (u16), // backtrackGlyphCount
@@ -313,12 +339,13 @@ table! {
         },
 
         records (Vec<ChainedRecord>) |this, tape, position| {
-            jump_take!(tape, position, this.count, this.offsets)
+            trace(tape, "records", |tape| jump_take!(tape, position, this.count, this.offsets))
         },
     }
 }
 
 table! {
+    @write
     /// A chained class context record.
     pub ChainedClassRecord { // ChainedClassSequenceRule
         backward_glyph_count (u16), // backtrackGlyphCount
@@ -361,12 +388,13 @@ table! {
         },
 
         records (Vec<ChainedClassRecord>) |this, tape, position| {
-            jump_take!(tape, position, this.count, this.offsets)
+            trace(tape, "records", |tape| jump_take!(tape, position, this.count, this.offsets))
         },
     }
 }
 
 table! {
+    @write
     /// A contextual action.
     #[derive(Copy)]
     pub Action { // SequenceLookupRecord
@@ -378,9 +406,9 @@ table! {
 impl crate::value::Read for Context {
     fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
         Ok(match tape.peek::<u16>()? {
-            1 => Self::Format1(tape.take()?),
-            2 => Self::Format2(tape.take()?),
-            3 => Self::Format3(tape.take()?),
+            1 => Self::Format1(trace(tape, "Context1", |tape| tape.take())?),
+            2 => Self::Format2(trace(tape, "Context2", |tape| tape.take())?),
+            3 => Self::Format3(trace(tape, "Context3", |tape| tape.take())?),
             value => raise!("found an unknown format of the context table ({value})"),
         })
     }
@@ -389,12 +417,151 @@ impl crate::value::Read for Context {
 impl crate::value::Read for ChainedContext {
     fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
         Ok(match tape.peek::<u16>()? {
-            1 => Self::Format1(tape.take()?),
-            2 => Self::Format2(tape.take()?),
-            3 => Self::Format3(tape.take()?),
+            1 => Self::Format1(trace(tape, "ChainedContext1", |tape| tape.take())?),
+            2 => Self::Format2(trace(tape, "ChainedContext2", |tape| tape.take())?),
+            3 => Self::Format3(trace(tape, "ChainedContext3", |tape| tape.take())?),
             value => {
                 raise!("found an unknown format of the chained context table ({value})")
             }
         })
     }
 }
+
+impl crate::graph::Write for Context {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        match self {
+            Self::Format1(table) => graph.give(table),
+            Self::Format2(table) => graph.give(table),
+            Self::Format3(table) => graph.give(table),
+        }
+    }
+}
+
+impl crate::graph::Write for Context1 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.link(coverage);
+        object.give_count(records.len())?;
+        records.into_iter().for_each(|index| object.link(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Context2 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let class = graph.give(&self.class)?;
+        let records = (self.records.iter())
+            .map(|record| graph.give_maybe(record))
+            .collect::<Result<Vec<_>>>()?;
+        let mut object = Object::default();
+        object.give(&2u16)?;
+        object.link(coverage);
+        object.link(class);
+        object.give_count(records.len())?;
+        records
+            .into_iter()
+            .for_each(|index| object.link_maybe(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Context3 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverages = graph.give_all(&self.coverages)?;
+        let mut object = Object::default();
+        object.give(&3u16)?;
+        object.give_count(coverages.len())?;
+        object.give_count(self.actions.len())?;
+        coverages.into_iter().for_each(|index| object.link(index));
+        object.give(&*self.actions)?;
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for ChainedContext {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        match self {
+            Self::Format1(table) => graph.give(table),
+            Self::Format2(table) => graph.give(table),
+            Self::Format3(table) => graph.give(table),
+        }
+    }
+}
+
+impl crate::graph::Write for ChainedContext1 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.link(coverage);
+        object.give_count(records.len())?;
+        records.into_iter().for_each(|index| object.link(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for ChainedContext2 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let backward_class = graph.give(&self.backward_class)?;
+        let class = graph.give(&self.class)?;
+        let forward_class = graph.give(&self.forward_class)?;
+        let records = (self.records.iter())
+            .map(|record| graph.give_maybe(record))
+            .collect::<Result<Vec<_>>>()?;
+        let mut object = Object::default();
+        object.give(&2u16)?;
+        object.link(coverage);
+        object.link(backward_class);
+        object.link(class);
+        object.link(forward_class);
+        object.give_count(records.len())?;
+        records
+            .into_iter()
+            .for_each(|index| object.link_maybe(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for ChainedContext3 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let backward_coverages = graph.give_all(&self.backward_coverages)?;
+        let coverages = graph.give_all(&self.coverages)?;
+        let forward_coverages = graph.give_all(&self.forward_coverages)?;
+        let mut object = Object::default();
+        object.give(&3u16)?;
+        for coverages in [backward_coverages, coverages, forward_coverages] {
+            object.give_count(coverages.len())?;
+            coverages.into_iter().for_each(|index| object.link(index));
+        }
+        object.give_count(self.actions.len())?;
+        object.give(&*self.actions)?;
+        Ok(graph.add(object))
+    }
+}
+
+macro_rules! implement {
+    ($($name:ident,)*) => (
+        $(impl crate::graph::Write for $name {
+            fn write(&self, graph: &mut Graph) -> Result<usize> {
+                let records = graph.give_all(&self.records)?;
+                let mut object = Object::default();
+                object.give_count(records.len())?;
+                records.into_iter().for_each(|index| object.link(index));
+                Ok(graph.add(object))
+            }
+        })*
+    );
+}
+
+implement! {
+    Records,
+    ClassRecords,
+    ChainedRecords,
+    ChainedClassRecords,
+}
@@ -1,5 +1,8 @@
 //! The adjustment correction.
 
+use truetype::q16;
+
+use crate::variations::item::Store;
 use crate::Result;
 
 /// A correction.
@@ -23,6 +26,7 @@ table! {
 }
 
 table! {
+    @write
     /// A variation correction.
     pub Variation { // VariationIndex
         outer_index (u16), // deltaSetOuterIndex
@@ -31,6 +35,20 @@ table! {
     }
 }
 
+impl Correction {
+    /// Evaluate the delta of a variation correction at normalized coordinates.
+    ///
+    /// `None` is returned for device corrections and for indices not present in the store.
+    pub fn evaluate(&self, store: &Store, coordinates: &[q16]) -> Option<f32> {
+        match self {
+            Correction::Device(_) => None,
+            Correction::Variation(table) => {
+                store.evaluate(table.outer_index, table.inner_index, coordinates)
+            }
+        }
+    }
+}
+
 impl crate::value::Read for Correction {
     fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
         Ok(match tape.peek::<(u32, u16)>()?.1 {
@@ -41,6 +59,15 @@ impl crate::value::Read for Correction {
     }
 }
 
+impl crate::value::Write for Correction {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        match self {
+            Correction::Device(table) => tape.give(table),
+            Correction::Variation(table) => tape.give(table),
+        }
+    }
+}
+
 impl Default for Correction {
     #[inline]
     fn default() -> Self {
@@ -71,3 +98,12 @@ impl crate::value::Read for Device {
         })
     }
 }
+
+impl crate::value::Write for Device {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        tape.give(&self.start_size)?;
+        tape.give(&self.end_size)?;
+        tape.give(&self.format)?;
+        tape.give(&*self.deltas)
+    }
+}
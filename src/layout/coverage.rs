@@ -14,6 +14,7 @@ pub enum Coverage {
 }
 
 table! {
+    @write
     /// A coverage in format 1.
     pub Coverage1 { // CoverageFormat1
         format      (u16), // coverageFormat
@@ -26,6 +27,7 @@ table! {
 }
 
 table! {
+    @write
     /// A coverage in format 2.
     pub Coverage2 { // CoverageFormat2
         format       (u16), // coverageFormat
@@ -38,6 +40,7 @@ table! {
 }
 
 table! {
+    @write
     /// A coverage record.
     #[derive(Copy)]
     pub Record { // RangeRecord
@@ -47,6 +50,45 @@ table! {
     }
 }
 
+impl Coverage {
+    /// Return the coverage index of a glyph if covered.
+    ///
+    /// In case of unsorted or overlapping entries, the first entry covering the glyph is taken.
+    pub fn index_of(&self, glyph_id: GlyphID) -> Option<u16> {
+        match self {
+            Coverage::Format1(table) => {
+                let glyph_ids = &table.glyph_ids;
+                match glyph_ids.windows(2).all(|pair| pair[0] < pair[1]) {
+                    true => glyph_ids.binary_search(&glyph_id).ok(),
+                    _ => glyph_ids.iter().position(|&other| other == glyph_id),
+                }
+                .map(|index| index as u16)
+            }
+            Coverage::Format2(table) => {
+                let record = find(&table.records, glyph_id)?;
+                Some(record.index.wrapping_add(glyph_id - record.start_glyph_id))
+            }
+        }
+    }
+
+    /// Iterate over the covered glyphs in the order of their coverage indices.
+    pub fn iter(&self) -> impl Iterator<Item = GlyphID> + '_ {
+        let (glyph_ids, records) = match self {
+            Coverage::Format1(table) => (&table.glyph_ids[..], vec![]),
+            Coverage::Format2(table) => {
+                let mut records = table.records.iter().collect::<Vec<_>>();
+                records.sort_by_key(|record| record.index);
+                (&[][..], records)
+            }
+        };
+        glyph_ids.iter().cloned().chain(
+            records
+                .into_iter()
+                .flat_map(|record| record.start_glyph_id..=record.end_glyph_id),
+        )
+    }
+}
+
 impl Default for Coverage {
     #[inline]
     fn default() -> Self {
@@ -63,3 +105,55 @@ impl crate::value::Read for Coverage {
         })
     }
 }
+
+impl crate::value::Write for Coverage {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        match self {
+            Coverage::Format1(table) => tape.give(table),
+            Coverage::Format2(table) => tape.give(table),
+        }
+    }
+}
+
+/// Find the first record covering a glyph.
+///
+/// Sorted and disjoint records are searched in logarithmic time and the rest in linear time.
+pub(crate) fn find<T: Range>(records: &[T], glyph_id: GlyphID) -> Option<&T> {
+    if !sorted(records) {
+        return records
+            .iter()
+            .find(|record| record.start() <= glyph_id && glyph_id <= record.end());
+    }
+    let record = records.get(records.partition_point(|record| record.end() < glyph_id))?;
+    if record.start() <= glyph_id {
+        Some(record)
+    } else {
+        None
+    }
+}
+
+/// Check if records are sorted and disjoint.
+pub(crate) fn sorted<T: Range>(records: &[T]) -> bool {
+    records.iter().all(|record| record.start() <= record.end())
+        && records
+            .windows(2)
+            .all(|pair| pair[0].end() < pair[1].start())
+}
+
+/// A range of glyphs.
+pub(crate) trait Range {
+    fn start(&self) -> GlyphID;
+    fn end(&self) -> GlyphID;
+}
+
+impl Range for Record {
+    #[inline]
+    fn start(&self) -> GlyphID {
+        self.start_glyph_id
+    }
+
+    #[inline]
+    fn end(&self) -> GlyphID {
+        self.end_glyph_id
+    }
+}
@@ -1,6 +1,12 @@
-use crate::layout::feature::Variations;
-use crate::layout::{Features, Lookups, Scripts};
-use crate::Result;
+use truetype::q16;
+
+use crate::graph::{Graph, Object};
+use crate::layout::feature::{Record, Variations};
+use crate::layout::{lookup, Features, Lookups, Scripts};
+use crate::options::Repair;
+use crate::tape::Write;
+use crate::trace::trace;
+use crate::{ReadOptions, Result};
 
 /// A layout directory.
 #[derive(Clone, Debug)]
@@ -21,9 +27,21 @@ pub struct Directory<T> {
 
 impl<U> crate::value::Read for Directory<U>
 where
-    U: crate::walue::Read<'static, Parameter = u16>,
+    U: for<'l> crate::walue::Read<'l, Parameter = (u16, &'l mut ReadOptions)>,
 {
+    #[inline]
     fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+        tape.take_given(&mut ReadOptions::strict())
+    }
+}
+
+impl<'l, U> crate::walue::Read<'l> for Directory<U>
+where
+    U: for<'m> crate::walue::Read<'m, Parameter = (u16, &'m mut ReadOptions)>,
+{
+    type Parameter = &'l mut ReadOptions;
+
+    fn read<T: crate::tape::Read>(tape: &mut T, options: Self::Parameter) -> Result<Self> {
         let position = tape.position()?;
         let major_version = tape.take()?;
         let minor_version = tape.take()?;
@@ -38,19 +56,45 @@ where
             (1, 1) => tape.take()?,
             _ => 0,
         };
-        #[cfg(not(feature = "ignore-incomplete-directories"))]
-        let (scripts, features, lookups) = (
-            jump_take!(@unwrap tape, position, script_offset),
-            jump_take!(@unwrap tape, position, feature_offset),
-            jump_take!(@unwrap tape, position, lookup_offset),
-        );
-        #[cfg(feature = "ignore-incomplete-directories")]
-        let (scripts, features, lookups) = (
-            jump_take_maybe!(@unwrap tape, position, script_offset).unwrap_or_default(),
-            jump_take_maybe!(@unwrap tape, position, feature_offset).unwrap_or_default(),
-            jump_take_maybe!(@unwrap tape, position, lookup_offset).unwrap_or_default(),
-        );
-        let variations = jump_take_maybe!(@unwrap tape, position, variation_offset);
+        let incomplete = script_offset == 0 || feature_offset == 0 || lookup_offset == 0;
+        let (scripts, features, lookups): (Scripts, Features, Lookups<U>) = if incomplete
+            && options.repair(
+                options.ignore_incomplete_directories,
+                Repair::IncompleteDirectory,
+            ) {
+            (
+                trace(tape, "scripts", |tape| {
+                    jump_take_maybe!(tape, position, script_offset)
+                })?
+                .unwrap_or_default(),
+                trace(tape, "features", |tape| {
+                    jump_take_maybe!(tape, position, feature_offset)
+                })?
+                .unwrap_or_default(),
+                trace(tape, "lookups", |tape| match lookup_offset {
+                    0 => Ok(Default::default()),
+                    _ => jump_take_given!(tape, position, lookup_offset, &mut *options),
+                })?,
+            )
+        } else {
+            (
+                trace(tape, "scripts", |tape| {
+                    jump_take!(tape, position, script_offset)
+                })?,
+                trace(tape, "features", |tape| {
+                    jump_take!(tape, position, feature_offset)
+                })?,
+                trace(tape, "lookups", |tape| {
+                    jump_take_given!(tape, position, lookup_offset, &mut *options)
+                })?,
+            )
+        };
+        let variations = trace(tape, "variations", |tape| match variation_offset {
+            0 => Ok(None),
+            _ => {
+                jump_take_given!(tape, position, variation_offset, &features.headers[..]).map(Some)
+            }
+        })?;
         Ok(Directory {
             major_version,
             minor_version,
@@ -65,3 +109,59 @@ where
         })
     }
 }
+
+impl<U> Directory<U> {
+    /// Return the feature records effective at normalized coordinates.
+    ///
+    /// The records follow the order of the feature list with the substitutions of the first
+    /// feature-variation record whose conditions are satisfied applied.
+    pub fn effective_features(&self, coordinates: &[q16]) -> Vec<&Record> {
+        let mut records = self.features.records.iter().collect::<Vec<_>>();
+        let substitutions = self
+            .variations
+            .as_ref()
+            .and_then(|variations| variations.find(coordinates))
+            .and_then(|record| record.substitutions.as_ref());
+        if let Some(substitutions) = substitutions {
+            for (header, record) in substitutions.headers.iter().zip(&substitutions.records) {
+                if let Some(value) = records.get_mut(header.feature_index as usize) {
+                    *value = record;
+                }
+            }
+        }
+        records
+    }
+
+    /// Write the directory given the lookup type used for extensions.
+    pub(crate) fn write<T>(&self, tape: &mut T, extension: u16) -> Result<()>
+    where
+        T: Write,
+        U: crate::graph::Write + lookup::Subtable,
+    {
+        let mut graph = Graph::default();
+        let scripts = graph.give(&self.scripts)?;
+        let features = graph.give(&self.features)?;
+        let lookups = graph.give(&self.lookups)?;
+        let variations = graph.give_maybe(&self.variations)?;
+        let minor_version = if variations.is_some() {
+            1
+        } else {
+            self.minor_version
+        };
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.give(&minor_version)?;
+        object.link(scripts);
+        object.link(features);
+        object.link(lookups);
+        if minor_version > 0 {
+            object.link_long_maybe(variations);
+        }
+        let root = graph.add(object);
+        let mut promoted = vec![];
+        let data = graph.serialize(root, |graph, parent, _| {
+            lookup::promote(graph, lookups, &mut promoted, parent, extension)
+        })?;
+        tape.give_bytes(&data)
+    }
+}
@@ -1,6 +1,10 @@
 //! The feature list.
 
-use truetype::Tag;
+use truetype::{q16, Tag};
+
+use crate::graph::{Graph, Object};
+use crate::tape::Write;
+use crate::Result;
 
 table! {
     @position
@@ -13,7 +17,11 @@ table! {
         },
 
         records (Vec<Record>) |this, tape, position| {
-            jump_take!(tape, position, this.count, i => this.headers[i].offset)
+            let mut values = Vec::with_capacity(this.count as usize);
+            for header in this.headers.iter() {
+                values.push(jump_take_given!(@unwrap tape, position, header.offset, header.tag));
+            }
+            Ok(values)
         },
     }
 }
@@ -28,34 +36,187 @@ table! {
 }
 
 table! {
-    @position
+    @define
     /// A feature record.
     pub Record { // Feature
-        parameter_offset   (u16), // featureParamsOffset
-        lookup_index_count (u16), // lookupIndexCount
+        parameter_offset   (u16     ), // featureParamsOffset
+        lookup_index_count (u16     ), // lookupIndexCount
+        lookup_indices     (Vec<u16>), // lookupListIndices
+
+        parameters (Option<Parameters>),
+    }
+}
+
+/// Feature parameters.
+#[derive(Clone, Debug)]
+pub enum Parameters {
+    /// Parameters of the optical-size feature.
+    OpticalSize(OpticalSize),
+    /// Parameters of a stylistic-set feature.
+    StylisticSet(StylisticSet),
+    /// Parameters of a character-variant feature.
+    CharacterVariant(CharacterVariant),
+}
+
+table! {
+    @write
+    /// Parameters of the optical-size feature.
+    #[derive(Copy)]
+    pub OpticalSize { // FeatureParamsSize
+        design_size       (u16), // designSize
+        subfamily_id      (u16), // subfamilyIdentifier
+        subfamily_name_id (u16), // subfamilyNameID
+        range_start       (u16), // recommendedUsageRangeStart
+        range_end         (u16), // recommendedUsageRangeEnd
+    }
+}
+
+table! {
+    @write
+    /// Parameters of a stylistic-set feature.
+    #[derive(Copy)]
+    pub StylisticSet { // FeatureParamsStylisticSet
+        version    (u16) = { 0 }, // version
+        ui_name_id (u16), // uiNameID
+    }
+}
+
+table! {
+    @define
+    /// Parameters of a character-variant feature.
+    pub CharacterVariant { // FeatureParamsCharacterVariants
+        format                  (u16     ), // format
+        label_name_id           (u16     ), // featUiLabelNameId
+        tooltip_name_id         (u16     ), // featUiTooltipTextNameId
+        sample_name_id          (u16     ), // sampleTextNameId
+        parameter_count         (u16     ), // numNamedParameters
+        first_parameter_name_id (u16     ), // firstParamUiLabelNameId
+        character_count         (u16     ), // charCount
+        characters              (Vec<u32>), // character
+    }
+}
+
+/// Feature variations.
+#[derive(Clone, Debug, Default)]
+#[rustfmt::skip]
+pub struct Variations { // FeatureVariations
+    pub major_version: u16, // majorVersion
+    pub minor_version: u16, // minorVersion
+    pub count: u32, // featureVariationRecordCount
+    pub headers: Vec<VariationHeader>, // featureVariationRecords
+
+    pub records: Vec<VariationRecord>,
+}
+
+table! {
+    /// A feature-variation header.
+    #[derive(Copy)]
+    pub VariationHeader { // FeatureVariationRecord
+        condition_offset    (u32), // conditionSetOffset
+        substitution_offset (u32), // featureTableSubstitutionOffset
+    }
+}
 
-        lookup_indices (Vec<u16>) |this, tape, _| { // lookupListIndices
-            tape.take_given(this.lookup_index_count as usize)
+table! {
+    @define
+    /// A feature-variation record.
+    pub VariationRecord {
+        conditions    (Option<Conditions>   ),
+        substitutions (Option<Substitutions>),
+    }
+}
+
+table! {
+    @position
+    /// A set of conditions.
+    pub Conditions { // ConditionSet
+        count (u16), // conditionCount
+
+        offsets (Vec<u32>) |this, tape, _| { // conditionOffsets
+            tape.take_given(this.count as usize)
         },
 
-        parameters (Option<Vec<u8>>) |this, tape, position| {
-            if this.parameter_offset > 0 {
-                tape.jump(position + this.parameter_offset as u64)?;
-                Ok(Some(tape.take_bytes(0)?))
-            } else {
-                Ok(None)
-            }
+        records (Vec<Condition>) |this, tape, position| {
+            jump_take!(tape, position, this.count, this.offsets)
         },
     }
 }
 
+/// A condition.
+#[derive(Clone, Debug)]
+pub enum Condition {
+    /// Format 1.
+    Format1(Condition1),
+}
+
+table! {
+    @write
+    /// A condition in format 1.
+    #[derive(Copy)]
+    pub Condition1 { // ConditionFormat1
+        format     (u16), // format
+        axis_index (u16), // axisIndex
+        min_value  (q16), // filterRangeMinValue
+        max_value  (q16), // filterRangeMaxValue
+    }
+}
+
+/// Feature substitutions.
+#[derive(Clone, Debug, Default)]
+#[rustfmt::skip]
+pub struct Substitutions { // FeatureTableSubstitution
+    pub major_version: u16, // majorVersion
+    pub minor_version: u16, // minorVersion
+    pub count: u16, // substitutionCount
+    pub headers: Vec<SubstitutionHeader>, // substitutions
+
+    pub records: Vec<Record>,
+}
+
 table! {
-    /// Feature variations.
+    /// A feature-substitution header.
     #[derive(Copy)]
-    pub Variations { // FeatureVariations
-        major_version (u16) = { 1 }, // MajorVersion
-        minor_version (u16) = { 0 }, // MinorVersion
-        count         (u32), // FeatureVariationRecordsCount
+    pub SubstitutionHeader { // FeatureTableSubstitutionRecord
+        feature_index (u16), // featureIndex
+        offset        (u32), // alternateFeatureOffset
+    }
+}
+
+impl Condition {
+    /// Check if the condition is satisfied at normalized coordinates.
+    ///
+    /// Axes without coordinates are assumed to be at their default values.
+    pub fn matches(&self, coordinates: &[q16]) -> bool {
+        match self {
+            Self::Format1(condition) => {
+                let value = coordinates
+                    .get(condition.axis_index as usize)
+                    .map_or(0, |value| value.0);
+                condition.min_value.0 <= value && value <= condition.max_value.0
+            }
+        }
+    }
+}
+
+impl Conditions {
+    /// Check if all conditions are satisfied at normalized coordinates.
+    #[inline]
+    pub fn matches(&self, coordinates: &[q16]) -> bool {
+        self.records
+            .iter()
+            .all(|condition| condition.matches(coordinates))
+    }
+}
+
+impl Variations {
+    /// Return the first record whose conditions are satisfied at normalized coordinates.
+    pub fn find(&self, coordinates: &[q16]) -> Option<&VariationRecord> {
+        self.records.iter().find(|record| {
+            record
+                .conditions
+                .as_ref()
+                .is_none_or(|conditions| conditions.matches(coordinates))
+        })
     }
 }
 
@@ -71,6 +232,268 @@ impl Features {
     }
 }
 
+impl crate::graph::Write for Features {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give_count(records.len())?;
+        for (header, index) in self.headers.iter().zip(records) {
+            object.give(&header.tag)?;
+            object.link(index);
+        }
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Record {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        if self.parameters.is_none() && self.parameter_offset > 0 {
+            raise!("found feature parameters of an unknown kind, which cannot be written");
+        }
+        let parameters = graph.give_maybe(&self.parameters)?;
+        let mut object = Object::default();
+        object.link_maybe(parameters);
+        object.give_count(self.lookup_indices.len())?;
+        object.give(&*self.lookup_indices)?;
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Variations {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.give(&self.minor_version)?;
+        object.give(&(self.records.len() as u32))?;
+        for record in self.records.iter() {
+            object.link_long_maybe(graph.give_maybe(&record.conditions)?);
+            object.link_long_maybe(graph.give_maybe(&record.substitutions)?);
+        }
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Conditions {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give_count(records.len())?;
+        records
+            .into_iter()
+            .for_each(|index| object.link_long(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Substitutions {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.give(&self.minor_version)?;
+        object.give_count(records.len())?;
+        for (header, index) in self.headers.iter().zip(records) {
+            object.give(&header.feature_index)?;
+            object.link_long(index);
+        }
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::value::Read for Condition {
+    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+        Ok(match tape.peek::<u16>()? {
+            1 => Self::Format1(tape.take()?),
+            value => raise!("found an unknown format of the condition table ({value})"),
+        })
+    }
+}
+
+impl crate::value::Write for Condition {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        match self {
+            Self::Format1(table) => tape.give(table),
+        }
+    }
+}
+
+impl crate::value::Read for CharacterVariant {
+    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+        let format = tape.take()?;
+        if format != 0 {
+            raise!("found an unknown format of the character-variant parameters ({format})");
+        }
+        let label_name_id = tape.take()?;
+        let tooltip_name_id = tape.take()?;
+        let sample_name_id = tape.take()?;
+        let parameter_count = tape.take()?;
+        let first_parameter_name_id = tape.take()?;
+        let character_count = tape.take()?;
+        let characters = (0..character_count)
+            .map(|_| {
+                let [a, b, c] = tape.take::<[u8; 3]>()?;
+                Ok(u32::from_be_bytes([0, a, b, c]))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            format,
+            label_name_id,
+            tooltip_name_id,
+            sample_name_id,
+            parameter_count,
+            first_parameter_name_id,
+            character_count,
+            characters,
+        })
+    }
+}
+
+impl crate::value::Write for CharacterVariant {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        if self.characters.len() > u16::MAX as usize {
+            raise!(
+                "found too many characters to write ({})",
+                self.characters.len()
+            );
+        }
+        tape.give(&0u16)?;
+        tape.give(&self.label_name_id)?;
+        tape.give(&self.tooltip_name_id)?;
+        tape.give(&self.sample_name_id)?;
+        tape.give(&self.parameter_count)?;
+        tape.give(&self.first_parameter_name_id)?;
+        tape.give(&(self.characters.len() as u16))?;
+        for &character in self.characters.iter() {
+            match character.to_be_bytes() {
+                [0, a, b, c] => tape.give(&[a, b, c])?,
+                _ => raise!("found a character that cannot be written ({character:#x})"),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl crate::value::Write for Parameters {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        match self {
+            Self::OpticalSize(table) => tape.give(table),
+            Self::StylisticSet(table) => tape.give(table),
+            Self::CharacterVariant(table) => tape.give(table),
+        }
+    }
+}
+
+impl crate::walue::Read<'static> for Record {
+    type Parameter = Tag;
+
+    /// Read a feature record given the tag of the feature.
+    ///
+    /// The parameters are read for the optical-size, stylistic-set, and character-variant
+    /// features and left out for other features.
+    fn read<T: crate::tape::Read>(tape: &mut T, tag: Self::Parameter) -> Result<Self> {
+        let position = tape.position()?;
+        let parameter_offset = tape.take()?;
+        let lookup_index_count = tape.take()?;
+        let lookup_indices = tape.take_given(lookup_index_count as usize)?;
+        let parameters = if parameter_offset > 0 {
+            tape.jump(position + parameter_offset as u64)?;
+            match &*tag {
+                b"size" => Some(Parameters::OpticalSize(tape.take()?)),
+                [b's', b's', b'0'..=b'9', b'0'..=b'9'] => {
+                    Some(Parameters::StylisticSet(tape.take()?))
+                }
+                [b'c', b'v', b'0'..=b'9', b'0'..=b'9'] => {
+                    Some(Parameters::CharacterVariant(tape.take()?))
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
+        Ok(Self {
+            parameter_offset,
+            lookup_index_count,
+            lookup_indices,
+            parameters,
+        })
+    }
+}
+
+impl<'l> crate::walue::Read<'l> for Substitutions {
+    type Parameter = &'l [Header];
+
+    /// Read feature substitutions given the headers of the feature list.
+    fn read<T: crate::tape::Read>(tape: &mut T, features: Self::Parameter) -> Result<Self> {
+        let position = tape.position()?;
+        let major_version = tape.take()?;
+        let minor_version = tape.take()?;
+        if (major_version, minor_version) != (1, 0) {
+            raise!("found an unknown version of feature substitutions ({major_version}.{minor_version})");
+        }
+        let count = tape.take()?;
+        let headers: Vec<SubstitutionHeader> = tape.take_given(count as usize)?;
+        let mut records = Vec::with_capacity(count as usize);
+        for header in headers.iter() {
+            let tag = match features.get(header.feature_index as usize) {
+                Some(feature) => feature.tag,
+                _ => raise!(
+                    "found a substitution of an unknown feature ({})",
+                    header.feature_index
+                ),
+            };
+            records.push(jump_take_given!(@unwrap tape, position, header.offset, tag));
+        }
+        Ok(Self {
+            major_version,
+            minor_version,
+            count,
+            headers,
+            records,
+        })
+    }
+}
+
+impl<'l> crate::walue::Read<'l> for Variations {
+    type Parameter = &'l [Header];
+
+    /// Read feature variations given the headers of the feature list.
+    fn read<T: crate::tape::Read>(tape: &mut T, features: Self::Parameter) -> Result<Self> {
+        let position = tape.position()?;
+        let major_version = tape.take()?;
+        let minor_version = tape.take()?;
+        if (major_version, minor_version) != (1, 0) {
+            raise!(
+                "found an unknown version of feature variations ({major_version}.{minor_version})"
+            );
+        }
+        let count = tape.take()?;
+        let headers: Vec<VariationHeader> = tape.take_given(count as usize)?;
+        let mut records = Vec::with_capacity(count as usize);
+        for header in headers.iter() {
+            records.push(VariationRecord {
+                conditions: jump_take_maybe!(@unwrap tape, position, header.condition_offset),
+                substitutions: if header.substitution_offset > 0 {
+                    Some(jump_take_given!(
+                        @unwrap tape,
+                        position,
+                        header.substitution_offset,
+                        features
+                    ))
+                } else {
+                    None
+                },
+            });
+        }
+        Ok(Self {
+            major_version,
+            minor_version,
+            count,
+            headers,
+            records,
+        })
+    }
+}
+
 macro_rules! implement {
     ($($tag:literal => $name:literal => $variant:ident,)*) => (
         /// A feature.
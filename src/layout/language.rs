@@ -2,6 +2,9 @@
 
 use truetype::Tag;
 
+/// The index indicating the absence of a required feature.
+pub(crate) const NO_REQUIRED_FEATURE: u16 = 0xFFFF;
+
 table! {
     /// A language-system header.
     pub Header { // LangSysRecord
@@ -11,6 +14,7 @@ table! {
 }
 
 table! {
+    @write
     /// A language-system record.
     pub Record { // LangSys
         lookup_order_offset    (u16) = { 0 }, // lookupOrderOffset
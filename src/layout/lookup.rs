@@ -1,6 +1,9 @@
 //! The lookup list.
 
-use crate::Result;
+use crate::graph::{Graph, Object};
+use crate::tape::Write;
+use crate::trace::trace;
+use crate::{ReadOptions, Result};
 
 /// A lookup list.
 #[derive(Clone, Debug)]
@@ -25,6 +28,12 @@ pub struct Record<T> { // Lookup
     pub tables: Vec<T>,
 }
 
+/// A lookup subtable.
+pub trait Subtable {
+    /// Return the lookup type with extensions resolved.
+    fn r#type(&self) -> u16;
+}
+
 flags! {
     /// Lookup flags.
     pub Flags(u16) {
@@ -37,6 +46,19 @@ flags! {
     }
 }
 
+impl<T: Subtable> Record<T> {
+    /// Return the lookup type with extensions resolved.
+    ///
+    /// The type differs from the one stored in the record for extension lookups, whose subtables
+    /// are resolved at parse time.
+    pub fn effective_type(&self) -> u16 {
+        match self.tables.first() {
+            Some(table) => table.r#type(),
+            _ => self.r#type,
+        }
+    }
+}
+
 impl<T> Default for Lookups<T> {
     #[inline]
     fn default() -> Self {
@@ -48,15 +70,21 @@ impl<T> Default for Lookups<T> {
     }
 }
 
-impl<U> crate::value::Read for Lookups<U>
+impl<'l, U> crate::walue::Read<'l> for Lookups<U>
 where
-    U: crate::walue::Read<'static, Parameter = u16>,
+    U: for<'m> crate::walue::Read<'m, Parameter = (u16, &'m mut ReadOptions)>,
 {
-    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+    type Parameter = &'l mut ReadOptions;
+
+    fn read<T: crate::tape::Read>(tape: &mut T, options: Self::Parameter) -> Result<Self> {
         let position = tape.position()?;
         let count = tape.take::<u16>()?;
         let offsets: Vec<u16> = tape.take_given(count as usize)?;
-        let records = jump_take!(@unwrap tape, position, count, offsets);
+        let mut records = Vec::with_capacity(count as usize);
+        for (i, &offset) in offsets.iter().enumerate() {
+            tape.jump(position + offset as u64)?;
+            records.push(trace(tape, i, |tape| tape.take_given(&mut *options))?);
+        }
         Ok(Lookups {
             count,
             offsets,
@@ -65,11 +93,13 @@ where
     }
 }
 
-impl<U> crate::value::Read for Record<U>
+impl<'l, U> crate::walue::Read<'l> for Record<U>
 where
-    U: crate::walue::Read<'static, Parameter = u16>,
+    U: for<'m> crate::walue::Read<'m, Parameter = (u16, &'m mut ReadOptions)>,
 {
-    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+    type Parameter = &'l mut ReadOptions;
+
+    fn read<T: crate::tape::Read>(tape: &mut T, options: Self::Parameter) -> Result<Self> {
         let position = tape.position()?;
         let r#type = tape.take()?;
         let flags = tape.take::<Flags>()?;
@@ -80,7 +110,16 @@ where
         } else {
             None
         };
-        let tables = jump_take_given!(@unwrap tape, position, table_count, table_offsets, r#type);
+        let tables = trace(tape, "tables", |tape| {
+            let mut tables = Vec::with_capacity(table_count as usize);
+            for (i, &offset) in table_offsets.iter().enumerate() {
+                tape.jump(position + offset as u64)?;
+                tables.push(trace(tape, i, |tape| {
+                    tape.take_given((r#type, &mut *options))
+                })?);
+            }
+            Ok(tables)
+        })?;
         Ok(Record {
             r#type,
             flags,
@@ -91,3 +130,81 @@ where
         })
     }
 }
+
+impl<U: crate::graph::Write + Subtable> crate::graph::Write for Lookups<U> {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give_count(records.len())?;
+        records.into_iter().for_each(|index| object.link(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl<U: crate::graph::Write + Subtable> crate::graph::Write for Record<U> {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let r#type = self.effective_type();
+        if self.tables.iter().any(|table| table.r#type() != r#type) {
+            raise!("found a lookup with subtables of different types");
+        }
+        let tables = graph.give_all(&self.tables)?;
+        let mut object = Object::default();
+        object.give(&r#type)?;
+        object.give(&self.flags)?;
+        object.give_count(tables.len())?;
+        tables.into_iter().for_each(|index| object.link(index));
+        if self.flags.has_mark_filtering() {
+            match self.mark_filtering_set {
+                Some(value) => object.give(&value)?,
+                _ => raise!("found a lookup with mark filtering but without a mark filtering set"),
+            }
+        }
+        Ok(graph.add(object))
+    }
+}
+
+/// Promote a lookup to the extension type in order to resolve an offset overflow.
+///
+/// The lookup is the one whose subtables contain the overflowing offset if any or the largest
+/// one otherwise.
+pub(crate) fn promote(
+    graph: &mut Graph,
+    lookups: usize,
+    promoted: &mut Vec<usize>,
+    parent: usize,
+    extension: u16,
+) -> Result<bool> {
+    let candidates = graph
+        .get(lookups)
+        .targets()
+        .filter(|index| !promoted.contains(index))
+        .collect::<Vec<_>>();
+    let lookup = match candidates
+        .iter()
+        .find(|&&index| graph.descendants(index).any(|other| other == parent))
+    {
+        Some(&index) => index,
+        _ => match candidates.iter().max_by_key(|&&index| {
+            let descendants = graph.descendants(index);
+            descendants
+                .map(|other| graph.get(other).data().len())
+                .sum::<usize>()
+        }) {
+            Some(&index) => index,
+            _ => return Ok(false),
+        },
+    };
+    promoted.push(lookup);
+    let r#type = u16::from_be_bytes([graph.get(lookup).data()[0], graph.get(lookup).data()[1]]);
+    graph.get_mut(lookup).data_mut()[..2].copy_from_slice(&extension.to_be_bytes());
+    let tables = graph.get(lookup).targets().collect::<Vec<_>>();
+    for table in tables {
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.give(&r#type)?;
+        object.link_long(table);
+        let index = graph.add_unique(object);
+        graph.get_mut(lookup).redirect(table, index);
+    }
+    Ok(true)
+}
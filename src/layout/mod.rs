@@ -10,6 +10,7 @@ pub mod feature;
 pub mod language;
 pub mod lookup;
 pub mod script;
+pub mod view;
 
 mod directory;
 
@@ -2,7 +2,10 @@
 
 use truetype::Tag;
 
+use crate::graph::{Graph, Object};
 use crate::layout::language;
+use crate::tape::Write;
+use crate::Result;
 
 table! {
     @position
@@ -74,6 +77,41 @@ impl Record {
     }
 }
 
+impl Default for Script {
+    #[inline]
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+impl crate::graph::Write for Scripts {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give_count(records.len())?;
+        for (header, index) in self.headers.iter().zip(records) {
+            object.give(&header.tag)?;
+            object.link(index);
+        }
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Record {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let default_language = graph.give_maybe(&self.default_language)?;
+        let language_records = graph.give_all(&self.language_records)?;
+        let mut object = Object::default();
+        object.link_maybe(default_language);
+        object.give_count(language_records.len())?;
+        for (header, index) in self.language_headers.iter().zip(language_records) {
+            object.give(&header.tag)?;
+            object.link(index);
+        }
+        Ok(graph.add(object))
+    }
+}
+
 macro_rules! implement {
     ($($tag:literal => $name:literal => $variant:ident,)*) => (
         /// A script.
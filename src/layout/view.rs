@@ -0,0 +1,462 @@
+//! Borrowed views of layout tables.
+//!
+//! The views refer to the data of a table and read their fields on access, which allows one to
+//! open a font and decode only the parts that are actually used. There are views of the
+//! directory, the lookup list, lookup records, lookup subtables, coverages, and classes. The
+//! script list, the feature list, and the feature variations are decoded into the corresponding
+//! owned types when accessed, and lookup subtables can be decoded likewise. The accessors bear
+//! the names of the fields of the corresponding owned types.
+
+use std::io::{Cursor, ErrorKind};
+use std::marker::PhantomData;
+
+use truetype::GlyphID;
+
+use crate::layout::feature::Variations;
+use crate::layout::lookup::Flags;
+use crate::layout::{Features, Scripts};
+use crate::tape::Read;
+use crate::{Error, ReadOptions, Result};
+
+/// A view of a layout directory.
+pub struct Directory<'l, T> {
+    data: &'l [u8],
+    phantom: PhantomData<T>,
+}
+
+/// A view of a lookup list.
+pub struct Lookups<'l, T> {
+    data: &'l [u8],
+    phantom: PhantomData<T>,
+}
+
+/// A view of a lookup record.
+pub struct Record<'l, T> {
+    data: &'l [u8],
+    phantom: PhantomData<T>,
+}
+
+/// A view of a lookup subtable.
+pub struct Table<'l, T> {
+    data: &'l [u8],
+    r#type: u16,
+    phantom: PhantomData<T>,
+}
+
+/// A view of a coverage.
+pub struct Coverage<'l> {
+    data: &'l [u8],
+}
+
+/// A view of a class.
+pub struct Class<'l> {
+    data: &'l [u8],
+}
+
+impl<'l, T> Directory<'l, T> {
+    /// Create a view of the data of a table.
+    pub fn new(data: &'l [u8]) -> Result<Self> {
+        match (take(data, 0)?, take(data, 2)?) {
+            (1, 0) => check(data, 10)?,
+            (1, 1) => check(data, 14)?,
+            value => raise!("found an unknown version of the directory table {value:?}"),
+        }
+        Ok(Self {
+            data,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Return the major version.
+    #[inline]
+    pub fn major_version(&self) -> u16 {
+        get(self.data, 0)
+    }
+
+    /// Return the minor version.
+    #[inline]
+    pub fn minor_version(&self) -> u16 {
+        get(self.data, 2)
+    }
+
+    /// Return the offset of the script list.
+    #[inline]
+    pub fn script_offset(&self) -> u16 {
+        get(self.data, 4)
+    }
+
+    /// Return the offset of the feature list.
+    #[inline]
+    pub fn feature_offset(&self) -> u16 {
+        get(self.data, 6)
+    }
+
+    /// Return the offset of the lookup list.
+    #[inline]
+    pub fn lookup_offset(&self) -> u16 {
+        get(self.data, 8)
+    }
+
+    /// Return the offset of the feature variations.
+    #[inline]
+    pub fn variation_offset(&self) -> u32 {
+        match self.minor_version() {
+            0 => 0,
+            _ => (get(self.data, 10) as u32) << 16 | get(self.data, 12) as u32,
+        }
+    }
+
+    /// Decode the script list.
+    #[inline]
+    pub fn scripts(&self) -> Result<Scripts> {
+        decode(self.data, self.script_offset() as usize)
+    }
+
+    /// Decode the feature list.
+    #[inline]
+    pub fn features(&self) -> Result<Features> {
+        decode(self.data, self.feature_offset() as usize)
+    }
+
+    /// Return a view of the lookup list.
+    #[inline]
+    pub fn lookups(&self) -> Result<Lookups<'l, T>> {
+        Lookups::new(slice(self.data, self.lookup_offset() as usize)?)
+    }
+
+    /// Decode the feature variations if present.
+    pub fn variations(&self) -> Result<Option<Variations>> {
+        match self.variation_offset() {
+            0 => Ok(None),
+            offset => {
+                let features = self.features()?;
+                let data = slice(self.data, offset as usize)?;
+                Cursor::new(data)
+                    .take_given(&features.headers[..])
+                    .map(Some)
+            }
+        }
+    }
+}
+
+impl<'l, T> Lookups<'l, T> {
+    /// Create a view of the data of a lookup list.
+    pub fn new(data: &'l [u8]) -> Result<Self> {
+        check(data, 2 + 2 * take(data, 0)? as usize)?;
+        Ok(Self {
+            data,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Return the number of records.
+    #[inline]
+    pub fn count(&self) -> u16 {
+        get(self.data, 0)
+    }
+
+    /// Return the offsets of the records.
+    #[inline]
+    pub fn offsets(&self) -> impl Iterator<Item = u16> + 'l {
+        let data = self.data;
+        (0..self.count() as usize).map(move |i| get(data, 2 + 2 * i))
+    }
+
+    /// Return a view of a record.
+    pub fn get(&self, index: usize) -> Result<Record<'l, T>> {
+        match self.offsets().nth(index) {
+            Some(offset) => Record::new(slice(self.data, offset as usize)?),
+            _ => raise!("found no lookup with index {index}"),
+        }
+    }
+
+    /// Return views of the records.
+    #[inline]
+    pub fn records(&self) -> impl Iterator<Item = Result<Record<'l, T>>> + 'l {
+        let data = self.data;
+        self.offsets()
+            .map(move |offset| Record::new(slice(data, offset as usize)?))
+    }
+}
+
+impl<'l, T> Record<'l, T> {
+    /// Create a view of the data of a lookup record.
+    pub fn new(data: &'l [u8]) -> Result<Self> {
+        let flags = Flags(take(data, 2)?);
+        let size = 6 + 2 * take(data, 4)? as usize;
+        check(data, size + if flags.has_mark_filtering() { 2 } else { 0 })?;
+        Ok(Self {
+            data,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Return the type.
+    #[inline]
+    pub fn r#type(&self) -> u16 {
+        get(self.data, 0)
+    }
+
+    /// Return the flags.
+    #[inline]
+    pub fn flags(&self) -> Flags {
+        Flags(get(self.data, 2))
+    }
+
+    /// Return the number of subtables.
+    #[inline]
+    pub fn table_count(&self) -> u16 {
+        get(self.data, 4)
+    }
+
+    /// Return the offsets of the subtables.
+    #[inline]
+    pub fn table_offsets(&self) -> impl Iterator<Item = u16> + 'l {
+        let data = self.data;
+        (0..self.table_count() as usize).map(move |i| get(data, 6 + 2 * i))
+    }
+
+    /// Return the mark filtering set.
+    pub fn mark_filtering_set(&self) -> Option<u16> {
+        match self.flags().has_mark_filtering() {
+            true => Some(get(self.data, 6 + 2 * self.table_count() as usize)),
+            _ => None,
+        }
+    }
+}
+
+impl<'l, T> Record<'l, T> {
+    /// Return a view of a subtable.
+    pub fn table(&self, index: usize) -> Result<Table<'l, T>> {
+        match self.table_offsets().nth(index) {
+            Some(offset) => Table::new(slice(self.data, offset as usize)?, self.r#type()),
+            _ => raise!("found no lookup subtable with index {index}"),
+        }
+    }
+}
+
+impl<T> Record<'_, T>
+where
+    T: for<'l> crate::walue::Read<'l, Parameter = (u16, &'l mut ReadOptions)>,
+{
+    /// Decode a subtable into an owned value strictly.
+    pub fn get(&self, index: usize) -> Result<T> {
+        match self.table_offsets().nth(index) {
+            Some(offset) => decode_given(self.data, offset as usize, self.r#type()),
+            _ => raise!("found no lookup subtable with index {index}"),
+        }
+    }
+
+    /// Decode the subtables into owned values one at a time strictly.
+    pub fn tables(&self) -> impl Iterator<Item = Result<T>> + '_ {
+        let r#type = self.r#type();
+        self.table_offsets()
+            .map(move |offset| decode_given(self.data, offset as usize, r#type))
+    }
+}
+
+impl<'l, T> Table<'l, T> {
+    /// Create a view of the data of a lookup subtable given the type of the lookup.
+    pub fn new(data: &'l [u8], r#type: u16) -> Result<Self> {
+        check(data, 2)?;
+        Ok(Self {
+            data,
+            r#type,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Return the data.
+    #[inline]
+    pub fn data(&self) -> &'l [u8] {
+        self.data
+    }
+
+    /// Return the type of the lookup as stored in the record.
+    #[inline]
+    pub fn r#type(&self) -> u16 {
+        self.r#type
+    }
+
+    /// Return the format.
+    #[inline]
+    pub fn format(&self) -> u16 {
+        get(self.data, 0)
+    }
+
+    /// Return a view of the coverage whose offset follows the format.
+    ///
+    /// The offset is located so in all subtables except extensions and contextual subtables in
+    /// format 3, for which the data are to be interpreted by the caller.
+    pub fn coverage(&self) -> Result<Coverage<'l>> {
+        Coverage::new(slice(self.data, take(self.data, 2)? as usize)?)
+    }
+}
+
+impl<'l> Coverage<'l> {
+    /// Create a view of the data of a coverage.
+    ///
+    /// Only the header is checked; the entries are read when searched.
+    pub fn new(data: &'l [u8]) -> Result<Self> {
+        match take(data, 0)? {
+            1 | 2 => check(data, 4)?,
+            value => raise!("found an unknown format of the glyph coverage ({value})"),
+        }
+        Ok(Self { data })
+    }
+
+    /// Return the format.
+    #[inline]
+    pub fn format(&self) -> u16 {
+        get(self.data, 0)
+    }
+
+    /// Return the number of glyphs in format 1 or the number of records in format 2.
+    #[inline]
+    pub fn count(&self) -> u16 {
+        get(self.data, 2)
+    }
+
+    /// Return the coverage index of a glyph if covered.
+    ///
+    /// In case of unsorted or overlapping entries, the first entry covering the glyph is taken.
+    pub fn index_of(&self, glyph_id: GlyphID) -> Result<Option<u16>> {
+        let data = self.data;
+        let count = self.count() as usize;
+        Ok(match self.format() {
+            1 => find(count, glyph_id, |i| {
+                let glyph_id = take(data, 4 + 2 * i)?;
+                Ok((glyph_id, glyph_id))
+            })?
+            .map(|i| i as u16),
+            _ => match find(count, glyph_id, |i| range(data, 4 + 6 * i))? {
+                Some(i) => {
+                    let start_glyph_id = get(data, 4 + 6 * i);
+                    let index = take(data, 4 + 6 * i + 4)?;
+                    Some(index.wrapping_add(glyph_id - start_glyph_id))
+                }
+                _ => None,
+            },
+        })
+    }
+}
+
+impl<'l> Class<'l> {
+    /// Create a view of the data of a class.
+    ///
+    /// Only the header is checked; the entries are read when searched.
+    pub fn new(data: &'l [u8]) -> Result<Self> {
+        match take(data, 0)? {
+            1 => check(data, 6)?,
+            2 => check(data, 4)?,
+            value => raise!("found an unknown format of the glyph class ({value})"),
+        }
+        Ok(Self { data })
+    }
+
+    /// Return the format.
+    #[inline]
+    pub fn format(&self) -> u16 {
+        get(self.data, 0)
+    }
+
+    /// Return the class of a glyph.
+    ///
+    /// Glyphs not assigned explicitly belong to class 0. In case of unsorted or overlapping
+    /// entries, the first entry covering the glyph is taken.
+    pub fn class_of(&self, glyph_id: GlyphID) -> Result<u16> {
+        let data = self.data;
+        Ok(match self.format() {
+            1 => match glyph_id.checked_sub(get(data, 2)) {
+                Some(i) if i < get(data, 4) => take(data, 6 + 2 * i as usize)?,
+                _ => 0,
+            },
+            _ => match find(get(data, 2) as usize, glyph_id, |i| range(data, 4 + 6 * i))? {
+                Some(i) => take(data, 4 + 6 * i + 4)?,
+                _ => 0,
+            },
+        })
+    }
+}
+
+fn check(data: &[u8], size: usize) -> Result<()> {
+    match data.len() >= size {
+        true => Ok(()),
+        _ => Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "found a truncated table",
+        )),
+    }
+}
+
+fn decode<T: crate::value::Read>(data: &[u8], offset: usize) -> Result<T> {
+    Cursor::new(slice(data, offset)?).take()
+}
+
+fn decode_given<T>(data: &[u8], offset: usize, parameter: u16) -> Result<T>
+where
+    T: for<'l> crate::walue::Read<'l, Parameter = (u16, &'l mut ReadOptions)>,
+{
+    Cursor::new(slice(data, offset)?).take_given((parameter, &mut ReadOptions::strict()))
+}
+
+/// Find the first of a number of glyph ranges covering a glyph.
+///
+/// Sorted and disjoint ranges are searched in logarithmic time and the rest in linear time.
+fn find<F>(count: usize, glyph_id: GlyphID, range: F) -> Result<Option<usize>>
+where
+    F: Fn(usize) -> Result<(GlyphID, GlyphID)>,
+{
+    let mut sorted = true;
+    let mut last = None;
+    for i in 0..count {
+        let (start, end) = range(i)?;
+        if start > end || last.is_some_and(|last| last >= start) {
+            sorted = false;
+            break;
+        }
+        last = Some(end);
+    }
+    if !sorted {
+        for i in 0..count {
+            let (start, end) = range(i)?;
+            if start <= glyph_id && glyph_id <= end {
+                return Ok(Some(i));
+            }
+        }
+        return Ok(None);
+    }
+    let (mut lower, mut upper) = (0, count);
+    while lower < upper {
+        let middle = lower + (upper - lower) / 2;
+        let (start, end) = range(middle)?;
+        if end < glyph_id {
+            lower = middle + 1;
+        } else if glyph_id < start {
+            upper = middle;
+        } else {
+            return Ok(Some(middle));
+        }
+    }
+    Ok(None)
+}
+
+#[inline]
+fn get(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn slice(data: &[u8], offset: usize) -> Result<&[u8]> {
+    check(data, offset)?;
+    Ok(&data[offset..])
+}
+
+#[inline]
+fn take(data: &[u8], offset: usize) -> Result<u16> {
+    check(data, offset + 2)?;
+    Ok(get(data, offset))
+}
+
+fn range(data: &[u8], offset: usize) -> Result<(GlyphID, GlyphID)> {
+    Ok((take(data, offset)?, take(data, offset + 2)?))
+}
@@ -44,21 +44,34 @@ pub extern crate truetype;
 extern crate typeface;
 
 pub mod layout;
+pub mod shaping;
+pub mod subsetting;
 pub mod tables;
 pub mod variations;
+pub mod woff;
+pub mod woff2;
 
+mod builder;
+mod collection;
 mod file;
 mod font;
+mod graph;
+mod options;
 mod table;
+mod trace;
 
 pub use typeface::{tape, value, walue, Error, Result};
 
+pub use builder::Builder;
+pub use collection::Collection;
 pub use file::File;
 pub use font::Font;
+pub use options::{ReadOptions, Repair};
 pub use table::Table;
+pub use trace::{Failure, Path, Segment};
 
 /// Check if a tag is recognized.
 #[inline]
 pub fn accept(tag: &truetype::Tag) -> bool {
-    matches!(&tag.0, b"ttcf") || truetype::accept(tag)
+    matches!(&tag.0, b"ttcf" | b"wOFF" | b"wOF2") || truetype::accept(tag)
 }
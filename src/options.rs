@@ -0,0 +1,60 @@
+use truetype::Tag;
+
+/// Options for reading fonts.
+///
+/// The options are passed to the reading functions that accept them, such as
+/// [`crate::Font::take_with`], which pass them on to nested tables. Elsewhere, fonts are read
+/// strictly.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOptions {
+    /// Treat missing script, feature, and lookup lists in layout tables as empty.
+    // https://github.com/google/fonts/issues/6888
+    pub ignore_incomplete_directories: bool,
+    /// Treat missing anchors of marks in glyph positioning as default anchors.
+    // https://github.com/google/fonts/issues/6894
+    pub ignore_incomplete_marks: bool,
+    /// Accept tables whose checksums do not match.
+    // https://github.com/google/fonts/issues/6892
+    // https://github.com/google/fonts/issues/6893
+    pub ignore_invalid_checksums: bool,
+    /// The problems repaired while reading.
+    pub repairs: Vec<Repair>,
+}
+
+/// A problem repaired while reading.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Repair {
+    /// A layout table without a script, feature, or lookup list.
+    IncompleteDirectory,
+    /// A mark without an anchor.
+    IncompleteMark,
+    /// A table with a checksum that does not match.
+    InvalidChecksum(Tag),
+}
+
+impl ReadOptions {
+    /// Create options rejecting any problem.
+    #[inline]
+    pub fn strict() -> Self {
+        Self::default()
+    }
+
+    /// Create options repairing any problem that can be repaired.
+    #[inline]
+    pub fn lenient() -> Self {
+        Self {
+            ignore_incomplete_directories: true,
+            ignore_incomplete_marks: true,
+            ignore_invalid_checksums: true,
+            repairs: vec![],
+        }
+    }
+
+    /// Check if a problem is to be repaired, in which case it is recorded, or rejected.
+    pub(crate) fn repair(&mut self, accept: bool, repair: Repair) -> bool {
+        if accept {
+            self.repairs.push(repair);
+        }
+        accept
+    }
+}
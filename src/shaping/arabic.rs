@@ -0,0 +1,566 @@
+//! The shaping of Arabic and related scripts.
+
+use crate::layout::Feature;
+use crate::shaping::shaper::{Complex, Shaper};
+use crate::shaping::{Buffer, Glyph, Plan};
+
+/// A shaper for Arabic, Mongolian, N’Ko, Phags-pa, and Syriac.
+pub(crate) struct Arabic;
+
+/// A joining type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum JoiningType {
+    NonJoining,
+    LeftJoining,
+    RightJoining,
+    DualJoining,
+    Alaph,
+    DalathRish,
+    Transparent,
+}
+
+/// The joining forms, whose positions are the mask bits of the corresponding features.
+const FORMS: &[Feature] = &[
+    Feature::IsolatedForms,
+    Feature::TerminalForms1,
+    Feature::TerminalForms2,
+    Feature::TerminalForms3,
+    Feature::MedialForms1,
+    Feature::MedialForms2,
+    Feature::InitialForms,
+];
+
+const ISOL: u8 = 0;
+const FINA: u8 = 1;
+const FIN2: u8 = 2;
+const FIN3: u8 = 3;
+const MEDI: u8 = 4;
+const MED2: u8 = 5;
+const INIT: u8 = 6;
+const NONE: u8 = 7;
+
+/// The transitions of the joining state machine.
+///
+/// Each entry gives the form of the preceding character, the form of the current character,
+/// and the next state for each joining type except for transparent.
+#[rustfmt::skip]
+const STATES: [[(u8, u8, usize); 6]; 7] = [
+    // The preceding character is non-joining.
+    [(NONE, NONE, 0), (NONE, ISOL, 2), (NONE, ISOL, 1), (NONE, ISOL, 2), (NONE, ISOL, 1), (NONE, ISOL, 6)],
+    // The preceding character is right-joining or an isolated alaph.
+    [(NONE, NONE, 0), (NONE, ISOL, 2), (NONE, ISOL, 1), (NONE, ISOL, 2), (NONE, FIN2, 5), (NONE, ISOL, 6)],
+    // The preceding character is left- or dual-joining in the isolated form.
+    [(NONE, NONE, 0), (NONE, ISOL, 2), (INIT, FINA, 1), (INIT, FINA, 3), (INIT, FINA, 4), (INIT, FINA, 6)],
+    // The preceding character is dual-joining in the terminal form.
+    [(NONE, NONE, 0), (NONE, ISOL, 2), (MEDI, FINA, 1), (MEDI, FINA, 3), (MEDI, FINA, 4), (MEDI, FINA, 6)],
+    // The preceding character is an alaph in the terminal form.
+    [(NONE, NONE, 0), (NONE, ISOL, 2), (MED2, ISOL, 1), (MED2, ISOL, 2), (MED2, FIN2, 5), (MED2, ISOL, 6)],
+    // The preceding character is an alaph in the second or third terminal form.
+    [(NONE, NONE, 0), (NONE, ISOL, 2), (ISOL, ISOL, 1), (ISOL, ISOL, 2), (ISOL, FIN2, 5), (ISOL, ISOL, 6)],
+    // The preceding character is a dalath or a rish.
+    [(NONE, NONE, 0), (NONE, ISOL, 2), (NONE, ISOL, 1), (NONE, ISOL, 2), (NONE, FIN3, 5), (NONE, ISOL, 6)],
+];
+
+/// The stage of stretching glyph decomposition.
+const STRETCHING_STAGE: usize = 1;
+
+/// The category of glyphs of the Syriac abbreviation mark.
+const ABBREVIATION: u8 = 1;
+/// The category of stretched glyphs that are not repeated.
+const FIXED: u8 = 2;
+/// The category of stretched glyphs that are repeated.
+const REPEATING: u8 = 3;
+
+/// The modifier combining marks, which are placed first among marks of the same class.
+const MODIFIERS: &[char] = &[
+    '\u{0654}', '\u{0655}', '\u{0658}', '\u{06DC}', '\u{06E3}', '\u{06E7}', '\u{06E8}', '\u{08CA}',
+    '\u{08CB}', '\u{08CD}', '\u{08CE}', '\u{08CF}', '\u{08D3}', '\u{08F3}',
+];
+
+impl Complex for Arabic {
+    fn masked(&self) -> &'static [Feature] {
+        FORMS
+    }
+
+    fn substitution(&self) -> &'static [&'static [Feature]] {
+        &[
+            &[Feature::StretchingGlyphDecomposition],
+            &[
+                Feature::GlyphCompositionDecomposition,
+                Feature::LocalizedForms,
+            ],
+            &[Feature::IsolatedForms],
+            &[Feature::TerminalForms1],
+            &[Feature::TerminalForms2],
+            &[Feature::TerminalForms3],
+            &[Feature::MedialForms1],
+            &[Feature::MedialForms2],
+            &[Feature::InitialForms],
+            &[
+                Feature::RequiredLigatures,
+                Feature::RequiredContextualAlternates,
+                Feature::ContextualAlternates,
+            ],
+            &[
+                Feature::MarkPositioningViaSubstitution,
+                Feature::ContextualLigatures,
+                Feature::StandardLigatures,
+            ],
+        ]
+    }
+
+    fn prepare(&self, _: &Shaper, _: &Plan, characters: &[char], buffer: &mut Buffer) {
+        let mut characters = characters.to_vec();
+        reorder(&mut characters, buffer);
+        let mut forms = vec![NONE; characters.len()];
+        let (mut previous, mut state) = (None, 0);
+        for (i, &character) in characters.iter().enumerate() {
+            let joining = joining(character);
+            if joining == JoiningType::Transparent {
+                continue;
+            }
+            let (previous_form, form, next) = STATES[state][joining as usize];
+            if let Some(j) = previous.filter(|_| previous_form != NONE) {
+                forms[j] = previous_form;
+            }
+            forms[i] = form;
+            previous = Some(i);
+            state = next;
+        }
+        for i in 1..characters.len() {
+            if matches!(characters[i], '\u{180B}'..='\u{180D}' | '\u{180F}') {
+                forms[i] = forms[i - 1];
+            }
+        }
+        for ((glyph, &character), form) in buffer.glyphs.iter_mut().zip(&characters).zip(forms) {
+            if form != NONE {
+                glyph.set_mask(glyph.mask() | 1 << form);
+            }
+            if character == '\u{070F}' {
+                glyph.set_category(ABBREVIATION);
+            }
+        }
+    }
+
+    fn pause(&self, _: &Shaper, _: &Plan, stage: usize, buffer: &mut Buffer) {
+        if stage != STRETCHING_STAGE {
+            return;
+        }
+        let mut i = 0;
+        while i < buffer.len() {
+            if buffer.glyphs[i].category() != ABBREVIATION {
+                i += 1;
+                continue;
+            }
+            let cluster = buffer.glyphs[i].cluster;
+            let count = buffer.glyphs[i..]
+                .iter()
+                .take_while(|glyph| glyph.category() == ABBREVIATION && glyph.cluster == cluster)
+                .count();
+            for (j, glyph) in buffer.glyphs[i..(i + count)].iter_mut().enumerate() {
+                glyph.set_category(match (count, j % 2) {
+                    (1, _) => 0,
+                    (_, 0) => FIXED,
+                    _ => REPEATING,
+                });
+            }
+            i += count;
+        }
+    }
+
+    fn finish(&self, shaper: &Shaper, text: &str, buffer: &mut Buffer) {
+        let stretched = |category| category == FIXED || category == REPEATING;
+        if !buffer
+            .glyphs
+            .iter()
+            .any(|glyph| stretched(glyph.category()))
+        {
+            return;
+        }
+        let glyphs = std::mem::take(&mut buffer.glyphs);
+        let mut i = 0;
+        while i < glyphs.len() {
+            if !stretched(glyphs[i].category()) {
+                buffer.glyphs.push(glyphs[i].clone());
+                i += 1;
+                continue;
+            }
+            let (start, mut end) = (i, i);
+            while end < glyphs.len() && stretched(glyphs[end].category()) {
+                end += 1;
+            }
+            let width = |glyph: &Glyph| shaper.metrics.get(glyph.glyph_id).0 as i32;
+            let (mut fixed, mut repeating, mut count) = (0, 0, 0);
+            for glyph in &glyphs[start..end] {
+                match glyph.category() {
+                    FIXED => fixed += width(glyph),
+                    _ => {
+                        repeating += width(glyph);
+                        count += 1;
+                    }
+                }
+            }
+            let mut total = 0;
+            for glyph in glyphs[end..].iter().take_while(|glyph| {
+                let character = text[glyph.cluster..].chars().next();
+                !stretched(glyph.category()) && character.is_some_and(word)
+            }) {
+                total += glyph.x_advance;
+            }
+            let remaining = total - fixed;
+            let mut copies = 0;
+            if remaining > repeating && repeating > 0 {
+                copies = remaining / repeating - 1;
+            }
+            let mut overlap = 0;
+            let shortfall = remaining - repeating * (copies + 1);
+            if shortfall > 0 && count > 0 {
+                copies += 1;
+                let excess = (copies + 1) * repeating - remaining;
+                if excess > 0 {
+                    overlap = excess / (copies * count);
+                }
+            }
+            let mut x_offset = 0;
+            for glyph in &glyphs[start..end] {
+                let repeat = match glyph.category() {
+                    REPEATING => copies + 1,
+                    _ => 1,
+                };
+                for j in 0..repeat {
+                    x_offset -= width(glyph);
+                    if j > 0 {
+                        x_offset += overlap;
+                    }
+                    let mut glyph = glyph.clone();
+                    glyph.x_advance = 0;
+                    glyph.x_offset = x_offset;
+                    buffer.glyphs.push(glyph);
+                }
+            }
+            i = end;
+        }
+    }
+}
+
+fn combining_class(character: char) -> u8 {
+    find(COMBINING_CLASSES, character, |&(start, end, _)| {
+        (start, end)
+    })
+    .map(|&(_, _, class)| class)
+    .unwrap_or(0)
+}
+
+fn find<T, F>(ranges: &[T], character: char, range: F) -> Option<&T>
+where
+    F: Fn(&T) -> (u32, u32),
+{
+    let value = character as u32;
+    ranges
+        .binary_search_by(|item| {
+            let (start, end) = range(item);
+            if end < value {
+                std::cmp::Ordering::Less
+            } else if start > value {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|i| &ranges[i])
+}
+
+fn joining(character: char) -> JoiningType {
+    match find(JOINING, character, |&(start, end, _)| (start, end)) {
+        Some(&(_, _, joining)) => joining,
+        _ if transparent(character) => JoiningType::Transparent,
+        _ => JoiningType::NonJoining,
+    }
+}
+
+/// Put marks in canonical order with modifier combining marks first among marks of the same
+/// class, merging the clusters of the marks moved.
+fn reorder(characters: &mut [char], buffer: &mut Buffer) {
+    let mut i = 0;
+    while i < characters.len() {
+        if combining_class(characters[i]) == 0 {
+            i += 1;
+            continue;
+        }
+        let (start, mut end) = (i, i);
+        while end < characters.len() && combining_class(characters[end]) != 0 {
+            end += 1;
+        }
+        let mut order = (start..end).collect::<Vec<_>>();
+        order.sort_by_key(|&j| combining_class(characters[j]));
+        let mut first = 0;
+        for class in [220, 230] {
+            let mut j = first;
+            while j < order.len() && combining_class(characters[order[j]]) < class {
+                j += 1;
+            }
+            let mut k = j;
+            while k < order.len()
+                && combining_class(characters[order[k]]) == class
+                && MODIFIERS.contains(&characters[order[k]])
+            {
+                k += 1;
+            }
+            if j < k {
+                order[first..k].rotate_right(k - j);
+                first += k - j;
+            }
+        }
+        if order.iter().enumerate().any(|(j, &k)| start + j != k) {
+            let cluster = buffer.glyphs[start..end]
+                .iter()
+                .map(|glyph| glyph.cluster)
+                .min()
+                .unwrap_or(0);
+            let glyphs = order
+                .iter()
+                .map(|&j| buffer.glyphs[j].clone())
+                .collect::<Vec<_>>();
+            let values = order.iter().map(|&j| characters[j]).collect::<Vec<_>>();
+            for (j, mut glyph) in glyphs.into_iter().enumerate() {
+                glyph.cluster = cluster;
+                buffer.glyphs[start + j] = glyph;
+            }
+            characters[start..end].copy_from_slice(&values);
+        }
+        i = end;
+    }
+}
+
+fn transparent(character: char) -> bool {
+    find(TRANSPARENT, character, |&range| range).is_some()
+}
+
+fn word(character: char) -> bool {
+    character.is_alphanumeric() || transparent(character)
+}
+
+/// The ranges of characters with their joining types except for transparent.
+const JOINING: &[(u32, u32, JoiningType)] = &[
+    (0x0600, 0x0605, JoiningType::NonJoining),
+    (0x0608, 0x0608, JoiningType::NonJoining),
+    (0x060B, 0x060B, JoiningType::NonJoining),
+    (0x0620, 0x0620, JoiningType::DualJoining),
+    (0x0621, 0x0621, JoiningType::NonJoining),
+    (0x0622, 0x0625, JoiningType::RightJoining),
+    (0x0626, 0x0626, JoiningType::DualJoining),
+    (0x0627, 0x0627, JoiningType::RightJoining),
+    (0x0628, 0x0628, JoiningType::DualJoining),
+    (0x0629, 0x0629, JoiningType::RightJoining),
+    (0x062A, 0x062E, JoiningType::DualJoining),
+    (0x062F, 0x0632, JoiningType::RightJoining),
+    (0x0633, 0x063F, JoiningType::DualJoining),
+    (0x0640, 0x0640, JoiningType::DualJoining),
+    (0x0641, 0x0647, JoiningType::DualJoining),
+    (0x0648, 0x0648, JoiningType::RightJoining),
+    (0x0649, 0x064A, JoiningType::DualJoining),
+    (0x066E, 0x066F, JoiningType::DualJoining),
+    (0x0671, 0x0673, JoiningType::RightJoining),
+    (0x0674, 0x0674, JoiningType::NonJoining),
+    (0x0675, 0x0677, JoiningType::RightJoining),
+    (0x0678, 0x0687, JoiningType::DualJoining),
+    (0x0688, 0x0699, JoiningType::RightJoining),
+    (0x069A, 0x06BF, JoiningType::DualJoining),
+    (0x06C0, 0x06C0, JoiningType::RightJoining),
+    (0x06C1, 0x06C2, JoiningType::DualJoining),
+    (0x06C3, 0x06CB, JoiningType::RightJoining),
+    (0x06CC, 0x06CC, JoiningType::DualJoining),
+    (0x06CD, 0x06CD, JoiningType::RightJoining),
+    (0x06CE, 0x06CE, JoiningType::DualJoining),
+    (0x06CF, 0x06CF, JoiningType::RightJoining),
+    (0x06D0, 0x06D1, JoiningType::DualJoining),
+    (0x06D2, 0x06D3, JoiningType::RightJoining),
+    (0x06D5, 0x06D5, JoiningType::RightJoining),
+    (0x06DD, 0x06DD, JoiningType::NonJoining),
+    (0x06EE, 0x06EF, JoiningType::RightJoining),
+    (0x06FA, 0x06FC, JoiningType::DualJoining),
+    (0x06FF, 0x06FF, JoiningType::DualJoining),
+    (0x0710, 0x0710, JoiningType::Alaph),
+    (0x0712, 0x0714, JoiningType::DualJoining),
+    (0x0715, 0x0716, JoiningType::DalathRish),
+    (0x0717, 0x0719, JoiningType::RightJoining),
+    (0x071A, 0x071D, JoiningType::DualJoining),
+    (0x071E, 0x071E, JoiningType::RightJoining),
+    (0x071F, 0x0727, JoiningType::DualJoining),
+    (0x0728, 0x0728, JoiningType::RightJoining),
+    (0x0729, 0x0729, JoiningType::DualJoining),
+    (0x072A, 0x072A, JoiningType::DalathRish),
+    (0x072B, 0x072B, JoiningType::DualJoining),
+    (0x072C, 0x072C, JoiningType::RightJoining),
+    (0x072D, 0x072E, JoiningType::DualJoining),
+    (0x072F, 0x072F, JoiningType::DalathRish),
+    (0x074D, 0x074D, JoiningType::RightJoining),
+    (0x074E, 0x074F, JoiningType::DualJoining),
+    (0x0750, 0x0758, JoiningType::DualJoining),
+    (0x0759, 0x075B, JoiningType::RightJoining),
+    (0x075C, 0x076A, JoiningType::DualJoining),
+    (0x076B, 0x076C, JoiningType::RightJoining),
+    (0x076D, 0x0770, JoiningType::DualJoining),
+    (0x0771, 0x0771, JoiningType::RightJoining),
+    (0x0772, 0x0772, JoiningType::DualJoining),
+    (0x0773, 0x0774, JoiningType::RightJoining),
+    (0x0775, 0x0777, JoiningType::DualJoining),
+    (0x0778, 0x0779, JoiningType::RightJoining),
+    (0x077A, 0x077F, JoiningType::DualJoining),
+    (0x07CA, 0x07EA, JoiningType::DualJoining),
+    (0x07FA, 0x07FA, JoiningType::DualJoining),
+    (0x08A0, 0x08A9, JoiningType::DualJoining),
+    (0x08AA, 0x08AC, JoiningType::RightJoining),
+    (0x08AD, 0x08AD, JoiningType::NonJoining),
+    (0x08AE, 0x08AE, JoiningType::RightJoining),
+    (0x08AF, 0x08B0, JoiningType::DualJoining),
+    (0x08B1, 0x08B2, JoiningType::RightJoining),
+    (0x08B3, 0x08B4, JoiningType::DualJoining),
+    (0x08B6, 0x08B8, JoiningType::DualJoining),
+    (0x08B9, 0x08B9, JoiningType::RightJoining),
+    (0x08BA, 0x08C7, JoiningType::DualJoining),
+    (0x08E2, 0x08E2, JoiningType::NonJoining),
+    (0x1807, 0x1807, JoiningType::DualJoining),
+    (0x180A, 0x180A, JoiningType::DualJoining),
+    (0x1820, 0x1878, JoiningType::DualJoining),
+    (0x1880, 0x1884, JoiningType::NonJoining),
+    (0x1887, 0x18A8, JoiningType::DualJoining),
+    (0x18AA, 0x18AA, JoiningType::DualJoining),
+    (0x200C, 0x200C, JoiningType::NonJoining),
+    (0x200D, 0x200D, JoiningType::DualJoining),
+    (0x202F, 0x202F, JoiningType::NonJoining),
+    (0xA840, 0xA871, JoiningType::DualJoining),
+    (0xA872, 0xA872, JoiningType::LeftJoining),
+    (0xA873, 0xA873, JoiningType::NonJoining),
+];
+
+/// The ranges of transparent characters.
+const TRANSPARENT: &[(u32, u32)] = &[
+    (0x0300, 0x036F),
+    (0x0483, 0x0489),
+    (0x0591, 0x05BD),
+    (0x05BF, 0x05BF),
+    (0x05C1, 0x05C2),
+    (0x05C4, 0x05C5),
+    (0x05C7, 0x05C7),
+    (0x0600, 0x0605),
+    (0x0610, 0x061A),
+    (0x061C, 0x061C),
+    (0x064B, 0x065F),
+    (0x0670, 0x0670),
+    (0x06D6, 0x06DD),
+    (0x06DF, 0x06E4),
+    (0x06E7, 0x06E8),
+    (0x06EA, 0x06ED),
+    (0x070F, 0x070F),
+    (0x0711, 0x0711),
+    (0x0730, 0x074A),
+    (0x07A6, 0x07B0),
+    (0x07EB, 0x07F3),
+    (0x07FD, 0x07FD),
+    (0x0816, 0x0819),
+    (0x081B, 0x0823),
+    (0x0825, 0x0827),
+    (0x0829, 0x082D),
+    (0x0859, 0x085B),
+    (0x0890, 0x0891),
+    (0x0898, 0x089F),
+    (0x08CA, 0x08FF),
+    (0x180B, 0x180F),
+    (0x1885, 0x1886),
+    (0x18A9, 0x18A9),
+    (0x1AB0, 0x1ACE),
+    (0x1DC0, 0x1DFF),
+    (0x200B, 0x200B),
+    (0x200E, 0x200F),
+    (0x202A, 0x202E),
+    (0x2060, 0x2064),
+    (0x2066, 0x206F),
+    (0x20D0, 0x20F0),
+    (0xFE00, 0xFE0F),
+    (0xFE20, 0xFE2F),
+    (0xFEFF, 0xFEFF),
+];
+
+/// The ranges of marks with their canonical combining classes.
+const COMBINING_CLASSES: &[(u32, u32, u8)] = &[
+    (0x0610, 0x0617, 230),
+    (0x0618, 0x0618, 30),
+    (0x0619, 0x0619, 31),
+    (0x061A, 0x061A, 32),
+    (0x064B, 0x064B, 27),
+    (0x064C, 0x064C, 28),
+    (0x064D, 0x064D, 29),
+    (0x064E, 0x064E, 30),
+    (0x064F, 0x064F, 31),
+    (0x0650, 0x0650, 32),
+    (0x0651, 0x0651, 33),
+    (0x0652, 0x0652, 34),
+    (0x0653, 0x0654, 230),
+    (0x0655, 0x0656, 220),
+    (0x0657, 0x065B, 230),
+    (0x065C, 0x065C, 220),
+    (0x065D, 0x065E, 230),
+    (0x065F, 0x065F, 220),
+    (0x0670, 0x0670, 35),
+    (0x06D6, 0x06DC, 230),
+    (0x06DF, 0x06E2, 230),
+    (0x06E3, 0x06E3, 220),
+    (0x06E4, 0x06E4, 230),
+    (0x06E7, 0x06E8, 230),
+    (0x06EA, 0x06EA, 220),
+    (0x06EB, 0x06EC, 230),
+    (0x06ED, 0x06ED, 220),
+    (0x0711, 0x0711, 36),
+    (0x0730, 0x0730, 230),
+    (0x0731, 0x0731, 220),
+    (0x0732, 0x0733, 230),
+    (0x0734, 0x0734, 220),
+    (0x0735, 0x0736, 230),
+    (0x0737, 0x0739, 220),
+    (0x073A, 0x073A, 230),
+    (0x073B, 0x073C, 220),
+    (0x073D, 0x073D, 230),
+    (0x073E, 0x073E, 220),
+    (0x073F, 0x0741, 230),
+    (0x0742, 0x0742, 220),
+    (0x0743, 0x0743, 230),
+    (0x0744, 0x0744, 220),
+    (0x0745, 0x0745, 230),
+    (0x0746, 0x0746, 220),
+    (0x0747, 0x0747, 230),
+    (0x0748, 0x0748, 220),
+    (0x0749, 0x074A, 230),
+    (0x07EB, 0x07F1, 230),
+    (0x07F2, 0x07F2, 220),
+    (0x07F3, 0x07F3, 230),
+    (0x07FD, 0x07FD, 220),
+    (0x0816, 0x0819, 230),
+    (0x081B, 0x0823, 230),
+    (0x0825, 0x0827, 230),
+    (0x0829, 0x082D, 230),
+    (0x0859, 0x085B, 220),
+    (0x0898, 0x0898, 230),
+    (0x0899, 0x089B, 220),
+    (0x089C, 0x089F, 230),
+    (0x08CA, 0x08CE, 230),
+    (0x08CF, 0x08D3, 220),
+    (0x08D4, 0x08E1, 230),
+    (0x08E3, 0x08E3, 220),
+    (0x08E4, 0x08E5, 230),
+    (0x08E6, 0x08E6, 220),
+    (0x08E7, 0x08E8, 230),
+    (0x08E9, 0x08E9, 220),
+    (0x08EA, 0x08EC, 230),
+    (0x08ED, 0x08EF, 220),
+    (0x08F0, 0x08F0, 27),
+    (0x08F1, 0x08F1, 28),
+    (0x08F2, 0x08F2, 29),
+    (0x08F3, 0x08F5, 230),
+    (0x08F6, 0x08F6, 220),
+    (0x08F7, 0x08F8, 230),
+    (0x08F9, 0x08FA, 220),
+    (0x08FB, 0x08FF, 230),
+    (0x18A9, 0x18A9, 228),
+];
@@ -0,0 +1,314 @@
+//! The glyph buffer.
+
+use std::ops::Range;
+
+use truetype::GlyphID;
+
+use crate::Result;
+
+/// A glyph buffer.
+#[derive(Clone, Debug, Default)]
+pub struct Buffer {
+    /// The glyphs.
+    pub glyphs: Vec<Glyph>,
+
+    serial: usize,
+    ligature_id: u16,
+}
+
+/// A glyph in a buffer.
+#[derive(Clone, Debug)]
+pub struct Glyph {
+    /// The glyph identifier.
+    pub glyph_id: GlyphID,
+    /// The index of the cluster in the original text.
+    pub cluster: usize,
+    /// The identifier of the ligature the glyph belongs to or 0.
+    pub ligature_id: u16,
+    /// The index of the ligature component the glyph belongs to.
+    pub component: u16,
+    /// The horizontal advance in font units.
+    pub x_advance: i32,
+    /// The vertical advance in font units.
+    pub y_advance: i32,
+    /// The horizontal offset in font units.
+    pub x_offset: i32,
+    /// The vertical offset in font units.
+    pub y_offset: i32,
+
+    serial: usize,
+    attachment: Option<Attachment>,
+    mask: u32,
+    category: u8,
+    order: u8,
+    syllable: u8,
+    substituted: bool,
+}
+
+/// An attachment of a glyph to a preceding or following one.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Attachment {
+    /// The position of the glyph attached to.
+    pub position: usize,
+    /// The kind of the attachment.
+    pub cursive: bool,
+}
+
+impl Buffer {
+    /// Create a buffer with glyphs whose clusters are their indices.
+    pub fn new<T: IntoIterator<Item = GlyphID>>(glyph_ids: T) -> Self {
+        let mut buffer = Self::default();
+        for (cluster, glyph_id) in glyph_ids.into_iter().enumerate() {
+            buffer.push(glyph_id, cluster);
+        }
+        buffer
+    }
+
+    /// Append a glyph.
+    pub fn push(&mut self, glyph_id: GlyphID, cluster: usize) {
+        let glyph = self.glyph(glyph_id, cluster);
+        self.glyphs.push(glyph);
+    }
+
+    /// Return the glyph identifiers.
+    #[inline]
+    pub fn glyph_ids(&self) -> Vec<GlyphID> {
+        self.glyphs.iter().map(|glyph| glyph.glyph_id).collect()
+    }
+
+    /// Return the glyph count.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Check if the buffer is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Replace a glyph with a sequence of glyphs sharing its cluster.
+    ///
+    /// The glyphs following the first one are numbered as components in the order of the
+    /// sequence.
+    pub(crate) fn expand(&mut self, position: usize, glyph_ids: &[GlyphID]) {
+        let glyph = self.glyphs[position].clone();
+        let mut glyphs = Vec::with_capacity(glyph_ids.len());
+        for (i, &glyph_id) in glyph_ids.iter().enumerate() {
+            glyphs.push(match i {
+                0 => Glyph {
+                    glyph_id,
+                    ..glyph.clone()
+                },
+                _ => Glyph {
+                    component: i as u16,
+                    mask: glyph.mask,
+                    category: glyph.category,
+                    order: glyph.order,
+                    syllable: glyph.syllable,
+                    ..self.glyph(glyph_id, glyph.cluster)
+                },
+            });
+        }
+        self.glyphs.splice(position..(position + 1), glyphs);
+    }
+
+    /// Replace a run of glyphs with a glyph taking the smallest of their clusters.
+    pub(crate) fn compose(&mut self, range: Range<usize>, glyph_id: GlyphID) {
+        let cluster = self.glyphs[range.clone()]
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .min()
+            .unwrap_or(0);
+        let glyph = &mut self.glyphs[range.start];
+        glyph.glyph_id = glyph_id;
+        glyph.cluster = cluster;
+        self.glyphs.drain((range.start + 1)..range.end);
+    }
+
+    /// Replace glyphs with a ligature placed at the first position.
+    ///
+    /// The positions are in ascending order, and the glyphs in between that do not take part in
+    /// the ligature are kept and assigned to the preceding components.
+    pub(crate) fn ligate(&mut self, positions: &[usize], glyph_id: GlyphID) {
+        let (first, last) = (positions[0], positions[positions.len() - 1]);
+        self.ligature_id = self.ligature_id.wrapping_add(1).max(1);
+        let ligature_id = self.ligature_id;
+        let cluster = self.glyphs[first..=last]
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .min()
+            .unwrap_or(0);
+        for (component, pair) in positions.windows(2).enumerate() {
+            for glyph in &mut self.glyphs[(pair[0] + 1)..pair[1]] {
+                glyph.ligature_id = ligature_id;
+                glyph.component = component as u16;
+            }
+        }
+        let glyph = &mut self.glyphs[first];
+        glyph.glyph_id = glyph_id;
+        glyph.cluster = cluster;
+        glyph.ligature_id = ligature_id;
+        glyph.component = 0;
+        for &position in positions[1..].iter().rev() {
+            self.glyphs.remove(position);
+        }
+    }
+
+    /// Insert a glyph.
+    pub(crate) fn insert(&mut self, position: usize, glyph_id: GlyphID, cluster: usize) {
+        let glyph = self.glyph(glyph_id, cluster);
+        self.glyphs.insert(position, glyph);
+    }
+
+    /// Apply a function to each run of glyphs belonging to the same syllable as if the run were a
+    /// buffer of its own.
+    pub(crate) fn each_syllable<F>(&mut self, mut apply: F) -> Result<()>
+    where
+        F: FnMut(&mut Buffer) -> Result<()>,
+    {
+        let mut glyphs = std::mem::take(&mut self.glyphs).into_iter().peekable();
+        while let Some(glyph) = glyphs.next() {
+            let syllable = glyph.syllable;
+            let mut buffer = Buffer {
+                glyphs: vec![glyph],
+                serial: self.serial,
+                ligature_id: self.ligature_id,
+            };
+            while let Some(glyph) = glyphs.next_if(|glyph| glyph.syllable == syllable) {
+                buffer.glyphs.push(glyph);
+            }
+            let result = apply(&mut buffer);
+            self.serial = buffer.serial;
+            self.ligature_id = buffer.ligature_id;
+            self.glyphs.extend(buffer.glyphs);
+            if result.is_err() {
+                self.glyphs.extend(glyphs);
+                return result;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the ranges of the runs of glyphs belonging to the same syllable.
+    pub(crate) fn syllables(&self) -> Vec<Range<usize>> {
+        let mut ranges = vec![];
+        let mut start = 0;
+        for chunk in self
+            .glyphs
+            .chunk_by(|one, other| one.syllable == other.syllable)
+        {
+            ranges.push(start..(start + chunk.len()));
+            start += chunk.len();
+        }
+        ranges
+    }
+
+    /// Return the position of the glyph with a serial number.
+    #[inline]
+    pub(crate) fn find(&self, serial: usize) -> Option<usize> {
+        self.glyphs.iter().position(|glyph| glyph.serial == serial)
+    }
+
+    fn glyph(&mut self, glyph_id: GlyphID, cluster: usize) -> Glyph {
+        self.serial += 1;
+        Glyph {
+            glyph_id,
+            cluster,
+            ligature_id: 0,
+            component: 0,
+            x_advance: 0,
+            y_advance: 0,
+            x_offset: 0,
+            y_offset: 0,
+            serial: self.serial,
+            attachment: None,
+            mask: 0,
+            category: 0,
+            order: 0,
+            syllable: 0,
+            substituted: false,
+        }
+    }
+}
+
+impl Glyph {
+    /// Return the serial number, which identifies the glyph within a buffer.
+    #[inline]
+    pub(crate) fn serial(&self) -> usize {
+        self.serial
+    }
+
+    /// Return the attachment.
+    #[inline]
+    pub(crate) fn attachment(&self) -> Option<Attachment> {
+        self.attachment
+    }
+
+    /// Set the attachment.
+    #[inline]
+    pub(crate) fn attach(&mut self, attachment: Option<Attachment>) {
+        self.attachment = attachment;
+    }
+
+    /// Return the mask of the features enabled for the glyph.
+    #[inline]
+    pub(crate) fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Set the mask of the features enabled for the glyph.
+    #[inline]
+    pub(crate) fn set_mask(&mut self, mask: u32) {
+        self.mask = mask;
+    }
+
+    /// Return the category assigned by a script-specific shaper.
+    #[inline]
+    pub(crate) fn category(&self) -> u8 {
+        self.category
+    }
+
+    /// Set the category assigned by a script-specific shaper.
+    #[inline]
+    pub(crate) fn set_category(&mut self, category: u8) {
+        self.category = category;
+    }
+
+    /// Return the reordering class assigned by a script-specific shaper.
+    #[inline]
+    pub(crate) fn order(&self) -> u8 {
+        self.order
+    }
+
+    /// Set the reordering class assigned by a script-specific shaper.
+    #[inline]
+    pub(crate) fn set_order(&mut self, order: u8) {
+        self.order = order;
+    }
+
+    /// Return the syllable assigned by a script-specific shaper.
+    #[inline]
+    pub(crate) fn syllable(&self) -> u8 {
+        self.syllable
+    }
+
+    /// Set the syllable assigned by a script-specific shaper.
+    #[inline]
+    pub(crate) fn set_syllable(&mut self, syllable: u8) {
+        self.syllable = syllable;
+    }
+
+    /// Check if the glyph has been substituted since the flag was last cleared.
+    #[inline]
+    pub(crate) fn substituted(&self) -> bool {
+        self.substituted
+    }
+
+    /// Set the flag indicating that the glyph has been substituted.
+    #[inline]
+    pub(crate) fn set_substituted(&mut self, substituted: bool) {
+        self.substituted = substituted;
+    }
+}
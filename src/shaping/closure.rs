@@ -0,0 +1,352 @@
+//! The closure of glyphs under glyph substitutions.
+
+use std::collections::BTreeSet;
+
+use truetype::GlyphID;
+
+use crate::layout::context::{Action, ChainedContext, Context};
+use crate::layout::{Class, Coverage};
+use crate::shaping::matching::MAX_DEPTH;
+use crate::shaping::Plan;
+use crate::tables::glyph_substitution::{SingleSubstitution, Type};
+use crate::tables::GlyphSubstitution;
+
+/// Compute the closure of glyphs under the glyph-substitution lookups of a plan.
+///
+/// The result contains the given glyphs and every glyph the lookups can produce from them,
+/// including via contextual rules whose contexts can be matched by the glyphs and via all
+/// alternates of alternate substitutions. Lookup flags are disregarded, which can only make the
+/// closure larger.
+pub fn closure<T>(table: &GlyphSubstitution, plan: &Plan, glyph_ids: T) -> BTreeSet<GlyphID>
+where
+    T: IntoIterator<Item = GlyphID>,
+{
+    let mut glyph_ids = glyph_ids.into_iter().collect::<BTreeSet<_>>();
+    loop {
+        let mut added = BTreeSet::new();
+        for lookup_index in plan.substitution_indices() {
+            close(table, lookup_index, &glyph_ids, &glyph_ids, &mut added, 0);
+        }
+        let count = glyph_ids.len();
+        glyph_ids.extend(added);
+        if glyph_ids.len() == count {
+            return glyph_ids;
+        }
+    }
+}
+
+/// Return the rules of a chained contextual substitution whose sequences can be formed from
+/// the glyphs, together with the glyphs possible at each input position.
+fn chained_contexts<'l>(
+    table: &'l ChainedContext,
+    input: &BTreeSet<GlyphID>,
+    glyph_ids: &BTreeSet<GlyphID>,
+) -> Vec<(Vec<BTreeSet<GlyphID>>, &'l [Action])> {
+    let mut values = vec![];
+    match table {
+        ChainedContext::Format1(table) => {
+            for (index, glyph_id) in covered(&table.coverage, input) {
+                let records = match table.records.get(index as usize) {
+                    Some(records) => &records.records,
+                    _ => continue,
+                };
+                for record in records {
+                    let mut context = record
+                        .backward_glyph_ids
+                        .iter()
+                        .chain(&record.forward_glyph_ids);
+                    if !context.all(|value| glyph_ids.contains(value)) {
+                        continue;
+                    }
+                    if let Some(positions) = sequence(glyph_id, &record.glyph_ids, glyph_ids) {
+                        values.push((positions, &record.actions[..]));
+                    }
+                }
+            }
+        }
+        ChainedContext::Format2(table) => {
+            for (class, records) in table.records.iter().enumerate() {
+                let records = match records {
+                    Some(records) => &records.records,
+                    _ => continue,
+                };
+                let first = classified(&table.class, class as u16, &table.coverage, input);
+                if first.is_empty() {
+                    continue;
+                }
+                for record in records {
+                    let found = |class: &Class, indices: &[u16]| {
+                        indices.iter().all(|&index| {
+                            glyph_ids
+                                .iter()
+                                .any(|&glyph_id| class.class_of(glyph_id) == index)
+                        })
+                    };
+                    if !found(&table.backward_class, &record.backward_indices)
+                        || !found(&table.forward_class, &record.forward_indices)
+                    {
+                        continue;
+                    }
+                    if let Some(positions) =
+                        classes(&first, &table.class, &record.indices, glyph_ids)
+                    {
+                        values.push((positions, &record.actions[..]));
+                    }
+                }
+            }
+        }
+        ChainedContext::Format3(table) => {
+            let mut context = table
+                .backward_coverages
+                .iter()
+                .chain(&table.forward_coverages);
+            if !context.all(|coverage| intersects(coverage, glyph_ids)) {
+                return values;
+            }
+            if let Some(positions) = coverages(&table.coverages, input, glyph_ids) {
+                values.push((positions, &table.actions[..]));
+            }
+        }
+    }
+    values
+}
+
+/// Return the glyphs possible at the input positions of a rule given by classes, if any.
+fn classes(
+    first: &BTreeSet<GlyphID>,
+    class: &Class,
+    indices: &[u16],
+    glyph_ids: &BTreeSet<GlyphID>,
+) -> Option<Vec<BTreeSet<GlyphID>>> {
+    let mut positions = vec![first.clone()];
+    for &index in indices {
+        let values = glyph_ids
+            .iter()
+            .copied()
+            .filter(|&glyph_id| class.class_of(glyph_id) == index)
+            .collect::<BTreeSet<_>>();
+        if values.is_empty() {
+            return None;
+        }
+        positions.push(values);
+    }
+    Some(positions)
+}
+
+/// Return the input glyphs covered by a coverage of a given class.
+fn classified(
+    class: &Class,
+    index: u16,
+    coverage: &Coverage,
+    input: &BTreeSet<GlyphID>,
+) -> BTreeSet<GlyphID> {
+    covered(coverage, input)
+        .map(|(_, glyph_id)| glyph_id)
+        .filter(|&glyph_id| class.class_of(glyph_id) == index)
+        .collect()
+}
+
+/// Collect the glyphs a lookup can produce when applied to the input glyphs, with the context
+/// drawn from all glyphs.
+fn close(
+    table: &GlyphSubstitution,
+    lookup_index: u16,
+    input: &BTreeSet<GlyphID>,
+    glyph_ids: &BTreeSet<GlyphID>,
+    added: &mut BTreeSet<GlyphID>,
+    depth: usize,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let record = match table.lookups.records.get(lookup_index as usize) {
+        Some(record) => record,
+        _ => return,
+    };
+    for subtable in &record.tables {
+        let contexts = match subtable {
+            Type::SingleSubstitution(SingleSubstitution::Format1(subtable)) => {
+                for (_, glyph_id) in covered(&subtable.coverage, input) {
+                    added.insert(glyph_id.wrapping_add_signed(subtable.delta_glyph_id));
+                }
+                continue;
+            }
+            Type::SingleSubstitution(SingleSubstitution::Format2(subtable)) => {
+                for (index, _) in covered(&subtable.coverage, input) {
+                    added.extend(subtable.glyph_ids.get(index as usize));
+                }
+                continue;
+            }
+            Type::MultipleSubstitution(subtable) => {
+                for (index, _) in covered(&subtable.coverage, input) {
+                    if let Some(record) = subtable.records.get(index as usize) {
+                        added.extend(&record.glyph_ids);
+                    }
+                }
+                continue;
+            }
+            Type::AlternateSubstitution(subtable) => {
+                for (index, _) in covered(&subtable.coverage, input) {
+                    if let Some(record) = subtable.records.get(index as usize) {
+                        added.extend(&record.glyph_ids);
+                    }
+                }
+                continue;
+            }
+            Type::LigatureSubstitution(subtable) => {
+                for (index, _) in covered(&subtable.coverage, input) {
+                    let records = match subtable.records.get(index as usize) {
+                        Some(records) => &records.records,
+                        _ => continue,
+                    };
+                    for record in records {
+                        if record
+                            .glyph_ids
+                            .iter()
+                            .all(|value| glyph_ids.contains(value))
+                        {
+                            added.insert(record.glyph_id);
+                        }
+                    }
+                }
+                continue;
+            }
+            Type::ContextualSubstitution(subtable) => contexts(subtable, input, glyph_ids),
+            Type::ChainedContextualSubstitution(subtable) => {
+                chained_contexts(subtable, input, glyph_ids)
+            }
+            Type::ReverseChainedContextualSubstibution(subtable) => {
+                let mut context = subtable
+                    .backward_coverages
+                    .iter()
+                    .chain(&subtable.forward_coverages);
+                if context.all(|coverage| intersects(coverage, glyph_ids)) {
+                    for (index, _) in covered(&subtable.coverage, input) {
+                        added.extend(subtable.glyph_ids.get(index as usize));
+                    }
+                }
+                continue;
+            }
+            Type::ExtensionSubstitution(_) => continue,
+        };
+        for (positions, actions) in contexts {
+            for action in actions {
+                if let Some(input) = positions.get(action.position_index as usize) {
+                    close(
+                        table,
+                        action.lookup_index,
+                        input,
+                        glyph_ids,
+                        added,
+                        depth + 1,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Return the rules of a contextual substitution whose input sequences can be formed from the
+/// glyphs, together with the glyphs possible at each input position.
+fn contexts<'l>(
+    table: &'l Context,
+    input: &BTreeSet<GlyphID>,
+    glyph_ids: &BTreeSet<GlyphID>,
+) -> Vec<(Vec<BTreeSet<GlyphID>>, &'l [Action])> {
+    let mut values = vec![];
+    match table {
+        Context::Format1(table) => {
+            for (index, glyph_id) in covered(&table.coverage, input) {
+                let records = match table.records.get(index as usize) {
+                    Some(records) => &records.records,
+                    _ => continue,
+                };
+                for record in records {
+                    if let Some(positions) = sequence(glyph_id, &record.glyph_ids, glyph_ids) {
+                        values.push((positions, &record.actions[..]));
+                    }
+                }
+            }
+        }
+        Context::Format2(table) => {
+            for (class, records) in table.records.iter().enumerate() {
+                let records = match records {
+                    Some(records) => &records.records,
+                    _ => continue,
+                };
+                let first = classified(&table.class, class as u16, &table.coverage, input);
+                if first.is_empty() {
+                    continue;
+                }
+                for record in records {
+                    if let Some(positions) =
+                        classes(&first, &table.class, &record.indices, glyph_ids)
+                    {
+                        values.push((positions, &record.actions[..]));
+                    }
+                }
+            }
+        }
+        Context::Format3(table) => {
+            if let Some(positions) = coverages(&table.coverages, input, glyph_ids) {
+                values.push((positions, &table.actions[..]));
+            }
+        }
+    }
+    values
+}
+
+/// Return the glyphs possible at the input positions of a rule given by coverages, if any.
+fn coverages(
+    coverages: &[Coverage],
+    input: &BTreeSet<GlyphID>,
+    glyph_ids: &BTreeSet<GlyphID>,
+) -> Option<Vec<BTreeSet<GlyphID>>> {
+    let mut positions = Vec::with_capacity(coverages.len());
+    for (i, coverage) in coverages.iter().enumerate() {
+        let values = match i {
+            0 => input,
+            _ => glyph_ids,
+        };
+        let values = covered(coverage, values)
+            .map(|(_, glyph_id)| glyph_id)
+            .collect::<BTreeSet<_>>();
+        if values.is_empty() {
+            return None;
+        }
+        positions.push(values);
+    }
+    Some(positions)
+}
+
+/// Iterate over the glyphs of a set that are covered together with their coverage indices.
+fn covered<'l>(
+    coverage: &'l Coverage,
+    glyph_ids: &'l BTreeSet<GlyphID>,
+) -> impl Iterator<Item = (u16, GlyphID)> + 'l {
+    glyph_ids
+        .iter()
+        .filter_map(|&glyph_id| coverage.index_of(glyph_id).map(|index| (index, glyph_id)))
+}
+
+#[inline]
+fn intersects(coverage: &Coverage, glyph_ids: &BTreeSet<GlyphID>) -> bool {
+    covered(coverage, glyph_ids).next().is_some()
+}
+
+/// Return the glyphs possible at the input positions of a rule given by glyphs, if any.
+fn sequence(
+    first: GlyphID,
+    rest: &[GlyphID],
+    glyph_ids: &BTreeSet<GlyphID>,
+) -> Option<Vec<BTreeSet<GlyphID>>> {
+    if !rest.iter().all(|value| glyph_ids.contains(value)) {
+        return None;
+    }
+    let positions = std::iter::once(first).chain(rest.iter().copied());
+    Some(
+        positions
+            .map(|glyph_id| BTreeSet::from([glyph_id]))
+            .collect(),
+    )
+}
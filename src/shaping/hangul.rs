@@ -0,0 +1,159 @@
+//! The shaping of Hangul.
+
+use crate::layout::Feature;
+use crate::shaping::shaper::{Complex, Shaper};
+use crate::shaping::{Buffer, Plan};
+
+/// A shaper for Hangul.
+///
+/// Sequences of conjoining jamo are composed into precomposed syllables if supported by the
+/// font, and precomposed syllables are decomposed into conjoining jamo otherwise. The jamo that
+/// remain are shaped by the jamo features.
+pub(crate) struct Hangul;
+
+/// The jamo features, whose positions are the mask bits of the features.
+const MASKED: &[Feature] = &[
+    Feature::LeadingJamoForms,
+    Feature::VowelJamoForms,
+    Feature::TrailingJamoForms,
+];
+
+const LJMO: u32 = 1 << 0;
+const VJMO: u32 = 1 << 1;
+const TJMO: u32 = 1 << 2;
+
+const S_BASE: u32 = 0xAC00;
+const L_BASE: u32 = 0x1100;
+const V_BASE: u32 = 0x1161;
+const T_BASE: u32 = 0x11A7;
+const L_COUNT: u32 = 19;
+const V_COUNT: u32 = 21;
+const T_COUNT: u32 = 28;
+const N_COUNT: u32 = V_COUNT * T_COUNT;
+const S_COUNT: u32 = L_COUNT * N_COUNT;
+
+impl Complex for Hangul {
+    fn masked(&self) -> &'static [Feature] {
+        MASKED
+    }
+
+    fn substitution(&self) -> &'static [&'static [Feature]] {
+        &[&[
+            Feature::LeadingJamoForms,
+            Feature::VowelJamoForms,
+            Feature::TrailingJamoForms,
+            Feature::GlyphCompositionDecomposition,
+            Feature::LocalizedForms,
+            Feature::RequiredLigatures,
+            Feature::ContextualAlternates,
+            Feature::ContextualLigatures,
+            Feature::StandardLigatures,
+            Feature::RequiredContextualAlternates,
+        ]]
+    }
+
+    fn prepare(&self, shaper: &Shaper, _: &Plan, characters: &[char], buffer: &mut Buffer) {
+        let glyph_id = |value: u32| shaper.mapping.get(&value).copied();
+        let mut characters = characters
+            .iter()
+            .map(|&value| value as u32)
+            .collect::<Vec<_>>();
+        let mut i = 0;
+        while i < characters.len() {
+            let value = characters[i];
+            let next = characters.get(i + 1).copied().unwrap_or(0);
+            if leading(value) && vowel(next) {
+                let last = characters
+                    .get(i + 2)
+                    .copied()
+                    .filter(|&value| trailing(value));
+                let count = if last.is_some() { 3 } else { 2 };
+                if (L_BASE..(L_BASE + L_COUNT)).contains(&value)
+                    && (V_BASE..(V_BASE + V_COUNT)).contains(&next)
+                    && last.is_none_or(combining)
+                {
+                    let syllable = S_BASE
+                        + (value - L_BASE) * N_COUNT
+                        + (next - V_BASE) * T_COUNT
+                        + last.map_or(0, |value| value - T_BASE);
+                    if let Some(glyph_id) = glyph_id(syllable) {
+                        buffer.compose(i..(i + count), glyph_id);
+                        characters.splice(i..(i + count), [syllable]);
+                        i += 1;
+                        continue;
+                    }
+                }
+                mask(buffer, i, count);
+                i += count;
+                continue;
+            }
+            if !(S_BASE..(S_BASE + S_COUNT)).contains(&value) {
+                i += 1;
+                continue;
+            }
+            let index = value - S_BASE;
+            let (l, v, t) = (index / N_COUNT, index % N_COUNT / T_COUNT, index % T_COUNT);
+            let followed = t == 0 && trailing(next);
+            if t == 0 && combining(next) {
+                if let Some(glyph_id) = glyph_id(value + next - T_BASE) {
+                    buffer.compose(i..(i + 2), glyph_id);
+                    characters.splice(i..(i + 2), [value + next - T_BASE]);
+                    i += 1;
+                    continue;
+                }
+            }
+            if glyph_id(value).is_some() && !followed {
+                i += 1;
+                continue;
+            }
+            let mut parts = vec![L_BASE + l, V_BASE + v];
+            if t != 0 {
+                parts.push(T_BASE + t);
+            }
+            let glyph_ids = parts
+                .iter()
+                .map(|&part| glyph_id(part))
+                .collect::<Option<Vec<_>>>();
+            match glyph_ids {
+                Some(glyph_ids) => {
+                    buffer.expand(i, &glyph_ids);
+                    characters.splice(i..(i + 1), parts.iter().copied());
+                    let count = parts.len() + usize::from(followed);
+                    mask(buffer, i, count);
+                    i += count;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+}
+
+#[inline]
+fn combining(value: u32) -> bool {
+    (0x11A8..=0x11C2).contains(&value)
+}
+
+#[inline]
+fn leading(value: u32) -> bool {
+    matches!(value, 0x1100..=0x115F | 0xA960..=0xA97C)
+}
+
+/// Enable the jamo features for a leading, a vowel, and optionally a trailing jamo.
+fn mask(buffer: &mut Buffer, position: usize, count: usize) {
+    for (glyph, mask) in buffer.glyphs[position..(position + count)]
+        .iter_mut()
+        .zip([LJMO, VJMO, TJMO])
+    {
+        glyph.set_mask(glyph.mask() | mask);
+    }
+}
+
+#[inline]
+fn trailing(value: u32) -> bool {
+    matches!(value, 0x11A8..=0x11FF | 0xD7CB..=0xD7FB)
+}
+
+#[inline]
+fn vowel(value: u32) -> bool {
+    matches!(value, 0x1160..=0x11A7 | 0xD7B0..=0xD7C6)
+}
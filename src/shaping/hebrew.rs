@@ -0,0 +1,79 @@
+//! The shaping of Hebrew.
+
+use crate::layout::Feature;
+use crate::shaping::shaper::{Complex, Shaper};
+use crate::shaping::{Buffer, Plan};
+
+/// A shaper for Hebrew.
+///
+/// For fonts without mark positioning, letters followed by points are composed into the
+/// presentation forms, if present, which are excluded from canonical composition.
+pub(crate) struct Hebrew;
+
+/// The presentation forms of letters with a dagesh, starting with alef.
+const DAGESH: [u32; 27] = [
+    0xFB30, 0xFB31, 0xFB32, 0xFB33, 0xFB34, 0xFB35, 0xFB36, 0x0000, 0xFB38, 0xFB39, 0xFB3A, 0xFB3B,
+    0xFB3C, 0x0000, 0xFB3E, 0x0000, 0xFB40, 0xFB41, 0x0000, 0xFB43, 0xFB44, 0x0000, 0xFB46, 0xFB47,
+    0xFB48, 0xFB49, 0xFB4A,
+];
+
+impl Complex for Hebrew {
+    fn prepare(&self, shaper: &Shaper, plan: &Plan, characters: &[char], buffer: &mut Buffer) {
+        let positioned = plan
+            .positioning
+            .iter()
+            .flat_map(|stage| &stage.lookups)
+            .any(|lookup| {
+                lookup
+                    .settings
+                    .iter()
+                    .any(|setting| setting.feature == Feature::MarkPositioning)
+            });
+        if positioned {
+            return;
+        }
+        let mut characters = characters
+            .iter()
+            .map(|&value| value as u32)
+            .collect::<Vec<_>>();
+        let mut i = 0;
+        while i + 1 < characters.len() {
+            let glyph_id = compose(characters[i], characters[i + 1]).and_then(|value| {
+                shaper
+                    .mapping
+                    .get(&value)
+                    .map(|&glyph_id| (value, glyph_id))
+            });
+            match glyph_id {
+                Some((value, glyph_id)) => {
+                    buffer.compose(i..(i + 2), glyph_id);
+                    characters.splice(i..(i + 2), [value]);
+                }
+                _ => i += 1,
+            }
+        }
+    }
+}
+
+/// Return the presentation form of a letter followed by a point.
+fn compose(letter: u32, point: u32) -> Option<u32> {
+    let value = match (point, letter) {
+        (0x05B4, 0x05D9) => 0xFB1D,
+        (0x05B7, 0x05F2) => 0xFB1F,
+        (0x05B7, 0x05D0) => 0xFB2E,
+        (0x05B8, 0x05D0) => 0xFB2F,
+        (0x05B9, 0x05D5) => 0xFB4B,
+        (0x05BC, 0x05D0..=0x05EA) => DAGESH[(letter - 0x05D0) as usize],
+        (0x05BC, 0xFB2A) => 0xFB2C,
+        (0x05BC, 0xFB2B) => 0xFB2D,
+        (0x05BF, 0x05D1) => 0xFB4C,
+        (0x05BF, 0x05DB) => 0xFB4D,
+        (0x05BF, 0x05E4) => 0xFB4E,
+        (0x05C1, 0x05E9) => 0xFB2A,
+        (0x05C1, 0xFB49) => 0xFB2C,
+        (0x05C2, 0x05E9) => 0xFB2B,
+        (0x05C2, 0xFB49) => 0xFB2D,
+        _ => return None,
+    };
+    (value != 0).then_some(value)
+}
@@ -0,0 +1,1145 @@
+//! The shaping of Indic scripts.
+
+use std::collections::HashMap;
+
+use crate::layout::{Feature, Script};
+use crate::shaping::shaper::{Complex, Shaper};
+use crate::shaping::{Buffer, Glyph, Plan};
+
+/// A shaper for Bengali, Devanagari, Gujarati, Gurmukhi, Kannada, Malayalam, Odia, Tamil, and
+/// Telugu.
+pub(crate) struct Indic {
+    config: &'static Config,
+    old: bool,
+}
+
+/// A script-specific configuration.
+struct Config {
+    /// The script tags of the old and new specifications.
+    scripts: (Script, Script),
+    /// The virama.
+    virama: char,
+    /// The reordering class of the final position of the reph.
+    reph: u8,
+    /// The way the reph is encoded.
+    mode: Mode,
+    /// The flag indicating that below-base forms are also applied before the base consonant.
+    below: bool,
+}
+
+/// A way of encoding the reph.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Mode {
+    /// A ra followed by a virama.
+    Implicit,
+    /// A ra followed by a virama and a zero-width joiner.
+    Explicit,
+    /// A dedicated character.
+    Logical,
+}
+
+const BENGALI: Config = Config {
+    scripts: (Script::Bengali, Script::BengaliV2),
+    virama: '\u{09CD}',
+    reph: AFTER_SUB,
+    mode: Mode::Implicit,
+    below: true,
+};
+
+const DEVANAGARI: Config = Config {
+    scripts: (Script::Devanagari, Script::DevanagariV2),
+    virama: '\u{094D}',
+    reph: BEFORE_POST,
+    mode: Mode::Implicit,
+    below: true,
+};
+
+const GUJARATI: Config = Config {
+    scripts: (Script::Gujarati, Script::GujaratiV2),
+    virama: '\u{0ACD}',
+    reph: BEFORE_POST,
+    mode: Mode::Implicit,
+    below: true,
+};
+
+const GURMUKHI: Config = Config {
+    scripts: (Script::Gurmukhi, Script::GurmukhiV2),
+    virama: '\u{0A4D}',
+    reph: BEFORE_SUB,
+    mode: Mode::Implicit,
+    below: true,
+};
+
+const KANNADA: Config = Config {
+    scripts: (Script::Kannada, Script::KannadaV2),
+    virama: '\u{0CCD}',
+    reph: AFTER_POST,
+    mode: Mode::Implicit,
+    below: false,
+};
+
+const MALAYALAM: Config = Config {
+    scripts: (Script::Malayalam, Script::MalayalamV2),
+    virama: '\u{0D4D}',
+    reph: AFTER_MAIN,
+    mode: Mode::Logical,
+    below: true,
+};
+
+const ODIA: Config = Config {
+    scripts: (Script::Odia, Script::OdiaV2),
+    virama: '\u{0B4D}',
+    reph: AFTER_MAIN,
+    mode: Mode::Implicit,
+    below: true,
+};
+
+const TAMIL: Config = Config {
+    scripts: (Script::Tamil, Script::TamilV2),
+    virama: '\u{0BCD}',
+    reph: AFTER_POST,
+    mode: Mode::Implicit,
+    below: true,
+};
+
+const TELUGU: Config = Config {
+    scripts: (Script::Telugu, Script::TeluguV2),
+    virama: '\u{0C4D}',
+    reph: AFTER_POST,
+    mode: Mode::Explicit,
+    below: false,
+};
+
+/// The features enabled per glyph, whose positions are the mask bits of the features.
+const MASKED: &[Feature] = &[
+    Feature::RephForms,
+    Feature::PreBaseForms,
+    Feature::BelowBaseForms,
+    Feature::AboveBaseForms,
+    Feature::HalfForms,
+    Feature::PostBaseForms,
+    Feature::InitialForms,
+];
+
+const RPHF: u32 = 1 << 0;
+const PREF: u32 = 1 << 1;
+const BLWF: u32 = 1 << 2;
+const ABVF: u32 = 1 << 3;
+const HALF: u32 = 1 << 4;
+const PSTF: u32 = 1 << 5;
+const INIT: u32 = 1 << 6;
+
+/// The features applied within syllables.
+const SYLLABIC: &[Feature] = &[
+    Feature::LocalizedForms,
+    Feature::GlyphCompositionDecomposition,
+    Feature::NuktaForms,
+    Feature::Akhand,
+    Feature::RephForms,
+    Feature::RakarForms,
+    Feature::PreBaseForms,
+    Feature::BelowBaseForms,
+    Feature::AboveBaseForms,
+    Feature::HalfForms,
+    Feature::PostBaseForms,
+    Feature::VattuVariants,
+    Feature::ConjunctForms,
+    Feature::InitialForms,
+    Feature::PreBaseSubstitutions,
+    Feature::AboveBaseSubstitutions,
+    Feature::BelowBaseSubstitutions,
+    Feature::PostBaseSubstitutions,
+    Feature::HalantForms,
+];
+
+/// The stage after which the initial reordering takes place.
+const INITIAL_STAGE: usize = 1;
+/// The stage after which the final reordering takes place.
+const FINAL_STAGE: usize = 12;
+
+const X: u8 = 0;
+const C: u8 = 1;
+const V: u8 = 2;
+const N: u8 = 3;
+const H: u8 = 4;
+const ZWNJ: u8 = 5;
+const ZWJ: u8 = 6;
+const M: u8 = 7;
+const SM: u8 = 8;
+const A: u8 = 9;
+const PLACEHOLDER: u8 = 10;
+const DOTTED_CIRCLE: u8 = 11;
+const REPHA: u8 = 12;
+const RA: u8 = 13;
+const CM: u8 = 14;
+
+const START: u8 = 0;
+const RA_TO_BECOME_REPH: u8 = 1;
+const PRE_M: u8 = 2;
+const PRE_C: u8 = 3;
+const BASE_C: u8 = 4;
+const AFTER_MAIN: u8 = 5;
+const BEFORE_SUB: u8 = 7;
+const BELOW_C: u8 = 8;
+const AFTER_SUB: u8 = 9;
+const BEFORE_POST: u8 = 10;
+const POST_C: u8 = 11;
+const AFTER_POST: u8 = 12;
+const SMVD: u8 = 13;
+const END: u8 = 14;
+
+const CONSONANT_SYLLABLE: u8 = 1;
+const VOWEL_SYLLABLE: u8 = 2;
+const STANDALONE_CLUSTER: u8 = 3;
+const BROKEN_CLUSTER: u8 = 4;
+const OTHER: u8 = 5;
+
+impl Indic {
+    /// Return the shaper of a script.
+    pub fn find(script: Script) -> Option<&'static Self> {
+        Some(match script {
+            Script::Bengali => &Self {
+                config: &BENGALI,
+                old: true,
+            },
+            Script::BengaliV2 => &Self {
+                config: &BENGALI,
+                old: false,
+            },
+            Script::Devanagari => &Self {
+                config: &DEVANAGARI,
+                old: true,
+            },
+            Script::DevanagariV2 => &Self {
+                config: &DEVANAGARI,
+                old: false,
+            },
+            Script::Gujarati => &Self {
+                config: &GUJARATI,
+                old: true,
+            },
+            Script::GujaratiV2 => &Self {
+                config: &GUJARATI,
+                old: false,
+            },
+            Script::Gurmukhi => &Self {
+                config: &GURMUKHI,
+                old: true,
+            },
+            Script::GurmukhiV2 => &Self {
+                config: &GURMUKHI,
+                old: false,
+            },
+            Script::Kannada => &Self {
+                config: &KANNADA,
+                old: true,
+            },
+            Script::KannadaV2 => &Self {
+                config: &KANNADA,
+                old: false,
+            },
+            Script::Malayalam => &Self {
+                config: &MALAYALAM,
+                old: true,
+            },
+            Script::MalayalamV2 => &Self {
+                config: &MALAYALAM,
+                old: false,
+            },
+            Script::Odia => &Self {
+                config: &ODIA,
+                old: true,
+            },
+            Script::OdiaV2 => &Self {
+                config: &ODIA,
+                old: false,
+            },
+            Script::Tamil => &Self {
+                config: &TAMIL,
+                old: true,
+            },
+            Script::TamilV2 => &Self {
+                config: &TAMIL,
+                old: false,
+            },
+            Script::Telugu => &Self {
+                config: &TELUGU,
+                old: true,
+            },
+            Script::TeluguV2 => &Self {
+                config: &TELUGU,
+                old: false,
+            },
+            _ => return None,
+        })
+    }
+
+    fn is(&self, script: Script) -> bool {
+        self.config.scripts.0 == script
+    }
+
+    fn reorder_initially(&self, shaper: &Shaper, plan: &Plan, buffer: &mut Buffer) {
+        if let Some(&virama) = shaper.mapping.get(&(self.config.virama as u32)) {
+            let mut orders = HashMap::new();
+            for glyph in buffer.glyphs.iter_mut() {
+                if glyph.order() != BASE_C {
+                    continue;
+                }
+                let glyph_id = glyph.glyph_id;
+                glyph.set_order(*orders.entry(glyph_id).or_insert_with(|| {
+                    let substitutes = |feature| {
+                        shaper.substitutes(plan, feature, &[virama, glyph_id])
+                            || shaper.substitutes(plan, feature, &[glyph_id, virama])
+                    };
+                    if substitutes(Feature::BelowBaseForms) || substitutes(Feature::VattuVariants) {
+                        BELOW_C
+                    } else if substitutes(Feature::PostBaseForms)
+                        || substitutes(Feature::PreBaseForms)
+                    {
+                        POST_C
+                    } else {
+                        BASE_C
+                    }
+                }));
+            }
+        }
+        if let Some(&glyph_id) = shaper.mapping.get(&0x25CC) {
+            for range in buffer.syllables().into_iter().rev() {
+                let glyph = &buffer.glyphs[range.start];
+                if glyph.syllable() & 0x0F != BROKEN_CLUSTER {
+                    continue;
+                }
+                let (syllable, cluster) = (glyph.syllable(), glyph.cluster);
+                let position = match glyph.category() {
+                    REPHA => range.start + 1,
+                    _ => range.start,
+                };
+                buffer.insert(position, glyph_id, cluster);
+                let glyph = &mut buffer.glyphs[position];
+                glyph.set_category(DOTTED_CIRCLE);
+                glyph.set_order(BASE_C);
+                glyph.set_syllable(syllable);
+            }
+        }
+        for range in buffer.syllables() {
+            if buffer.glyphs[range.start].syllable() & 0x0F != OTHER {
+                self.reorder_syllable_initially(shaper, plan, &mut buffer.glyphs[range]);
+            }
+        }
+    }
+
+    fn reorder_syllable_initially(&self, shaper: &Shaper, plan: &Plan, glyphs: &mut [Glyph]) {
+        let serials = glyphs.iter().map(Glyph::serial).collect::<Vec<_>>();
+        let end = glyphs.len();
+        let (mut base, mut limit, mut reph) = (end, 0, false);
+        let mode = self.config.mode;
+        if enabled(plan, Feature::RephForms)
+            && end >= 3
+            && ((mode == Mode::Implicit && !joiner(&glyphs[2]))
+                || (mode == Mode::Explicit && glyphs[2].category() == ZWJ))
+        {
+            let glyph_ids = [glyphs[0].glyph_id, glyphs[1].glyph_id, glyphs[2].glyph_id];
+            if shaper.substitutes(plan, Feature::RephForms, &glyph_ids[..2])
+                || (mode == Mode::Explicit
+                    && shaper.substitutes(plan, Feature::RephForms, &glyph_ids))
+            {
+                limit = 2;
+                while limit < end && joiner(&glyphs[limit]) {
+                    limit += 1;
+                }
+                base = 0;
+                reph = true;
+            }
+        } else if mode == Mode::Logical && glyphs[0].category() == REPHA {
+            limit = 1;
+            while limit < end && joiner(&glyphs[limit]) {
+                limit += 1;
+            }
+            base = 0;
+            reph = true;
+        }
+        let (mut i, mut below) = (end, false);
+        loop {
+            i -= 1;
+            if consonant(&glyphs[i]) {
+                let order = glyphs[i].order();
+                if order != BELOW_C && (order != POST_C || below) {
+                    base = i;
+                    break;
+                }
+                below |= order == BELOW_C;
+                base = i;
+            } else if i > 0 && glyphs[i].category() == ZWJ && glyphs[i - 1].category() == H {
+                break;
+            }
+            if i <= limit {
+                break;
+            }
+        }
+        if reph && base == 0 && limit <= 2 {
+            reph = false;
+        }
+
+        for glyph in &mut glyphs[..base] {
+            glyph.set_order(glyph.order().min(PRE_C));
+        }
+        if base < end {
+            glyphs[base].set_order(BASE_C);
+        }
+        if reph {
+            glyphs[0].set_order(RA_TO_BECOME_REPH);
+        }
+        if self.old {
+            let double = self.is(Script::Kannada);
+            if let Some(i) = ((base + 1)..end).find(|&i| glyphs[i].category() == H) {
+                let j = ((i + 1)..end)
+                    .rev()
+                    .find(|&j| consonant(&glyphs[j]) || (double && glyphs[j].category() == H));
+                if let Some(j) = j.filter(|&j| glyphs[j].category() != H) {
+                    glyphs[i..=j].rotate_left(1);
+                }
+            }
+        }
+        let mut last = START;
+        for i in 0..end {
+            let category = glyphs[i].category();
+            if matches!(category, ZWNJ | ZWJ | N | CM | H) {
+                glyphs[i].set_order(last);
+                if category == H && last == PRE_M {
+                    if let Some(j) = (0..i).rev().find(|&j| glyphs[j].order() != PRE_M) {
+                        let order = glyphs[j].order();
+                        glyphs[i].set_order(order);
+                    }
+                }
+            } else if glyphs[i].order() != SMVD {
+                last = glyphs[i].order();
+            }
+        }
+        let mut last = base;
+        for i in (base + 1)..end {
+            if consonant(&glyphs[i]) {
+                let order = glyphs[i].order();
+                for glyph in &mut glyphs[(last + 1)..i] {
+                    if glyph.order() < SMVD {
+                        glyph.set_order(order);
+                    }
+                }
+                last = i;
+            } else if glyphs[i].category() == M {
+                last = i;
+            }
+        }
+        glyphs.sort_by_key(|glyph| glyph.order());
+        let base = glyphs
+            .iter()
+            .position(|glyph| glyph.order() == BASE_C)
+            .unwrap_or(end);
+
+        for glyph in glyphs.iter_mut() {
+            if glyph.order() != RA_TO_BECOME_REPH {
+                break;
+            }
+            glyph.set_mask(glyph.mask() | RPHF);
+        }
+        let mask = match !self.old && self.config.below {
+            true => HALF | BLWF,
+            _ => HALF,
+        };
+        for glyph in &mut glyphs[..base] {
+            glyph.set_mask(glyph.mask() | mask);
+        }
+        for glyph in glyphs.iter_mut().skip(base + 1) {
+            glyph.set_mask(glyph.mask() | BLWF | ABVF | PSTF);
+        }
+        if self.old && self.is(Script::Devanagari) {
+            for i in 0..base.saturating_sub(1) {
+                if glyphs[i].category() == RA
+                    && glyphs[i + 1].category() == H
+                    && (i + 2 == base || glyphs[i + 2].category() != ZWJ)
+                {
+                    for glyph in &mut glyphs[i..(i + 2)] {
+                        glyph.set_mask(glyph.mask() | BLWF);
+                    }
+                }
+            }
+        }
+        if enabled(plan, Feature::PreBaseForms) && base + 2 < end {
+            for i in (base + 1)..(end - 1) {
+                let glyph_ids = [glyphs[i].glyph_id, glyphs[i + 1].glyph_id];
+                if shaper.substitutes(plan, Feature::PreBaseForms, &glyph_ids) {
+                    for glyph in &mut glyphs[i..(i + 2)] {
+                        glyph.set_mask(glyph.mask() | PREF);
+                    }
+                    break;
+                }
+            }
+        }
+        for i in (base + 1)..end {
+            if glyphs[i].category() != ZWNJ {
+                continue;
+            }
+            let mut j = i;
+            loop {
+                j -= 1;
+                glyphs[j].set_mask(glyphs[j].mask() & !HALF);
+                if j == 0 || consonant(&glyphs[j]) {
+                    break;
+                }
+            }
+        }
+        merge(glyphs, &serials);
+    }
+
+    fn reorder_finally(&self, plan: &Plan, buffer: &mut Buffer) {
+        for range in buffer.syllables() {
+            if buffer.glyphs[range.start].syllable() & 0x0F == OTHER {
+                continue;
+            }
+            let initial = range.start == 0 || buffer.glyphs[range.start - 1].category() == X;
+            self.reorder_syllable_finally(plan, &mut buffer.glyphs[range], initial);
+        }
+    }
+
+    fn reorder_syllable_finally(&self, plan: &Plan, glyphs: &mut [Glyph], initial: bool) {
+        let serials = glyphs.iter().map(Glyph::serial).collect::<Vec<_>>();
+        let end = glyphs.len();
+        let unformed = self.is(Script::Malayalam) || self.is(Script::Tamil);
+        let mut pref = enabled(plan, Feature::PreBaseForms);
+        let mut base = 0;
+        while base < end {
+            if glyphs[base].order() < BASE_C {
+                base += 1;
+                continue;
+            }
+            if pref && base + 1 < end {
+                if let Some(i) = ((base + 1)..end).find(|&i| glyphs[i].mask() & PREF != 0) {
+                    if !ligated(&glyphs[i]) {
+                        base = i;
+                        while base < end && glyphs[base].category() == H {
+                            base += 1;
+                        }
+                        if base < end {
+                            glyphs[base].set_order(BASE_C);
+                        }
+                        pref = false;
+                    }
+                }
+                if base == end {
+                    break;
+                }
+            }
+            if self.is(Script::Malayalam) {
+                let mut i = base + 1;
+                while i < end {
+                    while i < end && joiner(&glyphs[i]) {
+                        i += 1;
+                    }
+                    if i == end || glyphs[i].category() != H {
+                        break;
+                    }
+                    i += 1;
+                    while i < end && joiner(&glyphs[i]) {
+                        i += 1;
+                    }
+                    if i < end && consonant(&glyphs[i]) && glyphs[i].order() == BELOW_C {
+                        base = i;
+                        glyphs[base].set_order(BASE_C);
+                    }
+                    i += 1;
+                }
+            }
+            if base > 0 && glyphs[base].order() > BASE_C {
+                base -= 1;
+            }
+            break;
+        }
+        if base == end && base > 0 && glyphs[base - 1].category() == ZWJ {
+            base -= 1;
+        }
+        if base < end {
+            while base > 0 && matches!(glyphs[base].category(), N | H) {
+                base -= 1;
+            }
+        }
+
+        if end > 1 && base > 0 {
+            let mut new = if base == end { base - 2 } else { base - 1 };
+            if !unformed {
+                loop {
+                    while new > 0 && !matches!(glyphs[new].category(), M | H) {
+                        new -= 1;
+                    }
+                    if glyphs[new].category() == H && glyphs[new].order() != PRE_M {
+                        if new + 1 < end && glyphs[new + 1].category() == ZWJ && new > 0 {
+                            new -= 1;
+                            continue;
+                        }
+                    } else {
+                        new = 0;
+                    }
+                    break;
+                }
+            }
+            if new > 0 && glyphs[new].order() != PRE_M {
+                let mut target = new;
+                for i in (1..=new).rev() {
+                    if glyphs[i - 1].order() == PRE_M {
+                        let old = i - 1;
+                        if old < base && base <= target {
+                            base -= 1;
+                        }
+                        glyphs[old..=target].rotate_left(1);
+                        target -= 1;
+                    }
+                }
+            }
+        }
+
+        if end > 1
+            && glyphs[0].order() == RA_TO_BECOME_REPH
+            && ((glyphs[0].category() == REPHA) ^ ligated(&glyphs[0]))
+        {
+            let mut new = 1;
+            while new < base && glyphs[new].category() != H {
+                new += 1;
+            }
+            let mut found = new < base;
+            if found && new + 1 < base && joiner(&glyphs[new + 1]) {
+                new += 1;
+            }
+            if !found && self.config.reph == AFTER_MAIN {
+                new = base;
+                while new + 1 < end && glyphs[new + 1].order() <= AFTER_MAIN {
+                    new += 1;
+                }
+                found = new < end;
+            }
+            if !found && self.config.reph == AFTER_SUB {
+                new = base;
+                while new + 1 < end
+                    && !matches!(glyphs[new + 1].order(), POST_C | AFTER_POST | SMVD)
+                {
+                    new += 1;
+                }
+                found = new < end;
+            }
+            if !found {
+                new = end - 1;
+                while new > 0 && glyphs[new].order() == SMVD {
+                    new -= 1;
+                }
+                if glyphs[new].category() == H {
+                    let mut i = base + 1;
+                    while i < new {
+                        if glyphs[i].category() == M {
+                            new -= 1;
+                        }
+                        i += 1;
+                    }
+                }
+            }
+            glyphs[..=new].rotate_left(1);
+            if base > 0 && base <= new {
+                base -= 1;
+            }
+        }
+
+        if pref && base + 1 < end {
+            if let Some(i) = ((base + 1)..end).find(|&i| glyphs[i].mask() & PREF != 0) {
+                if ligated(&glyphs[i]) {
+                    let mut new = base;
+                    if !unformed {
+                        while new > 0 && !matches!(glyphs[new - 1].category(), M | H) {
+                            new -= 1;
+                        }
+                    }
+                    if new > 0
+                        && glyphs[new - 1].category() == H
+                        && new < end
+                        && joiner(&glyphs[new])
+                    {
+                        new += 1;
+                    }
+                    glyphs[new..=i].rotate_right(1);
+                }
+            }
+        }
+
+        if initial && glyphs[0].order() == PRE_M {
+            glyphs[0].set_mask(glyphs[0].mask() | INIT);
+        }
+        merge(glyphs, &serials);
+    }
+}
+
+impl Complex for Indic {
+    fn masked(&self) -> &'static [Feature] {
+        MASKED
+    }
+
+    fn syllabic(&self) -> &'static [Feature] {
+        SYLLABIC
+    }
+
+    fn substitution(&self) -> &'static [&'static [Feature]] {
+        &[
+            &[
+                Feature::LocalizedForms,
+                Feature::GlyphCompositionDecomposition,
+            ],
+            &[Feature::NuktaForms],
+            &[Feature::Akhand],
+            &[Feature::RephForms],
+            &[Feature::RakarForms],
+            &[Feature::PreBaseForms],
+            &[Feature::BelowBaseForms],
+            &[Feature::AboveBaseForms],
+            &[Feature::HalfForms],
+            &[Feature::PostBaseForms],
+            &[Feature::VattuVariants],
+            &[Feature::ConjunctForms],
+            &[
+                Feature::InitialForms,
+                Feature::PreBaseSubstitutions,
+                Feature::AboveBaseSubstitutions,
+                Feature::BelowBaseSubstitutions,
+                Feature::PostBaseSubstitutions,
+                Feature::HalantForms,
+                Feature::RequiredLigatures,
+                Feature::ContextualAlternates,
+                Feature::ContextualLigatures,
+                Feature::StandardLigatures,
+                Feature::RequiredContextualAlternates,
+            ],
+        ]
+    }
+
+    fn prepare(&self, shaper: &Shaper, _: &Plan, characters: &[char], buffer: &mut Buffer) {
+        let mut characters = characters.to_vec();
+        decompose(shaper, &mut characters, buffer);
+        for (glyph, &character) in buffer.glyphs.iter_mut().zip(&characters) {
+            let (category, order) = classify(character);
+            glyph.set_category(category);
+            glyph.set_order(order);
+        }
+        let categories = buffer
+            .glyphs
+            .iter()
+            .map(Glyph::category)
+            .collect::<Vec<_>>();
+        let (mut i, mut serial) = (0, 0);
+        while i < categories.len() {
+            let (end, kind) = syllable(&categories, i);
+            serial = serial % 15 + 1;
+            for glyph in &mut buffer.glyphs[i..end] {
+                glyph.set_syllable(serial << 4 | kind);
+            }
+            i = end;
+        }
+    }
+
+    fn pause(&self, shaper: &Shaper, plan: &Plan, stage: usize, buffer: &mut Buffer) {
+        match stage {
+            INITIAL_STAGE => self.reorder_initially(shaper, plan, buffer),
+            FINAL_STAGE => self.reorder_finally(plan, buffer),
+            _ => {}
+        }
+    }
+}
+
+/// Return the script tags of the old and new specifications of an Indic script.
+pub(crate) fn versions(script: Script) -> Option<(Script, Script)> {
+    Indic::find(script).map(|indic| indic.config.scripts)
+}
+
+fn classify(character: char) -> (u8, u8) {
+    match character {
+        '\u{200C}' => return (ZWNJ, END),
+        '\u{200D}' => return (ZWJ, END),
+        '\u{25CC}' => return (DOTTED_CIRCLE, BASE_C),
+        '\u{00A0}'
+        | '\u{00D7}'
+        | '\u{2010}'..='\u{2014}'
+        | '\u{2022}'
+        | '\u{25FB}'..='\u{25FE}' => return (PLACEHOLDER, BASE_C),
+        _ => {}
+    }
+    let value = character as u32;
+    CATEGORIES
+        .binary_search_by(|&(start, end, _, _)| {
+            if end < value {
+                std::cmp::Ordering::Less
+            } else if start > value {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .map(|i| (CATEGORIES[i].2, CATEGORIES[i].3))
+        .unwrap_or((X, END))
+}
+
+#[inline]
+fn consonant(glyph: &Glyph) -> bool {
+    matches!(
+        glyph.category(),
+        C | V | RA | CM | PLACEHOLDER | DOTTED_CIRCLE
+    )
+}
+
+/// Decompose split vowel signs, unless the font supports only the composed form.
+fn decompose(shaper: &Shaper, characters: &mut Vec<char>, buffer: &mut Buffer) {
+    let glyph_id = |character: char| shaper.mapping.get(&(character as u32)).copied();
+    let mut i = 0;
+    while i < characters.len() {
+        let parts = match DECOMPOSITIONS.binary_search_by_key(&characters[i], |&(value, _)| value) {
+            Ok(j) => DECOMPOSITIONS[j].1,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let glyph_ids = parts
+            .iter()
+            .map(|&part| glyph_id(part))
+            .collect::<Option<Vec<_>>>();
+        if glyph_ids.is_none() && glyph_id(characters[i]).is_some() {
+            i += 1;
+            continue;
+        }
+        let glyph_ids = parts
+            .iter()
+            .map(|&part| glyph_id(part).unwrap_or(0))
+            .collect::<Vec<_>>();
+        buffer.expand(i, &glyph_ids);
+        characters.splice(i..(i + 1), parts.iter().copied());
+        i += parts.len();
+    }
+}
+
+fn enabled(plan: &Plan, feature: Feature) -> bool {
+    plan.substitution
+        .iter()
+        .flat_map(|stage| &stage.lookups)
+        .any(|lookup| {
+            lookup
+                .settings
+                .iter()
+                .any(|setting| setting.feature == feature)
+        })
+}
+
+#[inline]
+fn joiner(glyph: &Glyph) -> bool {
+    matches!(glyph.category(), ZWNJ | ZWJ)
+}
+
+#[inline]
+fn ligated(glyph: &Glyph) -> bool {
+    glyph.ligature_id != 0
+}
+
+/// Merge the clusters of a syllable if its glyphs have been reordered.
+fn merge(glyphs: &mut [Glyph], serials: &[usize]) {
+    if glyphs
+        .iter()
+        .zip(serials)
+        .all(|(glyph, &serial)| glyph.serial() == serial)
+    {
+        return;
+    }
+    let cluster = glyphs.iter().map(|glyph| glyph.cluster).min().unwrap_or(0);
+    for glyph in glyphs.iter_mut() {
+        glyph.cluster = cluster;
+    }
+}
+
+/// Find the end and kind of the syllable starting at a position.
+///
+/// The grammar is the one of the Indic shaping specification: consonant syllables, vowel
+/// syllables, standalone clusters, and broken clusters, where the longest match is taken.
+fn syllable(categories: &[u8], i: usize) -> (usize, u8) {
+    let is = |i: usize, set: &[u8]| categories.get(i).is_some_and(|value| set.contains(value));
+    let optional = |i: usize, set: &[u8]| if is(i, set) { i + 1 } else { i };
+    let nukta = |i: usize| optional(optional(i, &[N]), &[N]);
+    let consonant = |i: usize| is(i, &[C, RA]).then(|| nukta(optional(i + 1, &[ZWJ])));
+    let halant = |i: usize| {
+        let i = optional(i, &[ZWJ, ZWNJ]);
+        is(i, &[H]).then(|| match is(i + 1, &[ZWJ]) {
+            true => optional(i + 2, &[N]),
+            _ => i + 1,
+        })
+    };
+    let reph = |i: usize| match is(i, &[RA]) && is(i + 1, &[H]) {
+        true => Some(i + 2),
+        _ => is(i, &[REPHA]).then_some(i + 1),
+    };
+    let tail = |mut i: usize| {
+        while let Some(j) = halant(i).and_then(consonant) {
+            i = j;
+        }
+        i = optional(i, &[CM]);
+        let final_halant = halant(i).max((is(i, &[H]) && is(i + 1, &[ZWNJ])).then_some(i + 2));
+        match final_halant {
+            Some(j) => i = j,
+            _ => loop {
+                let mut j = i;
+                while is(j, &[ZWJ, ZWNJ]) {
+                    j += 1;
+                }
+                if !is(j, &[M]) {
+                    break;
+                }
+                i = optional(optional(j + 1, &[N]), &[H]);
+            },
+        }
+        let j = optional(i, &[ZWJ, ZWNJ]);
+        if is(j, &[SM]) {
+            i = optional(optional(j + 1, &[SM]), &[ZWNJ]);
+        }
+        while is(i, &[A]) {
+            i += 1;
+        }
+        i
+    };
+    let mut candidates = vec![];
+    if let Some(j) = consonant(optional(i, &[REPHA])) {
+        candidates.push((tail(j), CONSONANT_SYLLABLE));
+    }
+    let j = reph(i).unwrap_or(i);
+    if is(j, &[V]) {
+        let j = nukta(j + 1);
+        let end = tail(j).max(if is(j, &[ZWJ]) { j + 1 } else { j });
+        candidates.push((end, VOWEL_SYLLABLE));
+    }
+    let j = match is(optional(i, &[REPHA]), &[PLACEHOLDER]) {
+        true => Some(optional(i, &[REPHA]) + 1),
+        _ => Some(reph(i).unwrap_or(i))
+            .filter(|&j| is(j, &[DOTTED_CIRCLE]))
+            .map(|j| j + 1),
+    };
+    if let Some(j) = j {
+        candidates.push((tail(nukta(j)), STANDALONE_CLUSTER));
+    }
+    let end = tail(nukta(reph(i).unwrap_or(i)));
+    if end > i {
+        candidates.push((end, BROKEN_CLUSTER));
+    }
+    candidates
+        .into_iter()
+        .rev()
+        .max_by_key(|&(end, _)| end)
+        .unwrap_or((i + 1, OTHER))
+}
+
+/// The decompositions of split vowel signs.
+const DECOMPOSITIONS: &[(char, &[char])] = &[
+    ('\u{09CB}', &['\u{09C7}', '\u{09BE}']),
+    ('\u{09CC}', &['\u{09C7}', '\u{09D7}']),
+    ('\u{0B48}', &['\u{0B47}', '\u{0B56}']),
+    ('\u{0B4B}', &['\u{0B47}', '\u{0B3E}']),
+    ('\u{0B4C}', &['\u{0B47}', '\u{0B57}']),
+    ('\u{0BCA}', &['\u{0BC6}', '\u{0BBE}']),
+    ('\u{0BCB}', &['\u{0BC7}', '\u{0BBE}']),
+    ('\u{0BCC}', &['\u{0BC6}', '\u{0BD7}']),
+    ('\u{0C48}', &['\u{0C46}', '\u{0C56}']),
+    ('\u{0CC0}', &['\u{0CBF}', '\u{0CD5}']),
+    ('\u{0CC7}', &['\u{0CC6}', '\u{0CD5}']),
+    ('\u{0CC8}', &['\u{0CC6}', '\u{0CD6}']),
+    ('\u{0CCA}', &['\u{0CC6}', '\u{0CC2}']),
+    ('\u{0CCB}', &['\u{0CC6}', '\u{0CC2}', '\u{0CD5}']),
+    ('\u{0D4A}', &['\u{0D46}', '\u{0D3E}']),
+    ('\u{0D4B}', &['\u{0D47}', '\u{0D3E}']),
+    ('\u{0D4C}', &['\u{0D46}', '\u{0D57}']),
+];
+
+/// The categories and reordering classes of characters.
+#[rustfmt::skip]
+const CATEGORIES: &[(u32, u32, u8, u8)] = &[
+    (0x0900, 0x0903, SM, SMVD),
+    (0x0904, 0x0914, V, BASE_C),
+    (0x0915, 0x092F, C, BASE_C),
+    (0x0930, 0x0930, RA, BASE_C),
+    (0x0931, 0x0939, C, BASE_C),
+    (0x093A, 0x093B, M, AFTER_SUB),
+    (0x093C, 0x093C, N, END),
+    (0x093E, 0x093E, M, AFTER_SUB),
+    (0x093F, 0x093F, M, PRE_M),
+    (0x0940, 0x094C, M, AFTER_SUB),
+    (0x094D, 0x094D, H, END),
+    (0x094E, 0x094E, M, PRE_M),
+    (0x094F, 0x094F, M, AFTER_SUB),
+    (0x0951, 0x0954, A, SMVD),
+    (0x0955, 0x0957, M, AFTER_SUB),
+    (0x0958, 0x095F, C, BASE_C),
+    (0x0960, 0x0961, V, BASE_C),
+    (0x0962, 0x0963, M, AFTER_SUB),
+    (0x0972, 0x0977, V, BASE_C),
+    (0x0978, 0x097F, C, BASE_C),
+    (0x0981, 0x0983, SM, SMVD),
+    (0x0985, 0x098C, V, BASE_C),
+    (0x098F, 0x0990, V, BASE_C),
+    (0x0993, 0x0994, V, BASE_C),
+    (0x0995, 0x09A8, C, BASE_C),
+    (0x09AA, 0x09AF, C, BASE_C),
+    (0x09B0, 0x09B0, RA, BASE_C),
+    (0x09B2, 0x09B2, C, BASE_C),
+    (0x09B6, 0x09B9, C, BASE_C),
+    (0x09BC, 0x09BC, N, END),
+    (0x09BE, 0x09BE, M, AFTER_POST),
+    (0x09BF, 0x09BF, M, PRE_M),
+    (0x09C0, 0x09C0, M, AFTER_POST),
+    (0x09C1, 0x09C4, M, AFTER_SUB),
+    (0x09C7, 0x09C8, M, PRE_M),
+    (0x09CD, 0x09CD, H, END),
+    (0x09CE, 0x09CE, C, BASE_C),
+    (0x09D7, 0x09D7, M, AFTER_POST),
+    (0x09DC, 0x09DD, C, BASE_C),
+    (0x09DF, 0x09DF, C, BASE_C),
+    (0x09E0, 0x09E1, V, BASE_C),
+    (0x09E2, 0x09E3, M, AFTER_SUB),
+    (0x09F0, 0x09F0, RA, BASE_C),
+    (0x09F1, 0x09F1, C, BASE_C),
+    (0x09FC, 0x09FC, C, BASE_C),
+    (0x0A01, 0x0A03, SM, SMVD),
+    (0x0A05, 0x0A0A, V, BASE_C),
+    (0x0A0F, 0x0A10, V, BASE_C),
+    (0x0A13, 0x0A14, V, BASE_C),
+    (0x0A15, 0x0A28, C, BASE_C),
+    (0x0A2A, 0x0A2F, C, BASE_C),
+    (0x0A30, 0x0A30, RA, BASE_C),
+    (0x0A32, 0x0A33, C, BASE_C),
+    (0x0A35, 0x0A36, C, BASE_C),
+    (0x0A38, 0x0A39, C, BASE_C),
+    (0x0A3C, 0x0A3C, N, END),
+    (0x0A3E, 0x0A3E, M, AFTER_POST),
+    (0x0A3F, 0x0A3F, M, PRE_M),
+    (0x0A40, 0x0A42, M, AFTER_POST),
+    (0x0A47, 0x0A48, M, AFTER_POST),
+    (0x0A4B, 0x0A4C, M, AFTER_POST),
+    (0x0A4D, 0x0A4D, H, END),
+    (0x0A59, 0x0A5C, C, BASE_C),
+    (0x0A5E, 0x0A5E, C, BASE_C),
+    (0x0A70, 0x0A71, SM, SMVD),
+    (0x0A75, 0x0A75, CM, END),
+    (0x0A81, 0x0A83, SM, SMVD),
+    (0x0A85, 0x0A8C, V, BASE_C),
+    (0x0A8F, 0x0A90, V, BASE_C),
+    (0x0A93, 0x0A94, V, BASE_C),
+    (0x0A95, 0x0AA8, C, BASE_C),
+    (0x0AAA, 0x0AAF, C, BASE_C),
+    (0x0AB0, 0x0AB0, RA, BASE_C),
+    (0x0AB2, 0x0AB3, C, BASE_C),
+    (0x0AB5, 0x0AB9, C, BASE_C),
+    (0x0ABC, 0x0ABC, N, END),
+    (0x0ABE, 0x0ABE, M, AFTER_POST),
+    (0x0ABF, 0x0ABF, M, PRE_M),
+    (0x0AC0, 0x0AC4, M, AFTER_POST),
+    (0x0AC5, 0x0AC5, M, AFTER_SUB),
+    (0x0AC7, 0x0AC8, M, AFTER_SUB),
+    (0x0AC9, 0x0AC9, M, AFTER_POST),
+    (0x0ACB, 0x0ACC, M, AFTER_POST),
+    (0x0ACD, 0x0ACD, H, END),
+    (0x0AE0, 0x0AE1, V, BASE_C),
+    (0x0AE2, 0x0AE3, M, AFTER_POST),
+    (0x0AF9, 0x0AF9, C, BASE_C),
+    (0x0B01, 0x0B03, SM, SMVD),
+    (0x0B05, 0x0B0C, V, BASE_C),
+    (0x0B0F, 0x0B10, V, BASE_C),
+    (0x0B13, 0x0B14, V, BASE_C),
+    (0x0B15, 0x0B28, C, BASE_C),
+    (0x0B2A, 0x0B2F, C, BASE_C),
+    (0x0B30, 0x0B30, RA, BASE_C),
+    (0x0B32, 0x0B33, C, BASE_C),
+    (0x0B35, 0x0B39, C, BASE_C),
+    (0x0B3C, 0x0B3C, N, END),
+    (0x0B3E, 0x0B3E, M, AFTER_POST),
+    (0x0B3F, 0x0B3F, M, AFTER_MAIN),
+    (0x0B40, 0x0B40, M, AFTER_POST),
+    (0x0B41, 0x0B44, M, AFTER_SUB),
+    (0x0B47, 0x0B47, M, PRE_M),
+    (0x0B4D, 0x0B4D, H, END),
+    (0x0B56, 0x0B56, M, AFTER_MAIN),
+    (0x0B57, 0x0B57, M, AFTER_POST),
+    (0x0B5C, 0x0B5D, C, BASE_C),
+    (0x0B5F, 0x0B5F, C, BASE_C),
+    (0x0B60, 0x0B61, V, BASE_C),
+    (0x0B62, 0x0B63, M, AFTER_SUB),
+    (0x0B71, 0x0B71, C, BASE_C),
+    (0x0B82, 0x0B82, SM, SMVD),
+    (0x0B85, 0x0B8A, V, BASE_C),
+    (0x0B8E, 0x0B90, V, BASE_C),
+    (0x0B92, 0x0B94, V, BASE_C),
+    (0x0B95, 0x0B95, C, BASE_C),
+    (0x0B99, 0x0B9A, C, BASE_C),
+    (0x0B9C, 0x0B9C, C, BASE_C),
+    (0x0B9E, 0x0B9F, C, BASE_C),
+    (0x0BA3, 0x0BA4, C, BASE_C),
+    (0x0BA8, 0x0BAA, C, BASE_C),
+    (0x0BAE, 0x0BAF, C, BASE_C),
+    (0x0BB0, 0x0BB0, RA, BASE_C),
+    (0x0BB1, 0x0BB9, C, BASE_C),
+    (0x0BBE, 0x0BBF, M, AFTER_POST),
+    (0x0BC0, 0x0BC0, M, AFTER_SUB),
+    (0x0BC1, 0x0BC2, M, AFTER_POST),
+    (0x0BC6, 0x0BC8, M, PRE_M),
+    (0x0BCD, 0x0BCD, H, END),
+    (0x0BD7, 0x0BD7, M, AFTER_POST),
+    (0x0C00, 0x0C04, SM, SMVD),
+    (0x0C05, 0x0C0C, V, BASE_C),
+    (0x0C0E, 0x0C10, V, BASE_C),
+    (0x0C12, 0x0C14, V, BASE_C),
+    (0x0C15, 0x0C28, C, BASE_C),
+    (0x0C2A, 0x0C2F, C, BASE_C),
+    (0x0C30, 0x0C30, RA, BASE_C),
+    (0x0C31, 0x0C39, C, BASE_C),
+    (0x0C3C, 0x0C3C, N, END),
+    (0x0C3E, 0x0C42, M, BEFORE_SUB),
+    (0x0C43, 0x0C44, M, AFTER_SUB),
+    (0x0C46, 0x0C47, M, BEFORE_SUB),
+    (0x0C4A, 0x0C4C, M, BEFORE_SUB),
+    (0x0C4D, 0x0C4D, H, END),
+    (0x0C55, 0x0C56, M, BEFORE_SUB),
+    (0x0C58, 0x0C5A, C, BASE_C),
+    (0x0C5D, 0x0C5D, C, BASE_C),
+    (0x0C60, 0x0C61, V, BASE_C),
+    (0x0C62, 0x0C63, M, BEFORE_SUB),
+    (0x0C81, 0x0C83, SM, SMVD),
+    (0x0C85, 0x0C8C, V, BASE_C),
+    (0x0C8E, 0x0C90, V, BASE_C),
+    (0x0C92, 0x0C94, V, BASE_C),
+    (0x0C95, 0x0CA8, C, BASE_C),
+    (0x0CAA, 0x0CAF, C, BASE_C),
+    (0x0CB0, 0x0CB0, RA, BASE_C),
+    (0x0CB1, 0x0CB3, C, BASE_C),
+    (0x0CB5, 0x0CB9, C, BASE_C),
+    (0x0CBC, 0x0CBC, N, END),
+    (0x0CBE, 0x0CBF, M, BEFORE_SUB),
+    (0x0CC1, 0x0CC2, M, BEFORE_SUB),
+    (0x0CC3, 0x0CC4, M, AFTER_SUB),
+    (0x0CC6, 0x0CC6, M, BEFORE_SUB),
+    (0x0CCC, 0x0CCC, M, BEFORE_SUB),
+    (0x0CCD, 0x0CCD, H, END),
+    (0x0CD5, 0x0CD6, M, AFTER_SUB),
+    (0x0CDD, 0x0CDE, C, BASE_C),
+    (0x0CE0, 0x0CE1, V, BASE_C),
+    (0x0CE2, 0x0CE3, M, BEFORE_SUB),
+    (0x0D00, 0x0D03, SM, SMVD),
+    (0x0D04, 0x0D04, C, BASE_C),
+    (0x0D05, 0x0D0C, V, BASE_C),
+    (0x0D0E, 0x0D10, V, BASE_C),
+    (0x0D12, 0x0D14, V, BASE_C),
+    (0x0D15, 0x0D2F, C, BASE_C),
+    (0x0D30, 0x0D30, RA, BASE_C),
+    (0x0D31, 0x0D3A, C, BASE_C),
+    (0x0D3B, 0x0D3C, H, END),
+    (0x0D3E, 0x0D44, M, AFTER_POST),
+    (0x0D46, 0x0D48, M, PRE_M),
+    (0x0D4D, 0x0D4D, H, END),
+    (0x0D4E, 0x0D4E, REPHA, END),
+    (0x0D54, 0x0D56, C, BASE_C),
+    (0x0D57, 0x0D57, M, AFTER_POST),
+    (0x0D5F, 0x0D61, V, BASE_C),
+    (0x0D62, 0x0D63, M, AFTER_POST),
+    (0x0D7A, 0x0D7F, C, BASE_C),
+];
@@ -0,0 +1,235 @@
+//! The matching of glyph sequences.
+
+use truetype::GlyphID;
+
+use crate::layout::context::{Action, ChainedContext, Context};
+use crate::layout::lookup::{Flags, Record};
+use crate::shaping::Glyph;
+use crate::tables::GlyphDefinition;
+
+/// The maximal depth of nested lookups.
+pub(crate) const MAX_DEPTH: usize = 64;
+
+/// A filter of glyphs according to lookup flags.
+#[derive(Clone, Copy)]
+pub(crate) struct Filter<'l> {
+    flags: Flags,
+    mark_filtering_set: Option<u16>,
+    definition: Option<&'l GlyphDefinition>,
+}
+
+/// A part of a contextual rule.
+#[derive(Clone, Copy)]
+pub(crate) enum Side {
+    Backward,
+    Input,
+    Forward,
+}
+
+impl<'l> Filter<'l> {
+    /// Create a filter for a lookup.
+    pub fn new<T>(record: &Record<T>, definition: Option<&'l GlyphDefinition>) -> Self {
+        Self {
+            flags: record.flags,
+            mark_filtering_set: record.mark_filtering_set,
+            definition,
+        }
+    }
+
+    /// Check if a glyph is to be skipped.
+    pub fn skips(&self, glyph_id: GlyphID) -> bool {
+        let definition = match self.definition {
+            Some(definition) => definition,
+            _ => return false,
+        };
+        match class_of(Some(definition), glyph_id) {
+            1 => self.flags.should_ignore_base_glyphs(),
+            2 => self.flags.should_ignore_ligature(),
+            3 if self.flags.should_ignore_marks() => true,
+            3 if self.flags.has_mark_filtering() => {
+                let coverage = definition.marks.as_ref().and_then(|marks| {
+                    let index = self.mark_filtering_set? as usize;
+                    marks.coverages.get(index)
+                });
+                coverage.is_none_or(|coverage| coverage.index_of(glyph_id).is_none())
+            }
+            3 => {
+                let class = self.flags.0 >> 8;
+                class != 0
+                    && definition
+                        .mark_class
+                        .as_ref()
+                        .is_none_or(|value| value.class_of(glyph_id) != class)
+            }
+            _ => false,
+        }
+    }
+
+    /// Return the position of the next glyph that is not skipped.
+    pub fn next(&self, glyphs: &[Glyph], position: usize) -> Option<usize> {
+        ((position + 1)..glyphs.len()).find(|&i| !self.skips(glyphs[i].glyph_id))
+    }
+
+    /// Return the position of the previous glyph that is not skipped.
+    pub fn previous(&self, glyphs: &[Glyph], position: usize) -> Option<usize> {
+        (0..position)
+            .rev()
+            .find(|&i| !self.skips(glyphs[i].glyph_id))
+    }
+}
+
+/// Return the glyph class of a glyph, which is 0 if unknown.
+pub(crate) fn class_of(definition: Option<&GlyphDefinition>, glyph_id: GlyphID) -> u16 {
+    definition
+        .and_then(|definition| definition.glyph_class.as_ref())
+        .map(|class| class.class_of(glyph_id))
+        .unwrap_or(0)
+}
+
+/// Match a rule whose first input glyph is at a position and return the input positions.
+pub(crate) fn match_rule<T, F>(
+    filter: &Filter,
+    glyphs: &[Glyph],
+    position: usize,
+    (backward, input, forward): (&[T], &[T], &[T]),
+    check: F,
+) -> Option<Vec<usize>>
+where
+    F: Fn(Side, &T, GlyphID) -> bool,
+{
+    let mut positions = vec![position];
+    let mut last = position;
+    for value in input {
+        last = filter.next(glyphs, last)?;
+        if !check(Side::Input, value, glyphs[last].glyph_id) {
+            return None;
+        }
+        positions.push(last);
+    }
+    let mut other = position;
+    for value in backward {
+        other = filter.previous(glyphs, other)?;
+        if !check(Side::Backward, value, glyphs[other].glyph_id) {
+            return None;
+        }
+    }
+    let mut other = last;
+    for value in forward {
+        other = filter.next(glyphs, other)?;
+        if !check(Side::Forward, value, glyphs[other].glyph_id) {
+            return None;
+        }
+    }
+    Some(positions)
+}
+
+/// Match a contextual lookup at a position and return the input positions and actions.
+pub(crate) fn match_context<'l>(
+    table: &'l Context,
+    filter: &Filter,
+    glyphs: &[Glyph],
+    position: usize,
+) -> Option<(Vec<usize>, &'l [Action])> {
+    let glyph_id = glyphs[position].glyph_id;
+    match table {
+        Context::Format1(table) => {
+            let index = table.coverage.index_of(glyph_id)?;
+            table
+                .records
+                .get(index as usize)?
+                .records
+                .iter()
+                .find_map(|record| {
+                    let rule = (&[][..], &record.glyph_ids[..], &[][..]);
+                    match_rule(filter, glyphs, position, rule, |_, &value, glyph_id| {
+                        value == glyph_id
+                    })
+                    .map(|positions| (positions, &record.actions[..]))
+                })
+        }
+        Context::Format2(table) => {
+            table.coverage.index_of(glyph_id)?;
+            let index = table.class.class_of(glyph_id);
+            let records = table.records.get(index as usize)?.as_ref()?;
+            records.records.iter().find_map(|record| {
+                let rule = (&[][..], &record.indices[..], &[][..]);
+                match_rule(filter, glyphs, position, rule, |_, &value, glyph_id| {
+                    table.class.class_of(glyph_id) == value
+                })
+                .map(|positions| (positions, &record.actions[..]))
+            })
+        }
+        Context::Format3(table) => {
+            table.coverages.first()?.index_of(glyph_id)?;
+            let rule = (&[][..], &table.coverages[1..], &[][..]);
+            match_rule(filter, glyphs, position, rule, |_, coverage, glyph_id| {
+                coverage.index_of(glyph_id).is_some()
+            })
+            .map(|positions| (positions, &table.actions[..]))
+        }
+    }
+}
+
+/// Match a chained contextual lookup at a position and return the input positions and actions.
+pub(crate) fn match_chained_context<'l>(
+    table: &'l ChainedContext,
+    filter: &Filter,
+    glyphs: &[Glyph],
+    position: usize,
+) -> Option<(Vec<usize>, &'l [Action])> {
+    let glyph_id = glyphs[position].glyph_id;
+    match table {
+        ChainedContext::Format1(table) => {
+            let index = table.coverage.index_of(glyph_id)?;
+            table
+                .records
+                .get(index as usize)?
+                .records
+                .iter()
+                .find_map(|record| {
+                    let rule = (
+                        &record.backward_glyph_ids[..],
+                        &record.glyph_ids[..],
+                        &record.forward_glyph_ids[..],
+                    );
+                    match_rule(filter, glyphs, position, rule, |_, &value, glyph_id| {
+                        value == glyph_id
+                    })
+                    .map(|positions| (positions, &record.actions[..]))
+                })
+        }
+        ChainedContext::Format2(table) => {
+            table.coverage.index_of(glyph_id)?;
+            let index = table.class.class_of(glyph_id);
+            let records = table.records.get(index as usize)?.as_ref()?;
+            records.records.iter().find_map(|record| {
+                let rule = (
+                    &record.backward_indices[..],
+                    &record.indices[..],
+                    &record.forward_indices[..],
+                );
+                match_rule(filter, glyphs, position, rule, |side, &value, glyph_id| {
+                    let class = match side {
+                        Side::Backward => &table.backward_class,
+                        Side::Input => &table.class,
+                        Side::Forward => &table.forward_class,
+                    };
+                    class.class_of(glyph_id) == value
+                })
+                .map(|positions| (positions, &record.actions[..]))
+            })
+        }
+        ChainedContext::Format3(table) => {
+            table.coverages.first()?.index_of(glyph_id)?;
+            let rule = (
+                &table.backward_coverages[..],
+                &table.coverages[1..],
+                &table.forward_coverages[..],
+            );
+            match_rule(filter, glyphs, position, rule, |_, coverage, glyph_id| {
+                coverage.index_of(glyph_id).is_some()
+            })
+            .map(|positions| (positions, &table.actions[..]))
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! The shaping of glyphs.
+
+pub mod buffer;
+pub mod closure;
+pub mod plan;
+pub mod positioning;
+pub mod shaper;
+pub mod substitution;
+
+mod arabic;
+mod hangul;
+mod hebrew;
+mod indic;
+mod matching;
+mod thai;
+mod universal;
+
+pub use buffer::{Buffer, Glyph};
+pub use closure::closure;
+pub use plan::{Plan, Planner, Setting};
+pub use positioning::Positioning;
+pub use shaper::{shape, Options, Shaper};
+pub use substitution::Substitution;
@@ -0,0 +1,204 @@
+//! The planning of features.
+
+use truetype::{q16, Tag};
+
+use crate::layout::{language, Directory, Feature, Language, Script};
+use crate::tables::{GlyphPositioning, GlyphSubstitution};
+
+/// A planner of features.
+///
+/// Features are grouped in stages. The lookups of a stage are applied in the order of the lookup
+/// list, and stages are applied one after another.
+#[derive(Clone, Debug)]
+pub struct Planner<'l> {
+    /// The script.
+    pub script: Script,
+    /// The language or the default language system of the script if absent.
+    pub language: Option<Language>,
+    /// The normalized variation coordinates used for selecting feature variations.
+    pub coordinates: &'l [q16],
+    /// The stages of glyph-substitution features.
+    pub substitution: Vec<Vec<Setting>>,
+    /// The stages of glyph-positioning features.
+    pub positioning: Vec<Vec<Setting>>,
+}
+
+/// A feature setting.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Setting {
+    /// The feature.
+    pub feature: Feature,
+    /// The value, which is 0 if the feature is disabled, 1 if enabled, and the index of an
+    /// alternate plus 1 otherwise.
+    pub value: u32,
+}
+
+/// A plan of features.
+#[derive(Clone, Debug, Default)]
+pub struct Plan {
+    /// The script the lookups are planned for.
+    pub script: Script,
+    /// The stages of glyph-substitution lookups.
+    pub substitution: Vec<Stage>,
+    /// The stages of glyph-positioning lookups.
+    pub positioning: Vec<Stage>,
+}
+
+/// A stage of a plan.
+#[derive(Clone, Debug, Default)]
+pub struct Stage {
+    /// The lookups in the order of the lookup list.
+    pub lookups: Vec<Lookup>,
+}
+
+/// A lookup of a plan.
+#[derive(Clone, Debug)]
+pub struct Lookup {
+    /// The index in the lookup list.
+    pub index: u16,
+    /// The settings of the features referring to the lookup.
+    pub settings: Vec<Setting>,
+}
+
+impl<'l> Planner<'l> {
+    /// Create a planner.
+    pub fn new(script: Script, language: Option<Language>) -> Self {
+        Self {
+            script,
+            language,
+            coordinates: &[],
+            substitution: vec![],
+            positioning: vec![],
+        }
+    }
+
+    /// Plan the lookups of a glyph substitution and a glyph positioning.
+    ///
+    /// The stages of an absent table are kept empty.
+    pub fn plan(
+        &self,
+        substitution: Option<&GlyphSubstitution>,
+        positioning: Option<&GlyphPositioning>,
+    ) -> Plan {
+        Plan {
+            script: self.script,
+            substitution: match substitution {
+                Some(table) => self.resolve(table, &self.substitution),
+                _ => self.substitution.iter().map(|_| Stage::default()).collect(),
+            },
+            positioning: match positioning {
+                Some(table) => self.resolve(table, &self.positioning),
+                _ => self.positioning.iter().map(|_| Stage::default()).collect(),
+            },
+        }
+    }
+
+    /// Resolve stages of settings into stages of lookups.
+    ///
+    /// The required feature of the language system, if any, is enabled in the first stage. When
+    /// a feature is set several times within a stage, the last setting is taken.
+    pub fn resolve<T>(&self, directory: &Directory<T>, stages: &[Vec<Setting>]) -> Vec<Stage> {
+        let record = match self.find(directory) {
+            Some(record) => record,
+            _ => return stages.iter().map(|_| Stage::default()).collect(),
+        };
+        let records = directory.effective_features(self.coordinates);
+        let mut values = Vec::with_capacity(stages.len());
+        for (i, settings) in stages.iter().enumerate() {
+            let mut lookups: Vec<Lookup> = vec![];
+            let mut add = |index: u16, setting: Setting| {
+                let lookup_indices = match records.get(index as usize) {
+                    Some(record) => &record.lookup_indices,
+                    _ => return,
+                };
+                for &lookup_index in lookup_indices {
+                    if lookup_index >= directory.lookups.records.len() as u16 {
+                        continue;
+                    }
+                    match lookups
+                        .iter_mut()
+                        .find(|lookup| lookup.index == lookup_index)
+                    {
+                        Some(lookup) if !lookup.settings.contains(&setting) => {
+                            lookup.settings.push(setting)
+                        }
+                        Some(_) => {}
+                        _ => lookups.push(Lookup {
+                            index: lookup_index,
+                            settings: vec![setting],
+                        }),
+                    }
+                }
+            };
+            if i == 0 && record.required_feature_index != language::NO_REQUIRED_FEATURE {
+                let index = record.required_feature_index;
+                if let Some(header) = directory.features.headers.get(index as usize) {
+                    add(index, Setting::on(Feature::from_tag(&header.tag)));
+                }
+            }
+            for (j, setting) in settings.iter().enumerate() {
+                let overridden = settings[(j + 1)..]
+                    .iter()
+                    .any(|other| other.feature == setting.feature);
+                if setting.value == 0 || overridden {
+                    continue;
+                }
+                let tag = Tag::from(setting.feature);
+                for &index in &record.feature_indices {
+                    let header = directory.features.headers.get(index as usize);
+                    if header.is_some_and(|header| header.tag == tag) {
+                        add(index, *setting);
+                    }
+                }
+            }
+            lookups.sort_by_key(|lookup| lookup.index);
+            values.push(Stage { lookups });
+        }
+        values
+    }
+
+    fn find<'m, T>(&self, directory: &'m Directory<T>) -> Option<&'m language::Record> {
+        let scripts = &directory.scripts;
+        let record = scripts
+            .get(self.script)
+            .or_else(|| scripts.get(Script::Default))
+            .or_else(|| scripts.get(Tag(*b"dflt")))
+            .or_else(|| scripts.get(Script::Latin))?;
+        match self.language {
+            Some(language) => record.get(language).or(record.default_language.as_ref()),
+            _ => record.default_language.as_ref(),
+        }
+    }
+}
+
+impl Setting {
+    /// Create an enabled setting.
+    #[inline]
+    pub fn on(feature: Feature) -> Self {
+        Self { feature, value: 1 }
+    }
+
+    /// Create a disabled setting.
+    #[inline]
+    pub fn off(feature: Feature) -> Self {
+        Self { feature, value: 0 }
+    }
+}
+
+impl Plan {
+    /// Return the indices of the glyph-substitution lookups in the order of application.
+    pub fn substitution_indices(&self) -> impl Iterator<Item = u16> + '_ {
+        indices(&self.substitution)
+    }
+
+    /// Return the indices of the glyph-positioning lookups in the order of application.
+    pub fn positioning_indices(&self) -> impl Iterator<Item = u16> + '_ {
+        indices(&self.positioning)
+    }
+}
+
+fn indices(stages: &[Stage]) -> impl Iterator<Item = u16> + '_ {
+    stages
+        .iter()
+        .flat_map(|stage| stage.lookups.iter().map(|lookup| lookup.index))
+}
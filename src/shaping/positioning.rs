@@ -0,0 +1,425 @@
+//! The application of glyph positionings.
+
+use truetype::q16;
+
+use crate::layout::context::Action;
+use crate::layout::lookup::Flags;
+use crate::layout::Correction;
+use crate::shaping::buffer::Attachment;
+use crate::shaping::matching::{self, Filter, MAX_DEPTH};
+use crate::shaping::{Buffer, Glyph};
+use crate::tables::glyph_positioning::{Anchor, PairAdjustment, Single, SingleAdjustment, Type};
+use crate::tables::{GlyphDefinition, GlyphPositioning};
+use crate::Result;
+
+/// An engine applying glyph positionings.
+///
+/// The glyphs of a buffer are expected to be in logical order with their advances set, and marks
+/// are expected to have zero advances. Attachments are recorded by the lookups and turned into
+/// offsets by [`Positioning::resolve`], which is to be called after all lookups are applied.
+#[derive(Clone, Copy)]
+pub struct Positioning<'l> {
+    /// The glyph positioning.
+    pub table: &'l GlyphPositioning,
+    /// The glyph definition used for filtering glyphs and evaluating variations.
+    pub definition: Option<&'l GlyphDefinition>,
+    /// The normalized variation coordinates.
+    pub coordinates: &'l [q16],
+    /// The flag indicating right-to-left text, whose glyphs are to be presented in reverse
+    /// order once resolved.
+    pub right_to_left: bool,
+}
+
+impl<'l> Positioning<'l> {
+    /// Create an engine.
+    #[inline]
+    pub fn new(table: &'l GlyphPositioning, definition: Option<&'l GlyphDefinition>) -> Self {
+        Self {
+            table,
+            definition,
+            coordinates: &[],
+            right_to_left: false,
+        }
+    }
+
+    /// Apply a lookup to a buffer.
+    pub fn apply(&self, buffer: &mut Buffer, lookup_index: u16) -> Result<()> {
+        self.apply_where(buffer, lookup_index, |_| true)
+    }
+
+    /// Apply a lookup to the glyphs of a buffer selected by a predicate.
+    pub(crate) fn apply_where<F>(
+        &self,
+        buffer: &mut Buffer,
+        lookup_index: u16,
+        select: F,
+    ) -> Result<()>
+    where
+        F: Fn(&Glyph) -> bool,
+    {
+        let record = match self.table.lookups.records.get(lookup_index as usize) {
+            Some(record) => record,
+            _ => raise!("found no glyph positioning with index {lookup_index}"),
+        };
+        let filter = Filter::new(record, self.definition);
+        let mut i = 0;
+        while i < buffer.len() {
+            let glyph = &buffer.glyphs[i];
+            let mut next = None;
+            if select(glyph) && !filter.skips(glyph.glyph_id) {
+                for table in &record.tables {
+                    next = self.apply_table(buffer, &filter, record.flags, table, i, 0)?;
+                    if next.is_some() {
+                        break;
+                    }
+                }
+            }
+            i = next.unwrap_or(i + 1).max(i + 1);
+        }
+        Ok(())
+    }
+
+    /// Turn the attachments of a buffer into offsets.
+    pub fn resolve(&self, buffer: &mut Buffer) {
+        let mut resolved = vec![false; buffer.len()];
+        for i in 0..buffer.len() {
+            self.resolve_at(buffer, &mut resolved, i, 0);
+        }
+    }
+
+    fn resolve_at(&self, buffer: &mut Buffer, resolved: &mut [bool], i: usize, depth: usize) {
+        if resolved[i] {
+            return;
+        }
+        resolved[i] = true;
+        let attachment = match buffer.glyphs[i].attachment() {
+            Some(attachment) if attachment.position < buffer.len() => attachment,
+            _ => return,
+        };
+        let j = attachment.position;
+        if depth < buffer.len() {
+            self.resolve_at(buffer, resolved, j, depth + 1);
+        }
+        let (x_offset, y_offset) = (buffer.glyphs[j].x_offset, buffer.glyphs[j].y_offset);
+        if attachment.cursive {
+            let glyph = &mut buffer.glyphs[i];
+            glyph.y_offset += y_offset;
+            glyph.attach(None);
+            return;
+        }
+        let (mut x, mut y) = (x_offset, y_offset);
+        let (range, sign) = match (j < i, self.right_to_left) {
+            (true, false) => (j..i, -1),
+            (true, true) => ((j + 1)..(i + 1), 1),
+            (false, false) => (i..j, 1),
+            (false, true) => ((i + 1)..(j + 1), -1),
+        };
+        for glyph in &buffer.glyphs[range] {
+            x += sign * glyph.x_advance;
+            y += sign * glyph.y_advance;
+        }
+        let glyph = &mut buffer.glyphs[i];
+        glyph.x_offset += x;
+        glyph.y_offset += y;
+        glyph.attach(None);
+    }
+
+    fn apply_at(
+        &self,
+        buffer: &mut Buffer,
+        lookup_index: u16,
+        position: usize,
+        depth: usize,
+    ) -> Result<bool> {
+        if depth > MAX_DEPTH {
+            return Ok(false);
+        }
+        let record = match self.table.lookups.records.get(lookup_index as usize) {
+            Some(record) => record,
+            _ => raise!("found no glyph positioning with index {lookup_index}"),
+        };
+        let filter = Filter::new(record, self.definition);
+        if filter.skips(buffer.glyphs[position].glyph_id) {
+            return Ok(false);
+        }
+        for table in &record.tables {
+            if self
+                .apply_table(buffer, &filter, record.flags, table, position, depth)?
+                .is_some()
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn apply_table(
+        &self,
+        buffer: &mut Buffer,
+        filter: &Filter,
+        flags: Flags,
+        table: &Type,
+        position: usize,
+        depth: usize,
+    ) -> Result<Option<usize>> {
+        let matched = match table {
+            Type::ContextualPositioning(table) => {
+                matching::match_context(table, filter, &buffer.glyphs, position)
+            }
+            Type::ChainedContextualPositioning(table) => {
+                matching::match_chained_context(table, filter, &buffer.glyphs, position)
+            }
+            _ => return Ok(self.apply_simple(buffer, filter, flags, table, position)),
+        };
+        match matched {
+            Some((positions, actions)) => self
+                .apply_actions(buffer, &positions, actions, depth)
+                .map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn apply_simple(
+        &self,
+        buffer: &mut Buffer,
+        filter: &Filter,
+        flags: Flags,
+        table: &Type,
+        i: usize,
+    ) -> Option<usize> {
+        let glyph_id = buffer.glyphs[i].glyph_id;
+        match table {
+            Type::SingleAdjustment(SingleAdjustment::Format1(table)) => {
+                table.coverage.index_of(glyph_id)?;
+                self.adjust(&mut buffer.glyphs[i], &table.value);
+                Some(i + 1)
+            }
+            Type::SingleAdjustment(SingleAdjustment::Format2(table)) => {
+                let index = table.coverage.index_of(glyph_id)?;
+                self.adjust(&mut buffer.glyphs[i], table.values.get(index as usize)?);
+                Some(i + 1)
+            }
+            Type::PairAdjustment(table) => {
+                let j = filter.next(&buffer.glyphs, i)?;
+                let glyph2_id = buffer.glyphs[j].glyph_id;
+                let (value1, value2) = match table {
+                    PairAdjustment::Format1(table) => {
+                        let index = table.coverage.index_of(glyph_id)?;
+                        let record = table
+                            .records
+                            .get(index as usize)?
+                            .records
+                            .iter()
+                            .find(|record| record.glyph2_id == glyph2_id)?;
+                        (&record.value1, &record.value2)
+                    }
+                    PairAdjustment::Format2(table) => {
+                        table.coverage.index_of(glyph_id)?;
+                        let class1 = table.class1.class_of(glyph_id);
+                        let class2 = table.class2.class_of(glyph2_id);
+                        let record = table
+                            .records
+                            .get(class1 as usize)?
+                            .records
+                            .get(class2 as usize)?;
+                        (&record.value1, &record.value2)
+                    }
+                };
+                if let Some(value) = value1 {
+                    self.adjust(&mut buffer.glyphs[i], value);
+                }
+                match value2 {
+                    Some(value) => {
+                        self.adjust(&mut buffer.glyphs[j], value);
+                        Some(j + 1)
+                    }
+                    _ => Some(j),
+                }
+            }
+            Type::CursiveAttachment(table) => {
+                let index = table.coverage.index_of(glyph_id)?;
+                let end = table.connections.get(index as usize)?.end_anchor.as_ref()?;
+                let j = filter.next(&buffer.glyphs, i)?;
+                let index = table.coverage.index_of(buffer.glyphs[j].glyph_id)?;
+                let start = table
+                    .connections
+                    .get(index as usize)?
+                    .start_anchor
+                    .as_ref()?;
+                let (end_x, end_y) = self.anchor(end);
+                let (start_x, start_y) = self.anchor(start);
+                if self.right_to_left {
+                    let glyph = &mut buffer.glyphs[i];
+                    let delta = end_x + glyph.x_offset;
+                    glyph.x_advance -= delta;
+                    glyph.x_offset -= delta;
+                    let glyph = &mut buffer.glyphs[j];
+                    glyph.x_advance = start_x + glyph.x_offset;
+                } else {
+                    let glyph = &mut buffer.glyphs[i];
+                    glyph.x_advance = end_x + glyph.x_offset;
+                    let glyph = &mut buffer.glyphs[j];
+                    let delta = start_x + glyph.x_offset;
+                    glyph.x_advance -= delta;
+                    glyph.x_offset -= delta;
+                }
+                let (child, parent, y_offset) = if flags.is_right_to_left() {
+                    (i, j, start_y - end_y)
+                } else {
+                    (j, i, end_y - start_y)
+                };
+                if buffer.glyphs[parent]
+                    .attachment()
+                    .is_some_and(|attachment| attachment.position == child)
+                {
+                    buffer.glyphs[parent].attach(None);
+                }
+                let glyph = &mut buffer.glyphs[child];
+                glyph.y_offset = y_offset;
+                glyph.attach(Some(Attachment {
+                    position: parent,
+                    cursive: true,
+                }));
+                Some(j)
+            }
+            Type::MarkToBaseAttachment(table) => {
+                let mark_index = table.mark_coverage.index_of(glyph_id)?;
+                let j = self.base(buffer, i, |glyph_id| {
+                    table.mark_coverage.index_of(glyph_id).is_some()
+                })?;
+                let index = table.base_coverage.index_of(buffer.glyphs[j].glyph_id)?;
+                let mark = table.marks.records.get(mark_index as usize)?;
+                let anchor = table
+                    .bases
+                    .records
+                    .get(index as usize)?
+                    .anchors
+                    .get(mark.class_id as usize)?
+                    .as_ref()?;
+                self.attach(buffer, i, j, &mark.anchor, anchor);
+                Some(i + 1)
+            }
+            Type::MarkToLigatureAttachment(table) => {
+                let mark_index = table.mark_coverage.index_of(glyph_id)?;
+                let j = self.base(buffer, i, |glyph_id| {
+                    table.mark_coverage.index_of(glyph_id).is_some()
+                })?;
+                let index = table
+                    .ligature_coverage
+                    .index_of(buffer.glyphs[j].glyph_id)?;
+                let mark = table.marks.records.get(mark_index as usize)?;
+                let ligature = table.ligatures.records.get(index as usize)?;
+                let count = ligature.components.len();
+                if count == 0 {
+                    return None;
+                }
+                let (mark_glyph, base_glyph) = (&buffer.glyphs[i], &buffer.glyphs[j]);
+                let component = if mark_glyph.ligature_id != 0
+                    && mark_glyph.ligature_id == base_glyph.ligature_id
+                {
+                    (mark_glyph.component as usize).min(count - 1)
+                } else {
+                    count - 1
+                };
+                let anchor = ligature.components[component]
+                    .anchors
+                    .get(mark.class_id as usize)?
+                    .as_ref()?;
+                self.attach(buffer, i, j, &mark.anchor, anchor);
+                Some(i + 1)
+            }
+            Type::MarkToMarkAttachment(table) => {
+                let mark_index = table.mark1_coverage.index_of(glyph_id)?;
+                let j = filter.previous(&buffer.glyphs, i)?;
+                let (mark1, mark2) = (&buffer.glyphs[i], &buffer.glyphs[j]);
+                if mark1.ligature_id != mark2.ligature_id
+                    || (mark1.ligature_id != 0 && mark1.component != mark2.component)
+                {
+                    return None;
+                }
+                let index = table.mark2_coverage.index_of(mark2.glyph_id)?;
+                let mark = table.mark1s.records.get(mark_index as usize)?;
+                let anchor = table
+                    .mark2s
+                    .records
+                    .get(index as usize)?
+                    .anchors
+                    .get(mark.class_id as usize)?
+                    .as_ref()?;
+                self.attach(buffer, i, j, &mark.anchor, anchor);
+                Some(i + 1)
+            }
+            _ => None,
+        }
+    }
+
+    fn apply_actions(
+        &self,
+        buffer: &mut Buffer,
+        positions: &[usize],
+        actions: &[Action],
+        depth: usize,
+    ) -> Result<usize> {
+        for action in actions {
+            if let Some(&position) = positions.get(action.position_index as usize) {
+                self.apply_at(buffer, action.lookup_index, position, depth + 1)?;
+            }
+        }
+        Ok(positions[positions.len() - 1] + 1)
+    }
+
+    fn adjust(&self, glyph: &mut Glyph, value: &Single) {
+        glyph.x_offset +=
+            value.x_placement.unwrap_or(0) as i32 + self.correct(&value.x_placement_correction);
+        glyph.y_offset +=
+            value.y_placement.unwrap_or(0) as i32 + self.correct(&value.y_placement_correction);
+        glyph.x_advance +=
+            value.x_advance.unwrap_or(0) as i32 + self.correct(&value.x_advance_correction);
+        glyph.y_advance +=
+            value.y_advance.unwrap_or(0) as i32 + self.correct(&value.y_advance_correction);
+    }
+
+    fn anchor(&self, anchor: &Anchor) -> (i32, i32) {
+        match anchor {
+            Anchor::Format1(anchor) => (anchor.x as i32, anchor.y as i32),
+            Anchor::Format2(anchor) => (anchor.x as i32, anchor.y as i32),
+            Anchor::Format3(anchor) => (
+                anchor.x as i32 + self.correct(&anchor.x_correction),
+                anchor.y as i32 + self.correct(&anchor.y_correction),
+            ),
+        }
+    }
+
+    fn attach(&self, buffer: &mut Buffer, i: usize, j: usize, mark: &Anchor, base: &Anchor) {
+        let ((mark_x, mark_y), (base_x, base_y)) = (self.anchor(mark), self.anchor(base));
+        let glyph = &mut buffer.glyphs[i];
+        glyph.x_offset = base_x - mark_x;
+        glyph.y_offset = base_y - mark_y;
+        glyph.attach(Some(Attachment {
+            position: j,
+            cursive: false,
+        }));
+    }
+
+    fn base<F>(&self, buffer: &Buffer, i: usize, is_mark: F) -> Option<usize>
+    where
+        F: Fn(u16) -> bool,
+    {
+        (0..i).rev().find(|&j| {
+            let glyph_id = buffer.glyphs[j].glyph_id;
+            matching::class_of(self.definition, glyph_id) != 3 && !is_mark(glyph_id)
+        })
+    }
+
+    fn correct(&self, correction: &Option<Correction>) -> i32 {
+        let store = match self.definition.and_then(|value| value.variations.as_ref()) {
+            Some(store) if !self.coordinates.is_empty() => store,
+            _ => return 0,
+        };
+        correction
+            .as_ref()
+            .and_then(|correction| correction.evaluate(store, self.coordinates))
+            .map(|value| value.round() as i32)
+            .unwrap_or(0)
+    }
+}
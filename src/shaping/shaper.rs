@@ -0,0 +1,406 @@
+//! The shaping of text.
+
+use std::collections::{BTreeSet, HashMap};
+
+use truetype::tables::character_mapping::Encoding;
+use truetype::tables::{CharacterMapping, HorizontalHeader, HorizontalMetrics, MaximumProfile};
+use truetype::{q16, GlyphID};
+
+use crate::layout::{Feature, Language, Script};
+use crate::shaping::arabic::Arabic;
+use crate::shaping::hangul::Hangul;
+use crate::shaping::hebrew::Hebrew;
+use crate::shaping::indic::{self, Indic};
+use crate::shaping::plan::{Lookup, Plan, Planner, Setting};
+use crate::shaping::thai::Thai;
+use crate::shaping::universal::Universal;
+use crate::shaping::{closure, matching};
+use crate::shaping::{Buffer, Glyph, Positioning, Substitution};
+use crate::tables::{GlyphDefinition, GlyphPositioning, GlyphSubstitution};
+use crate::{Font, Result};
+
+/// The glyph-substitution features applied by default.
+const SUBSTITUTION_FEATURES: &[&[Feature]] = &[&[
+    Feature::GlyphCompositionDecomposition,
+    Feature::LocalizedForms,
+    Feature::RequiredLigatures,
+    Feature::ContextualAlternates,
+    Feature::ContextualLigatures,
+    Feature::StandardLigatures,
+    Feature::RequiredContextualAlternates,
+]];
+
+/// The glyph-positioning features applied by default.
+const POSITIONING_FEATURES: &[&[Feature]] = &[&[
+    Feature::AboveBaseMarkPositioning,
+    Feature::BelowBaseMarkPositioning,
+    Feature::MarkPositioning,
+    Feature::MarkToMarkPositioning,
+    Feature::CursivePositioning,
+    Feature::Distances,
+    Feature::Kerning,
+]];
+
+/// A shaper of text.
+///
+/// The tables needed for shaping are read once and can then be used for shaping any number of
+/// strings.
+#[derive(Clone, Debug)]
+pub struct Shaper {
+    /// The character-to-glyph mapping.
+    pub mapping: HashMap<u32, GlyphID>,
+    /// The horizontal metrics.
+    pub metrics: HorizontalMetrics,
+    /// The glyph definition if present.
+    pub definition: Option<GlyphDefinition>,
+    /// The glyph substitution if present.
+    pub substitution: Option<GlyphSubstitution>,
+    /// The glyph positioning if present.
+    pub positioning: Option<GlyphPositioning>,
+}
+
+/// A script-specific shaper.
+///
+/// The glyph-substitution stages start with one for required variation alternates, which is
+/// followed by the stages given by the shaper, the first of which also receives the directional
+/// features.
+pub(crate) trait Complex {
+    /// Return the features enabled per glyph, whose positions are the bits of glyph masks.
+    fn masked(&self) -> &'static [Feature] {
+        &[]
+    }
+
+    /// Return the features applied within syllables.
+    fn syllabic(&self) -> &'static [Feature] {
+        &[]
+    }
+
+    /// Return the stages of glyph-substitution features.
+    fn substitution(&self) -> &'static [&'static [Feature]] {
+        SUBSTITUTION_FEATURES
+    }
+
+    /// Prepare a buffer whose glyphs correspond to the characters one to one.
+    fn prepare(&self, _: &Shaper, _: &Plan, _: &[char], _: &mut Buffer) {}
+
+    /// Process a buffer after a stage of glyph substitution.
+    fn pause(&self, _: &Shaper, _: &Plan, _: usize, _: &mut Buffer) {}
+
+    /// Process a buffer after glyph positioning and before its reversal for right-to-left text.
+    fn finish(&self, _: &Shaper, _: &str, _: &mut Buffer) {}
+}
+
+/// A shaper for scripts without specific needs.
+struct Simple;
+
+/// Shaping options.
+#[derive(Clone, Debug)]
+pub struct Options<'l> {
+    /// The script.
+    pub script: Script,
+    /// The language or the default language system of the script if absent.
+    pub language: Option<Language>,
+    /// The feature settings overriding or extending the default ones.
+    pub features: Vec<Setting>,
+    /// The normalized variation coordinates.
+    pub coordinates: &'l [q16],
+    /// The flag indicating right-to-left text.
+    pub right_to_left: bool,
+}
+
+impl Shaper {
+    /// Read the tables of a font.
+    pub fn read<T>(font: &Font, tape: &mut T) -> Result<Self>
+    where
+        T: crate::tape::Read,
+    {
+        let mapping = match font.take::<_, CharacterMapping>(tape)? {
+            Some(table) => map(&table),
+            _ => raise!("found no character-to-glyph mapping"),
+        };
+        let header = match font.take::<_, HorizontalHeader>(tape)? {
+            Some(table) => table,
+            _ => raise!("found no horizontal header"),
+        };
+        let profile = match font.take::<_, MaximumProfile>(tape)? {
+            Some(table) => table,
+            _ => raise!("found no maximum profile"),
+        };
+        let metrics = match font.take_given::<_, HorizontalMetrics>(tape, (&header, &profile))? {
+            Some(table) => table,
+            _ => raise!("found no horizontal metrics"),
+        };
+        Ok(Self {
+            mapping,
+            metrics,
+            definition: font.take(tape)?,
+            substitution: font.take(tape)?,
+            positioning: font.take(tape)?,
+        })
+    }
+
+    /// Shape a string.
+    ///
+    /// The clusters of the resulting glyphs are the byte offsets of the corresponding characters
+    /// in the string, and the advances and offsets are in font units. Right-to-left text is
+    /// returned in visual order, that is, reversed.
+    pub fn shape(&self, text: &str, options: &Options) -> Result<Buffer> {
+        let plan = self.plan(options);
+        let complex = select(plan.script);
+        let mut buffer = Buffer::default();
+        let mut characters = Vec::with_capacity(text.len());
+        for (cluster, character) in text.char_indices() {
+            let glyph_id = self.mapping.get(&(character as u32)).copied();
+            buffer.push(glyph_id.unwrap_or(0), cluster);
+            characters.push(character);
+        }
+        complex.prepare(self, &plan, &characters, &mut buffer);
+        let (masked, syllabic) = (complex.masked(), complex.syllabic());
+        let mut engine = self
+            .substitution
+            .as_ref()
+            .map(|table| Substitution::new(table, self.definition.as_ref()));
+        for (i, stage) in plan.substitution.iter().enumerate() {
+            if let Some(engine) = engine.as_mut() {
+                for lookup in &stage.lookups {
+                    let value = lookup.settings.iter().map(|setting| setting.value);
+                    engine.alternate = value.max().unwrap_or(1).saturating_sub(1) as usize;
+                    let mask = mask(lookup, masked);
+                    let select = |glyph: &Glyph| mask.is_none_or(|mask| glyph.mask() & mask != 0);
+                    if confined(lookup, syllabic) {
+                        buffer.each_syllable(|buffer| {
+                            engine.apply_where(buffer, lookup.index, select)
+                        })?;
+                    } else {
+                        engine.apply_where(&mut buffer, lookup.index, select)?;
+                    }
+                }
+            }
+            complex.pause(self, &plan, i, &mut buffer);
+        }
+        for glyph in buffer.glyphs.iter_mut() {
+            glyph.x_advance = match matching::class_of(self.definition.as_ref(), glyph.glyph_id) {
+                3 => 0,
+                _ => self.metrics.get(glyph.glyph_id).0 as i32,
+            };
+        }
+        if let Some(table) = self.positioning.as_ref() {
+            let engine = Positioning {
+                table,
+                definition: self.definition.as_ref(),
+                coordinates: options.coordinates,
+                right_to_left: options.right_to_left,
+            };
+            for lookup in plan.positioning.iter().flat_map(|stage| &stage.lookups) {
+                let mask = mask(lookup, masked);
+                engine.apply_where(&mut buffer, lookup.index, |glyph| {
+                    mask.is_none_or(|mask| glyph.mask() & mask != 0)
+                })?;
+            }
+            engine.resolve(&mut buffer);
+        }
+        complex.finish(self, text, &mut buffer);
+        if options.right_to_left {
+            buffer.glyphs.reverse();
+        }
+        Ok(buffer)
+    }
+
+    /// Plan the lookups for shaping options.
+    ///
+    /// The script is resolved to the version supported by the font, which is recorded in the
+    /// plan and used for selecting the shaper of complex scripts.
+    pub fn plan(&self, options: &Options) -> Plan {
+        let script = self.script(options.script);
+        let mut planner = Planner::new(script, options.language);
+        planner.coordinates = options.coordinates;
+        let (first, second) = match options.right_to_left {
+            false => (
+                Feature::LeftToRightAlternates,
+                Feature::LeftToRightMirroredForms,
+            ),
+            _ => (
+                Feature::RightToLeftAlternates,
+                Feature::RightToLeftMirroredForms,
+            ),
+        };
+        let mut substitution = vec![vec![Setting::on(Feature::RequiredVariationAlternates)]];
+        substitution.extend(stages(select(script).substitution()));
+        if let Some(stage) = substitution.get_mut(1) {
+            stage.splice(0..0, [Setting::on(first), Setting::on(second)]);
+        }
+        planner.substitution = customize(substitution, &options.features);
+        planner.positioning = customize(stages(POSITIONING_FEATURES), &options.features);
+        planner.plan(self.substitution.as_ref(), self.positioning.as_ref())
+    }
+
+    /// Return the glyphs that shaping a string can produce, which are the glyphs of its
+    /// characters and those reachable from them via the glyph-substitution lookups planned for
+    /// the options.
+    pub fn closure(&self, text: &str, options: &Options) -> BTreeSet<GlyphID> {
+        let glyph_ids = text
+            .chars()
+            .map(|character| self.mapping.get(&(character as u32)).copied().unwrap_or(0));
+        match self.substitution.as_ref() {
+            Some(table) => closure::closure(table, &self.plan(options), glyph_ids),
+            _ => glyph_ids.collect(),
+        }
+    }
+
+    /// Check if a feature substitutes a sequence of glyphs when applied on its own.
+    pub(crate) fn substitutes(&self, plan: &Plan, feature: Feature, glyph_ids: &[GlyphID]) -> bool {
+        let table = match self.substitution.as_ref() {
+            Some(table) => table,
+            _ => return false,
+        };
+        let engine = Substitution::new(table, self.definition.as_ref());
+        plan.substitution
+            .iter()
+            .flat_map(|stage| &stage.lookups)
+            .filter(|lookup| {
+                lookup
+                    .settings
+                    .iter()
+                    .any(|setting| setting.feature == feature)
+            })
+            .any(|lookup| {
+                let mut buffer = Buffer::new(glyph_ids.iter().copied());
+                engine.apply(&mut buffer, lookup.index).is_ok() && buffer.glyph_ids() != glyph_ids
+            })
+    }
+
+    /// Choose the script tag to use, which for Indic scripts and Myanmar is the one of the new
+    /// specification if supported by the font and the one of the old specification otherwise.
+    fn script(&self, script: Script) -> Script {
+        let (old, new) = match script {
+            Script::Myanmar | Script::MyanmarV2 => (Script::Myanmar, Script::MyanmarV2),
+            _ => match indic::versions(script) {
+                Some(versions) => versions,
+                _ => return script,
+            },
+        };
+        let supported = self
+            .substitution
+            .as_ref()
+            .is_some_and(|table| table.scripts.get(new).is_some())
+            || self
+                .positioning
+                .as_ref()
+                .is_some_and(|table| table.scripts.get(new).is_some());
+        match supported {
+            true => new,
+            _ => old,
+        }
+    }
+}
+
+impl<'l> Options<'l> {
+    /// Create options for left-to-right text.
+    pub fn new(script: Script) -> Self {
+        Self {
+            script,
+            language: None,
+            features: vec![],
+            coordinates: &[],
+            right_to_left: false,
+        }
+    }
+}
+
+/// Shape a string using the tables of a font.
+pub fn shape<T>(font: &Font, tape: &mut T, text: &str, options: &Options) -> Result<Buffer>
+where
+    T: crate::tape::Read,
+{
+    Shaper::read(font, tape)?.shape(text, options)
+}
+
+fn confined(lookup: &Lookup, syllabic: &[Feature]) -> bool {
+    !lookup.settings.is_empty()
+        && lookup
+            .settings
+            .iter()
+            .all(|setting| syllabic.contains(&setting.feature))
+}
+
+fn customize(mut stages: Vec<Vec<Setting>>, features: &[Setting]) -> Vec<Vec<Setting>> {
+    for setting in features {
+        let value = stages
+            .iter_mut()
+            .flatten()
+            .find(|value| value.feature == setting.feature);
+        match value {
+            Some(value) => value.value = setting.value,
+            _ => {
+                if let Some(stage) = stages.last_mut() {
+                    stage.push(*setting);
+                }
+            }
+        }
+    }
+    stages
+}
+
+fn mask(lookup: &Lookup, masked: &[Feature]) -> Option<u32> {
+    let mut value = 0;
+    for setting in &lookup.settings {
+        match masked
+            .iter()
+            .position(|feature| *feature == setting.feature)
+        {
+            Some(i) => value |= 1 << i,
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+fn map(table: &CharacterMapping) -> HashMap<u32, GlyphID> {
+    let mut candidates = table
+        .records
+        .iter()
+        .zip(&table.encodings)
+        .filter_map(|(record, encoding)| {
+            let priority = match (record.platform_id, record.encoding_id, encoding) {
+                (3, 10, Encoding::Format12(_)) | (0, 4 | 6, Encoding::Format12(_)) => 0,
+                (3, 1, Encoding::Format4(_)) | (0, 0..=3, Encoding::Format4(_)) => 1,
+                (3, 1 | 10, _) | (0, 0..=6, _) => 2,
+                _ => return None,
+            };
+            Some((priority, encoding))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|(priority, _)| *priority);
+    match candidates.first().map(|(_, encoding)| encoding) {
+        Some(Encoding::Format0(encoding)) => encoding.mapping::<u32>(),
+        Some(Encoding::Format4(encoding)) => encoding.mapping::<u32>(),
+        Some(Encoding::Format6(encoding)) => encoding.mapping::<u32>(),
+        Some(Encoding::Format12(encoding)) => encoding.mapping::<u32>(),
+        _ => HashMap::new(),
+    }
+}
+
+fn select(script: Script) -> &'static dyn Complex {
+    match script {
+        Script::Arabic | Script::Mongolian | Script::NKo | Script::Phagspa | Script::Syriac => {
+            &Arabic
+        }
+        Script::Hangul | Script::HangulJamo => &Hangul,
+        Script::Hebrew => &Hebrew,
+        Script::Lao | Script::Thai => &Thai,
+        _ => match (Indic::find(script), Universal::find(script)) {
+            (Some(indic), _) => indic,
+            (_, Some(universal)) => universal,
+            _ => &Simple,
+        },
+    }
+}
+
+fn stages(features: &[&[Feature]]) -> Vec<Vec<Setting>> {
+    features
+        .iter()
+        .map(|stage| stage.iter().copied().map(Setting::on).collect())
+        .collect()
+}
+
+impl Complex for Simple {}
@@ -0,0 +1,243 @@
+//! The application of glyph substitutions.
+
+use crate::layout::context::Action;
+use crate::shaping::matching::{self, Filter, MAX_DEPTH};
+use crate::shaping::{Buffer, Glyph};
+use crate::tables::glyph_substitution::{SingleSubstitution, Type};
+use crate::tables::{GlyphDefinition, GlyphSubstitution};
+use crate::Result;
+
+/// An engine applying glyph substitutions.
+#[derive(Clone, Copy)]
+pub struct Substitution<'l> {
+    /// The glyph substitution.
+    pub table: &'l GlyphSubstitution,
+    /// The glyph definition used for filtering glyphs.
+    pub definition: Option<&'l GlyphDefinition>,
+    /// The index of the alternate chosen by alternate substitutions.
+    pub alternate: usize,
+}
+
+impl<'l> Substitution<'l> {
+    /// Create an engine.
+    #[inline]
+    pub fn new(table: &'l GlyphSubstitution, definition: Option<&'l GlyphDefinition>) -> Self {
+        Self {
+            table,
+            definition,
+            alternate: 0,
+        }
+    }
+
+    /// Apply a lookup to a buffer.
+    pub fn apply(&self, buffer: &mut Buffer, lookup_index: u16) -> Result<()> {
+        self.apply_where(buffer, lookup_index, |_| true)
+    }
+
+    /// Apply a lookup to the glyphs of a buffer selected by a predicate.
+    pub(crate) fn apply_where<F>(
+        &self,
+        buffer: &mut Buffer,
+        lookup_index: u16,
+        select: F,
+    ) -> Result<()>
+    where
+        F: Fn(&Glyph) -> bool,
+    {
+        let record = match self.table.lookups.records.get(lookup_index as usize) {
+            Some(record) => record,
+            _ => raise!("found no glyph substitution with index {lookup_index}"),
+        };
+        let filter = Filter::new(record, self.definition);
+        if record.effective_type() == 8 {
+            for i in (0..buffer.len()).rev() {
+                let glyph = &buffer.glyphs[i];
+                if !select(glyph) || filter.skips(glyph.glyph_id) {
+                    continue;
+                }
+                for table in &record.tables {
+                    if self.apply_table(buffer, &filter, table, i, 0)?.is_some() {
+                        break;
+                    }
+                }
+            }
+            return Ok(());
+        }
+        let mut i = 0;
+        while i < buffer.len() {
+            let (glyph, length) = (&buffer.glyphs[i], buffer.len());
+            let mut next = None;
+            if select(glyph) && !filter.skips(glyph.glyph_id) {
+                for table in &record.tables {
+                    next = self.apply_table(buffer, &filter, table, i, 0)?;
+                    if next.is_some() {
+                        break;
+                    }
+                }
+            }
+            i = match next {
+                Some(next) if next > i || buffer.len() < length => next,
+                _ => i + 1,
+            };
+        }
+        Ok(())
+    }
+
+    fn apply_at(
+        &self,
+        buffer: &mut Buffer,
+        lookup_index: u16,
+        position: usize,
+        depth: usize,
+    ) -> Result<bool> {
+        if depth > MAX_DEPTH {
+            return Ok(false);
+        }
+        let record = match self.table.lookups.records.get(lookup_index as usize) {
+            Some(record) => record,
+            _ => raise!("found no glyph substitution with index {lookup_index}"),
+        };
+        let filter = Filter::new(record, self.definition);
+        if filter.skips(buffer.glyphs[position].glyph_id) {
+            return Ok(false);
+        }
+        for table in &record.tables {
+            if self
+                .apply_table(buffer, &filter, table, position, depth)?
+                .is_some()
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn apply_table(
+        &self,
+        buffer: &mut Buffer,
+        filter: &Filter,
+        table: &Type,
+        position: usize,
+        depth: usize,
+    ) -> Result<Option<usize>> {
+        let matched = match table {
+            Type::ContextualSubstitution(table) => {
+                matching::match_context(table, filter, &buffer.glyphs, position)
+            }
+            Type::ChainedContextualSubstitution(table) => {
+                matching::match_chained_context(table, filter, &buffer.glyphs, position)
+            }
+            _ => return Ok(self.apply_simple(buffer, filter, table, position)),
+        };
+        match matched {
+            Some((positions, actions)) => self
+                .apply_actions(buffer, &positions, actions, depth)
+                .map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn apply_simple(
+        &self,
+        buffer: &mut Buffer,
+        filter: &Filter,
+        table: &Type,
+        position: usize,
+    ) -> Option<usize> {
+        let glyph_id = buffer.glyphs[position].glyph_id;
+        match table {
+            Type::SingleSubstitution(SingleSubstitution::Format1(table)) => {
+                table.coverage.index_of(glyph_id)?;
+                buffer.glyphs[position].glyph_id =
+                    glyph_id.wrapping_add_signed(table.delta_glyph_id);
+            }
+            Type::SingleSubstitution(SingleSubstitution::Format2(table)) => {
+                let index = table.coverage.index_of(glyph_id)?;
+                buffer.glyphs[position].glyph_id = *table.glyph_ids.get(index as usize)?;
+            }
+            Type::MultipleSubstitution(table) => {
+                let index = table.coverage.index_of(glyph_id)?;
+                let glyph_ids = &table.records.get(index as usize)?.glyph_ids;
+                buffer.expand(position, glyph_ids);
+                for glyph in &mut buffer.glyphs[position..(position + glyph_ids.len())] {
+                    glyph.set_substituted(true);
+                }
+                return Some(position + glyph_ids.len());
+            }
+            Type::AlternateSubstitution(table) => {
+                let index = table.coverage.index_of(glyph_id)?;
+                let glyph_ids = &table.records.get(index as usize)?.glyph_ids;
+                buffer.glyphs[position].glyph_id = *glyph_ids.get(self.alternate)?;
+            }
+            Type::LigatureSubstitution(table) => {
+                let index = table.coverage.index_of(glyph_id)?;
+                let (positions, glyph_id) = table
+                    .records
+                    .get(index as usize)?
+                    .records
+                    .iter()
+                    .find_map(|record| {
+                        let rule = (&[][..], &record.glyph_ids[..], &[][..]);
+                        matching::match_rule(
+                            filter,
+                            &buffer.glyphs,
+                            position,
+                            rule,
+                            |_, &value, glyph_id| value == glyph_id,
+                        )
+                        .map(|positions| (positions, record.glyph_id))
+                    })?;
+                buffer.ligate(&positions, glyph_id);
+            }
+            Type::ReverseChainedContextualSubstibution(table) => {
+                let index = table.coverage.index_of(glyph_id)?;
+                let rule = (
+                    &table.backward_coverages[..],
+                    &[][..],
+                    &table.forward_coverages[..],
+                );
+                matching::match_rule(
+                    filter,
+                    &buffer.glyphs,
+                    position,
+                    rule,
+                    |_, coverage, glyph_id| coverage.index_of(glyph_id).is_some(),
+                )?;
+                buffer.glyphs[position].glyph_id = *table.glyph_ids.get(index as usize)?;
+            }
+            _ => return None,
+        }
+        buffer.glyphs[position].set_substituted(true);
+        Some(position + 1)
+    }
+
+    fn apply_actions(
+        &self,
+        buffer: &mut Buffer,
+        positions: &[usize],
+        actions: &[Action],
+        depth: usize,
+    ) -> Result<usize> {
+        let serials = positions
+            .iter()
+            .map(|&position| buffer.glyphs[position].serial())
+            .collect::<Vec<_>>();
+        let end = positions[positions.len() - 1] + 1;
+        let (length, following) = (
+            buffer.len(),
+            buffer.glyphs.get(end).map(|glyph| glyph.serial()),
+        );
+        for action in actions {
+            let position = serials
+                .get(action.position_index as usize)
+                .and_then(|&serial| buffer.find(serial));
+            if let Some(position) = position {
+                self.apply_at(buffer, action.lookup_index, position, depth + 1)?;
+            }
+        }
+        Ok(match following.and_then(|serial| buffer.find(serial)) {
+            Some(position) => position,
+            _ => (end + buffer.len()).saturating_sub(length),
+        })
+    }
+}
@@ -0,0 +1,219 @@
+//! The shaping of Thai and Lao.
+
+use crate::shaping::shaper::{Complex, Shaper};
+use crate::shaping::{Buffer, Plan};
+
+/// A shaper for Thai and Lao.
+///
+/// The sara am is decomposed into a nikhahit and a sara aa, and the nikhahit is moved before
+/// the preceding above-base marks. For fonts without glyph substitutions for Thai, marks and
+/// consonants are replaced with the positional variants in the private use area, if present.
+pub(crate) struct Thai;
+
+/// A kind of consonant.
+#[derive(Clone, Copy)]
+enum Consonant {
+    Normal,
+    Ascender,
+    RemovableDescender,
+    Descender,
+    Other,
+}
+
+/// A kind of mark.
+#[derive(Clone, Copy)]
+enum Mark {
+    AboveVowel,
+    BelowVowel,
+    Tone,
+}
+
+/// An adjustment of a glyph.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Action {
+    Keep,
+    ShiftDown,
+    ShiftDownLeft,
+    ShiftLeft,
+    RemoveDescender,
+}
+
+/// The transitions for above-base marks given the state and the kind of mark.
+const ABOVE: [[(Action, usize); 3]; 4] = [
+    [(Action::Keep, 3), (Action::Keep, 0), (Action::ShiftDown, 3)],
+    [
+        (Action::ShiftLeft, 2),
+        (Action::Keep, 1),
+        (Action::ShiftDownLeft, 2),
+    ],
+    [(Action::Keep, 3), (Action::Keep, 2), (Action::ShiftLeft, 3)],
+    [(Action::Keep, 3), (Action::Keep, 3), (Action::Keep, 3)],
+];
+
+/// The transitions for below-base marks given the state and the kind of mark.
+const BELOW: [[(Action, usize); 3]; 3] = [
+    [(Action::Keep, 0), (Action::Keep, 2), (Action::Keep, 0)],
+    [
+        (Action::Keep, 1),
+        (Action::RemoveDescender, 2),
+        (Action::Keep, 1),
+    ],
+    [(Action::Keep, 2), (Action::ShiftDown, 2), (Action::Keep, 2)],
+];
+
+/// The variants in the private use area used by Windows and macOS for each adjustment.
+#[rustfmt::skip]
+const VARIANTS: &[(Action, char, char, char)] = &[
+    (Action::ShiftDown, '\u{0E48}', '\u{F70A}', '\u{F88B}'),
+    (Action::ShiftDown, '\u{0E49}', '\u{F70B}', '\u{F88E}'),
+    (Action::ShiftDown, '\u{0E4A}', '\u{F70C}', '\u{F891}'),
+    (Action::ShiftDown, '\u{0E4B}', '\u{F70D}', '\u{F894}'),
+    (Action::ShiftDown, '\u{0E4C}', '\u{F70E}', '\u{F897}'),
+    (Action::ShiftDown, '\u{0E38}', '\u{F718}', '\u{F89B}'),
+    (Action::ShiftDown, '\u{0E39}', '\u{F719}', '\u{F89C}'),
+    (Action::ShiftDown, '\u{0E3A}', '\u{F71A}', '\u{F89D}'),
+    (Action::ShiftDownLeft, '\u{0E48}', '\u{F705}', '\u{F88C}'),
+    (Action::ShiftDownLeft, '\u{0E49}', '\u{F706}', '\u{F88F}'),
+    (Action::ShiftDownLeft, '\u{0E4A}', '\u{F707}', '\u{F892}'),
+    (Action::ShiftDownLeft, '\u{0E4B}', '\u{F708}', '\u{F895}'),
+    (Action::ShiftDownLeft, '\u{0E4C}', '\u{F709}', '\u{F898}'),
+    (Action::ShiftLeft, '\u{0E48}', '\u{F713}', '\u{F88A}'),
+    (Action::ShiftLeft, '\u{0E49}', '\u{F714}', '\u{F88D}'),
+    (Action::ShiftLeft, '\u{0E4A}', '\u{F715}', '\u{F890}'),
+    (Action::ShiftLeft, '\u{0E4B}', '\u{F716}', '\u{F893}'),
+    (Action::ShiftLeft, '\u{0E4C}', '\u{F717}', '\u{F896}'),
+    (Action::ShiftLeft, '\u{0E31}', '\u{F710}', '\u{F884}'),
+    (Action::ShiftLeft, '\u{0E34}', '\u{F701}', '\u{F885}'),
+    (Action::ShiftLeft, '\u{0E35}', '\u{F702}', '\u{F886}'),
+    (Action::ShiftLeft, '\u{0E36}', '\u{F703}', '\u{F887}'),
+    (Action::ShiftLeft, '\u{0E37}', '\u{F704}', '\u{F888}'),
+    (Action::ShiftLeft, '\u{0E47}', '\u{F712}', '\u{F889}'),
+    (Action::ShiftLeft, '\u{0E4D}', '\u{F711}', '\u{F899}'),
+    (Action::RemoveDescender, '\u{0E0D}', '\u{F70F}', '\u{F89A}'),
+    (Action::RemoveDescender, '\u{0E10}', '\u{F700}', '\u{F89E}'),
+];
+
+impl Complex for Thai {
+    fn prepare(&self, shaper: &Shaper, plan: &Plan, characters: &[char], buffer: &mut Buffer) {
+        let mut characters = characters.to_vec();
+        decompose(shaper, &mut characters, buffer);
+        let supported = shaper
+            .substitution
+            .as_ref()
+            .is_some_and(|table| table.scripts.get(plan.script).is_some());
+        if !supported {
+            substitute(shaper, &characters, buffer);
+        }
+    }
+}
+
+#[inline]
+fn above_base(character: char) -> bool {
+    matches!(
+        character as u32 & !0x80,
+        0x0E31 | 0x0E34..=0x0E37 | 0x0E3B | 0x0E47..=0x0E4E
+    )
+}
+
+fn consonant(character: char) -> Consonant {
+    match character {
+        '\u{0E1B}' | '\u{0E1D}' | '\u{0E1F}' => Consonant::Ascender,
+        '\u{0E0D}' | '\u{0E10}' => Consonant::RemovableDescender,
+        '\u{0E0E}' | '\u{0E0F}' => Consonant::Descender,
+        '\u{0E01}'..='\u{0E2E}' => Consonant::Normal,
+        _ => Consonant::Other,
+    }
+}
+
+/// Decompose each sara am and move the resulting nikhahit before the preceding above-base marks.
+fn decompose(shaper: &Shaper, characters: &mut Vec<char>, buffer: &mut Buffer) {
+    let glyph_id = |character: char| {
+        let glyph_id = shaper.mapping.get(&(character as u32));
+        glyph_id.copied().unwrap_or(0)
+    };
+    let mut i = 0;
+    while i < characters.len() {
+        let value = characters[i] as u32;
+        if value & !0x80 != 0x0E33 {
+            i += 1;
+            continue;
+        }
+        let parts = [
+            char::from_u32(value + 0x1A).unwrap_or_default(),
+            char::from_u32(value - 1).unwrap_or_default(),
+        ];
+        buffer.expand(i, &[glyph_id(parts[0]), glyph_id(parts[1])]);
+        characters.splice(i..(i + 1), parts);
+        let mut start = i;
+        while start > 0 && above_base(characters[start - 1]) {
+            start -= 1;
+        }
+        if start < i {
+            let cluster = buffer.glyphs[start..(i + 2)]
+                .iter()
+                .map(|glyph| glyph.cluster)
+                .min()
+                .unwrap_or(0);
+            for glyph in &mut buffer.glyphs[start..(i + 2)] {
+                glyph.cluster = cluster;
+            }
+            characters[start..=i].rotate_right(1);
+            buffer.glyphs[start..=i].rotate_right(1);
+        }
+        i += 2;
+    }
+}
+
+fn mark(character: char) -> Option<Mark> {
+    Some(match character {
+        '\u{0E31}' | '\u{0E34}'..='\u{0E37}' | '\u{0E47}' | '\u{0E4D}' | '\u{0E4E}' => {
+            Mark::AboveVowel
+        }
+        '\u{0E38}'..='\u{0E3A}' => Mark::BelowVowel,
+        '\u{0E48}'..='\u{0E4C}' => Mark::Tone,
+        _ => return None,
+    })
+}
+
+/// Replace glyphs with the variants in the private use area needed for positioning marks.
+fn substitute(shaper: &Shaper, characters: &[char], buffer: &mut Buffer) {
+    let variant = |character: char, action: Action| {
+        VARIANTS
+            .iter()
+            .filter(|&&(value, original, _, _)| value == action && original == character)
+            .flat_map(|&(_, _, windows, macos)| [windows, macos])
+            .find_map(|variant| shaper.mapping.get(&(variant as u32)).copied())
+    };
+    let (mut above, mut below, mut base) = (3, 2, 0);
+    for (i, &character) in characters.iter().enumerate() {
+        let mark = match mark(character) {
+            Some(mark) => mark as usize,
+            _ => {
+                (above, below) = match consonant(character) {
+                    Consonant::Normal => (0, 0),
+                    Consonant::Ascender => (1, 0),
+                    Consonant::RemovableDescender => (0, 1),
+                    Consonant::Descender => (0, 2),
+                    Consonant::Other => (3, 2),
+                };
+                base = i;
+                continue;
+            }
+        };
+        let (above_action, next) = ABOVE[above][mark];
+        let (below_action, below_next) = BELOW[below][mark];
+        (above, below) = (next, below_next);
+        let action = match above_action {
+            Action::Keep => below_action,
+            _ => above_action,
+        };
+        let position = if action == Action::RemoveDescender {
+            base
+        } else {
+            i
+        };
+        if let Some(glyph_id) = variant(characters[position], action) {
+            buffer.glyphs[position].glyph_id = glyph_id;
+        }
+    }
+}
@@ -0,0 +1,983 @@
+//! The shaping of scripts handled by the universal shaping engine.
+
+use crate::layout::{Feature, Script};
+use crate::shaping::shaper::{Complex, Shaper};
+use crate::shaping::{Buffer, Glyph, Plan};
+
+/// A shaper for Khmer, Myanmar, Balinese, Javanese, Tibetan, and other complex scripts.
+pub(crate) struct Universal;
+
+/// The scripts handled by the shaper.
+const SCRIPTS: &[Script] = &[
+    Script::Ahom,
+    Script::Balinese,
+    Script::Batak,
+    Script::Bhaiksuki,
+    Script::Brahmi,
+    Script::Buginese,
+    Script::Buhid,
+    Script::Chakma,
+    Script::Cham,
+    Script::Grantha,
+    Script::Hanunoo,
+    Script::Javanese,
+    Script::Kaithi,
+    Script::KayahLi,
+    Script::Kharosthi,
+    Script::Khmer,
+    Script::Khojki,
+    Script::Khudawadi,
+    Script::Lepcha,
+    Script::Limbu,
+    Script::Mahajani,
+    Script::Marchen,
+    Script::MeiteiMayek,
+    Script::Miao,
+    Script::Modi,
+    Script::Multani,
+    Script::Myanmar,
+    Script::MyanmarV2,
+    Script::Newa,
+    Script::Rejang,
+    Script::Saurashtra,
+    Script::Sharada,
+    Script::Siddham,
+    Script::Sinhala,
+    Script::Sundanese,
+    Script::SylotiNagri,
+    Script::Tagalog,
+    Script::Tagbanwa,
+    Script::TaiLe,
+    Script::TaiTham,
+    Script::TaiViet,
+    Script::Takri,
+    Script::Tibetan,
+    Script::Tifinagh,
+    Script::Tirhuta,
+];
+
+/// The features enabled per glyph, whose positions are the mask bits of the features.
+const MASKED: &[Feature] = &[
+    Feature::RephForms,
+    Feature::IsolatedForms,
+    Feature::TerminalForms1,
+    Feature::MedialForms1,
+    Feature::InitialForms,
+];
+
+const RPHF: u32 = 1 << 0;
+const ISOL: u32 = 1 << 1;
+const FINA: u32 = 1 << 2;
+const MEDI: u32 = 1 << 3;
+const INIT: u32 = 1 << 4;
+
+/// The features applied within syllables.
+const SYLLABIC: &[Feature] = &[
+    Feature::LocalizedForms,
+    Feature::GlyphCompositionDecomposition,
+    Feature::NuktaForms,
+    Feature::Akhand,
+    Feature::RephForms,
+    Feature::PreBaseForms,
+    Feature::RakarForms,
+    Feature::AboveBaseForms,
+    Feature::BelowBaseForms,
+    Feature::HalfForms,
+    Feature::PostBaseForms,
+    Feature::VattuVariants,
+    Feature::ConjunctForms,
+];
+
+/// The stage of the basic features preceding the reph forms.
+const BASIC_STAGE: usize = 1;
+/// The stage of the reph forms.
+const REPH_STAGE: usize = 2;
+/// The stage of the pre-base forms.
+const PREF_STAGE: usize = 3;
+/// The stage after which syllables are reordered.
+const REORDERING_STAGE: usize = 4;
+
+const O: u8 = 0;
+const B: u8 = 1;
+const GB: u8 = 2;
+const SUB: u8 = 3;
+const H: u8 = 4;
+const IS: u8 = 5;
+const HVM: u8 = 6;
+const ZWNJ: u8 = 7;
+const ZWJ: u8 = 8;
+const CGJ: u8 = 9;
+const R: u8 = 10;
+const CM_ABV: u8 = 11;
+const CM_BLW: u8 = 12;
+const M_PRE: u8 = 13;
+const M_ABV: u8 = 14;
+const M_BLW: u8 = 15;
+const M_PST: u8 = 16;
+const V_PRE: u8 = 17;
+const V_ABV: u8 = 18;
+const V_BLW: u8 = 19;
+const V_PST: u8 = 20;
+const VM_PRE: u8 = 21;
+const VM_ABV: u8 = 22;
+const VM_BLW: u8 = 23;
+const VM_PST: u8 = 24;
+const F_ABV: u8 = 25;
+const F_BLW: u8 = 26;
+const F_PST: u8 = 27;
+
+const STANDARD_CLUSTER: u8 = 1;
+const VIRAMA_TERMINATED_CLUSTER: u8 = 2;
+const BROKEN_CLUSTER: u8 = 3;
+const NON_CLUSTER: u8 = 4;
+
+impl Universal {
+    /// Return the shaper of a script.
+    pub fn find(script: Script) -> Option<&'static Self> {
+        SCRIPTS.contains(&script).then_some(&Self)
+    }
+}
+
+impl Complex for Universal {
+    fn masked(&self) -> &'static [Feature] {
+        MASKED
+    }
+
+    fn syllabic(&self) -> &'static [Feature] {
+        SYLLABIC
+    }
+
+    fn substitution(&self) -> &'static [&'static [Feature]] {
+        &[
+            &[
+                Feature::LocalizedForms,
+                Feature::GlyphCompositionDecomposition,
+                Feature::NuktaForms,
+                Feature::Akhand,
+            ],
+            &[Feature::RephForms],
+            &[Feature::PreBaseForms],
+            &[
+                Feature::RakarForms,
+                Feature::AboveBaseForms,
+                Feature::BelowBaseForms,
+                Feature::HalfForms,
+                Feature::PostBaseForms,
+                Feature::VattuVariants,
+                Feature::ConjunctForms,
+            ],
+            &[
+                Feature::IsolatedForms,
+                Feature::InitialForms,
+                Feature::MedialForms1,
+                Feature::TerminalForms1,
+            ],
+            &[
+                Feature::AboveBaseSubstitutions,
+                Feature::BelowBaseSubstitutions,
+                Feature::HalantForms,
+                Feature::PreBaseSubstitutions,
+                Feature::PostBaseSubstitutions,
+                Feature::RequiredLigatures,
+                Feature::ContextualAlternates,
+                Feature::ContextualLigatures,
+                Feature::StandardLigatures,
+                Feature::RequiredContextualAlternates,
+            ],
+        ]
+    }
+
+    fn prepare(&self, _: &Shaper, _: &Plan, characters: &[char], buffer: &mut Buffer) {
+        for (glyph, &character) in buffer.glyphs.iter_mut().zip(characters) {
+            glyph.set_category(classify(character));
+        }
+        segment(buffer);
+        let ranges = buffer.syllables();
+        let mut forms = vec![0; ranges.len()];
+        for (i, range) in ranges.iter().enumerate() {
+            if kind(&buffer.glyphs[range.start]) == NON_CLUSTER {
+                continue;
+            }
+            forms[i] = match i.checked_sub(1).map(|j| forms[j]) {
+                Some(FINA) => {
+                    forms[i - 1] = MEDI;
+                    FINA
+                }
+                Some(ISOL) => {
+                    forms[i - 1] = INIT;
+                    FINA
+                }
+                _ => ISOL,
+            };
+        }
+        for (range, form) in ranges.into_iter().zip(forms) {
+            let limit = match buffer.glyphs[range.start].category() {
+                R => 1,
+                _ => range.len().min(3),
+            };
+            for (i, glyph) in buffer.glyphs[range].iter_mut().enumerate() {
+                let reph = if i < limit { RPHF } else { 0 };
+                glyph.set_mask(glyph.mask() | reph | form);
+            }
+        }
+    }
+
+    fn pause(&self, shaper: &Shaper, _: &Plan, stage: usize, buffer: &mut Buffer) {
+        match stage {
+            BASIC_STAGE => {}
+            REPH_STAGE => {
+                for range in buffer.syllables() {
+                    for glyph in buffer.glyphs[range].iter_mut() {
+                        if glyph.mask() & RPHF == 0 {
+                            break;
+                        }
+                        if glyph.substituted() {
+                            glyph.set_category(R);
+                            break;
+                        }
+                    }
+                }
+            }
+            PREF_STAGE => {
+                for range in buffer.syllables() {
+                    let glyph = buffer.glyphs[range]
+                        .iter_mut()
+                        .find(|glyph| glyph.substituted());
+                    if let Some(glyph) = glyph {
+                        glyph.set_category(V_PRE);
+                    }
+                }
+            }
+            REORDERING_STAGE => reorder(shaper, buffer),
+            _ => return,
+        }
+        for glyph in buffer.glyphs.iter_mut() {
+            glyph.set_substituted(false);
+        }
+    }
+}
+
+fn classify(character: char) -> u8 {
+    match character {
+        '\u{034F}' => return CGJ,
+        '\u{200C}' => return ZWNJ,
+        '\u{200D}' => return ZWJ,
+        '\u{00A0}'
+        | '\u{00D7}'
+        | '\u{2010}'..='\u{2014}'
+        | '\u{2022}'
+        | '\u{25CC}'
+        | '\u{25FB}'..='\u{25FE}' => return GB,
+        _ => {}
+    }
+    let value = character as u32;
+    CATEGORIES
+        .binary_search_by(|&(start, end, _)| {
+            if end < value {
+                std::cmp::Ordering::Less
+            } else if start > value {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .map(|i| CATEGORIES[i].2)
+        .unwrap_or(O)
+}
+
+#[inline]
+fn halant(glyph: &Glyph) -> bool {
+    matches!(glyph.category(), H | HVM | IS) && glyph.ligature_id == 0
+}
+
+#[inline]
+fn kind(glyph: &Glyph) -> u8 {
+    glyph.syllable() & 0x0F
+}
+
+/// Set the clusters of glyphs to the smallest one among them.
+fn merge(glyphs: &mut [Glyph]) {
+    let cluster = glyphs.iter().map(|glyph| glyph.cluster).min().unwrap_or(0);
+    for glyph in glyphs.iter_mut() {
+        glyph.cluster = cluster;
+    }
+}
+
+#[inline]
+fn post_base(glyph: &Glyph) -> bool {
+    matches!(
+        glyph.category(),
+        F_ABV
+            | F_BLW
+            | F_PST
+            | M_ABV
+            | M_BLW
+            | M_PST
+            | V_ABV
+            | V_BLW
+            | V_PST
+            | VM_ABV
+            | VM_BLW
+            | VM_PST
+    )
+}
+
+/// Insert dotted circles into broken clusters and reorder the glyphs of syllables.
+fn reorder(shaper: &Shaper, buffer: &mut Buffer) {
+    if let Some(&glyph_id) = shaper.mapping.get(&0x25CC) {
+        for range in buffer.syllables().into_iter().rev() {
+            let glyph = &buffer.glyphs[range.start];
+            if kind(glyph) != BROKEN_CLUSTER {
+                continue;
+            }
+            let (syllable, cluster) = (glyph.syllable(), glyph.cluster);
+            let position = match glyph.category() {
+                R => range.start + 1,
+                _ => range.start,
+            };
+            let mask = buffer.glyphs[position.min(range.end - 1)].mask();
+            buffer.insert(position, glyph_id, cluster);
+            let glyph = &mut buffer.glyphs[position];
+            glyph.set_category(B);
+            glyph.set_mask(mask);
+            glyph.set_syllable(syllable);
+        }
+    }
+    for range in buffer.syllables() {
+        if matches!(
+            kind(&buffer.glyphs[range.start]),
+            STANDARD_CLUSTER | VIRAMA_TERMINATED_CLUSTER | BROKEN_CLUSTER
+        ) {
+            reorder_syllable(&mut buffer.glyphs[range]);
+        }
+    }
+}
+
+/// Move a reph toward the end of a syllable and pre-base vowels toward its start.
+fn reorder_syllable(glyphs: &mut [Glyph]) {
+    let end = glyphs.len();
+    if glyphs[0].category() == R && end > 1 {
+        for i in 1..end {
+            let post = post_base(&glyphs[i]) || halant(&glyphs[i]);
+            if post || i == end - 1 {
+                let i = if post { i - 1 } else { i };
+                merge(&mut glyphs[..=i]);
+                glyphs[..=i].rotate_left(1);
+                break;
+            }
+        }
+    }
+    let mut j = 0;
+    for i in 0..end {
+        if halant(&glyphs[i]) {
+            j = i + 1;
+        } else if matches!(glyphs[i].category(), V_PRE | VM_PRE)
+            && glyphs[i].component == 0
+            && j < i
+        {
+            merge(&mut glyphs[j..=i]);
+            glyphs[j..=i].rotate_right(1);
+        }
+    }
+}
+
+/// Assign syllables to the glyphs of a buffer, where joiners are attached to the preceding
+/// syllable.
+fn segment(buffer: &mut Buffer) {
+    let visible = buffer
+        .glyphs
+        .iter()
+        .enumerate()
+        .filter(|(_, glyph)| !matches!(glyph.category(), ZWJ | CGJ))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let categories = visible
+        .iter()
+        .map(|&i| buffer.glyphs[i].category())
+        .collect::<Vec<_>>();
+    let (mut i, mut start, mut serial) = (0, 0, 0);
+    while start < buffer.len() {
+        let (end, kind) = match i < categories.len() {
+            true => syllable(&categories, i),
+            _ => (i, NON_CLUSTER),
+        };
+        let stop = visible.get(end).copied().unwrap_or(buffer.len());
+        serial = serial % 15 + 1;
+        for glyph in &mut buffer.glyphs[start..stop] {
+            glyph.set_syllable(serial << 4 | kind);
+        }
+        (i, start) = (end, stop);
+    }
+}
+
+/// Find the end and kind of the syllable starting at a position.
+///
+/// The grammar is the one of the universal shaping engine restricted to the categories in use:
+/// standard clusters, virama-terminated clusters, and broken clusters, each optionally followed
+/// by a zero-width non-joiner, where the longest match is taken.
+fn syllable(categories: &[u8], i: usize) -> (usize, u8) {
+    let is = |i: usize, set: &[u8]| categories.get(i).is_some_and(|value| set.contains(value));
+    let optional = |i: usize, set: &[u8]| if is(i, set) { i + 1 } else { i };
+    let many = |mut i: usize, set: &[u8]| {
+        while is(i, set) {
+            i += 1;
+        }
+        i
+    };
+    let modifiers = |i: usize| {
+        let mut i = many(many(i, &[CM_ABV]), &[CM_BLW]);
+        loop {
+            let j = if is(i, &[H, HVM, IS]) && is(i + 1, &[B]) {
+                i + 2
+            } else if is(i, &[SUB]) {
+                i + 1
+            } else {
+                break;
+            };
+            i = many(optional(j, &[CM_ABV]), &[CM_BLW]);
+        }
+        i
+    };
+    let tail = |i: usize| {
+        let mut i = modifiers(i);
+        for set in [M_PRE, M_ABV, M_BLW, M_PST] {
+            i = optional(i, &[set]);
+        }
+        for set in [V_PRE, V_ABV, V_BLW, V_PST] {
+            i = many(i, &[set]);
+        }
+        i = optional(i, &[HVM]);
+        for set in [VM_PRE, VM_ABV, VM_BLW, VM_PST, F_ABV, F_BLW, F_PST] {
+            i = many(i, &[set]);
+        }
+        i
+    };
+    let terminated = |i: usize| {
+        let i = modifiers(i);
+        is(i, &[H, HVM, IS]).then_some(i + 1)
+    };
+    let mut candidates = vec![];
+    let j = optional(i, &[R]);
+    if is(j, &[B, GB]) {
+        candidates.push((tail(j + 1), STANDARD_CLUSTER));
+        if let Some(end) = terminated(j + 1) {
+            candidates.push((end, VIRAMA_TERMINATED_CLUSTER));
+        }
+    }
+    let end = tail(j).max(terminated(j).unwrap_or(j));
+    if end > i {
+        candidates.push((end, BROKEN_CLUSTER));
+    }
+    candidates
+        .into_iter()
+        .rev()
+        .max_by_key(|&(end, _)| end)
+        .map(|(end, kind)| (optional(end, &[ZWNJ]), kind))
+        .unwrap_or((i + 1, NON_CLUSTER))
+}
+
+/// The categories of characters.
+#[rustfmt::skip]
+const CATEGORIES: &[(u32, u32, u8)] = &[
+    (0x0D81, 0x0D81, VM_ABV),
+    (0x0D82, 0x0D83, VM_PST),
+    (0x0D85, 0x0D96, B),
+    (0x0D9A, 0x0DB1, B),
+    (0x0DB3, 0x0DBB, B),
+    (0x0DBD, 0x0DBD, B),
+    (0x0DC0, 0x0DC6, B),
+    (0x0DCA, 0x0DCA, VM_ABV),
+    (0x0DCF, 0x0DD1, V_PST),
+    (0x0DD2, 0x0DD4, V_ABV),
+    (0x0DD6, 0x0DD6, V_ABV),
+    (0x0DD8, 0x0DD8, V_PST),
+    (0x0DD9, 0x0DDE, V_PRE),
+    (0x0DDF, 0x0DDF, V_PST),
+    (0x0DF2, 0x0DF3, V_PST),
+    (0x0F00, 0x0F00, B),
+    (0x0F18, 0x0F19, VM_ABV),
+    (0x0F35, 0x0F35, VM_ABV),
+    (0x0F37, 0x0F37, VM_ABV),
+    (0x0F39, 0x0F39, VM_ABV),
+    (0x0F3E, 0x0F3F, VM_PST),
+    (0x0F40, 0x0F47, B),
+    (0x0F49, 0x0F6C, B),
+    (0x0F71, 0x0F73, V_ABV),
+    (0x0F74, 0x0F79, V_BLW),
+    (0x0F7A, 0x0F7D, V_ABV),
+    (0x0F7E, 0x0F7E, VM_ABV),
+    (0x0F7F, 0x0F7F, VM_PST),
+    (0x0F80, 0x0F81, V_ABV),
+    (0x0F82, 0x0F83, VM_ABV),
+    (0x0F84, 0x0F84, H),
+    (0x0F86, 0x0F87, VM_ABV),
+    (0x0F88, 0x0F8C, B),
+    (0x0F8D, 0x0F97, SUB),
+    (0x0F99, 0x0FBC, SUB),
+    (0x0FC6, 0x0FC6, VM_ABV),
+    (0x1000, 0x102A, B),
+    (0x102B, 0x102C, V_PST),
+    (0x102D, 0x102E, V_ABV),
+    (0x102F, 0x1030, V_BLW),
+    (0x1031, 0x1031, V_PRE),
+    (0x1032, 0x1035, V_ABV),
+    (0x1036, 0x1036, VM_ABV),
+    (0x1037, 0x1037, VM_BLW),
+    (0x1038, 0x1038, VM_PST),
+    (0x1039, 0x1039, H),
+    (0x103A, 0x103A, HVM),
+    (0x103B, 0x103B, M_PST),
+    (0x103C, 0x103C, M_PRE),
+    (0x103D, 0x103E, M_BLW),
+    (0x103F, 0x103F, B),
+    (0x1050, 0x1055, B),
+    (0x1056, 0x1057, V_PST),
+    (0x1058, 0x1059, V_BLW),
+    (0x105A, 0x105D, B),
+    (0x105E, 0x1060, M_BLW),
+    (0x1061, 0x1061, B),
+    (0x1062, 0x1062, V_PST),
+    (0x1063, 0x1064, VM_PST),
+    (0x1065, 0x1066, B),
+    (0x1067, 0x1068, V_PST),
+    (0x1069, 0x106D, VM_PST),
+    (0x106E, 0x1070, B),
+    (0x1071, 0x1072, V_ABV),
+    (0x1073, 0x1073, V_BLW),
+    (0x1074, 0x1074, V_ABV),
+    (0x1075, 0x1081, B),
+    (0x1082, 0x1082, M_BLW),
+    (0x1083, 0x1083, V_PST),
+    (0x1084, 0x1084, V_PRE),
+    (0x1085, 0x1085, V_ABV),
+    (0x1086, 0x1086, F_ABV),
+    (0x1087, 0x108C, VM_PST),
+    (0x108D, 0x108D, VM_ABV),
+    (0x108E, 0x108E, B),
+    (0x108F, 0x108F, VM_PST),
+    (0x109A, 0x109B, VM_PST),
+    (0x109C, 0x109C, V_PST),
+    (0x109D, 0x109D, V_ABV),
+    (0x1700, 0x1711, B),
+    (0x1712, 0x1712, V_ABV),
+    (0x1713, 0x1713, V_BLW),
+    (0x1714, 0x1714, H),
+    (0x1715, 0x1715, VM_PST),
+    (0x171F, 0x1731, B),
+    (0x1732, 0x1732, V_ABV),
+    (0x1733, 0x1733, V_BLW),
+    (0x1734, 0x1734, VM_PST),
+    (0x1740, 0x1751, B),
+    (0x1752, 0x1752, V_ABV),
+    (0x1753, 0x1753, V_BLW),
+    (0x1760, 0x176C, B),
+    (0x176E, 0x1770, B),
+    (0x1772, 0x1772, V_ABV),
+    (0x1773, 0x1773, V_BLW),
+    (0x1780, 0x17B3, B),
+    (0x17B4, 0x17B5, V_ABV),
+    (0x17B6, 0x17B6, V_PST),
+    (0x17B7, 0x17BA, V_ABV),
+    (0x17BB, 0x17BC, V_BLW),
+    (0x17BD, 0x17BD, V_ABV),
+    (0x17BE, 0x17C5, V_PRE),
+    (0x17C6, 0x17C6, VM_ABV),
+    (0x17C7, 0x17C8, VM_PST),
+    (0x17C9, 0x17D1, VM_ABV),
+    (0x17D2, 0x17D2, IS),
+    (0x17D3, 0x17D3, VM_ABV),
+    (0x17D7, 0x17D7, B),
+    (0x17DC, 0x17DC, B),
+    (0x17DD, 0x17DD, VM_ABV),
+    (0x1900, 0x191E, B),
+    (0x1920, 0x1921, V_ABV),
+    (0x1922, 0x1922, V_BLW),
+    (0x1923, 0x1926, V_PST),
+    (0x1927, 0x1928, V_ABV),
+    (0x1929, 0x192B, SUB),
+    (0x1930, 0x1931, VM_PST),
+    (0x1932, 0x1932, VM_ABV),
+    (0x1933, 0x1938, VM_PST),
+    (0x1939, 0x193B, VM_ABV),
+    (0x1950, 0x196D, B),
+    (0x1970, 0x1974, B),
+    (0x1A00, 0x1A16, B),
+    (0x1A17, 0x1A17, V_ABV),
+    (0x1A18, 0x1A18, V_BLW),
+    (0x1A19, 0x1A19, V_PRE),
+    (0x1A1A, 0x1A1A, V_PST),
+    (0x1A1B, 0x1A1B, V_PRE),
+    (0x1A20, 0x1A54, B),
+    (0x1A55, 0x1A55, M_PRE),
+    (0x1A56, 0x1A56, M_BLW),
+    (0x1A57, 0x1A57, M_PST),
+    (0x1A58, 0x1A58, VM_ABV),
+    (0x1A59, 0x1A59, F_ABV),
+    (0x1A5A, 0x1A5E, M_BLW),
+    (0x1A60, 0x1A60, VM_ABV),
+    (0x1A61, 0x1A61, V_PST),
+    (0x1A62, 0x1A62, V_ABV),
+    (0x1A63, 0x1A64, V_PST),
+    (0x1A65, 0x1A66, V_ABV),
+    (0x1A67, 0x1A6A, V_BLW),
+    (0x1A6B, 0x1A6B, V_ABV),
+    (0x1A6C, 0x1A6C, V_BLW),
+    (0x1A6D, 0x1A6D, V_PST),
+    (0x1A6E, 0x1A72, V_PRE),
+    (0x1A73, 0x1A73, V_ABV),
+    (0x1A74, 0x1A7C, VM_ABV),
+    (0x1A7F, 0x1A7F, VM_ABV),
+    (0x1AA7, 0x1AA7, B),
+    (0x1B00, 0x1B02, VM_ABV),
+    (0x1B03, 0x1B03, F_ABV),
+    (0x1B04, 0x1B04, VM_PST),
+    (0x1B05, 0x1B33, B),
+    (0x1B34, 0x1B34, CM_ABV),
+    (0x1B35, 0x1B35, V_PST),
+    (0x1B36, 0x1B37, V_ABV),
+    (0x1B38, 0x1B39, V_BLW),
+    (0x1B3A, 0x1B3A, V_ABV),
+    (0x1B3B, 0x1B3B, V_PST),
+    (0x1B3C, 0x1B3C, V_ABV),
+    (0x1B3D, 0x1B3D, V_PST),
+    (0x1B3E, 0x1B43, V_PRE),
+    (0x1B44, 0x1B44, H),
+    (0x1B45, 0x1B4C, B),
+    (0x1B6B, 0x1B73, VM_ABV),
+    (0x1B80, 0x1B81, VM_ABV),
+    (0x1B82, 0x1B82, VM_PST),
+    (0x1B83, 0x1BA0, B),
+    (0x1BA1, 0x1BA1, M_PST),
+    (0x1BA2, 0x1BA3, M_BLW),
+    (0x1BA4, 0x1BA5, V_ABV),
+    (0x1BA6, 0x1BA6, V_PRE),
+    (0x1BA7, 0x1BA7, V_PST),
+    (0x1BA8, 0x1BA9, V_ABV),
+    (0x1BAA, 0x1BAB, H),
+    (0x1BAC, 0x1BAD, M_BLW),
+    (0x1BAE, 0x1BAF, B),
+    (0x1BBA, 0x1BE5, B),
+    (0x1BE6, 0x1BE6, VM_ABV),
+    (0x1BE7, 0x1BE7, V_PST),
+    (0x1BE8, 0x1BE9, V_ABV),
+    (0x1BEA, 0x1BEC, V_PST),
+    (0x1BED, 0x1BED, V_ABV),
+    (0x1BEE, 0x1BEE, V_PST),
+    (0x1BEF, 0x1BEF, V_ABV),
+    (0x1BF0, 0x1BF1, M_BLW),
+    (0x1BF2, 0x1BF3, VM_PST),
+    (0x1C00, 0x1C23, B),
+    (0x1C24, 0x1C25, SUB),
+    (0x1C26, 0x1C26, V_PST),
+    (0x1C27, 0x1C28, V_PRE),
+    (0x1C29, 0x1C2B, V_PST),
+    (0x1C2C, 0x1C2C, V_ABV),
+    (0x1C2D, 0x1C33, M_BLW),
+    (0x1C34, 0x1C35, M_PST),
+    (0x1C36, 0x1C36, VM_ABV),
+    (0x1C37, 0x1C37, CM_BLW),
+    (0x1C4D, 0x1C4F, B),
+    (0x2D30, 0x2D67, B),
+    (0x2D6F, 0x2D6F, B),
+    (0x2D7F, 0x2D7F, VM_ABV),
+    (0xA800, 0xA801, B),
+    (0xA802, 0xA802, VM_ABV),
+    (0xA803, 0xA805, B),
+    (0xA806, 0xA806, VM_ABV),
+    (0xA807, 0xA80A, B),
+    (0xA80B, 0xA80B, VM_ABV),
+    (0xA80C, 0xA822, B),
+    (0xA823, 0xA824, V_PST),
+    (0xA825, 0xA825, V_BLW),
+    (0xA826, 0xA826, V_ABV),
+    (0xA827, 0xA827, V_PST),
+    (0xA82C, 0xA82C, VM_ABV),
+    (0xA880, 0xA881, VM_PST),
+    (0xA882, 0xA8B3, B),
+    (0xA8B4, 0xA8B4, M_PST),
+    (0xA8B5, 0xA8C3, V_PST),
+    (0xA8C4, 0xA8C4, H),
+    (0xA8C5, 0xA8C5, VM_ABV),
+    (0xA90A, 0xA925, B),
+    (0xA926, 0xA926, V_BLW),
+    (0xA927, 0xA927, V_ABV),
+    (0xA928, 0xA928, V_BLW),
+    (0xA929, 0xA92A, V_ABV),
+    (0xA92B, 0xA92D, VM_ABV),
+    (0xA930, 0xA946, B),
+    (0xA947, 0xA947, V_ABV),
+    (0xA948, 0xA948, V_BLW),
+    (0xA949, 0xA94E, V_ABV),
+    (0xA94F, 0xA951, M_BLW),
+    (0xA952, 0xA952, M_PST),
+    (0xA953, 0xA953, H),
+    (0xA980, 0xA981, VM_ABV),
+    (0xA982, 0xA982, F_ABV),
+    (0xA983, 0xA983, VM_PST),
+    (0xA984, 0xA9B2, B),
+    (0xA9B3, 0xA9B3, VM_ABV),
+    (0xA9B4, 0xA9B5, V_PST),
+    (0xA9B6, 0xA9B7, V_ABV),
+    (0xA9B8, 0xA9B9, V_BLW),
+    (0xA9BA, 0xA9BB, V_PRE),
+    (0xA9BC, 0xA9BC, V_ABV),
+    (0xA9BD, 0xA9BD, M_BLW),
+    (0xA9BE, 0xA9BF, M_PST),
+    (0xA9C0, 0xA9C0, H),
+    (0xA9CF, 0xA9CF, B),
+    (0xA9E0, 0xA9E4, B),
+    (0xA9E5, 0xA9E5, VM_ABV),
+    (0xA9E6, 0xA9EF, B),
+    (0xA9FA, 0xA9FE, B),
+    (0xAA00, 0xAA28, B),
+    (0xAA29, 0xAA2C, V_ABV),
+    (0xAA2D, 0xAA2D, V_BLW),
+    (0xAA2E, 0xAA2E, V_ABV),
+    (0xAA2F, 0xAA30, V_PRE),
+    (0xAA31, 0xAA31, V_ABV),
+    (0xAA32, 0xAA32, V_BLW),
+    (0xAA33, 0xAA33, M_PST),
+    (0xAA34, 0xAA34, M_PRE),
+    (0xAA35, 0xAA36, M_BLW),
+    (0xAA40, 0xAA42, B),
+    (0xAA43, 0xAA43, F_ABV),
+    (0xAA44, 0xAA4B, B),
+    (0xAA4C, 0xAA4C, F_ABV),
+    (0xAA4D, 0xAA4D, F_PST),
+    (0xAA60, 0xAA76, B),
+    (0xAA7A, 0xAA7A, B),
+    (0xAA7B, 0xAA7B, VM_PST),
+    (0xAA7C, 0xAA7C, VM_ABV),
+    (0xAA7D, 0xAA7D, VM_PST),
+    (0xAA7E, 0xAAAF, B),
+    (0xAAB0, 0xAAB0, VM_ABV),
+    (0xAAB1, 0xAAB1, B),
+    (0xAAB2, 0xAAB2, V_ABV),
+    (0xAAB3, 0xAAB4, V_BLW),
+    (0xAAB5, 0xAAB6, B),
+    (0xAAB7, 0xAAB7, VM_ABV),
+    (0xAAB8, 0xAAB8, V_ABV),
+    (0xAAB9, 0xAABD, B),
+    (0xAABE, 0xAABE, V_ABV),
+    (0xAABF, 0xAABF, VM_ABV),
+    (0xAAC0, 0xAAC0, B),
+    (0xAAC1, 0xAAC1, VM_ABV),
+    (0xAAC2, 0xAAC2, B),
+    (0xAADB, 0xAADD, B),
+    (0xAAE0, 0xAAEA, B),
+    (0xAAEB, 0xAAEB, V_PRE),
+    (0xAAEC, 0xAAEC, V_BLW),
+    (0xAAED, 0xAAED, V_ABV),
+    (0xAAEE, 0xAAEE, V_PRE),
+    (0xAAEF, 0xAAEF, V_PST),
+    (0xAAF2, 0xAAF4, B),
+    (0xAAF5, 0xAAF5, V_PST),
+    (0xAAF6, 0xAAF6, H),
+    (0xABC0, 0xABE2, B),
+    (0xABE3, 0xABE4, V_PST),
+    (0xABE5, 0xABE5, V_ABV),
+    (0xABE6, 0xABE7, V_PST),
+    (0xABE8, 0xABE8, V_ABV),
+    (0xABE9, 0xABEA, V_PST),
+    (0xABEC, 0xABEC, VM_PST),
+    (0xABED, 0xABED, VM_ABV),
+    (0x10A00, 0x10A00, B),
+    (0x10A01, 0x10A01, V_ABV),
+    (0x10A02, 0x10A03, V_BLW),
+    (0x10A05, 0x10A06, V_ABV),
+    (0x10A0C, 0x10A0C, V_ABV),
+    (0x10A0D, 0x10A0D, VM_BLW),
+    (0x10A0E, 0x10A0F, VM_ABV),
+    (0x10A10, 0x10A13, B),
+    (0x10A15, 0x10A17, B),
+    (0x10A19, 0x10A35, B),
+    (0x10A38, 0x10A39, VM_ABV),
+    (0x10A3A, 0x10A3A, VM_BLW),
+    (0x10A3F, 0x10A3F, H),
+    (0x11000, 0x11000, VM_PST),
+    (0x11001, 0x11001, VM_ABV),
+    (0x11002, 0x11002, VM_PST),
+    (0x11003, 0x11037, B),
+    (0x11038, 0x1103B, V_ABV),
+    (0x1103C, 0x11041, V_BLW),
+    (0x11042, 0x11045, V_ABV),
+    (0x11046, 0x11046, H),
+    (0x11070, 0x11070, H),
+    (0x11071, 0x11072, B),
+    (0x11073, 0x11074, V_ABV),
+    (0x11075, 0x11075, B),
+    (0x1107F, 0x11081, VM_ABV),
+    (0x11082, 0x11082, VM_PST),
+    (0x11083, 0x110AF, B),
+    (0x110B0, 0x110B0, V_PST),
+    (0x110B1, 0x110B1, V_PRE),
+    (0x110B2, 0x110B2, V_PST),
+    (0x110B3, 0x110B4, V_BLW),
+    (0x110B5, 0x110B6, V_ABV),
+    (0x110B7, 0x110B8, V_PST),
+    (0x110B9, 0x110B9, H),
+    (0x110BA, 0x110BA, CM_BLW),
+    (0x110C2, 0x110C2, V_BLW),
+    (0x11100, 0x11102, VM_ABV),
+    (0x11103, 0x11126, B),
+    (0x11127, 0x11129, V_ABV),
+    (0x1112A, 0x1112B, V_BLW),
+    (0x1112C, 0x1112C, V_PRE),
+    (0x1112D, 0x11130, V_ABV),
+    (0x11131, 0x11132, VM_ABV),
+    (0x11133, 0x11133, H),
+    (0x11134, 0x11134, VM_ABV),
+    (0x11144, 0x11144, B),
+    (0x11145, 0x11146, V_PST),
+    (0x11147, 0x11147, B),
+    (0x11150, 0x11172, B),
+    (0x11173, 0x11173, CM_BLW),
+    (0x11176, 0x11176, B),
+    (0x11180, 0x11181, VM_ABV),
+    (0x11182, 0x11182, VM_PST),
+    (0x11183, 0x111B2, B),
+    (0x111B3, 0x111B3, V_PST),
+    (0x111B4, 0x111B4, V_PRE),
+    (0x111B5, 0x111B5, V_PST),
+    (0x111B6, 0x111BB, V_BLW),
+    (0x111BC, 0x111BE, V_ABV),
+    (0x111BF, 0x111BF, V_PST),
+    (0x111C0, 0x111C0, H),
+    (0x111C1, 0x111C4, B),
+    (0x111C9, 0x111C9, VM_ABV),
+    (0x111CA, 0x111CA, CM_BLW),
+    (0x111CB, 0x111CC, V_ABV),
+    (0x111CE, 0x111CE, V_PST),
+    (0x111CF, 0x111CF, VM_ABV),
+    (0x111DA, 0x111DA, B),
+    (0x111DC, 0x111DC, B),
+    (0x11200, 0x11211, B),
+    (0x11213, 0x1122B, B),
+    (0x1122C, 0x1122E, V_PST),
+    (0x1122F, 0x1122F, V_BLW),
+    (0x11230, 0x11231, V_ABV),
+    (0x11232, 0x11233, V_PST),
+    (0x11234, 0x11234, VM_ABV),
+    (0x11235, 0x11235, H),
+    (0x11236, 0x11236, CM_BLW),
+    (0x11237, 0x11237, VM_ABV),
+    (0x1123E, 0x1123E, VM_ABV),
+    (0x11280, 0x11286, B),
+    (0x11288, 0x11288, B),
+    (0x1128A, 0x1128D, B),
+    (0x1128F, 0x1129D, B),
+    (0x1129F, 0x112A8, B),
+    (0x112B0, 0x112DE, B),
+    (0x112DF, 0x112DF, VM_ABV),
+    (0x112E0, 0x112E2, V_PST),
+    (0x112E3, 0x112E4, V_BLW),
+    (0x112E5, 0x112E8, V_ABV),
+    (0x112E9, 0x112E9, CM_BLW),
+    (0x112EA, 0x112EA, H),
+    (0x11300, 0x11301, VM_ABV),
+    (0x11302, 0x11303, VM_PST),
+    (0x11305, 0x1130C, B),
+    (0x1130F, 0x11310, B),
+    (0x11313, 0x11328, B),
+    (0x1132A, 0x11330, B),
+    (0x11332, 0x11333, B),
+    (0x11335, 0x11339, B),
+    (0x1133C, 0x1133C, CM_BLW),
+    (0x1133D, 0x1133D, B),
+    (0x1133E, 0x1133F, V_PST),
+    (0x11340, 0x11340, V_ABV),
+    (0x11341, 0x11344, V_PST),
+    (0x11347, 0x11348, V_PRE),
+    (0x1134B, 0x1134C, V_PRE),
+    (0x1134D, 0x1134D, H),
+    (0x11350, 0x11350, B),
+    (0x11357, 0x11357, V_PST),
+    (0x1135D, 0x11361, B),
+    (0x11362, 0x11363, V_PST),
+    (0x11400, 0x11434, B),
+    (0x11435, 0x11435, V_PST),
+    (0x11436, 0x11436, V_PRE),
+    (0x11437, 0x11437, V_PST),
+    (0x11438, 0x1143D, V_BLW),
+    (0x1143E, 0x1143F, V_ABV),
+    (0x11440, 0x11441, V_PST),
+    (0x11442, 0x11442, H),
+    (0x11443, 0x11444, VM_ABV),
+    (0x11445, 0x11445, VM_PST),
+    (0x11446, 0x11446, CM_BLW),
+    (0x11447, 0x1144A, B),
+    (0x1145E, 0x1145E, VM_ABV),
+    (0x1145F, 0x11461, B),
+    (0x11480, 0x114AF, B),
+    (0x114B0, 0x114B2, V_PST),
+    (0x114B3, 0x114B8, V_BLW),
+    (0x114B9, 0x114B9, V_PRE),
+    (0x114BA, 0x114BA, V_ABV),
+    (0x114BB, 0x114BC, V_PRE),
+    (0x114BD, 0x114BD, V_PST),
+    (0x114BE, 0x114BE, V_PRE),
+    (0x114BF, 0x114C0, VM_ABV),
+    (0x114C1, 0x114C1, VM_PST),
+    (0x114C2, 0x114C2, H),
+    (0x114C3, 0x114C3, CM_BLW),
+    (0x114C4, 0x114C5, B),
+    (0x114C7, 0x114C7, B),
+    (0x11580, 0x115AE, B),
+    (0x115AF, 0x115B1, V_PST),
+    (0x115B2, 0x115B5, V_BLW),
+    (0x115B8, 0x115BB, V_PRE),
+    (0x115BC, 0x115BD, VM_ABV),
+    (0x115BE, 0x115BE, VM_PST),
+    (0x115BF, 0x115BF, H),
+    (0x115C0, 0x115C0, CM_BLW),
+    (0x115D8, 0x115DB, B),
+    (0x115DC, 0x115DD, V_BLW),
+    (0x11600, 0x1162F, B),
+    (0x11630, 0x11632, V_PST),
+    (0x11633, 0x11638, V_BLW),
+    (0x11639, 0x1163A, V_ABV),
+    (0x1163B, 0x1163C, V_PST),
+    (0x1163D, 0x1163D, VM_ABV),
+    (0x1163E, 0x1163E, VM_PST),
+    (0x1163F, 0x1163F, H),
+    (0x11640, 0x11640, VM_ABV),
+    (0x11644, 0x11644, B),
+    (0x11680, 0x116AA, B),
+    (0x116AB, 0x116AB, VM_ABV),
+    (0x116AC, 0x116AC, VM_PST),
+    (0x116AD, 0x116AD, V_ABV),
+    (0x116AE, 0x116AF, V_PST),
+    (0x116B0, 0x116B1, V_BLW),
+    (0x116B2, 0x116B5, V_ABV),
+    (0x116B6, 0x116B6, H),
+    (0x116B7, 0x116B7, CM_BLW),
+    (0x116B8, 0x116B8, B),
+    (0x11700, 0x1171A, B),
+    (0x1171D, 0x1171F, M_BLW),
+    (0x11720, 0x11721, V_PST),
+    (0x11722, 0x11723, V_ABV),
+    (0x11724, 0x11725, V_BLW),
+    (0x11726, 0x11726, V_PST),
+    (0x11727, 0x1172A, V_ABV),
+    (0x1172B, 0x1172B, H),
+    (0x11740, 0x11746, B),
+    (0x11C00, 0x11C08, B),
+    (0x11C0A, 0x11C2E, B),
+    (0x11C2F, 0x11C2F, V_PST),
+    (0x11C30, 0x11C31, V_ABV),
+    (0x11C32, 0x11C36, V_BLW),
+    (0x11C38, 0x11C3B, V_ABV),
+    (0x11C3C, 0x11C3D, VM_ABV),
+    (0x11C3E, 0x11C3E, VM_PST),
+    (0x11C3F, 0x11C3F, H),
+    (0x11C40, 0x11C40, B),
+    (0x11C72, 0x11C7F, B),
+    (0x16F00, 0x16F4A, B),
+    (0x16F4F, 0x16F4F, VM_ABV),
+    (0x16F50, 0x16F50, B),
+    (0x16F51, 0x16F53, VM_PST),
+    (0x16F54, 0x16F87, V_PST),
+    (0x16F8F, 0x16F91, VM_ABV),
+    (0x16F92, 0x16F92, VM_BLW),
+    (0x16F93, 0x16F9F, B),
+];
@@ -0,0 +1,130 @@
+//! The subsetting of the character-to-glyph mapping.
+
+use truetype::GlyphID;
+
+use crate::Result;
+
+/// Write a character-to-glyph mapping.
+///
+/// The characters of the Basic Multilingual Plane are mapped via a subtable of format 4, and all
+/// characters are additionally mapped via a subtable of format 12 if there are characters beyond
+/// it.
+pub(crate) fn write<T>(characters: T) -> Result<Vec<u8>>
+where
+    T: IntoIterator<Item = (u32, GlyphID)>,
+{
+    let mut characters = characters.into_iter().collect::<Vec<_>>();
+    characters.sort();
+    characters.dedup_by_key(|(character, _)| *character);
+    let groups = groups(&characters);
+    let basic = groups
+        .iter()
+        .filter(|(start, _, _)| *start < 0xFFFF)
+        .map(|&(start, end, glyph_id)| (start, end.min(0xFFFE), glyph_id))
+        .collect::<Vec<_>>();
+    let mut subtables = vec![];
+    if basic.len() < (u16::MAX as usize - 16) / 8 {
+        subtables.push(([(0, 3), (3, 1)], format4(&basic)));
+    }
+    let beyond = characters
+        .last()
+        .is_some_and(|&(character, _)| character > 0xFFFE);
+    if beyond || subtables.is_empty() {
+        subtables.push(([(0, 4), (3, 10)], format12(&groups)));
+    }
+    let mut records = subtables
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (keys, _))| keys.iter().map(move |&key| (key, i)))
+        .collect::<Vec<_>>();
+    records.sort();
+    let mut offsets = vec![];
+    let mut offset = 4 + 8 * records.len();
+    for (_, data) in subtables.iter() {
+        offsets.push(offset as u32);
+        offset += data.len();
+    }
+    let mut data = Vec::with_capacity(offset);
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    for ((platform_id, encoding_id), i) in records {
+        data.extend_from_slice(&(platform_id as u16).to_be_bytes());
+        data.extend_from_slice(&(encoding_id as u16).to_be_bytes());
+        data.extend_from_slice(&offsets[i].to_be_bytes());
+    }
+    for (_, subtable) in subtables {
+        data.extend(subtable);
+    }
+    Ok(data)
+}
+
+fn format4(groups: &[(u32, u32, GlyphID)]) -> Vec<u8> {
+    let mut segments = (groups.iter())
+        .map(|&(start, end, glyph_id)| {
+            let delta = (glyph_id as u32).wrapping_sub(start) as u16;
+            (start as u16, end as u16, delta)
+        })
+        .collect::<Vec<_>>();
+    segments.push((0xFFFF, 0xFFFF, 1));
+    let count = segments.len() as u16;
+    let selector = 15 - count.leading_zeros() as u16;
+    let range = 2 << selector;
+    let mut data = Vec::with_capacity(16 + 8 * segments.len());
+    for value in [
+        4,
+        16 + 8 * count,
+        0,
+        2 * count,
+        range,
+        selector,
+        2 * count - range,
+    ] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    for &(_, end, _) in segments.iter() {
+        data.extend_from_slice(&end.to_be_bytes());
+    }
+    data.extend_from_slice(&0u16.to_be_bytes());
+    for &(start, _, _) in segments.iter() {
+        data.extend_from_slice(&start.to_be_bytes());
+    }
+    for &(_, _, delta) in segments.iter() {
+        data.extend_from_slice(&delta.to_be_bytes());
+    }
+    for _ in segments.iter() {
+        data.extend_from_slice(&0u16.to_be_bytes());
+    }
+    data
+}
+
+fn format12(groups: &[(u32, u32, GlyphID)]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(16 + 12 * groups.len());
+    data.extend_from_slice(&12u16.to_be_bytes());
+    data.extend_from_slice(&0u16.to_be_bytes());
+    for value in [16 + 12 * groups.len() as u32, 0, groups.len() as u32] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    for &(start, end, glyph_id) in groups {
+        for value in [start, end, glyph_id as u32] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+    data
+}
+
+/// Group consecutive characters mapped to consecutive glyphs.
+fn groups(characters: &[(u32, GlyphID)]) -> Vec<(u32, u32, GlyphID)> {
+    let mut groups: Vec<(u32, u32, GlyphID)> = vec![];
+    for &(character, glyph_id) in characters {
+        match groups.last_mut() {
+            Some((start, end, first))
+                if character == *end + 1
+                    && glyph_id as u32 == *first as u32 + (character - *start) =>
+            {
+                *end = character;
+            }
+            _ => groups.push((character, character, glyph_id)),
+        }
+    }
+    groups
+}
@@ -0,0 +1,422 @@
+//! The subsetting of the compact font format.
+//!
+//! The outlines of the retained glyphs are copied as they are, and so are the global and local
+//! subroutines, which the outlines might refer to. The character set is rewritten in format 0,
+//! the font-dictionary selection in format 3, and the encoding is dropped.
+
+use truetype::GlyphID;
+
+use crate::subsetting::Mapping;
+use crate::Result;
+
+const CHARACTER_SET: u16 = 15;
+const CHARACTER_STRINGS: u16 = 17;
+const ENCODING: u16 = 16;
+const FONT_DICTIONARIES: u16 = 12 << 8 | 36;
+const FONT_DICTIONARY_SELECTION: u16 = 12 << 8 | 37;
+const PRIVATE: u16 = 18;
+const REGISTRY_ORDERING_SUPPLEMENT: u16 = 12 << 8 | 30;
+const SUBROUTINES: u16 = 19;
+
+type Dictionary = Vec<(u16, Vec<Vec<u8>>)>;
+
+/// Write a font set containing only the retained glyphs.
+pub(crate) fn write(data: &[u8], mapping: &Mapping) -> Result<Vec<u8>> {
+    let header_size = take(data, 2, 1)?[0] as usize;
+    let (names, position) = index(data, header_size)?;
+    let (dictionaries, position) = index(data, position)?;
+    let (strings, position) = index(data, position)?;
+    let (subroutines, _) = index(data, position)?;
+    if names.len() != 1 || dictionaries.len() != 1 {
+        raise!("found a font set with other than one font");
+    }
+    let dictionary = parse(dictionaries[0])?;
+    let (character_strings, _) = index(data, offset(&dictionary, CHARACTER_STRINGS)?)?;
+    let glyph_count = character_strings.len();
+    let character_set = character_set(data, &dictionary, glyph_count)?;
+    let keyed = dictionary
+        .iter()
+        .any(|(operator, _)| *operator == REGISTRY_ORDERING_SUPPLEMENT);
+
+    let mut privates = vec![];
+    let mut font_dictionaries = vec![];
+    let mut selection = vec![];
+    if keyed {
+        let (values, _) = index(data, offset(&dictionary, FONT_DICTIONARIES)?)?;
+        for value in values {
+            let value = parse(value)?;
+            privates.push(private(data, &value)?);
+            font_dictionaries.push(value);
+        }
+        let position = offset(&dictionary, FONT_DICTIONARY_SELECTION)?;
+        selection = font_dictionary_selection(data, position, glyph_count)?;
+    } else {
+        privates.push(private(data, &dictionary)?);
+    }
+    for &glyph_id in mapping.glyph_ids() {
+        if glyph_id as usize >= glyph_count {
+            raise!("found a glyph beyond the character strings");
+        }
+    }
+
+    let mut character_set_data = vec![0];
+    for &glyph_id in mapping.glyph_ids().iter().skip(1) {
+        character_set_data.extend_from_slice(&character_set[glyph_id as usize].to_be_bytes());
+    }
+    let mut selection_data = vec![];
+    if keyed {
+        let mut ranges: Vec<(GlyphID, u8)> = vec![];
+        for (i, &glyph_id) in mapping.glyph_ids().iter().enumerate() {
+            let value = selection[glyph_id as usize];
+            if ranges.last().is_none_or(|range| range.1 != value) {
+                ranges.push((i as GlyphID, value));
+            }
+        }
+        selection_data.push(3);
+        selection_data.extend_from_slice(&(ranges.len() as u16).to_be_bytes());
+        for (glyph_id, value) in ranges {
+            selection_data.extend_from_slice(&glyph_id.to_be_bytes());
+            selection_data.push(value);
+        }
+        selection_data.extend_from_slice(&(mapping.len() as u16).to_be_bytes());
+    }
+    let character_strings_data = write_index(
+        mapping
+            .glyph_ids()
+            .iter()
+            .map(|&glyph_id| character_strings[glyph_id as usize]),
+    );
+
+    // The offsets are encoded with a fixed size, which makes the size of the dictionaries
+    // independent of their values.
+    let mut private_data = vec![];
+    let mut private_ranges = vec![];
+    for (dictionary, subroutines) in privates.iter() {
+        let mut dictionary = dictionary.clone();
+        let mut size = encode(&dictionary).len();
+        if subroutines.is_some() {
+            set(&mut dictionary, SUBROUTINES, &[0]);
+            size = encode(&dictionary).len();
+            set(&mut dictionary, SUBROUTINES, &[size as i32]);
+        }
+        let start = private_data.len();
+        private_data.extend(encode(&dictionary));
+        if let Some(subroutines) = subroutines {
+            private_data.extend_from_slice(subroutines);
+        }
+        private_ranges.push((start, size));
+    }
+    let mut dictionary = dictionary;
+    dictionary.retain(|(operator, _)| *operator != ENCODING);
+    set(&mut dictionary, CHARACTER_SET, &[0]);
+    set(&mut dictionary, CHARACTER_STRINGS, &[0]);
+    if keyed {
+        set(&mut dictionary, FONT_DICTIONARY_SELECTION, &[0]);
+        set(&mut dictionary, FONT_DICTIONARIES, &[0]);
+        for value in font_dictionaries.iter_mut() {
+            set(value, PRIVATE, &[0, 0]);
+        }
+    } else {
+        set(&mut dictionary, PRIVATE, &[0, 0]);
+    }
+    let mut position = 4
+        + write_index(names.iter().copied()).len()
+        + write_index([encode(&dictionary).as_slice()]).len()
+        + write_index(strings.iter().copied()).len()
+        + write_index(subroutines.iter().copied()).len();
+    set(&mut dictionary, CHARACTER_SET, &[position as i32]);
+    position += character_set_data.len();
+    if keyed {
+        set(
+            &mut dictionary,
+            FONT_DICTIONARY_SELECTION,
+            &[position as i32],
+        );
+        position += selection_data.len();
+    }
+    set(&mut dictionary, CHARACTER_STRINGS, &[position as i32]);
+    position += character_strings_data.len();
+    let mut font_dictionaries_data = vec![];
+    if keyed {
+        set(&mut dictionary, FONT_DICTIONARIES, &[position as i32]);
+        let values = font_dictionaries.iter().map(encode).collect::<Vec<_>>();
+        position += write_index(values.iter().map(Vec::as_slice)).len();
+        let values = (font_dictionaries.iter_mut())
+            .zip(&private_ranges)
+            .map(|(value, (start, size))| {
+                set(value, PRIVATE, &[*size as i32, (position + start) as i32]);
+                encode(value)
+            })
+            .collect::<Vec<_>>();
+        font_dictionaries_data = write_index(values.iter().map(Vec::as_slice));
+    } else {
+        let (start, size) = private_ranges[0];
+        set(
+            &mut dictionary,
+            PRIVATE,
+            &[size as i32, (position + start) as i32],
+        );
+    }
+
+    let mut output = vec![1, 0, 4, 4];
+    output.extend(write_index(names.iter().copied()));
+    output.extend(write_index([encode(&dictionary).as_slice()]));
+    output.extend(write_index(strings.iter().copied()));
+    output.extend(write_index(subroutines.iter().copied()));
+    output.extend(character_set_data);
+    output.extend(selection_data);
+    output.extend(character_strings_data);
+    output.extend(font_dictionaries_data);
+    output.extend(private_data);
+    Ok(output)
+}
+
+/// Return the string identifiers of the glyphs.
+fn character_set(data: &[u8], dictionary: &Dictionary, glyph_count: usize) -> Result<Vec<u16>> {
+    let position = match value(dictionary, CHARACTER_SET)? {
+        Some(0) | None => return Ok((0..glyph_count as u16).collect()),
+        Some(1) | Some(2) => raise!("found an unsupported character set"),
+        Some(value) => value as usize,
+    };
+    let mut values = vec![0];
+    let format = take(data, position, 1)?[0];
+    let mut position = position + 1;
+    while values.len() < glyph_count {
+        match format {
+            0 => {
+                values.push(read_u16(data, position)?);
+                position += 2;
+            }
+            1 | 2 => {
+                let first = read_u16(data, position)?;
+                let left = match format {
+                    1 => take(data, position + 2, 1)?[0] as u16,
+                    _ => read_u16(data, position + 2)?,
+                };
+                position += if format == 1 { 3 } else { 4 };
+                for i in 0..=left {
+                    values.push(first.wrapping_add(i));
+                }
+            }
+            _ => raise!("found an unknown format of character sets ({format})"),
+        }
+    }
+    values.truncate(glyph_count);
+    Ok(values)
+}
+
+/// Encode a dictionary.
+fn encode(dictionary: &Dictionary) -> Vec<u8> {
+    let mut data = vec![];
+    for (operator, operands) in dictionary {
+        for operand in operands {
+            data.extend_from_slice(operand);
+        }
+        if *operator > 0xFF {
+            data.push(12);
+        }
+        data.push(*operator as u8);
+    }
+    data
+}
+
+/// Return the font dictionaries of the glyphs.
+fn font_dictionary_selection(data: &[u8], position: usize, glyph_count: usize) -> Result<Vec<u8>> {
+    match take(data, position, 1)?[0] {
+        0 => Ok(take(data, position + 1, glyph_count)?.to_vec()),
+        3 => {
+            let count = read_u16(data, position + 1)? as usize;
+            let mut values = vec![0; glyph_count];
+            for i in 0..count {
+                let start = position + 3 + 3 * i;
+                let first = read_u16(data, start)? as usize;
+                let value = take(data, start + 2, 1)?[0];
+                let last = read_u16(data, start + 3)? as usize;
+                for entry in values.iter_mut().take(last).skip(first) {
+                    *entry = value;
+                }
+            }
+            Ok(values)
+        }
+        format => raise!("found an unknown format of font-dictionary selection ({format})"),
+    }
+}
+
+/// Read an index and return its entries together with the position following it.
+fn index(data: &[u8], position: usize) -> Result<(Vec<&[u8]>, usize)> {
+    let count = read_u16(data, position)? as usize;
+    if count == 0 {
+        return Ok((vec![], position + 2));
+    }
+    let offset_size = take(data, position + 2, 1)?[0] as usize;
+    if !(1..=4).contains(&offset_size) {
+        raise!("found a malformed index");
+    }
+    let offsets = take(data, position + 3, (count + 1) * offset_size)?
+        .chunks(offset_size)
+        .map(|chunk| (chunk.iter()).fold(0usize, |value, &byte| value << 8 | byte as usize))
+        .collect::<Vec<_>>();
+    let start = position + 3 + (count + 1) * offset_size - 1;
+    let mut values = Vec::with_capacity(count);
+    for pair in offsets.windows(2) {
+        if pair[0] == 0 || pair[0] > pair[1] {
+            raise!("found a malformed index");
+        }
+        values.push(take(data, start + pair[0], pair[1] - pair[0])?);
+    }
+    Ok((values, start + offsets[count]))
+}
+
+/// Return the position of a structure referred to by a dictionary.
+fn offset(dictionary: &Dictionary, operator: u16) -> Result<usize> {
+    match value(dictionary, operator)? {
+        Some(value) if value > 0 => Ok(value as usize),
+        _ => raise!("found no valid operation with operator {operator}"),
+    }
+}
+
+/// Parse a dictionary keeping the encoding of the operands.
+fn parse(data: &[u8]) -> Result<Dictionary> {
+    let mut dictionary = vec![];
+    let mut operands = vec![];
+    let mut position = 0;
+    while position < data.len() {
+        let size = match data[position] {
+            0..=11 | 13..=21 => {
+                dictionary.push((data[position] as u16, std::mem::take(&mut operands)));
+                position += 1;
+                continue;
+            }
+            12 => {
+                let operator = take(data, position + 1, 1)?[0];
+                dictionary.push((12 << 8 | operator as u16, std::mem::take(&mut operands)));
+                position += 2;
+                continue;
+            }
+            28 => 3,
+            29 => 5,
+            30 => match data[(position + 1)..]
+                .iter()
+                .position(|&byte| byte & 0x0F == 0x0F || byte & 0xF0 == 0xF0)
+            {
+                Some(index) => index + 2,
+                _ => raise!("found a malformed real number"),
+            },
+            32..=246 => 1,
+            247..=254 => 2,
+            _ => raise!("found a malformed dictionary"),
+        };
+        operands.push(take(data, position, size)?.to_vec());
+        position += size;
+    }
+    Ok(dictionary)
+}
+
+/// Return a private dictionary together with its local subroutines.
+fn private(data: &[u8], dictionary: &Dictionary) -> Result<(Dictionary, Option<Vec<u8>>)> {
+    let (size, position) = match dictionary.iter().find(|(operator, _)| *operator == PRIVATE) {
+        Some((_, operands)) if operands.len() == 2 => {
+            match (integer(&operands[0]), integer(&operands[1])) {
+                (Some(size), Some(position)) if size >= 0 && position >= 0 => {
+                    (size as usize, position as usize)
+                }
+                _ => raise!("found a malformed private dictionary"),
+            }
+        }
+        _ => raise!("found no private dictionary"),
+    };
+    let dictionary = parse(take(data, position, size)?)?;
+    let subroutines = match value(&dictionary, SUBROUTINES)? {
+        Some(offset) if offset > 0 => {
+            let start = position + offset as usize;
+            let (_, end) = index(data, start)?;
+            Some(data[start..end].to_vec())
+        }
+        _ => None,
+    };
+    Ok((dictionary, subroutines))
+}
+
+/// Decode an integer operand.
+fn integer(data: &[u8]) -> Option<i32> {
+    Some(match *data {
+        [28, b0, b1] => i16::from_be_bytes([b0, b1]) as i32,
+        [29, b0, b1, b2, b3] => i32::from_be_bytes([b0, b1, b2, b3]),
+        [b0 @ 32..=246] => b0 as i32 - 139,
+        [b0 @ 247..=250, b1] => (b0 as i32 - 247) * 256 + b1 as i32 + 108,
+        [b0 @ 251..=254, b1] => -(b0 as i32 - 251) * 256 - b1 as i32 - 108,
+        _ => return None,
+    })
+}
+
+#[inline]
+fn read_u16(data: &[u8], position: usize) -> Result<u16> {
+    let data = take(data, position, 2)?;
+    Ok(u16::from_be_bytes([data[0], data[1]]))
+}
+
+/// Set the operands of an operation, appending the operation if absent.
+fn set(dictionary: &mut Dictionary, operator: u16, values: &[i32]) {
+    let operands = values
+        .iter()
+        .map(|value| {
+            let mut data = vec![29];
+            data.extend_from_slice(&value.to_be_bytes());
+            data
+        })
+        .collect();
+    match dictionary.iter_mut().find(|(other, _)| *other == operator) {
+        Some((_, other)) => *other = operands,
+        _ => dictionary.push((operator, operands)),
+    }
+}
+
+#[inline]
+fn take(data: &[u8], position: usize, size: usize) -> Result<&[u8]> {
+    match data.get(position..(position + size)) {
+        Some(data) => Ok(data),
+        _ => raise!("found a malformed font set"),
+    }
+}
+
+/// Return the integer operand of an operation if present.
+fn value(dictionary: &Dictionary, operator: u16) -> Result<Option<i32>> {
+    match dictionary.iter().find(|(other, _)| *other == operator) {
+        Some((_, operands)) => match operands.first().and_then(|operand| integer(operand)) {
+            Some(value) => Ok(Some(value)),
+            _ => raise!("found a malformed operation with operator {operator}"),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Write an index with the smallest possible offset size.
+fn write_index<'l, T>(values: T) -> Vec<u8>
+where
+    T: IntoIterator<Item = &'l [u8]>,
+{
+    let values = values.into_iter().collect::<Vec<_>>();
+    if values.is_empty() {
+        return vec![0, 0];
+    }
+    let total = values.iter().map(|value| value.len()).sum::<usize>() + 1;
+    let offset_size = match total {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x10000..=0xFFFFFF => 3,
+        _ => 4,
+    };
+    let mut data = Vec::with_capacity(3 + (values.len() + 1) * offset_size + total);
+    data.extend_from_slice(&(values.len() as u16).to_be_bytes());
+    data.push(offset_size as u8);
+    let mut offset = 1u64;
+    data.extend_from_slice(&offset.to_be_bytes()[(8 - offset_size)..]);
+    for value in values.iter() {
+        offset += value.len() as u64;
+        data.extend_from_slice(&offset.to_be_bytes()[(8 - offset_size)..]);
+    }
+    for value in values {
+        data.extend_from_slice(value);
+    }
+    data
+}
@@ -0,0 +1,100 @@
+//! The subsetting of the glyph data and the glyph-to-location mapping.
+
+use std::collections::BTreeSet;
+
+use truetype::tables::glyph_data::ComponentFlags;
+use truetype::GlyphID;
+
+use crate::subsetting::Mapping;
+use crate::Result;
+
+/// Extend glyphs with the components of composite glyphs recursively.
+pub(crate) fn close(data: &[u8], offsets: &[u32], glyph_ids: &mut BTreeSet<GlyphID>) -> Result<()> {
+    let mut stack = glyph_ids.iter().copied().collect::<Vec<_>>();
+    while let Some(glyph_id) = stack.pop() {
+        for (_, glyph_id) in components(glyph(data, offsets, glyph_id)?)? {
+            if glyph_ids.insert(glyph_id) {
+                stack.push(glyph_id);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write the glyph data and the glyph-to-location mapping and return them together with the
+/// format of the latter.
+///
+/// The glyphs are aligned to four bytes, and the short format is used whenever possible.
+pub(crate) fn write(
+    data: &[u8],
+    offsets: &[u32],
+    mapping: &Mapping,
+) -> Result<(Vec<u8>, Vec<u8>, i16)> {
+    let mut glyphs = vec![];
+    let mut positions = vec![0];
+    for &glyph_id in mapping.glyph_ids() {
+        let start = glyphs.len();
+        glyphs.extend_from_slice(glyph(data, offsets, glyph_id)?);
+        for (position, glyph_id) in components(&glyphs[start..])? {
+            let glyph_id = match mapping.get(glyph_id) {
+                Some(glyph_id) => glyph_id,
+                _ => raise!("found a component of a composite glyph that is not retained"),
+            };
+            glyphs[(start + position)..(start + position + 2)]
+                .copy_from_slice(&glyph_id.to_be_bytes());
+        }
+        glyphs.resize(glyphs.len().next_multiple_of(4), 0);
+        positions.push(glyphs.len());
+    }
+    let short = glyphs.len() <= 2 * 0xFFFF;
+    let mut offsets = vec![];
+    for position in positions {
+        match short {
+            true => offsets.extend_from_slice(&((position / 2) as u16).to_be_bytes()),
+            _ => offsets.extend_from_slice(&(position as u32).to_be_bytes()),
+        }
+    }
+    Ok((glyphs, offsets, if short { 0 } else { 1 }))
+}
+
+/// Return the components of a glyph together with the positions of their glyph identifiers.
+fn components(data: &[u8]) -> Result<Vec<(usize, GlyphID)>> {
+    let mut values = vec![];
+    if data.len() < 10 || i16::from_be_bytes([data[0], data[1]]) >= 0 {
+        return Ok(values);
+    }
+    let mut position = 10;
+    loop {
+        if data.len() < position + 4 {
+            raise!("found a malformed composite glyph");
+        }
+        let flags = ComponentFlags(u16::from_be_bytes([data[position], data[position + 1]]));
+        let glyph_id = u16::from_be_bytes([data[position + 2], data[position + 3]]);
+        values.push((position + 2, glyph_id));
+        position += 4;
+        position += if flags.are_arguments_words() { 4 } else { 2 };
+        if flags.has_scalar_scale() {
+            position += 2;
+        } else if flags.has_vector_scale() {
+            position += 4;
+        } else if flags.has_matrix_scale() {
+            position += 8;
+        }
+        if !flags.has_more_components() {
+            return Ok(values);
+        }
+    }
+}
+
+/// Return the data of a glyph.
+fn glyph<'l>(data: &'l [u8], offsets: &[u32], glyph_id: GlyphID) -> Result<&'l [u8]> {
+    let index = glyph_id as usize;
+    let (start, end) = match (offsets.get(index), offsets.get(index + 1)) {
+        (Some(&start), Some(&end)) if start <= end => (start as usize, end as usize),
+        _ => raise!("found a malformed glyph-to-location mapping"),
+    };
+    match data.get(start..end) {
+        Some(data) => Ok(data),
+        _ => raise!("found a malformed glyph-to-location mapping"),
+    }
+}
@@ -0,0 +1,71 @@
+//! The subsetting of the glyph definition.
+
+use crate::subsetting::layout::{coverage, covered, subset_class};
+use crate::subsetting::Mapping;
+use crate::tables::glyph_definition::{Attachments, Ligatures, Marks};
+use crate::tables::GlyphDefinition;
+
+/// Subset a glyph definition.
+///
+/// Mark glyph sets are kept even if left empty, since lookups refer to them by index.
+pub(crate) fn subset(table: &GlyphDefinition, mapping: &Mapping) -> GlyphDefinition {
+    let attachments = table.attachments.as_ref().and_then(|table| {
+        let (glyph_ids, records): (Vec<_>, Vec<_>) = covered(&table.coverage, mapping)
+            .into_iter()
+            .filter_map(|(glyph_id, index)| Some((glyph_id, table.records.get(index)?.clone())))
+            .unzip();
+        if glyph_ids.is_empty() {
+            return None;
+        }
+        Some(Attachments {
+            coverage_offset: 0,
+            count: records.len() as u16,
+            offsets: vec![],
+            coverage: coverage(&glyph_ids),
+            records,
+        })
+    });
+    let ligatures = table.ligatures.as_ref().and_then(|table| {
+        let (glyph_ids, records): (Vec<_>, Vec<_>) = covered(&table.coverage, mapping)
+            .into_iter()
+            .filter_map(|(glyph_id, index)| Some((glyph_id, table.records.get(index)?.clone())))
+            .unzip();
+        if glyph_ids.is_empty() {
+            return None;
+        }
+        Some(Ligatures {
+            coverage_offset: 0,
+            count: records.len() as u16,
+            offsets: vec![],
+            coverage: coverage(&glyph_ids),
+            records,
+        })
+    });
+    let marks = table.marks.as_ref().map(|table| {
+        let coverages = (table.coverages.iter())
+            .map(|value| {
+                let glyph_ids = covered(value, mapping)
+                    .into_iter()
+                    .map(|(glyph_id, _)| glyph_id)
+                    .collect::<Vec<_>>();
+                coverage(&glyph_ids)
+            })
+            .collect::<Vec<_>>();
+        Marks {
+            format: 1,
+            count: coverages.len() as u16,
+            coverage_offsets: vec![],
+            coverages,
+        }
+    });
+    GlyphDefinition {
+        header: table.header.clone(),
+        glyph_class: (table.glyph_class.as_ref())
+            .map(|class| subset_class(class, mapping, false).0),
+        attachments,
+        ligatures,
+        mark_class: (table.mark_class.as_ref()).map(|class| subset_class(class, mapping, false).0),
+        marks,
+        variations: table.variations.clone(),
+    }
+}
@@ -0,0 +1,273 @@
+//! The subsetting of the glyph positioning.
+
+use std::collections::BTreeSet;
+
+use truetype::GlyphID;
+
+use crate::layout::Coverage;
+use crate::subsetting::layout::{coverage, covered, subset_class, Subtable};
+use crate::subsetting::Mapping;
+use crate::tables::glyph_positioning::{
+    Base, Bases, Component, Ligature, Ligatures, Mark1, Mark1s, Mark2, Mark2s, Pair1s, Pair2s,
+    PairAdjustment, SingleAdjustment, Type,
+};
+
+impl Subtable for Type {
+    fn subset(&self, mapping: &Mapping) -> Option<Self> {
+        Some(match self {
+            Self::SingleAdjustment(SingleAdjustment::Format1(table)) => {
+                let glyph_ids = covered(&table.coverage, mapping)
+                    .into_iter()
+                    .map(|(glyph_id, _)| glyph_id)
+                    .collect::<Vec<_>>();
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.coverage = coverage(&glyph_ids);
+                Self::SingleAdjustment(SingleAdjustment::Format1(table))
+            }
+            Self::SingleAdjustment(SingleAdjustment::Format2(table)) => {
+                let (glyph_ids, values) = select(&table.coverage, &table.values, mapping)?;
+                let mut table = table.clone();
+                table.value_count = values.len() as u16;
+                table.values = values;
+                table.coverage = coverage(&glyph_ids);
+                Self::SingleAdjustment(SingleAdjustment::Format2(table))
+            }
+            Self::PairAdjustment(PairAdjustment::Format1(table)) => {
+                let mut glyph_ids = vec![];
+                let mut records = vec![];
+                for (glyph_id, index) in covered(&table.coverage, mapping) {
+                    let pairs = match table.records.get(index) {
+                        Some(records) => &records.records,
+                        _ => continue,
+                    };
+                    let pairs = pairs
+                        .iter()
+                        .filter_map(|pair| {
+                            let mut pair = pair.clone();
+                            pair.glyph2_id = mapping.get(pair.glyph2_id)?;
+                            Some(pair)
+                        })
+                        .collect::<Vec<_>>();
+                    if pairs.is_empty() {
+                        continue;
+                    }
+                    glyph_ids.push(glyph_id);
+                    records.push(Pair1s {
+                        count: pairs.len() as u16,
+                        records: pairs,
+                    });
+                }
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.record_count = records.len() as u16;
+                table.coverage = coverage(&glyph_ids);
+                table.records = records;
+                Self::PairAdjustment(PairAdjustment::Format1(table))
+            }
+            Self::PairAdjustment(PairAdjustment::Format2(table)) => {
+                let glyph_ids = covered(&table.coverage, mapping)
+                    .into_iter()
+                    .map(|(glyph_id, _)| glyph_id)
+                    .collect::<Vec<_>>();
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let (class1, numbers1) = subset_class(&table.class1, mapping, true);
+                let (class2, numbers2) = subset_class(&table.class2, mapping, true);
+                let records = (table.records.iter())
+                    .zip(&numbers1)
+                    .filter(|(_, number)| number.is_some())
+                    .map(|(record, _)| Pair2s {
+                        records: columns(&record.records, &numbers2),
+                    })
+                    .collect::<Vec<_>>();
+                let mut table = table.clone();
+                table.class1_count = records.len() as u16;
+                table.class2_count =
+                    records.first().map_or(0, |record| record.records.len()) as u16;
+                table.records = records;
+                table.coverage = coverage(&glyph_ids);
+                table.class1 = class1;
+                table.class2 = class2;
+                Self::PairAdjustment(PairAdjustment::Format2(table))
+            }
+            Self::CursiveAttachment(table) => {
+                let (glyph_ids, connections) =
+                    select(&table.coverage, &table.connections, mapping)?;
+                let mut table = table.clone();
+                table.connection_count = connections.len() as u16;
+                table.connections = connections;
+                table.coverage = coverage(&glyph_ids);
+                Self::CursiveAttachment(table)
+            }
+            Self::MarkToBaseAttachment(table) => {
+                let (mark_coverage, marks, numbers) =
+                    marks(&table.mark_coverage, &table.marks, mapping)?;
+                let (glyph_ids, records) =
+                    select(&table.base_coverage, &table.bases.records, mapping)?;
+                let records = records
+                    .into_iter()
+                    .map(|record| Base {
+                        anchor_offsets: vec![],
+                        anchors: columns(&record.anchors, &numbers),
+                    })
+                    .collect::<Vec<_>>();
+                let mut table = table.clone();
+                table.mark_class_count = count(&numbers);
+                table.mark_coverage = mark_coverage;
+                table.base_coverage = coverage(&glyph_ids);
+                table.marks = marks;
+                table.bases = Bases {
+                    count: records.len() as u16,
+                    records,
+                };
+                Self::MarkToBaseAttachment(table)
+            }
+            Self::MarkToLigatureAttachment(table) => {
+                let (mark_coverage, marks, numbers) =
+                    marks(&table.mark_coverage, &table.marks, mapping)?;
+                let (glyph_ids, records) =
+                    select(&table.ligature_coverage, &table.ligatures.records, mapping)?;
+                let records = records
+                    .into_iter()
+                    .map(|record| Ligature {
+                        count: record.components.len() as u16,
+                        components: (record.components.iter())
+                            .map(|component| Component {
+                                anchor_offsets: vec![],
+                                anchors: columns(&component.anchors, &numbers),
+                            })
+                            .collect(),
+                    })
+                    .collect::<Vec<_>>();
+                let mut table = table.clone();
+                table.mark_class_count = count(&numbers);
+                table.mark_coverage = mark_coverage;
+                table.ligature_coverage = coverage(&glyph_ids);
+                table.marks = marks;
+                table.ligatures = Ligatures {
+                    count: records.len() as u16,
+                    offsets: vec![],
+                    records,
+                };
+                Self::MarkToLigatureAttachment(table)
+            }
+            Self::MarkToMarkAttachment(table) => {
+                let (mark1_coverage, mark1s, numbers) =
+                    marks(&table.mark1_coverage, &table.mark1s, mapping)?;
+                let (glyph_ids, records) =
+                    select(&table.mark2_coverage, &table.mark2s.records, mapping)?;
+                let records = records
+                    .into_iter()
+                    .map(|record| Mark2 {
+                        anchor_offsets: vec![],
+                        anchors: columns(&record.anchors, &numbers),
+                    })
+                    .collect::<Vec<_>>();
+                let mut table = table.clone();
+                table.mark_class_count = count(&numbers);
+                table.mark1_coverage = mark1_coverage;
+                table.mark2_coverage = coverage(&glyph_ids);
+                table.mark1s = mark1s;
+                table.mark2s = Mark2s {
+                    count: records.len() as u16,
+                    records,
+                };
+                Self::MarkToMarkAttachment(table)
+            }
+            Self::ContextualPositioning(table) => {
+                Self::ContextualPositioning(table.subset(mapping)?)
+            }
+            Self::ChainedContextualPositioning(table) => {
+                Self::ChainedContextualPositioning(table.subset(mapping)?)
+            }
+            Self::ExtensionPositioning(_) => return None,
+        })
+    }
+
+    fn lookups(&self) -> Vec<u16> {
+        match self {
+            Self::ContextualPositioning(table) => table.lookups(),
+            Self::ChainedContextualPositioning(table) => table.lookups(),
+            _ => vec![],
+        }
+    }
+
+    fn renumber(&mut self, lookups: &[Option<u16>]) {
+        match self {
+            Self::ContextualPositioning(table) => table.renumber(lookups),
+            Self::ChainedContextualPositioning(table) => table.renumber(lookups),
+            _ => {}
+        }
+    }
+}
+
+/// Select the entries of an array indexed by a coverage whose glyphs are retained or return
+/// `None` if none of them are.
+fn select<T: Clone>(
+    coverage: &Coverage,
+    values: &[T],
+    mapping: &Mapping,
+) -> Option<(Vec<GlyphID>, Vec<T>)> {
+    let (glyph_ids, values): (Vec<_>, Vec<_>) = covered(coverage, mapping)
+        .into_iter()
+        .filter_map(|(glyph_id, index)| Some((glyph_id, values.get(index)?.clone())))
+        .unzip();
+    match glyph_ids.is_empty() {
+        true => None,
+        _ => Some((glyph_ids, values)),
+    }
+}
+
+/// Select the entries of an array indexed by classes whose classes are retained.
+fn columns<T: Clone>(values: &[T], numbers: &[Option<u16>]) -> Vec<T> {
+    (values.iter())
+        .zip(numbers)
+        .filter(|(_, number)| number.is_some())
+        .map(|(value, _)| value.clone())
+        .collect()
+}
+
+#[inline]
+fn count(numbers: &[Option<u16>]) -> u16 {
+    numbers.iter().flatten().count() as u16
+}
+
+/// Subset marks and renumber their classes, returning the coverage, the marks, and the new
+/// numbers of the original classes.
+fn marks(
+    mark_coverage: &Coverage,
+    marks: &Mark1s,
+    mapping: &Mapping,
+) -> Option<(Coverage, Mark1s, Vec<Option<u16>>)> {
+    let (glyph_ids, records) = select(mark_coverage, &marks.records, mapping)?;
+    let used = records
+        .iter()
+        .map(|record| record.class_id)
+        .collect::<BTreeSet<_>>();
+    let length = used.last().map_or(0, |&index| index as usize + 1);
+    let mut numbers = vec![None; length];
+    for (i, &index) in used.iter().enumerate() {
+        numbers[index as usize] = Some(i as u16);
+    }
+    let records = records
+        .into_iter()
+        .map(|record| Mark1 {
+            class_id: numbers[record.class_id as usize].unwrap_or(0),
+            ..record
+        })
+        .collect::<Vec<_>>();
+    Some((
+        coverage(&glyph_ids),
+        Mark1s {
+            count: records.len() as u16,
+            records,
+        },
+        numbers,
+    ))
+}
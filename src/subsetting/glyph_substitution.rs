@@ -0,0 +1,193 @@
+//! The subsetting of the glyph substitution.
+
+use crate::subsetting::layout::{self, coverage, covered, glyphs, Subtable};
+use crate::subsetting::Mapping;
+use crate::tables::glyph_substitution::{
+    Alternates, Ligatures, SingleSubstitution, SingleSubstitution1, SingleSubstitution2, Type,
+};
+
+impl Subtable for Type {
+    fn subset(&self, mapping: &Mapping) -> Option<Self> {
+        Some(match self {
+            Self::SingleSubstitution(table) => Self::SingleSubstitution(single(table, mapping)?),
+            Self::MultipleSubstitution(table) => {
+                let mut glyph_ids = vec![];
+                let mut records = vec![];
+                for (glyph_id, index) in covered(&table.coverage, mapping) {
+                    let mut record = match table.records.get(index) {
+                        Some(record) => record.clone(),
+                        _ => continue,
+                    };
+                    record.glyph_ids = match glyphs(&record.glyph_ids, mapping) {
+                        Some(glyph_ids) => glyph_ids,
+                        _ => continue,
+                    };
+                    glyph_ids.push(glyph_id);
+                    records.push(record);
+                }
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.record_count = records.len() as u16;
+                table.coverage = coverage(&glyph_ids);
+                table.records = records;
+                Self::MultipleSubstitution(table)
+            }
+            Self::AlternateSubstitution(table) => {
+                let mut glyph_ids = vec![];
+                let mut records = vec![];
+                for (glyph_id, index) in covered(&table.coverage, mapping) {
+                    let record = match table.records.get(index) {
+                        Some(record) => record,
+                        _ => continue,
+                    };
+                    let alternates = (record.glyph_ids.iter())
+                        .filter_map(|&glyph_id| mapping.get(glyph_id))
+                        .collect::<Vec<_>>();
+                    if alternates.is_empty() {
+                        continue;
+                    }
+                    glyph_ids.push(glyph_id);
+                    records.push(Alternates {
+                        glyph_count: alternates.len() as u16,
+                        glyph_ids: alternates,
+                    });
+                }
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.record_count = records.len() as u16;
+                table.coverage = coverage(&glyph_ids);
+                table.records = records;
+                Self::AlternateSubstitution(table)
+            }
+            Self::LigatureSubstitution(table) => {
+                let mut glyph_ids = vec![];
+                let mut records = vec![];
+                for (glyph_id, index) in covered(&table.coverage, mapping) {
+                    let ligatures = match table.records.get(index) {
+                        Some(records) => &records.records,
+                        _ => continue,
+                    };
+                    let ligatures = ligatures
+                        .iter()
+                        .filter_map(|ligature| {
+                            let mut ligature = ligature.clone();
+                            ligature.glyph_id = mapping.get(ligature.glyph_id)?;
+                            ligature.glyph_ids = glyphs(&ligature.glyph_ids, mapping)?;
+                            Some(ligature)
+                        })
+                        .collect::<Vec<_>>();
+                    if ligatures.is_empty() {
+                        continue;
+                    }
+                    glyph_ids.push(glyph_id);
+                    records.push(Ligatures {
+                        count: ligatures.len() as u16,
+                        offsets: vec![],
+                        records: ligatures,
+                    });
+                }
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.record_count = records.len() as u16;
+                table.coverage = coverage(&glyph_ids);
+                table.records = records;
+                Self::LigatureSubstitution(table)
+            }
+            Self::ContextualSubstitution(table) => {
+                Self::ContextualSubstitution(table.subset(mapping)?)
+            }
+            Self::ChainedContextualSubstitution(table) => {
+                Self::ChainedContextualSubstitution(table.subset(mapping)?)
+            }
+            Self::ExtensionSubstitution(_) => return None,
+            Self::ReverseChainedContextualSubstibution(table) => {
+                let mut glyph_ids = vec![];
+                let mut substitutes = vec![];
+                for (glyph_id, index) in covered(&table.coverage, mapping) {
+                    let substitute = table.glyph_ids.get(index);
+                    if let Some(substitute) = substitute.and_then(|&value| mapping.get(value)) {
+                        glyph_ids.push(glyph_id);
+                        substitutes.push(substitute);
+                    }
+                }
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.backward_coverages = layout::coverages(&table.backward_coverages, mapping)?;
+                table.forward_coverages = layout::coverages(&table.forward_coverages, mapping)?;
+                table.glyph_count = substitutes.len() as u16;
+                table.glyph_ids = substitutes;
+                table.coverage = coverage(&glyph_ids);
+                Self::ReverseChainedContextualSubstibution(table)
+            }
+        })
+    }
+
+    fn lookups(&self) -> Vec<u16> {
+        match self {
+            Self::ContextualSubstitution(table) => table.lookups(),
+            Self::ChainedContextualSubstitution(table) => table.lookups(),
+            _ => vec![],
+        }
+    }
+
+    fn renumber(&mut self, lookups: &[Option<u16>]) {
+        match self {
+            Self::ContextualSubstitution(table) => table.renumber(lookups),
+            Self::ChainedContextualSubstitution(table) => table.renumber(lookups),
+            _ => {}
+        }
+    }
+}
+
+/// Subset a single substitution, which is turned into format 1 if all glyphs are substituted
+/// with the same delta and into format 2 otherwise.
+fn single(table: &SingleSubstitution, mapping: &Mapping) -> Option<SingleSubstitution> {
+    let mut values = vec![];
+    match table {
+        SingleSubstitution::Format1(table) => {
+            for (glyph_id, _) in covered(&table.coverage, mapping) {
+                let other = mapping.glyph_ids()[glyph_id as usize];
+                let substitute = other.wrapping_add_signed(table.delta_glyph_id);
+                if let Some(substitute) = mapping.get(substitute) {
+                    values.push((glyph_id, substitute));
+                }
+            }
+        }
+        SingleSubstitution::Format2(table) => {
+            for (glyph_id, index) in covered(&table.coverage, mapping) {
+                let substitute = table.glyph_ids.get(index);
+                if let Some(substitute) = substitute.and_then(|&value| mapping.get(value)) {
+                    values.push((glyph_id, substitute));
+                }
+            }
+        }
+    }
+    let delta = |(glyph_id, substitute): (u16, u16)| substitute.wrapping_sub(glyph_id) as i16;
+    let delta_glyph_id = delta(*values.first()?);
+    let glyph_ids = values.iter().map(|value| value.0).collect::<Vec<_>>();
+    if values.iter().all(|&value| delta(value) == delta_glyph_id) {
+        Some(SingleSubstitution::Format1(SingleSubstitution1 {
+            format: 1,
+            coverage_offset: 0,
+            delta_glyph_id,
+            coverage: coverage(&glyph_ids),
+        }))
+    } else {
+        let substitutes = values.iter().map(|value| value.1).collect::<Vec<_>>();
+        Some(SingleSubstitution::Format2(SingleSubstitution2 {
+            format: 2,
+            coverage_offset: 0,
+            glyph_count: substitutes.len() as u16,
+            glyph_ids: substitutes,
+            coverage: coverage(&glyph_ids),
+        }))
+    }
+}
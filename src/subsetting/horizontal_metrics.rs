@@ -0,0 +1,31 @@
+//! The subsetting of the horizontal metrics.
+
+use truetype::tables::HorizontalMetrics;
+
+use crate::subsetting::Mapping;
+use crate::Result;
+
+/// Write the horizontal metrics and return them together with the number of full records.
+///
+/// The trailing glyphs sharing the advance width of the last full record are given only their
+/// left side bearings.
+pub(crate) fn write(table: &HorizontalMetrics, mapping: &Mapping) -> Result<(Vec<u8>, u16)> {
+    let values = (mapping.glyph_ids().iter())
+        .map(|&glyph_id| table.get(glyph_id))
+        .collect::<Vec<_>>();
+    let mut count = values.len();
+    while count > 1 && values[count - 2].0 == values[count - 1].0 {
+        count -= 1;
+    }
+    if count > u16::MAX as usize {
+        raise!("found too many glyphs");
+    }
+    let mut data = Vec::with_capacity(4 * count + 2 * (values.len() - count));
+    for (i, (advance_width, left_side_bearing)) in values.into_iter().enumerate() {
+        if i < count {
+            data.extend_from_slice(&advance_width.to_be_bytes());
+        }
+        data.extend_from_slice(&left_side_bearing.to_be_bytes());
+    }
+    Ok((data, count as u16))
+}
@@ -0,0 +1,718 @@
+//! The subsetting of the common layout tables.
+
+use std::collections::BTreeSet;
+
+use truetype::GlyphID;
+
+use crate::layout::class::{self, Class1, Class2};
+use crate::layout::context::{
+    Action, ChainedClassRecords, ChainedContext, ChainedRecords, ClassRecords, Context, Records,
+};
+use crate::layout::coverage::{self, Coverage1, Coverage2};
+use crate::layout::feature::{self, SubstitutionHeader};
+use crate::layout::{language, lookup, script};
+use crate::layout::{Class, Coverage, Directory, Feature, Features, Lookups, Scripts};
+use crate::subsetting::Mapping;
+
+/// A lookup subtable that can be subset.
+pub(crate) trait Subtable: Sized {
+    /// Subset the subtable or return `None` if it can no longer apply.
+    fn subset(&self, mapping: &Mapping) -> Option<Self>;
+
+    /// Return the indices of the lookups referred to by contextual actions.
+    fn lookups(&self) -> Vec<u16> {
+        vec![]
+    }
+
+    /// Renumber the lookups referred to by contextual actions and drop those of removed lookups.
+    fn renumber(&mut self, _: &[Option<u16>]) {}
+}
+
+impl Subtable for Context {
+    fn subset(&self, mapping: &Mapping) -> Option<Self> {
+        match self {
+            Self::Format1(table) => {
+                let mut glyph_ids = vec![];
+                let mut records = vec![];
+                for (glyph_id, index) in covered(&table.coverage, mapping) {
+                    let rules = match table.records.get(index) {
+                        Some(records) => &records.records,
+                        _ => continue,
+                    };
+                    let rules = rules
+                        .iter()
+                        .filter_map(|rule| {
+                            let mut rule = rule.clone();
+                            rule.glyph_ids = glyphs(&rule.glyph_ids, mapping)?;
+                            Some(rule)
+                        })
+                        .collect::<Vec<_>>();
+                    if rules.is_empty() {
+                        continue;
+                    }
+                    glyph_ids.push(glyph_id);
+                    records.push(Records {
+                        count: rules.len() as u16,
+                        offsets: vec![],
+                        records: rules,
+                    });
+                }
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.record_count = records.len() as u16;
+                table.coverage = coverage(&glyph_ids);
+                table.records = records;
+                Some(Self::Format1(table))
+            }
+            Self::Format2(table) => {
+                let glyph_ids = covered(&table.coverage, mapping)
+                    .into_iter()
+                    .map(|(glyph_id, _)| glyph_id)
+                    .collect::<Vec<_>>();
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let (class, numbers) = subset_class(&table.class, mapping, false);
+                let records = table
+                    .records
+                    .iter()
+                    .enumerate()
+                    .map(|(index, records)| {
+                        let records = records.as_ref()?;
+                        numbers.get(index).copied().flatten()?;
+                        let rules = records
+                            .records
+                            .iter()
+                            .filter(|rule| usable(&rule.indices, &numbers))
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        if rules.is_empty() {
+                            return None;
+                        }
+                        Some(ClassRecords {
+                            count: rules.len() as u16,
+                            offsets: vec![],
+                            records: rules,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                if records.iter().all(Option::is_none) {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.coverage = coverage(&glyph_ids);
+                table.class = class;
+                table.records = records;
+                Some(Self::Format2(table))
+            }
+            Self::Format3(table) => {
+                let mut table = table.clone();
+                table.coverages = coverages(&table.coverages, mapping)?;
+                Some(Self::Format3(table))
+            }
+        }
+    }
+
+    fn lookups(&self) -> Vec<u16> {
+        let actions: Vec<&Action> = match self {
+            Self::Format1(table) => (table.records.iter())
+                .flat_map(|records| &records.records)
+                .flat_map(|record| &record.actions)
+                .collect(),
+            Self::Format2(table) => (table.records.iter().flatten())
+                .flat_map(|records| &records.records)
+                .flat_map(|record| &record.actions)
+                .collect(),
+            Self::Format3(table) => table.actions.iter().collect(),
+        };
+        actions
+            .into_iter()
+            .map(|action| action.lookup_index)
+            .collect()
+    }
+
+    fn renumber(&mut self, lookups: &[Option<u16>]) {
+        match self {
+            Self::Format1(table) => {
+                for record in table
+                    .records
+                    .iter_mut()
+                    .flat_map(|records| &mut records.records)
+                {
+                    record.action_count = redirect(&mut record.actions, lookups);
+                }
+            }
+            Self::Format2(table) => {
+                for record in
+                    (table.records.iter_mut().flatten()).flat_map(|records| &mut records.records)
+                {
+                    record.action_count = redirect(&mut record.actions, lookups);
+                }
+            }
+            Self::Format3(table) => {
+                table.action_count = redirect(&mut table.actions, lookups);
+            }
+        }
+    }
+}
+
+impl Subtable for ChainedContext {
+    fn subset(&self, mapping: &Mapping) -> Option<Self> {
+        match self {
+            Self::Format1(table) => {
+                let mut glyph_ids = vec![];
+                let mut records = vec![];
+                for (glyph_id, index) in covered(&table.coverage, mapping) {
+                    let rules = match table.records.get(index) {
+                        Some(records) => &records.records,
+                        _ => continue,
+                    };
+                    let rules = rules
+                        .iter()
+                        .filter_map(|rule| {
+                            let mut rule = rule.clone();
+                            rule.backward_glyph_ids = glyphs(&rule.backward_glyph_ids, mapping)?;
+                            rule.glyph_ids = glyphs(&rule.glyph_ids, mapping)?;
+                            rule.forward_glyph_ids = glyphs(&rule.forward_glyph_ids, mapping)?;
+                            Some(rule)
+                        })
+                        .collect::<Vec<_>>();
+                    if rules.is_empty() {
+                        continue;
+                    }
+                    glyph_ids.push(glyph_id);
+                    records.push(ChainedRecords {
+                        count: rules.len() as u16,
+                        offsets: vec![],
+                        records: rules,
+                    });
+                }
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.record_count = records.len() as u16;
+                table.coverage = coverage(&glyph_ids);
+                table.records = records;
+                Some(Self::Format1(table))
+            }
+            Self::Format2(table) => {
+                let glyph_ids = covered(&table.coverage, mapping)
+                    .into_iter()
+                    .map(|(glyph_id, _)| glyph_id)
+                    .collect::<Vec<_>>();
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let (backward_class, backward_numbers) =
+                    subset_class(&table.backward_class, mapping, false);
+                let (class, numbers) = subset_class(&table.class, mapping, false);
+                let (forward_class, forward_numbers) =
+                    subset_class(&table.forward_class, mapping, false);
+                let records = table
+                    .records
+                    .iter()
+                    .enumerate()
+                    .map(|(index, records)| {
+                        let records = records.as_ref()?;
+                        numbers.get(index).copied().flatten()?;
+                        let rules = records
+                            .records
+                            .iter()
+                            .filter(|rule| {
+                                usable(&rule.backward_indices, &backward_numbers)
+                                    && usable(&rule.indices, &numbers)
+                                    && usable(&rule.forward_indices, &forward_numbers)
+                            })
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        if rules.is_empty() {
+                            return None;
+                        }
+                        Some(ChainedClassRecords {
+                            count: rules.len() as u16,
+                            offsets: vec![],
+                            records: rules,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                if records.iter().all(Option::is_none) {
+                    return None;
+                }
+                let mut table = table.clone();
+                table.coverage = coverage(&glyph_ids);
+                table.backward_class = backward_class;
+                table.class = class;
+                table.forward_class = forward_class;
+                table.records = records;
+                Some(Self::Format2(table))
+            }
+            Self::Format3(table) => {
+                let mut table = table.clone();
+                table.backward_coverages = coverages(&table.backward_coverages, mapping)?;
+                table.coverages = coverages(&table.coverages, mapping)?;
+                table.forward_coverages = coverages(&table.forward_coverages, mapping)?;
+                Some(Self::Format3(table))
+            }
+        }
+    }
+
+    fn lookups(&self) -> Vec<u16> {
+        let actions: Vec<&Action> = match self {
+            Self::Format1(table) => (table.records.iter())
+                .flat_map(|records| &records.records)
+                .flat_map(|record| &record.actions)
+                .collect(),
+            Self::Format2(table) => (table.records.iter().flatten())
+                .flat_map(|records| &records.records)
+                .flat_map(|record| &record.actions)
+                .collect(),
+            Self::Format3(table) => table.actions.iter().collect(),
+        };
+        actions
+            .into_iter()
+            .map(|action| action.lookup_index)
+            .collect()
+    }
+
+    fn renumber(&mut self, lookups: &[Option<u16>]) {
+        match self {
+            Self::Format1(table) => {
+                for record in table
+                    .records
+                    .iter_mut()
+                    .flat_map(|records| &mut records.records)
+                {
+                    record.action_count = redirect(&mut record.actions, lookups);
+                }
+            }
+            Self::Format2(table) => {
+                for record in
+                    (table.records.iter_mut().flatten()).flat_map(|records| &mut records.records)
+                {
+                    record.action_count = redirect(&mut record.actions, lookups);
+                }
+            }
+            Self::Format3(table) => {
+                table.action_count = redirect(&mut table.actions, lookups);
+            }
+        }
+    }
+}
+
+/// Return the indices of the lookups of the retained features.
+///
+/// The retained features are the ones given, or all if absent, together with the required
+/// features of language systems, and their lookups include those of feature variations.
+pub(crate) fn lookups<T>(table: &Directory<T>, features: Option<&[Feature]>) -> BTreeSet<u16> {
+    let retained = retained(table, features);
+    let mut indices = BTreeSet::new();
+    for (i, record) in table.features.records.iter().enumerate() {
+        if retained[i] {
+            indices.extend(&record.lookup_indices);
+        }
+    }
+    for (header, record) in alternates(table) {
+        if retained
+            .get(header.feature_index as usize)
+            .copied()
+            .unwrap_or(false)
+        {
+            indices.extend(&record.lookup_indices);
+        }
+    }
+    indices.retain(|&index| (index as usize) < table.lookups.records.len());
+    indices
+}
+
+/// Subset a layout directory.
+///
+/// Lookups that are neither used by the retained features nor referred to by the lookups used,
+/// lookups whose subtables can no longer apply, features without lookups, and language systems
+/// without features that would otherwise fall back to default language systems without features
+/// are removed, and the rest is renumbered.
+pub(crate) fn subset<T>(
+    table: &Directory<T>,
+    mapping: &Mapping,
+    features: Option<&[Feature]>,
+) -> Directory<T>
+where
+    T: Clone + Subtable,
+{
+    let mut indices = lookups(table, features);
+    let mut stack = indices.iter().copied().collect::<Vec<_>>();
+    while let Some(index) = stack.pop() {
+        for subtable in &table.lookups.records[index as usize].tables {
+            for index in subtable.lookups() {
+                if (index as usize) < table.lookups.records.len() && indices.insert(index) {
+                    stack.push(index);
+                }
+            }
+        }
+    }
+    let mut lookup_numbers = vec![None; table.lookups.records.len()];
+    let mut records = vec![];
+    for index in indices {
+        let record = &table.lookups.records[index as usize];
+        let tables = (record.tables.iter())
+            .filter_map(|table| table.subset(mapping))
+            .collect::<Vec<_>>();
+        if tables.is_empty() {
+            continue;
+        }
+        lookup_numbers[index as usize] = Some(records.len() as u16);
+        records.push(lookup::Record {
+            r#type: record.r#type,
+            flags: record.flags,
+            table_count: tables.len() as u16,
+            table_offsets: vec![],
+            mark_filtering_set: record.mark_filtering_set,
+            tables,
+        });
+    }
+    for table in records.iter_mut().flat_map(|record| &mut record.tables) {
+        table.renumber(&lookup_numbers);
+    }
+
+    let retained = retained(table, features);
+    let mut feature_numbers = vec![None; table.features.records.len()];
+    let mut features = Features::default();
+    for (i, (header, record)) in (table.features.headers.iter())
+        .zip(&table.features.records)
+        .enumerate()
+    {
+        let record = subset_feature(record, &lookup_numbers);
+        let used = !record.lookup_indices.is_empty()
+            || record.parameters.is_some()
+            || alternates(table).any(|(other, record)| {
+                other.feature_index as usize == i
+                    && !subset_feature(record, &lookup_numbers)
+                        .lookup_indices
+                        .is_empty()
+            });
+        if !retained[i] || !used {
+            continue;
+        }
+        feature_numbers[i] = Some(features.records.len() as u16);
+        features.headers.push(*header);
+        features.records.push(record);
+    }
+    features.count = features.records.len() as u16;
+    let variations = table.variations.as_ref().map(|variations| {
+        let mut variations = variations.clone();
+        for substitutions in variations
+            .records
+            .iter_mut()
+            .filter_map(|record| record.substitutions.as_mut())
+        {
+            let mut headers = vec![];
+            let mut records = vec![];
+            for (header, record) in substitutions.headers.iter().zip(&substitutions.records) {
+                if let Some(Some(feature_index)) =
+                    feature_numbers.get(header.feature_index as usize)
+                {
+                    headers.push(SubstitutionHeader {
+                        feature_index: *feature_index,
+                        offset: 0,
+                    });
+                    records.push(subset_feature(record, &lookup_numbers));
+                }
+            }
+            substitutions.count = records.len() as u16;
+            substitutions.headers = headers;
+            substitutions.records = records;
+        }
+        variations
+    });
+
+    let mut scripts = Scripts::default();
+    for (header, record) in table.scripts.headers.iter().zip(&table.scripts.records) {
+        let default_language = (record.default_language.as_ref())
+            .map(|record| subset_language(record, &feature_numbers));
+        let fallback = default_language.as_ref().is_none_or(empty);
+        let mut language_headers = vec![];
+        let mut language_records = vec![];
+        for (header, record) in record.language_headers.iter().zip(&record.language_records) {
+            let record = subset_language(record, &feature_numbers);
+            if fallback && empty(&record) {
+                continue;
+            }
+            language_headers.push(header.clone());
+            language_records.push(record);
+        }
+        if default_language.is_none() && language_records.is_empty() {
+            continue;
+        }
+        scripts.headers.push(*header);
+        scripts.records.push(script::Record {
+            default_language_offset: 0,
+            language_count: language_records.len() as u16,
+            language_headers,
+            default_language,
+            language_records,
+        });
+    }
+    scripts.count = scripts.records.len() as u16;
+
+    Directory {
+        major_version: 1,
+        minor_version: table.minor_version,
+        script_offset: 0,
+        feature_offset: 0,
+        lookup_offset: 0,
+        variation_offset: 0,
+        scripts,
+        features,
+        lookups: Lookups {
+            count: records.len() as u16,
+            offsets: vec![],
+            records,
+        },
+        variations,
+    }
+}
+
+/// Create a coverage of glyphs given in ascending order.
+pub(crate) fn coverage(glyph_ids: &[GlyphID]) -> Coverage {
+    let ranges = runs(glyph_ids.iter().map(|&glyph_id| (glyph_id, 0)));
+    if 3 * ranges.len() < glyph_ids.len() {
+        let mut index = 0;
+        let records = ranges
+            .into_iter()
+            .map(|(start_glyph_id, end_glyph_id, _)| {
+                let record = coverage::Record {
+                    start_glyph_id,
+                    end_glyph_id,
+                    index,
+                };
+                index += end_glyph_id - start_glyph_id + 1;
+                record
+            })
+            .collect::<Vec<_>>();
+        Coverage::Format2(Coverage2 {
+            format: 2,
+            record_count: records.len() as u16,
+            records,
+        })
+    } else {
+        Coverage::Format1(Coverage1 {
+            format: 1,
+            glyph_count: glyph_ids.len() as u16,
+            glyph_ids: glyph_ids.to_vec(),
+        })
+    }
+}
+
+/// Subset coverages or return `None` if any of them is left empty.
+pub(crate) fn coverages(coverages: &[Coverage], mapping: &Mapping) -> Option<Vec<Coverage>> {
+    coverages
+        .iter()
+        .map(|value| {
+            let glyph_ids = covered(value, mapping)
+                .into_iter()
+                .map(|(glyph_id, _)| glyph_id)
+                .collect::<Vec<_>>();
+            match glyph_ids.is_empty() {
+                true => None,
+                _ => Some(coverage(&glyph_ids)),
+            }
+        })
+        .collect()
+}
+
+/// Return the retained glyphs of a coverage in ascending order together with their coverage
+/// indices.
+pub(crate) fn covered(coverage: &Coverage, mapping: &Mapping) -> Vec<(GlyphID, usize)> {
+    let mut values = coverage
+        .iter()
+        .enumerate()
+        .filter_map(|(index, glyph_id)| Some((mapping.get(glyph_id)?, index)))
+        .collect::<Vec<_>>();
+    values.sort();
+    values.dedup_by_key(|(glyph_id, _)| *glyph_id);
+    values
+}
+
+/// Map glyphs or return `None` if any of them is not retained.
+pub(crate) fn glyphs(glyph_ids: &[GlyphID], mapping: &Mapping) -> Option<Vec<GlyphID>> {
+    glyph_ids
+        .iter()
+        .map(|&glyph_id| mapping.get(glyph_id))
+        .collect()
+}
+
+/// Subset a class and return it together with the new numbers of the original classes.
+///
+/// Classes without retained glyphs other than class 0 are removed. If renumbering is requested,
+/// the rest is renumbered in ascending order; otherwise, the numbers are kept.
+pub(crate) fn subset_class(
+    class: &Class,
+    mapping: &Mapping,
+    renumber: bool,
+) -> (Class, Vec<Option<u16>>) {
+    let count = match class {
+        Class::Format1(table) => table.indices.iter().max(),
+        Class::Format2(table) => table.records.iter().map(|record| &record.index).max(),
+    }
+    .map_or(1, |&index| index as usize + 1);
+    let values = (mapping.glyph_ids().iter().enumerate())
+        .map(|(glyph_id, &other)| (glyph_id as GlyphID, class.class_of(other)))
+        .filter(|&(_, index)| index > 0)
+        .collect::<Vec<_>>();
+    let used = values
+        .iter()
+        .map(|&(_, index)| index)
+        .collect::<BTreeSet<_>>();
+    let mut numbers = vec![None; count];
+    numbers[0] = Some(0);
+    for (i, &index) in used.iter().enumerate() {
+        numbers[index as usize] = Some(if renumber { i as u16 + 1 } else { index });
+    }
+    let values = values
+        .into_iter()
+        .filter_map(|(glyph_id, index)| Some((glyph_id, numbers[index as usize]?)))
+        .collect::<Vec<_>>();
+    (build_class(&values), numbers)
+}
+
+/// Check if a sequence of classes can be matched given the new numbers of the classes.
+pub(crate) fn usable(indices: &[u16], numbers: &[Option<u16>]) -> bool {
+    indices
+        .iter()
+        .all(|&index| numbers.get(index as usize).copied().flatten().is_some())
+}
+
+fn alternates<T>(
+    table: &Directory<T>,
+) -> impl Iterator<Item = (&SubstitutionHeader, &feature::Record)> {
+    (table.variations.iter())
+        .flat_map(|variations| &variations.records)
+        .filter_map(|record| record.substitutions.as_ref())
+        .flat_map(|substitutions| substitutions.headers.iter().zip(&substitutions.records))
+}
+
+/// Create a class of glyphs given in ascending order together with their classes.
+fn build_class(values: &[(GlyphID, u16)]) -> Class {
+    let ranges = runs(values.iter().copied());
+    let span = match (values.first(), values.last()) {
+        (Some(first), Some(last)) => (last.0 - first.0) as usize + 1,
+        _ => 0,
+    };
+    if 3 * ranges.len() < span + 1 {
+        let records = ranges
+            .into_iter()
+            .map(|(start_glyph_id, end_glyph_id, index)| class::Record {
+                start_glyph_id,
+                end_glyph_id,
+                index,
+            })
+            .collect::<Vec<_>>();
+        Class::Format2(Class2 {
+            format: 2,
+            record_count: records.len() as u16,
+            records,
+        })
+    } else {
+        let start_glyph_id = values.first().map_or(0, |value| value.0);
+        let mut indices = vec![0; span];
+        for &(glyph_id, index) in values {
+            indices[(glyph_id - start_glyph_id) as usize] = index;
+        }
+        Class::Format1(Class1 {
+            format: 1,
+            start_glyph_id,
+            glyph_count: indices.len() as u16,
+            indices,
+        })
+    }
+}
+
+#[inline]
+fn empty(record: &language::Record) -> bool {
+    record.feature_indices.is_empty()
+        && record.required_feature_index == language::NO_REQUIRED_FEATURE
+}
+
+/// Renumber the lookups of actions, drop those of removed lookups, and return the count.
+fn redirect(actions: &mut Vec<Action>, lookups: &[Option<u16>]) -> u16 {
+    actions.retain_mut(|action| {
+        match lookups.get(action.lookup_index as usize).copied().flatten() {
+            Some(index) => {
+                action.lookup_index = index;
+                true
+            }
+            _ => false,
+        }
+    });
+    actions.len() as u16
+}
+
+/// Determine which features are retained.
+fn retained<T>(table: &Directory<T>, features: Option<&[Feature]>) -> Vec<bool> {
+    let mut values = table
+        .features
+        .headers
+        .iter()
+        .map(|header| {
+            features.is_none_or(|features| features.contains(&Feature::from_tag(&header.tag)))
+        })
+        .collect::<Vec<_>>();
+    let records = (table.scripts.records.iter()).flat_map(|record| {
+        record
+            .default_language
+            .iter()
+            .chain(&record.language_records)
+    });
+    for record in records {
+        if let Some(value) = values.get_mut(record.required_feature_index as usize) {
+            *value = true;
+        }
+    }
+    values
+}
+
+/// Split glyphs given in ascending order together with values into ranges of consecutive
+/// glyphs with the same value.
+fn runs<T>(values: T) -> Vec<(GlyphID, GlyphID, u16)>
+where
+    T: Iterator<Item = (GlyphID, u16)>,
+{
+    let mut ranges: Vec<(GlyphID, GlyphID, u16)> = vec![];
+    for (glyph_id, value) in values {
+        match ranges.last_mut() {
+            Some(range) if range.1 + 1 == glyph_id && range.2 == value => range.1 = glyph_id,
+            _ => ranges.push((glyph_id, glyph_id, value)),
+        }
+    }
+    ranges
+}
+
+fn subset_feature(record: &feature::Record, lookups: &[Option<u16>]) -> feature::Record {
+    let lookup_indices = (record.lookup_indices.iter())
+        .filter_map(|&index| lookups.get(index as usize).copied().flatten())
+        .collect::<Vec<_>>();
+    feature::Record {
+        lookup_index_count: lookup_indices.len() as u16,
+        lookup_indices,
+        ..record.clone()
+    }
+}
+
+fn subset_language(record: &language::Record, features: &[Option<u16>]) -> language::Record {
+    let feature_indices = (record.feature_indices.iter())
+        .filter_map(|&index| features.get(index as usize).copied().flatten())
+        .collect::<Vec<_>>();
+    let required_feature_index = features
+        .get(record.required_feature_index as usize)
+        .copied()
+        .flatten()
+        .unwrap_or(language::NO_REQUIRED_FEATURE);
+    language::Record {
+        lookup_order_offset: 0,
+        required_feature_index,
+        feature_index_count: feature_indices.len() as u16,
+        feature_indices,
+    }
+}
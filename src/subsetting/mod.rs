@@ -0,0 +1,274 @@
+//! The subsetting of fonts.
+//!
+//! ## Example
+//!
+//! ```
+//! use opentype::subsetting::{self, Options};
+//! use opentype::File;
+//!
+//! macro_rules! ok(($result:expr) => ($result.unwrap()));
+//!
+//! let path = "SourceSerifPro-Regular.otf";
+//! # let path = "tests/fixtures/SourceSerifPro-Regular.otf";
+//! let mut tape = ok!(std::fs::File::open(path));
+//! let File { fonts } = ok!(File::read(&mut tape));
+//!
+//! let options = Options {
+//!     characters: "Hello".chars().collect(),
+//!     ..Default::default()
+//! };
+//! let (builder, mapping) = ok!(subsetting::subset(&fonts[0], &mut tape, &options));
+//! assert!(mapping.len() < 100);
+//!
+//! let mut data = vec![];
+//! ok!(builder.write(&mut data));
+//! ```
+
+mod character_mapping;
+mod font_set;
+mod glyph_data;
+mod glyph_definition;
+mod glyph_positioning;
+mod glyph_substitution;
+mod horizontal_metrics;
+mod layout;
+mod vertical_origins;
+
+use std::collections::BTreeSet;
+
+use std::io::Cursor;
+
+use truetype::tables::{FontHeader, GlyphMapping, HorizontalHeader, MaximumProfile};
+use truetype::{GlyphID, Tag};
+
+use crate::layout::Feature;
+use crate::shaping::plan::{Lookup, Stage};
+use crate::shaping::{closure, Plan, Shaper};
+use crate::tape::Read;
+use crate::{Builder, Font, Result};
+
+/// The tables that do not refer to glyphs and are copied as they are.
+const COPIED: &[&[u8; 4]] = &[b"STAT", b"cvt ", b"fpgm", b"gasp", b"name", b"prep"];
+
+/// The outline and variation tables that cannot be subset, in which case subsetting fails.
+const UNSUPPORTED: &[&[u8; 4]] = &[
+    b"CBDT", b"CBLC", b"CFF2", b"COLR", b"EBDT", b"EBLC", b"HVAR", b"MVAR", b"SVG ", b"VVAR",
+    b"avar", b"cvar", b"fvar", b"gvar", b"sbix",
+];
+
+/// Subsetting options.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// The characters to retain.
+    pub characters: Vec<char>,
+    /// The glyphs to retain in addition to those of the characters.
+    pub glyph_ids: Vec<GlyphID>,
+    /// The layout features to retain or all features if absent.
+    pub features: Option<Vec<Feature>>,
+}
+
+/// A mapping of the glyphs of a font to the glyphs of its subset.
+#[derive(Clone, Debug, Default)]
+pub struct Mapping {
+    glyph_ids: Vec<GlyphID>,
+}
+
+impl Mapping {
+    /// Return the glyph in the subset corresponding to a glyph in the font if retained.
+    #[inline]
+    pub fn get(&self, glyph_id: GlyphID) -> Option<GlyphID> {
+        self.glyph_ids
+            .binary_search(&glyph_id)
+            .ok()
+            .map(|index| index as GlyphID)
+    }
+
+    /// Return the glyphs in the font corresponding to the glyphs in the subset.
+    #[inline]
+    pub fn glyph_ids(&self) -> &[GlyphID] {
+        &self.glyph_ids
+    }
+
+    /// Check if there are no glyphs.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.glyph_ids.is_empty()
+    }
+
+    /// Return the number of glyphs in the subset.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.glyph_ids.len()
+    }
+}
+
+/// Subset a font.
+///
+/// The retained glyphs are the notdef glyph, the glyphs of the characters, the glyphs given
+/// explicitly, the glyphs reachable from them via the glyph-substitution lookups of the retained
+/// features, and the components of composite glyphs, all of which are renumbered in ascending
+/// order. The layout, character-to-glyph, metric, and outline tables are rewritten, and other
+/// tables are either copied or, in case they refer to glyphs but are optional, dropped.
+///
+/// Variable fonts and fonts with color or bitmap outlines are not supported. In the OS/2 and
+/// Windows metrics, the first and last character indices are recomputed, while the Unicode and
+/// code-page ranges are left as they are.
+pub fn subset<T>(font: &Font, tape: &mut T, options: &Options) -> Result<(Builder, Mapping)>
+where
+    T: Read,
+{
+    let shaper = Shaper::read(font, tape)?;
+    let header = match font.take::<_, FontHeader>(tape)? {
+        Some(table) => table,
+        _ => raise!("found no font header"),
+    };
+    let profile = match font.take::<_, MaximumProfile>(tape)? {
+        Some(table) => table,
+        _ => raise!("found no maximum profile"),
+    };
+    let offsets = match font.take_given::<_, GlyphMapping>(tape, (&header, &profile))? {
+        Some(GlyphMapping::HalfOffsets(offsets)) => Some(
+            offsets
+                .into_iter()
+                .map(|offset| 2 * offset as u32)
+                .collect(),
+        ),
+        Some(GlyphMapping::Offsets(offsets)) => Some(offsets),
+        _ => None,
+    };
+    let mut builder = Builder::from_font(font, tape)?;
+    if let Some(tag) = builder.tags().find(|tag| UNSUPPORTED.contains(&&tag.0)) {
+        raise!("found a table with {tag:?}, which is not supported");
+    }
+    let glyph_count = profile.glyph_count();
+
+    let mut glyph_ids = BTreeSet::from([0]);
+    glyph_ids.extend(
+        options
+            .characters
+            .iter()
+            .filter_map(|character| shaper.mapping.get(&(*character as u32))),
+    );
+    glyph_ids.extend(&options.glyph_ids);
+    if let Some(table) = shaper.substitution.as_ref() {
+        let features = options.features.as_deref();
+        let lookups = layout::lookups(table, features)
+            .into_iter()
+            .map(|index| Lookup {
+                index,
+                settings: vec![],
+            })
+            .collect();
+        let plan = Plan {
+            substitution: vec![Stage { lookups }],
+            ..Default::default()
+        };
+        glyph_ids = closure(table, &plan, glyph_ids);
+    }
+    glyph_ids.retain(|&glyph_id| (glyph_id as usize) < glyph_count);
+    let glyph_data = builder.remove(Tag(*b"glyf"));
+    if let (Some(data), Some(offsets)) = (glyph_data.as_ref(), offsets.as_ref()) {
+        glyph_data::close(data, offsets, &mut glyph_ids)?;
+    }
+    let mapping = Mapping {
+        glyph_ids: glyph_ids.into_iter().collect(),
+    };
+
+    for tag in builder.tags().collect::<Vec<_>>() {
+        match &tag.0 {
+            b"CFF " | b"OS/2" | b"VORG" | b"head" | b"hhea" | b"maxp" | b"post" | b"vhea"
+            | b"vmtx" => {}
+            _ if COPIED.contains(&&tag.0) => {}
+            _ => {
+                builder.remove(tag);
+            }
+        }
+    }
+    let mut header = header;
+    if let (Some(data), Some(offsets)) = (glyph_data, offsets) {
+        let (data, offsets, format) = glyph_data::write(&data, &offsets, &mapping)?;
+        builder.add_bytes(Tag(*b"glyf"), data);
+        builder.add_bytes(Tag(*b"loca"), offsets);
+        header.glyph_mapping_format = format;
+    }
+    if let Some(data) = builder.remove(Tag(*b"CFF ")) {
+        builder.add_bytes(Tag(*b"CFF "), font_set::write(&data, &mapping)?);
+    }
+    header.checksum_adjustment = 0;
+    builder.add(&header)?;
+    let characters = (options.characters.iter())
+        .filter_map(|character| {
+            let glyph_id = shaper.mapping.get(&(*character as u32))?;
+            Some((*character as u32, mapping.get(*glyph_id)?))
+        })
+        .collect::<Vec<_>>();
+    let first = characters.iter().map(|value| value.0).min().unwrap_or(0);
+    let last = characters.iter().map(|value| value.0).max().unwrap_or(0);
+    builder.add_bytes(Tag(*b"cmap"), character_mapping::write(characters)?);
+    if builder.get(Tag(*b"OS/2")).is_some() {
+        patch(&mut builder, Tag(*b"OS/2"), 64, first.min(0xFFFF) as u16)?;
+        patch(&mut builder, Tag(*b"OS/2"), 66, last.min(0xFFFF) as u16)?;
+    }
+    let (data, count) = horizontal_metrics::write(&shaper.metrics, &mapping)?;
+    builder.add_bytes(Tag(*b"hmtx"), data);
+    patch(&mut builder, Tag(*b"hhea"), 34, count)?;
+    // The vertical header has the same layout as the horizontal one, and so do the metrics.
+    let data = builder.remove(Tag(*b"vmtx"));
+    let header = (builder.get(Tag(*b"vhea")))
+        .map(|data| Read::take::<HorizontalHeader>(&mut Cursor::new(data)))
+        .transpose()?;
+    match (header, data) {
+        (Some(header), Some(data)) => {
+            let metrics = Read::take_given(&mut Cursor::new(data), (&header, &profile))?;
+            let (data, count) = horizontal_metrics::write(&metrics, &mapping)?;
+            builder.add_bytes(Tag(*b"vmtx"), data);
+            patch(&mut builder, Tag(*b"vhea"), 34, count)?;
+        }
+        (None, None) => {}
+        _ => raise!("found vertical metrics without a vertical header or vice versa"),
+    }
+    if let Some(data) = builder.remove(Tag(*b"VORG")) {
+        builder.add_bytes(Tag(*b"VORG"), vertical_origins::write(&data, &mapping)?);
+    }
+    patch(&mut builder, Tag(*b"maxp"), 4, mapping.len() as u16)?;
+    if let Some(mut data) = builder.remove(Tag(*b"post")) {
+        if data.len() >= 32 && data[..4] != [0, 3, 0, 0] {
+            data.truncate(32);
+            data[..4].copy_from_slice(&[0, 3, 0, 0]);
+        }
+        builder.add_bytes(Tag(*b"post"), data);
+    }
+
+    if let Some(table) = shaper.definition.as_ref() {
+        builder.add(&glyph_definition::subset(table, &mapping))?;
+    }
+    if let Some(table) = shaper.substitution.as_ref() {
+        builder.add(&layout::subset(
+            table,
+            &mapping,
+            options.features.as_deref(),
+        ))?;
+    }
+    if let Some(table) = shaper.positioning.as_ref() {
+        builder.add(&layout::subset(
+            table,
+            &mapping,
+            options.features.as_deref(),
+        ))?;
+    }
+    Ok((builder, mapping))
+}
+
+/// Overwrite a 16-bit field of a table.
+fn patch(builder: &mut Builder, tag: Tag, position: usize, value: u16) -> Result<()> {
+    let mut data = match builder.remove(tag) {
+        Some(data) => data,
+        _ => raise!("found no table with {tag:?}"),
+    };
+    if data.len() < position + 2 {
+        raise!("found a malformed table with {tag:?}");
+    }
+    data[position..(position + 2)].copy_from_slice(&value.to_be_bytes());
+    builder.add_bytes(tag, data);
+    Ok(())
+}
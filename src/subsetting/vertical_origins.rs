@@ -0,0 +1,32 @@
+//! The subsetting of the vertical origins.
+
+use crate::subsetting::Mapping;
+use crate::Result;
+
+/// Write the vertical origins of the retained glyphs.
+pub(crate) fn write(data: &[u8], mapping: &Mapping) -> Result<Vec<u8>> {
+    if data.len() < 8 {
+        raise!("found a malformed table with vertical origins");
+    }
+    let count = u16::from_be_bytes([data[6], data[7]]) as usize;
+    let records = match data.get(8..(8 + 4 * count)) {
+        Some(records) => records,
+        _ => raise!("found a malformed table with vertical origins"),
+    };
+    let mut values = records
+        .chunks(4)
+        .filter_map(|chunk| {
+            let glyph_id = mapping.get(u16::from_be_bytes([chunk[0], chunk[1]]))?;
+            Some((glyph_id, [chunk[2], chunk[3]]))
+        })
+        .collect::<Vec<_>>();
+    values.sort_by_key(|value| value.0);
+    let mut output = Vec::with_capacity(8 + 4 * values.len());
+    output.extend_from_slice(&data[..6]);
+    output.extend_from_slice(&(values.len() as u16).to_be_bytes());
+    for (glyph_id, origin) in values {
+        output.extend_from_slice(&glyph_id.to_be_bytes());
+        output.extend_from_slice(&origin);
+    }
+    Ok(output)
+}
@@ -2,12 +2,18 @@
 //!
 //! [1]: https://learn.microsoft.com/en-us/typography/opentype/spec/cpal
 
+use truetype::tables::names::NameID;
+
 use crate::Result;
 
 /// A color-palette table.
+#[derive(Clone, Debug)]
 pub struct ColorPalettes {
     pub header: Header,
     pub colors: Vec<Color>,
+    pub palette_flags: Option<Vec<PaletteFlags>>,
+    pub palette_name_ids: Option<Vec<NameID>>,
+    pub entry_name_ids: Option<Vec<NameID>>,
 }
 
 /// The header of a color-palette table.
@@ -46,10 +52,15 @@ table! {
         color_indices (Vec<u16>) |this, tape| { // colorRecordIndices
             tape.take_given(this.palette_count as usize)
         },
+
+        palette_flags_offset    (u32), // paletteTypesArrayOffset
+        palette_name_ids_offset (u32), // paletteLabelsArrayOffset
+        entry_name_ids_offset   (u32), // paletteEntryLabelsArrayOffset
     }
 }
 
 table! {
+    @write
     /// A color.
     #[derive(Copy)]
     pub Color { // ColorRecord
@@ -60,6 +71,15 @@ table! {
     }
 }
 
+flags! {
+    /// Palette flags.
+    pub PaletteFlags(u32) { // paletteType
+        0b0000_0000_0000_0000_0000_0000_0000_0001 => is_usable_with_light_background,
+        0b0000_0000_0000_0000_0000_0000_0000_0010 => is_usable_with_dark_background,
+        0b1111_1111_1111_1111_1111_1111_1111_1100 => is_invalid,
+    }
+}
+
 impl ColorPalettes {
     /// Iterate over the entries.
     ///
@@ -96,7 +116,91 @@ impl crate::value::Read for ColorPalettes {
             Header::Version1(ref header) => (header.color_offset, header.color_count as usize),
         };
         let colors = jump_take_given!(@unwrap tape, position, offset, count);
-        Ok(Self { header, colors })
+        let (palette_flags, palette_name_ids, entry_name_ids) = match header {
+            Header::Version0(_) => (None, None, None),
+            Header::Version1(ref header) => {
+                let palette_count = header.palette_count as usize;
+                let entry_count = header.entry_count as usize;
+                (
+                    take(tape, position, header.palette_flags_offset, palette_count)?,
+                    take(
+                        tape,
+                        position,
+                        header.palette_name_ids_offset,
+                        palette_count,
+                    )?,
+                    take(tape, position, header.entry_name_ids_offset, entry_count)?,
+                )
+            }
+        };
+        Ok(Self {
+            header,
+            colors,
+            palette_flags,
+            palette_name_ids,
+            entry_name_ids,
+        })
+    }
+}
+
+impl crate::value::Write for ColorPalettes {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        let (version, entry_count, color_indices) = match self.header {
+            Header::Version0(ref header) => (0u16, header.entry_count, &header.color_indices),
+            Header::Version1(ref header) => (1u16, header.entry_count, &header.color_indices),
+        };
+        let palette_count = color_indices.len();
+        if palette_count > u16::MAX as usize || self.colors.len() > u16::MAX as usize {
+            raise!("found too many colors to write");
+        }
+        let mut size = 12 + 2 * palette_count + if version > 0 { 3 * 4 } else { 0 };
+        let color_offset = size as u32;
+        size += 4 * self.colors.len();
+        tape.give(&version)?;
+        tape.give(&entry_count)?;
+        tape.give(&(palette_count as u16))?;
+        tape.give(&(self.colors.len() as u16))?;
+        tape.give(&color_offset)?;
+        tape.give(&color_indices[..])?;
+        if version > 0 {
+            for (count, size_of, length) in [
+                (palette_count, 4, self.palette_flags.as_ref().map(Vec::len)),
+                (
+                    palette_count,
+                    2,
+                    self.palette_name_ids.as_ref().map(Vec::len),
+                ),
+                (
+                    entry_count as usize,
+                    2,
+                    self.entry_name_ids.as_ref().map(Vec::len),
+                ),
+            ] {
+                match length {
+                    Some(length) if length != count => {
+                        raise!("found palette metadata of an unexpected length ({length})")
+                    }
+                    Some(length) => {
+                        tape.give(&(size as u32))?;
+                        size += size_of * length;
+                    }
+                    _ => tape.give(&0u32)?,
+                }
+            }
+        }
+        tape.give(&self.colors[..])?;
+        if version > 0 {
+            if let Some(ref values) = self.palette_flags {
+                tape.give(&values[..])?;
+            }
+            if let Some(ref values) = self.palette_name_ids {
+                tape.give(&values[..])?;
+            }
+            if let Some(ref values) = self.entry_name_ids {
+                tape.give(&values[..])?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -109,3 +213,15 @@ impl crate::value::Read for Header {
         })
     }
 }
+
+fn take<T, U>(tape: &mut T, position: u64, offset: u32, count: usize) -> Result<Option<Vec<U>>>
+where
+    T: crate::tape::Read,
+    U: crate::value::Read,
+{
+    if offset == 0 {
+        return Ok(None);
+    }
+    tape.jump(position + offset as u64)?;
+    Ok(Some(tape.take_given(count)?))
+}
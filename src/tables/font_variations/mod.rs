@@ -16,6 +16,7 @@ table! {
 }
 
 table! {
+    @write
     /// The header of a font-variations table.
     #[derive(Copy)]
     pub Header {
@@ -31,6 +32,7 @@ table! {
 }
 
 table! {
+    @write
     /// An axis record of a font-variations table.
     #[derive(Copy)]
     pub AxisRecord { // VariationAxisRecord
@@ -47,10 +49,10 @@ table! {
     @define
     /// An instance record of a font-variations table.
     pub InstanceRecord { // InstanceRecord
-        subfamily_name_id  (NameID       ), // subfamilyNameID
-        flags              (InstanceFlags), // flags
-        coordinates        (Vec<q32>     ), // coordinates
-        postscript_name_id (NameID       ), // postScriptNameID
+        subfamily_name_id  (NameID         ), // subfamilyNameID
+        flags              (InstanceFlags  ), // flags
+        coordinates        (Vec<q32>       ), // coordinates
+        postscript_name_id (Option<NameID>), // postScriptNameID
     }
 }
 
@@ -84,7 +86,7 @@ impl crate::value::Read for FontVariations {
         let instance_records = (0..header.instance_count as u64)
             .map(|i| {
                 tape.jump(position + i * header.instance_size as u64)?;
-                tape.take_given(header.axis_count)
+                tape.take_given((header.axis_count, header.instance_size))
             })
             .collect::<Result<Vec<_>>>()?;
         Ok(Self {
@@ -95,15 +97,75 @@ impl crate::value::Read for FontVariations {
     }
 }
 
+impl crate::value::Write for FontVariations {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        let axis_count = self.axis_records.len();
+        let instance_count = self.instance_records.len();
+        if axis_count > u16::MAX as usize || instance_count > u16::MAX as usize {
+            raise!("found too many records to write");
+        }
+        let axis_size = self.header.axis_size.max(20);
+        let size = if self
+            .instance_records
+            .iter()
+            .any(|record| record.postscript_name_id.is_some())
+        {
+            6 + 4 * axis_count
+        } else {
+            4 + 4 * axis_count
+        };
+        let instance_size = match u16::try_from(size) {
+            Ok(size) => self.header.instance_size.max(size),
+            _ => raise!("found too many axes to write ({axis_count})"),
+        };
+        tape.give(&Header {
+            major_version: self.header.major_version,
+            minor_version: self.header.minor_version,
+            axis_offset: 16,
+            reserved: 2,
+            axis_count: axis_count as u16,
+            axis_size,
+            instance_count: instance_count as u16,
+            instance_size,
+        })?;
+        for record in self.axis_records.iter() {
+            tape.give(record)?;
+            tape.give_bytes(&vec![0; axis_size as usize - 20])?;
+        }
+        for record in self.instance_records.iter() {
+            if record.coordinates.len() != axis_count {
+                raise!("found an instance record with a wrong number of coordinates");
+            }
+            let mut size = 4 + 4 * axis_count;
+            tape.give(&record.subfamily_name_id)?;
+            tape.give(&record.flags)?;
+            tape.give(&record.coordinates[..])?;
+            if size + 2 <= instance_size as usize {
+                tape.give(&record.postscript_name_id.unwrap_or(NameID::from(0xFFFF)))?;
+                size += 2;
+            }
+            tape.give_bytes(&vec![0; instance_size as usize - size])?;
+        }
+        Ok(())
+    }
+}
+
 impl crate::walue::Read<'static> for InstanceRecord {
-    type Parameter = u16;
+    type Parameter = (u16, u16);
 
-    fn read<T: crate::tape::Read>(tape: &mut T, axis_count: Self::Parameter) -> Result<Self> {
+    fn read<T: crate::tape::Read>(
+        tape: &mut T,
+        (axis_count, instance_size): Self::Parameter,
+    ) -> Result<Self> {
         Ok(Self {
             subfamily_name_id: tape.take()?,
             flags: tape.take()?,
             coordinates: tape.take_given(axis_count as usize)?,
-            postscript_name_id: tape.take()?,
+            postscript_name_id: if instance_size as usize >= 6 + 4 * axis_count as usize {
+                Some(tape.take()?)
+            } else {
+                None
+            },
         })
     }
 }
@@ -1,7 +1,13 @@
+use truetype::q16;
+
+use crate::graph::{Graph, Object};
 use crate::layout::{Correction, Coverage};
+use crate::tape::Write;
+use crate::variations::item::Store;
 use crate::Result;
 
 table! {
+    @write
     /// A glyph attachment.
     pub Attachment { // AttachPoint
         index_count (u16), // pointCount
@@ -45,6 +51,7 @@ pub enum Caret {
 }
 
 table! {
+    @write
     /// A ligature caret in format 1.
     #[derive(Copy)]
     pub Caret1 { // CaretValueFormat1
@@ -54,6 +61,7 @@ table! {
 }
 
 table! {
+    @write
     /// A ligature caret in format 2.
     #[derive(Copy)]
     pub Caret2 { // CaretValueFormat2
@@ -130,9 +138,27 @@ table! {
     }
 }
 
+impl Caret {
+    /// Return the coordinate with variations applied at normalized coordinates.
+    ///
+    /// `None` is returned for carets given as contour points.
+    pub fn coordinate(&self, store: Option<&Store>, coordinates: &[q16]) -> Option<f32> {
+        match self {
+            Self::Format1(table) => Some(table.coordinate as f32),
+            Self::Format2(_) => None,
+            Self::Format3(table) => {
+                let delta = store
+                    .and_then(|store| table.correction.evaluate(store, coordinates))
+                    .unwrap_or(0.0);
+                Some(table.coordinate as f32 + delta)
+            }
+        }
+    }
+}
+
 impl crate::value::Read for Caret {
     fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
-        Ok(match tape.peek::<u32>()? {
+        Ok(match tape.peek::<u16>()? {
             1 => Self::Format1(tape.take()?),
             2 => Self::Format2(tape.take()?),
             3 => Self::Format3(tape.take()?),
@@ -140,3 +166,71 @@ impl crate::value::Read for Caret {
         })
     }
 }
+
+impl crate::graph::Write for Attachments {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.link(coverage);
+        object.give_count(records.len())?;
+        records.into_iter().for_each(|index| object.link(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Caret {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        match self {
+            Self::Format1(table) => graph.give(table),
+            Self::Format2(table) => graph.give(table),
+            Self::Format3(table) => graph.give(table),
+        }
+    }
+}
+
+impl crate::graph::Write for Caret3 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let correction = graph.give(&self.correction)?;
+        let mut object = Object::default();
+        object.give(&3u16)?;
+        object.give(&self.coordinate)?;
+        object.link(correction);
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Ligature {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let carets = graph.give_all(&self.carets)?;
+        let mut object = Object::default();
+        object.give_count(carets.len())?;
+        carets.into_iter().for_each(|index| object.link(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Ligatures {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.link(coverage);
+        object.give_count(records.len())?;
+        records.into_iter().for_each(|index| object.link(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Marks {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverages = graph.give_all(&self.coverages)?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.give_count(coverages.len())?;
+        coverages
+            .into_iter()
+            .for_each(|index| object.link_long(index));
+        Ok(graph.add(object))
+    }
+}
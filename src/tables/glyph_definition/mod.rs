@@ -6,7 +6,11 @@ mod element;
 
 pub use element::*;
 
+use truetype::q16;
+
+use crate::graph::{Graph, Object};
 use crate::layout::Class;
+use crate::tape::Write;
 use crate::variations::item::Store;
 use crate::Result;
 
@@ -74,12 +78,36 @@ table! {
             jump_take_maybe!(
                 tape,
                 position,
-                field!(this.header => mark_offset(0), Header::{Version13})
+                field!(this.header => variation_offset(0), Header::{Version13})
             )
         },
     }
 }
 
+impl GlyphDefinition {
+    /// Return the caret coordinates of the ligatures at normalized coordinates.
+    ///
+    /// The ligatures follow the order of the coverage of the ligature caret list, and carets given
+    /// as contour points are `None`.
+    pub fn ligature_carets(&self, coordinates: &[q16]) -> Vec<Vec<Option<f32>>> {
+        let ligatures = match self.ligatures {
+            Some(ref ligatures) => ligatures,
+            _ => return vec![],
+        };
+        ligatures
+            .records
+            .iter()
+            .map(|ligature| {
+                ligature
+                    .carets
+                    .iter()
+                    .map(|caret| caret.coordinate(self.variations.as_ref(), coordinates))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
 /// The header of a glyph definition.
 #[derive(Clone, Debug)]
 pub enum Header {
@@ -150,3 +178,44 @@ impl crate::value::Read for Header {
         })
     }
 }
+
+impl crate::value::Write for GlyphDefinition {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        let minor_version = match self.header {
+            Header::Version1(_) => 0u16,
+            Header::Version12(_) => 2,
+            Header::Version13(_) => 3,
+        };
+        if minor_version < 2 && self.marks.is_some() {
+            raise!("found mark glyph sets, which require a glyph definition of version 1.2");
+        }
+        if minor_version < 3 && self.variations.is_some() {
+            raise!(
+                "found an item variation store, which requires a glyph definition of version 1.3"
+            );
+        }
+        let mut graph = Graph::default();
+        let glyph_class = graph.give_maybe(&self.glyph_class)?;
+        let attachments = graph.give_maybe(&self.attachments)?;
+        let ligatures = graph.give_maybe(&self.ligatures)?;
+        let mark_class = graph.give_maybe(&self.mark_class)?;
+        let marks = graph.give_maybe(&self.marks)?;
+        let variations = graph.give_maybe(&self.variations)?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.give(&minor_version)?;
+        object.link_maybe(glyph_class);
+        object.link_maybe(attachments);
+        object.link_maybe(ligatures);
+        object.link_maybe(mark_class);
+        if minor_version >= 2 {
+            object.link_maybe(marks);
+        }
+        if minor_version >= 3 {
+            object.link_long_maybe(variations);
+        }
+        let root = graph.add(object);
+        let data = graph.serialize(root, |_, _, _| Ok(false))?;
+        tape.give_bytes(&data)
+    }
+}
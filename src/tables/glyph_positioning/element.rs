@@ -1,7 +1,10 @@
 use truetype::GlyphID;
 
+use crate::graph::{Graph, Object};
 use crate::layout::Correction;
-use crate::Result;
+use crate::options::Repair;
+use crate::tape::Write;
+use crate::{ReadOptions, Result};
 
 /// An anchor.
 #[derive(Clone, Debug)]
@@ -15,6 +18,7 @@ pub enum Anchor {
 }
 
 table! {
+    @write
     /// An anchor in format 1.
     #[derive(Copy)]
     pub Anchor1 { // AnchorFormat1
@@ -25,6 +29,7 @@ table! {
 }
 
 table! {
+    @write
     /// An anchor in format 2.
     #[derive(Copy)]
     pub Anchor2 { // AnchorFormat2
@@ -128,14 +133,11 @@ table! {
 }
 
 table! {
-    @position
+    @define
     /// Marks in format 1.
     pub Mark1s { // MarkArray
-        count (u16), // markCount
-
-        records (Vec<Mark1>) |this, tape, position| { // markRecords
-            (0..this.count).map(|_| tape.take_given(position)).collect()
-        },
+        count   (u16      ), // markCount
+        records (Vec<Mark1>), // markRecords
     }
 }
 
@@ -229,6 +231,71 @@ flags! {
     }
 }
 
+impl Single {
+    /// Return the flags of the fields that are present.
+    pub fn flags(&self) -> Flags {
+        let mut value = 0;
+        for (i, present) in [
+            self.x_placement.is_some(),
+            self.y_placement.is_some(),
+            self.x_advance.is_some(),
+            self.y_advance.is_some(),
+            self.x_placement_correction.is_some(),
+            self.y_placement_correction.is_some(),
+            self.x_advance_correction.is_some(),
+            self.y_advance_correction.is_some(),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if present {
+                value |= 1 << i;
+            }
+        }
+        Flags(value)
+    }
+
+    /// Write the fields given by the flags with the corrections relative to the object.
+    pub(crate) fn write(&self, object: &mut Object, graph: &mut Graph, flags: Flags) -> Result<()> {
+        for (flag, value) in [
+            (flags.has_x_placement(), self.x_placement),
+            (flags.has_y_placement(), self.y_placement),
+            (flags.has_x_advance(), self.x_advance),
+            (flags.has_y_advance(), self.y_advance),
+        ] {
+            if flag {
+                object.give(&value.unwrap_or(0))?;
+            }
+        }
+        for (flag, value) in [
+            (
+                flags.has_x_placement_correction(),
+                &self.x_placement_correction,
+            ),
+            (
+                flags.has_y_placement_correction(),
+                &self.y_placement_correction,
+            ),
+            (flags.has_x_advance_correction(), &self.x_advance_correction),
+            (flags.has_y_advance_correction(), &self.y_advance_correction),
+        ] {
+            if flag {
+                object.link_maybe(graph.give_maybe(value)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::BitOr for Flags {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, other: Self) -> Self {
+        Flags(self.0 | other.0)
+    }
+}
+
 impl Default for Anchor {
     #[inline]
     fn default() -> Self {
@@ -324,18 +391,22 @@ impl crate::walue::Read<'static> for Ligatures {
     }
 }
 
-impl crate::walue::Read<'static> for Mark1 {
-    type Parameter = u64;
+impl<'l> crate::walue::Read<'l> for Mark1 {
+    type Parameter = (u64, &'l mut ReadOptions);
 
-    fn read<T: crate::tape::Read>(tape: &mut T, position: Self::Parameter) -> Result<Self> {
+    fn read<T: crate::tape::Read>(
+        tape: &mut T,
+        (position, options): Self::Parameter,
+    ) -> Result<Self> {
         let class_id = tape.take()?;
         let anchor_offset = tape.take()?;
-        #[cfg(not(feature = "ignore-incomplete-marks"))]
-        let anchor = tape.stay(|tape| jump_take!(tape, position, anchor_offset))?;
-        #[cfg(feature = "ignore-incomplete-marks")]
-        let anchor = tape
-            .stay(|tape| jump_take_maybe!(tape, position, anchor_offset))?
-            .unwrap_or_default();
+        let anchor = if anchor_offset == 0
+            && options.repair(options.ignore_incomplete_marks, Repair::IncompleteMark)
+        {
+            Anchor::default()
+        } else {
+            tape.stay(|tape| jump_take!(tape, position, anchor_offset))?
+        };
         Ok(Self {
             class_id,
             anchor_offset,
@@ -344,6 +415,19 @@ impl crate::walue::Read<'static> for Mark1 {
     }
 }
 
+impl<'l> crate::walue::Read<'l> for Mark1s {
+    type Parameter = &'l mut ReadOptions;
+
+    fn read<T: crate::tape::Read>(tape: &mut T, options: Self::Parameter) -> Result<Self> {
+        let position = tape.position()?;
+        let count = tape.take()?;
+        let records = (0..count)
+            .map(|_| tape.take_given((position, &mut *options)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { count, records })
+    }
+}
+
 impl crate::walue::Read<'static> for Mark2 {
     type Parameter = (u64, u16);
 
@@ -523,3 +607,106 @@ impl crate::walue::Read<'static> for Single {
         })
     }
 }
+
+impl crate::graph::Write for Anchor {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        match self {
+            Anchor::Format1(table) => graph.give(table),
+            Anchor::Format2(table) => graph.give(table),
+            Anchor::Format3(table) => graph.give(table),
+        }
+    }
+}
+
+impl crate::graph::Write for Anchor3 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let x_correction = graph.give_maybe(&self.x_correction)?;
+        let y_correction = graph.give_maybe(&self.y_correction)?;
+        let mut object = Object::default();
+        object.give(&3u16)?;
+        object.give(&self.x)?;
+        object.give(&self.y)?;
+        object.link_maybe(x_correction);
+        object.link_maybe(y_correction);
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Bases {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let mut object = Object::default();
+        object.give_count(self.records.len())?;
+        for record in self.records.iter() {
+            for anchor in record.anchors.iter() {
+                object.link_maybe(graph.give_maybe(anchor)?);
+            }
+        }
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Ligature {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let mut object = Object::default();
+        object.give_count(self.components.len())?;
+        for component in self.components.iter() {
+            for anchor in component.anchors.iter() {
+                object.link_maybe(graph.give_maybe(anchor)?);
+            }
+        }
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Ligatures {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give_count(records.len())?;
+        records.into_iter().for_each(|index| object.link(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Mark1s {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let mut object = Object::default();
+        object.give_count(self.records.len())?;
+        for record in self.records.iter() {
+            object.give(&record.class_id)?;
+            object.link(graph.give(&record.anchor)?);
+        }
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for Mark2s {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let mut object = Object::default();
+        object.give_count(self.records.len())?;
+        for record in self.records.iter() {
+            for anchor in record.anchors.iter() {
+                object.link_maybe(graph.give_maybe(anchor)?);
+            }
+        }
+        Ok(graph.add(object))
+    }
+}
+
+/// Write pairs of values given the flags of the first and second values.
+pub(crate) fn write_pair1s(
+    pairs: &Pair1s,
+    graph: &mut Graph,
+    (value1_flags, value2_flags): (Flags, Flags),
+) -> Result<usize> {
+    let mut object = Object::default();
+    object.give_count(pairs.records.len())?;
+    for record in pairs.records.iter() {
+        object.give(&record.glyph2_id)?;
+        let value1 = record.value1.clone().unwrap_or_default();
+        value1.write(&mut object, graph, value1_flags)?;
+        let value2 = record.value2.clone().unwrap_or_default();
+        value2.write(&mut object, graph, value2_flags)?;
+    }
+    Ok(graph.add(object))
+}
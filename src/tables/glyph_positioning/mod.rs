@@ -6,8 +6,11 @@ mod element;
 
 pub use element::*;
 
+use crate::graph::{Graph, Object};
 use crate::layout::{ChainedContext, Class, Context, Coverage, Directory};
-use crate::Result;
+use crate::tape::Write;
+use crate::trace::trace;
+use crate::{ReadOptions, Result};
 
 /// A glyph positioning.
 pub type GlyphPositioning = Directory<Type>;
@@ -48,7 +51,7 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
     }
 }
@@ -69,7 +72,7 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
     }
 }
@@ -98,17 +101,19 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         records (Vec<Pair1s>) |this, tape, position| {
-            jump_take_given!(
-                tape,
-                position,
-                this.record_count,
-                this.record_offsets,
-                (this.value1_flags, this.value2_flags)
-            )
+            trace(tape, "records", |tape| {
+                jump_take_given!(
+                    tape,
+                    position,
+                    this.record_count,
+                    this.record_offsets,
+                    (this.value1_flags, this.value2_flags)
+                )
+            })
         },
     }
 }
@@ -133,15 +138,15 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         class1 (Class) |this, tape, position| {
-            jump_take!(tape, position, this.class1_offset)
+            trace(tape, "class1", |tape| jump_take!(tape, position, this.class1_offset))
         },
 
         class2 (Class) |this, tape, position| {
-            jump_take!(tape, position, this.class2_offset)
+            trace(tape, "class2", |tape| jump_take!(tape, position, this.class2_offset))
         },
     }
 }
@@ -161,100 +166,70 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
     }
 }
 
 table! {
-    @position
+    @define
     /// A mark-to-base attachment.
     pub MarkToBaseAttachment { // MarkBasePosFormat1
-        format               (u16) = { 1 }, // posFormat
+        format               (u16), // posFormat
         mark_coverage_offset (u16), // markCoverageOffset
         base_coverage_offset (u16), // baseCoverageOffset
         mark_class_count     (u16), // markClassCount
         marks_offset         (u16), // markArrayOffset
         bases_offset         (u16), // baseArrayOffset
 
-        mark_coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.mark_coverage_offset)
-        },
-
-        base_coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.base_coverage_offset)
-        },
-
-        marks (Mark1s) |this, tape, position| {
-            jump_take!(tape, position, this.marks_offset)
-        },
-
-        bases (Bases) |this, tape, position| {
-            jump_take_given!(tape, position, this.bases_offset, this.mark_class_count)
-        },
+        mark_coverage (Coverage),
+        base_coverage (Coverage),
+        marks         (Mark1s  ),
+        bases         (Bases   ),
     }
 }
 
 table! {
-    @position
+    @define
     /// A mark-to-ligature attachment.
     pub MarkToLigatureAttachment { // MarkLigPosFormat1
-        format                   (u16) = { 1 }, // posFormat
+        format                   (u16), // posFormat
         mark_coverage_offset     (u16), // markCoverageOffset
         ligature_coverage_offset (u16), // ligatureCoverageOffset
         mark_class_count         (u16), // markClassCount
         marks_offset             (u16), // markArrayOffset
         ligatures_offset         (u16), // ligatureArrayOffset
 
-        mark_coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.mark_coverage_offset)
-        },
-
-        ligature_coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.ligature_coverage_offset)
-        },
-
-        marks (Mark1s) |this, tape, position| {
-            jump_take!(tape, position, this.marks_offset)
-        },
-
-        ligatures (Ligatures) |this, tape, position| {
-            jump_take_given!(tape, position, this.ligatures_offset, this.mark_class_count)
-        },
+        mark_coverage     (Coverage ),
+        ligature_coverage (Coverage ),
+        marks             (Mark1s   ),
+        ligatures         (Ligatures),
     }
 }
 
 table! {
-    @position
+    @define
     /// A mark-to-mark attachment.
     pub MarkToMarkAttachment { // MarkMarkPosFormat1
-        format                (u16) = { 1 }, // posFormat
+        format                (u16), // posFormat
         mark1_coverage_offset (u16), // mark1CoverageOffset
         mark2_coverage_offset (u16), // mark2CoverageOffset
         mark_class_count      (u16), // markClassCount
         mark1s_offset         (u16), // mark1ArrayOffset
         mark2s_offset         (u16), // mark2ArrayOffset
 
-        mark1_coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.mark1_coverage_offset)
-        },
-
-        mark2_coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.mark2_coverage_offset)
-        },
-
-        mark1s (Mark1s) |this, tape, position| {
-            jump_take!(tape, position, this.mark1s_offset)
-        },
-
-        mark2s (Mark2s) |this, tape, position| {
-            jump_take_given!(tape, position, this.mark2s_offset, this.mark_class_count)
-        },
+        mark1_coverage (Coverage),
+        mark2_coverage (Coverage),
+        mark1s         (Mark1s  ),
+        mark2s         (Mark2s  ),
     }
 }
 
 table! {
     /// An extension positioning.
+    ///
+    /// Extensions are resolved when parsing lookups, in which case the referenced subtable is
+    /// returned instead.
     pub ExtensionPositioning { // ExtensionPosFormat1
         format (u16) = { 1 }, // posFormat
         r#type (u16), // extensionLookupType
@@ -262,30 +237,63 @@ table! {
     }
 }
 
-impl crate::walue::Read<'static> for Type {
-    type Parameter = u16;
+impl<'l> crate::walue::Read<'l> for Type {
+    type Parameter = (u16, &'l mut ReadOptions);
 
-    fn read<T: crate::tape::Read>(tape: &mut T, r#type: u16) -> Result<Self> {
+    fn read<T: crate::tape::Read>(
+        tape: &mut T,
+        (r#type, options): Self::Parameter,
+    ) -> Result<Self> {
         Ok(match r#type {
             1 => Self::SingleAdjustment(tape.take()?),
             2 => Self::PairAdjustment(tape.take()?),
-            3 => Self::CursiveAttachment(tape.take()?),
-            4 => Self::MarkToBaseAttachment(tape.take()?),
-            5 => Self::MarkToLigatureAttachment(tape.take()?),
-            6 => Self::MarkToMarkAttachment(tape.take()?),
+            3 => Self::CursiveAttachment(trace(tape, "CursiveAttachment", |tape| tape.take())?),
+            4 => Self::MarkToBaseAttachment(trace(tape, "MarkToBaseAttachment", |tape| {
+                tape.take_given(options)
+            })?),
+            5 => Self::MarkToLigatureAttachment(trace(tape, "MarkToLigatureAttachment", |tape| {
+                tape.take_given(options)
+            })?),
+            6 => Self::MarkToMarkAttachment(trace(tape, "MarkToMarkAttachment", |tape| {
+                tape.take_given(options)
+            })?),
             7 => Self::ContextualPositioning(tape.take()?),
             8 => Self::ChainedContextualPositioning(tape.take()?),
-            9 => Self::ExtensionPositioning(tape.take()?),
+            9 => trace(tape, "ExtensionPositioning", |tape| {
+                let position = tape.position()?;
+                let table = tape.take::<ExtensionPositioning>()?;
+                if table.r#type == 9 {
+                    raise!("found a nested extension positioning");
+                }
+                tape.jump(position + table.offset as u64)?;
+                tape.take_given((table.r#type, options))
+            })?,
             value => raise!("found an unknown type of glyph positioning ({value})"),
         })
     }
 }
 
+impl crate::layout::lookup::Subtable for Type {
+    fn r#type(&self) -> u16 {
+        match self {
+            Self::SingleAdjustment(_) => 1,
+            Self::PairAdjustment(_) => 2,
+            Self::CursiveAttachment(_) => 3,
+            Self::MarkToBaseAttachment(_) => 4,
+            Self::MarkToLigatureAttachment(_) => 5,
+            Self::MarkToMarkAttachment(_) => 6,
+            Self::ContextualPositioning(_) => 7,
+            Self::ChainedContextualPositioning(_) => 8,
+            Self::ExtensionPositioning(table) => table.r#type,
+        }
+    }
+}
+
 impl crate::value::Read for SingleAdjustment {
     fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
         Ok(match tape.peek::<u16>()? {
-            1 => Self::Format1(tape.take()?),
-            2 => Self::Format2(tape.take()?),
+            1 => Self::Format1(trace(tape, "SingleAdjustment1", |tape| tape.take())?),
+            2 => Self::Format2(trace(tape, "SingleAdjustment2", |tape| tape.take())?),
             value => raise!("found an unknown format of the single adjustment ({value})"),
         })
     }
@@ -294,9 +302,313 @@ impl crate::value::Read for SingleAdjustment {
 impl crate::value::Read for PairAdjustment {
     fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
         Ok(match tape.peek::<u16>()? {
-            1 => Self::Format1(tape.take()?),
-            2 => Self::Format2(tape.take()?),
+            1 => Self::Format1(trace(tape, "PairAdjustment1", |tape| tape.take())?),
+            2 => Self::Format2(trace(tape, "PairAdjustment2", |tape| tape.take())?),
             value => raise!("found an unknown format of the pair adjustment ({value})"),
         })
     }
 }
+
+impl<'l> crate::walue::Read<'l> for MarkToBaseAttachment {
+    type Parameter = &'l mut ReadOptions;
+
+    fn read<T: crate::tape::Read>(tape: &mut T, options: Self::Parameter) -> Result<Self> {
+        let position = tape.position()?;
+        let format = tape.take()?;
+        if format != 1 {
+            raise!("found an unknown format of the mark-to-base attachment ({format})");
+        }
+        let mark_coverage_offset = tape.take()?;
+        let base_coverage_offset = tape.take()?;
+        let mark_class_count = tape.take()?;
+        let marks_offset = tape.take()?;
+        let bases_offset = tape.take()?;
+        let mark_coverage = trace(tape, "mark_coverage", |tape| {
+            jump_take!(tape, position, mark_coverage_offset)
+        })?;
+        let base_coverage = trace(tape, "base_coverage", |tape| {
+            jump_take!(tape, position, base_coverage_offset)
+        })?;
+        let marks = trace(tape, "marks", |tape| {
+            jump_take_given!(tape, position, marks_offset, options)
+        })?;
+        let bases = trace(tape, "bases", |tape| {
+            jump_take_given!(tape, position, bases_offset, mark_class_count)
+        })?;
+        Ok(Self {
+            format,
+            mark_coverage_offset,
+            base_coverage_offset,
+            mark_class_count,
+            marks_offset,
+            bases_offset,
+            mark_coverage,
+            base_coverage,
+            marks,
+            bases,
+        })
+    }
+}
+
+impl<'l> crate::walue::Read<'l> for MarkToLigatureAttachment {
+    type Parameter = &'l mut ReadOptions;
+
+    fn read<T: crate::tape::Read>(tape: &mut T, options: Self::Parameter) -> Result<Self> {
+        let position = tape.position()?;
+        let format = tape.take()?;
+        if format != 1 {
+            raise!("found an unknown format of the mark-to-ligature attachment ({format})");
+        }
+        let mark_coverage_offset = tape.take()?;
+        let ligature_coverage_offset = tape.take()?;
+        let mark_class_count = tape.take()?;
+        let marks_offset = tape.take()?;
+        let ligatures_offset = tape.take()?;
+        let mark_coverage = trace(tape, "mark_coverage", |tape| {
+            jump_take!(tape, position, mark_coverage_offset)
+        })?;
+        let ligature_coverage = trace(tape, "ligature_coverage", |tape| {
+            jump_take!(tape, position, ligature_coverage_offset)
+        })?;
+        let marks = trace(tape, "marks", |tape| {
+            jump_take_given!(tape, position, marks_offset, options)
+        })?;
+        let ligatures = trace(tape, "ligatures", |tape| {
+            jump_take_given!(tape, position, ligatures_offset, mark_class_count)
+        })?;
+        Ok(Self {
+            format,
+            mark_coverage_offset,
+            ligature_coverage_offset,
+            mark_class_count,
+            marks_offset,
+            ligatures_offset,
+            mark_coverage,
+            ligature_coverage,
+            marks,
+            ligatures,
+        })
+    }
+}
+
+impl<'l> crate::walue::Read<'l> for MarkToMarkAttachment {
+    type Parameter = &'l mut ReadOptions;
+
+    fn read<T: crate::tape::Read>(tape: &mut T, options: Self::Parameter) -> Result<Self> {
+        let position = tape.position()?;
+        let format = tape.take()?;
+        if format != 1 {
+            raise!("found an unknown format of the mark-to-mark attachment ({format})");
+        }
+        let mark1_coverage_offset = tape.take()?;
+        let mark2_coverage_offset = tape.take()?;
+        let mark_class_count = tape.take()?;
+        let mark1s_offset = tape.take()?;
+        let mark2s_offset = tape.take()?;
+        let mark1_coverage = trace(tape, "mark1_coverage", |tape| {
+            jump_take!(tape, position, mark1_coverage_offset)
+        })?;
+        let mark2_coverage = trace(tape, "mark2_coverage", |tape| {
+            jump_take!(tape, position, mark2_coverage_offset)
+        })?;
+        let mark1s = trace(tape, "mark1s", |tape| {
+            jump_take_given!(tape, position, mark1s_offset, options)
+        })?;
+        let mark2s = trace(tape, "mark2s", |tape| {
+            jump_take_given!(tape, position, mark2s_offset, mark_class_count)
+        })?;
+        Ok(Self {
+            format,
+            mark1_coverage_offset,
+            mark2_coverage_offset,
+            mark_class_count,
+            mark1s_offset,
+            mark2s_offset,
+            mark1_coverage,
+            mark2_coverage,
+            mark1s,
+            mark2s,
+        })
+    }
+}
+
+impl crate::value::Write for GlyphPositioning {
+    #[inline]
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        Directory::write(self, tape, 9)
+    }
+}
+
+impl crate::graph::Write for Type {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        match self {
+            Self::SingleAdjustment(table) => graph.give(table),
+            Self::PairAdjustment(table) => graph.give(table),
+            Self::CursiveAttachment(table) => graph.give(table),
+            Self::MarkToBaseAttachment(table) => graph.give(table),
+            Self::MarkToLigatureAttachment(table) => graph.give(table),
+            Self::MarkToMarkAttachment(table) => graph.give(table),
+            Self::ContextualPositioning(table) => graph.give(table),
+            Self::ChainedContextualPositioning(table) => graph.give(table),
+            Self::ExtensionPositioning(_) => {
+                raise!("found an unresolved extension positioning, which cannot be written")
+            }
+        }
+    }
+}
+
+impl crate::graph::Write for SingleAdjustment {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        match self {
+            Self::Format1(table) => graph.give(table),
+            Self::Format2(table) => graph.give(table),
+        }
+    }
+}
+
+impl crate::graph::Write for SingleAdjustment1 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let value_flags = self.value.flags();
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.link(coverage);
+        object.give(&value_flags)?;
+        self.value.write(&mut object, graph, value_flags)?;
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for SingleAdjustment2 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let value_flags = flags(self.values.iter());
+        let mut object = Object::default();
+        object.give(&2u16)?;
+        object.link(coverage);
+        object.give(&value_flags)?;
+        object.give_count(self.values.len())?;
+        for value in self.values.iter() {
+            value.write(&mut object, graph, value_flags)?;
+        }
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for PairAdjustment {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        match self {
+            Self::Format1(table) => graph.give(table),
+            Self::Format2(table) => graph.give(table),
+        }
+    }
+}
+
+impl crate::graph::Write for PairAdjustment1 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let records = self.records.iter().flat_map(|record| record.records.iter());
+        let value1_flags = flags(records.clone().filter_map(|record| record.value1.as_ref()));
+        let value2_flags = flags(records.filter_map(|record| record.value2.as_ref()));
+        let coverage = graph.give(&self.coverage)?;
+        let records = self
+            .records
+            .iter()
+            .map(|record| write_pair1s(record, graph, (value1_flags, value2_flags)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.link(coverage);
+        object.give(&value1_flags)?;
+        object.give(&value2_flags)?;
+        object.give_count(records.len())?;
+        records.into_iter().for_each(|index| object.link(index));
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for PairAdjustment2 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let records = self.records.iter().flat_map(|record| record.records.iter());
+        let value1_flags = flags(records.clone().filter_map(|record| record.value1.as_ref()));
+        let value2_flags = flags(records.filter_map(|record| record.value2.as_ref()));
+        let class2_count = self
+            .records
+            .first()
+            .map_or(0, |record| record.records.len());
+        if self
+            .records
+            .iter()
+            .any(|record| record.records.len() != class2_count)
+        {
+            raise!("found class records of different lengths, which cannot be written");
+        }
+        let coverage = graph.give(&self.coverage)?;
+        let class1 = graph.give(&self.class1)?;
+        let class2 = graph.give(&self.class2)?;
+        let mut object = Object::default();
+        object.give(&2u16)?;
+        object.link(coverage);
+        object.give(&value1_flags)?;
+        object.give(&value2_flags)?;
+        object.link(class1);
+        object.link(class2);
+        object.give_count(self.records.len())?;
+        object.give_count(class2_count)?;
+        for record in self.records.iter().flat_map(|record| record.records.iter()) {
+            let value1 = record.value1.clone().unwrap_or_default();
+            value1.write(&mut object, graph, value1_flags)?;
+            let value2 = record.value2.clone().unwrap_or_default();
+            value2.write(&mut object, graph, value2_flags)?;
+        }
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for CursiveAttachment {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.link(coverage);
+        object.give_count(self.connections.len())?;
+        for connection in self.connections.iter() {
+            object.link_maybe(graph.give_maybe(&connection.start_anchor)?);
+            object.link_maybe(graph.give_maybe(&connection.end_anchor)?);
+        }
+        Ok(graph.add(object))
+    }
+}
+
+macro_rules! implement {
+    ($($name:ident { $coverage1:ident, $coverage2:ident, $marks1:ident, $marks2:ident },)*) => (
+        $(impl crate::graph::Write for $name {
+            fn write(&self, graph: &mut Graph) -> Result<usize> {
+                let coverage1 = graph.give(&self.$coverage1)?;
+                let coverage2 = graph.give(&self.$coverage2)?;
+                let marks1 = graph.give(&self.$marks1)?;
+                let marks2 = graph.give(&self.$marks2)?;
+                let mut object = Object::default();
+                object.give(&1u16)?;
+                object.link(coverage1);
+                object.link(coverage2);
+                object.give(&self.mark_class_count)?;
+                object.link(marks1);
+                object.link(marks2);
+                Ok(graph.add(object))
+            }
+        })*
+    );
+}
+
+implement! {
+    MarkToBaseAttachment { mark_coverage, base_coverage, marks, bases },
+    MarkToLigatureAttachment { mark_coverage, ligature_coverage, marks, ligatures },
+    MarkToMarkAttachment { mark1_coverage, mark2_coverage, mark1s, mark2s },
+}
+
+fn flags<'l, T>(values: T) -> Flags
+where
+    T: Iterator<Item = &'l Single>,
+{
+    values.fold(Flags(0), |flags, value| flags | value.flags())
+}
@@ -1,6 +1,10 @@
 use truetype::GlyphID;
 
+use crate::graph::{Graph, Object};
+use crate::Result;
+
 table! {
+    @write
     /// Alternates.
     pub Alternates { // AlternateSet
         glyph_count (u16), // glyphCount
@@ -12,6 +16,7 @@ table! {
 }
 
 table! {
+    @write
     /// A ligature.
     pub Ligature { // Ligature
         glyph_id    (GlyphID), // ligatureGlyph
@@ -43,6 +48,7 @@ table! {
 }
 
 table! {
+    @write
     /// A sequence.
     pub Sequence { // Sequence
         glyph_count (u16), // glyphCount
@@ -52,3 +58,13 @@ table! {
         },
     }
 }
+
+impl crate::graph::Write for Ligatures {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give_count(records.len())?;
+        records.into_iter().for_each(|index| object.link(index));
+        Ok(graph.add(object))
+    }
+}
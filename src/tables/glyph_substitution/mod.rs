@@ -6,8 +6,11 @@ mod element;
 
 use truetype::GlyphID;
 
+use crate::graph::{Graph, Object};
 use crate::layout::{ChainedContext, Context, Coverage, Directory};
-use crate::Result;
+use crate::tape::Write;
+use crate::trace::trace;
+use crate::{ReadOptions, Result};
 
 pub use element::*;
 
@@ -45,7 +48,7 @@ table! {
         delta_glyph_id  (i16), // DeltaGlyphID
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
     }
 }
@@ -63,7 +66,7 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
     }
 }
@@ -81,11 +84,13 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         records (Vec<Sequence>) |this, tape, position| {
-            jump_take!(tape, position, this.record_count, this.record_offsets)
+            trace(tape, "records", |tape| {
+                jump_take!(tape, position, this.record_count, this.record_offsets)
+            })
         },
     }
 }
@@ -103,11 +108,13 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         records (Vec<Alternates>) |this, tape, position| {
-            jump_take!(tape, position, this.record_count, this.record_offsets)
+            trace(tape, "records", |tape| {
+                jump_take!(tape, position, this.record_count, this.record_offsets)
+            })
         },
     }
 }
@@ -125,17 +132,22 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         records (Vec<Ligatures>) |this, tape, position| {
-            jump_take!(tape, position, this.record_count, this.record_offsets)
+            trace(tape, "records", |tape| {
+                jump_take!(tape, position, this.record_count, this.record_offsets)
+            })
         },
     }
 }
 
 table! {
     /// An extension substitution.
+    ///
+    /// Extensions are resolved when parsing lookups, in which case the referenced subtable is
+    /// returned instead.
     pub ExtensionSubstitution { // ExtensionSubstFormat1
         format (u16) = { 1 }, // SubstFormat
         r#type (u16), // ExtensionLookupType
@@ -168,43 +180,180 @@ table! {
         },
 
         coverage (Coverage) |this, tape, position| {
-            jump_take!(tape, position, this.coverage_offset)
+            trace(tape, "coverage", |tape| jump_take!(tape, position, this.coverage_offset))
         },
 
         backward_coverages (Vec<Coverage>) |this, tape, position| {
-            jump_take!(tape, position, this.backward_glyph_count, this.backward_coverage_offsets)
+            trace(tape, "backward_coverages", |tape| {
+                jump_take!(tape, position, this.backward_glyph_count, this.backward_coverage_offsets)
+            })
         },
 
         forward_coverages (Vec<Coverage>) |this, tape, position| {
-            jump_take!(tape, position, this.forward_glyph_count, this.forward_coverage_offsets)
+            trace(tape, "forward_coverages", |tape| {
+                jump_take!(tape, position, this.forward_glyph_count, this.forward_coverage_offsets)
+            })
         },
     }
 }
 
-impl crate::walue::Read<'static> for Type {
-    type Parameter = u16;
+impl<'l> crate::walue::Read<'l> for Type {
+    type Parameter = (u16, &'l mut ReadOptions);
 
-    fn read<T: crate::tape::Read>(tape: &mut T, r#type: u16) -> Result<Self> {
+    fn read<T: crate::tape::Read>(
+        tape: &mut T,
+        (r#type, options): Self::Parameter,
+    ) -> Result<Self> {
         Ok(match r#type {
             1 => Self::SingleSubstitution(tape.take()?),
-            2 => Self::MultipleSubstitution(tape.take()?),
-            3 => Self::AlternateSubstitution(tape.take()?),
-            4 => Self::LigatureSubstitution(tape.take()?),
+            2 => {
+                Self::MultipleSubstitution(trace(tape, "MultipleSubstitution", |tape| tape.take())?)
+            }
+            3 => Self::AlternateSubstitution(trace(tape, "AlternateSubstitution", |tape| {
+                tape.take()
+            })?),
+            4 => {
+                Self::LigatureSubstitution(trace(tape, "LigatureSubstitution", |tape| tape.take())?)
+            }
             5 => Self::ContextualSubstitution(tape.take()?),
             6 => Self::ChainedContextualSubstitution(tape.take()?),
-            7 => Self::ExtensionSubstitution(tape.take()?),
-            8 => Self::ReverseChainedContextualSubstibution(tape.take()?),
+            7 => trace(tape, "ExtensionSubstitution", |tape| {
+                let position = tape.position()?;
+                let table = tape.take::<ExtensionSubstitution>()?;
+                if table.r#type == 7 {
+                    raise!("found a nested extension substitution");
+                }
+                tape.jump(position + table.offset as u64)?;
+                tape.take_given((table.r#type, options))
+            })?,
+            8 => Self::ReverseChainedContextualSubstibution(trace(
+                tape,
+                "ReverseChainedContextualSubstibution",
+                |tape| tape.take(),
+            )?),
             value => raise!("found an unknown type of glyph substitution ({value})"),
         })
     }
 }
 
+impl crate::layout::lookup::Subtable for Type {
+    fn r#type(&self) -> u16 {
+        match self {
+            Self::SingleSubstitution(_) => 1,
+            Self::MultipleSubstitution(_) => 2,
+            Self::AlternateSubstitution(_) => 3,
+            Self::LigatureSubstitution(_) => 4,
+            Self::ContextualSubstitution(_) => 5,
+            Self::ChainedContextualSubstitution(_) => 6,
+            Self::ExtensionSubstitution(table) => table.r#type,
+            Self::ReverseChainedContextualSubstibution(_) => 8,
+        }
+    }
+}
+
 impl crate::value::Read for SingleSubstitution {
     fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
         Ok(match tape.peek::<u16>()? {
-            1 => Self::Format1(tape.take()?),
-            2 => Self::Format2(tape.take()?),
+            1 => Self::Format1(trace(tape, "SingleSubstitution1", |tape| tape.take())?),
+            2 => Self::Format2(trace(tape, "SingleSubstitution2", |tape| tape.take())?),
             value => raise!("found an unknown format of the single substitution ({value})"),
         })
     }
 }
+
+impl crate::value::Write for GlyphSubstitution {
+    #[inline]
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        Directory::write(self, tape, 7)
+    }
+}
+
+impl crate::graph::Write for Type {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        match self {
+            Self::SingleSubstitution(table) => graph.give(table),
+            Self::MultipleSubstitution(table) => graph.give(table),
+            Self::AlternateSubstitution(table) => graph.give(table),
+            Self::LigatureSubstitution(table) => graph.give(table),
+            Self::ContextualSubstitution(table) => graph.give(table),
+            Self::ChainedContextualSubstitution(table) => graph.give(table),
+            Self::ExtensionSubstitution(_) => {
+                raise!("found an unresolved extension substitution, which cannot be written")
+            }
+            Self::ReverseChainedContextualSubstibution(table) => graph.give(table),
+        }
+    }
+}
+
+impl crate::graph::Write for SingleSubstitution {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        match self {
+            Self::Format1(table) => graph.give(table),
+            Self::Format2(table) => graph.give(table),
+        }
+    }
+}
+
+impl crate::graph::Write for SingleSubstitution1 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.link(coverage);
+        object.give(&self.delta_glyph_id)?;
+        Ok(graph.add(object))
+    }
+}
+
+impl crate::graph::Write for SingleSubstitution2 {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let mut object = Object::default();
+        object.give(&2u16)?;
+        object.link(coverage);
+        object.give_count(self.glyph_ids.len())?;
+        object.give(&*self.glyph_ids)?;
+        Ok(graph.add(object))
+    }
+}
+
+macro_rules! implement {
+    ($($name:ident,)*) => (
+        $(impl crate::graph::Write for $name {
+            fn write(&self, graph: &mut Graph) -> Result<usize> {
+                let coverage = graph.give(&self.coverage)?;
+                let records = graph.give_all(&self.records)?;
+                let mut object = Object::default();
+                object.give(&1u16)?;
+                object.link(coverage);
+                object.give_count(records.len())?;
+                records.into_iter().for_each(|index| object.link(index));
+                Ok(graph.add(object))
+            }
+        })*
+    );
+}
+
+implement! {
+    MultipleSubstitution,
+    AlternateSubstitution,
+    LigatureSubstitution,
+}
+
+impl crate::graph::Write for ReverseChainedContextualSubstibution {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let coverage = graph.give(&self.coverage)?;
+        let backward_coverages = graph.give_all(&self.backward_coverages)?;
+        let forward_coverages = graph.give_all(&self.forward_coverages)?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.link(coverage);
+        for coverages in [backward_coverages, forward_coverages] {
+            object.give_count(coverages.len())?;
+            coverages.into_iter().for_each(|index| object.link(index));
+        }
+        object.give_count(self.glyph_ids.len())?;
+        object.give(&*self.glyph_ids)?;
+        Ok(graph.add(object))
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+
+use truetype::Tag;
+
+use crate::{Error, Result};
+
+/// An error located in a font.
+///
+/// Failures are carried inside the errors returned by the readers of layout tables and can be
+/// recovered via [`Failure::find`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Failure {
+    /// The kind of the underlying error.
+    pub kind: std::io::ErrorKind,
+    /// The message of the underlying error.
+    pub message: String,
+    /// The absolute offset of the innermost structure where the error occurred.
+    pub offset: u64,
+    /// The path to the innermost structure where the error occurred.
+    pub path: Path,
+}
+
+/// A path to a structure in a font.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Path(pub Vec<Segment>);
+
+/// A segment of a path.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Segment {
+    /// A font table.
+    Table(Tag),
+    /// A table, subtable, or field.
+    Name(&'static str),
+    /// An element of a list.
+    Index(usize),
+}
+
+impl Failure {
+    /// Return the failure carried by an error if any.
+    pub fn find(error: &Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "{} (path {}, offset {})",
+            self.message, self.path, self.offset,
+        )
+    }
+}
+
+impl std::error::Error for Failure {}
+
+impl fmt::Display for Path {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "/" };
+            match segment {
+                Segment::Table(value) => {
+                    let value = String::from_utf8_lossy(&value.0);
+                    write!(formatter, "{separator}{}", value.trim_end())?
+                }
+                Segment::Name(value) => write!(formatter, "{separator}{value}")?,
+                Segment::Index(value) => write!(formatter, "[{value}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&'static str> for Segment {
+    #[inline]
+    fn from(value: &'static str) -> Self {
+        Self::Name(value)
+    }
+}
+
+impl From<usize> for Segment {
+    #[inline]
+    fn from(value: usize) -> Self {
+        Self::Index(value)
+    }
+}
+
+/// Read a value and locate any error under a segment.
+///
+/// Errors not yet located are assigned the position of the tape before reading.
+pub(crate) fn trace<T, U, F, S>(tape: &mut T, segment: S, read: F) -> Result<U>
+where
+    T: crate::tape::Read,
+    F: FnOnce(&mut T) -> Result<U>,
+    S: Into<Segment>,
+{
+    let position = tape.position()?;
+    read(tape).map_err(|error| locate(error, segment.into(), position))
+}
+
+fn locate(error: Error, segment: Segment, position: u64) -> Error {
+    let failure = match Failure::find(&error) {
+        Some(failure) => {
+            let mut failure = failure.clone();
+            failure.path.0.insert(0, segment);
+            failure
+        }
+        _ => Failure {
+            kind: error.kind(),
+            message: error.to_string(),
+            offset: position,
+            path: Path(vec![segment]),
+        },
+    };
+    Error::new(failure.kind, failure)
+}
@@ -1,17 +1,27 @@
 //! The item variation store.
 
+use truetype::q16;
+
+use crate::graph::{Graph, Object};
+use crate::tape::Write;
+use crate::Result;
+
 table! {
     @position
     /// An item variation store.
     pub Store { // ItemVariationStore
         format        (u16) = { 1 }, // format
-        region_offset (u32), // offsetToVariationRegionList
+        region_offset (u32), // variationRegionListOffset
         count         (u16), // itemVariationDataCount
 
         offsets (Vec<u32>) |this, tape, _| { // itemVariationDataOffsets
             tape.take_given(this.count as usize)
         },
 
+        regions (Regions) |this, tape, position| {
+            jump_take!(tape, position, this.region_offset)
+        },
+
         records (Vec<Record>) |this, tape, position| {
             jump_take!(tape, position, this.count, this.offsets)
         },
@@ -19,19 +29,202 @@ table! {
 }
 
 table! {
+    @define
     /// A record of an item variation store.
     pub Record { // ItemVariationData
-        item_count        (u16), // itemCount
-        short_delta_count (u16), // shortDeltaCount
-        region_count      (u16), // regionCount
+        item_count        (u16          ), // itemCount
+        short_delta_count (u16          ), // wordDeltaCount
+        region_count      (u16          ), // regionIndexCount
+        region_indices    (Vec<u16>     ), // regionIndexes
+        deltas            (Vec<Vec<i32>>), // deltaSets
+    }
+}
 
-        region_indices (Vec<u16>) |this, tape| { // regionIndices
-            tape.take_given(this.region_count as usize)
-        },
+table! {
+    @write
+    /// A list of regions.
+    pub Regions { // VariationRegionList
+        axis_count   (u16), // axisCount
+        region_count (u16), // regionCount
 
-        deltas (Vec<u8>) |this, tape| { // deltaSets
-            let per_item_count = this.short_delta_count + this.region_count;
-            tape.take_given(this.item_count as usize * per_item_count as usize)
+        records (Vec<Region>) |this, tape| { // variationRegions
+            (0..this.region_count)
+                .map(|_| tape.take_given(this.axis_count))
+                .collect()
         },
     }
 }
+
+table! {
+    @define
+    /// A region.
+    pub Region { // VariationRegion
+        tents (Vec<Tent>), // regionAxes
+    }
+}
+
+table! {
+    @write
+    /// An axis tent.
+    #[derive(Copy)]
+    pub Tent { // RegionAxisCoordinates
+        start (q16), // startCoord
+        peak  (q16), // peakCoord
+        end   (q16), // endCoord
+    }
+}
+
+const LONG_WORDS: u16 = 0x8000;
+const WORD_DELTA_COUNT_MASK: u16 = 0x7FFF;
+
+impl Store {
+    /// Evaluate a delta at normalized coordinates.
+    ///
+    /// The delta is identified by the indices of a record and of an item in that record, and
+    /// `None` is returned if either is out of range.
+    pub fn evaluate(&self, outer_index: u16, inner_index: u16, coordinates: &[q16]) -> Option<f32> {
+        let record = self.records.get(outer_index as usize)?;
+        let deltas = record.deltas.get(inner_index as usize)?;
+        let mut value = 0.0;
+        for (&index, &delta) in record.region_indices.iter().zip(deltas) {
+            let region = self.regions.records.get(index as usize)?;
+            value += region.scalar(coordinates) * delta as f32;
+        }
+        Some(value)
+    }
+}
+
+impl Region {
+    /// Compute the scalar at normalized coordinates.
+    ///
+    /// Axes without coordinates are assumed to be at their default values.
+    pub fn scalar(&self, coordinates: &[q16]) -> f32 {
+        self.tents
+            .iter()
+            .enumerate()
+            .map(|(i, tent)| tent.scalar(coordinates.get(i).cloned().unwrap_or_default()))
+            .product()
+    }
+}
+
+impl Tent {
+    /// Compute the scalar at a normalized coordinate.
+    pub fn scalar(&self, coordinate: q16) -> f32 {
+        let (start, peak, end) = (self.start.0 as i32, self.peak.0 as i32, self.end.0 as i32);
+        let coordinate = coordinate.0 as i32;
+        if peak == 0 || start > peak || peak > end || (start < 0 && end > 0) {
+            return 1.0;
+        }
+        if coordinate == peak {
+            return 1.0;
+        }
+        if coordinate <= start || coordinate >= end {
+            return 0.0;
+        }
+        if coordinate < peak {
+            (coordinate - start) as f32 / (peak - start) as f32
+        } else {
+            (end - coordinate) as f32 / (end - peak) as f32
+        }
+    }
+}
+
+impl crate::value::Read for Record {
+    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+        let item_count = tape.take::<u16>()?;
+        let short_delta_count = tape.take()?;
+        let region_count = tape.take()?;
+        let region_indices = tape.take_given(region_count as usize)?;
+        let word_count = (short_delta_count & WORD_DELTA_COUNT_MASK) as usize;
+        if word_count > region_count as usize {
+            raise!("found a malformed item variation record");
+        }
+        let long = short_delta_count & LONG_WORDS != 0;
+        let mut deltas = Vec::with_capacity(item_count as usize);
+        for _ in 0..item_count {
+            let mut row = Vec::with_capacity(region_count as usize);
+            for j in 0..region_count as usize {
+                row.push(match (long, j < word_count) {
+                    (true, true) => tape.take::<i32>()?,
+                    (false, true) | (true, false) => tape.take::<i16>()? as i32,
+                    (false, false) => tape.take::<i8>()? as i32,
+                });
+            }
+            deltas.push(row);
+        }
+        Ok(Self {
+            item_count,
+            short_delta_count,
+            region_count,
+            region_indices,
+            deltas,
+        })
+    }
+}
+
+impl crate::value::Write for Record {
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        let region_count = self.region_indices.len();
+        let word_count = (self.short_delta_count & WORD_DELTA_COUNT_MASK) as usize;
+        if word_count > region_count {
+            raise!("found a malformed item variation record");
+        }
+        let long = self.short_delta_count & LONG_WORDS != 0;
+        tape.give(&(self.deltas.len() as u16))?;
+        tape.give(&self.short_delta_count)?;
+        tape.give(&(region_count as u16))?;
+        tape.give(&self.region_indices[..])?;
+        for row in self.deltas.iter() {
+            if row.len() != region_count {
+                raise!("found a delta row of an unexpected length ({})", row.len());
+            }
+            for (j, &value) in row.iter().enumerate() {
+                match (long, j < word_count) {
+                    (true, true) => tape.give(&value)?,
+                    (false, true) | (true, false) => match i16::try_from(value) {
+                        Ok(value) => tape.give(&value)?,
+                        _ => raise!("found a delta that does not fit into 16 bits ({value})"),
+                    },
+                    (false, false) => match i8::try_from(value) {
+                        Ok(value) => tape.give(&value)?,
+                        _ => raise!("found a delta that does not fit into 8 bits ({value})"),
+                    },
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl crate::walue::Read<'static> for Region {
+    type Parameter = u16;
+
+    #[inline]
+    fn read<T: crate::tape::Read>(tape: &mut T, axis_count: Self::Parameter) -> Result<Self> {
+        Ok(Self {
+            tents: tape.take_given(axis_count as usize)?,
+        })
+    }
+}
+
+impl crate::value::Write for Region {
+    #[inline]
+    fn write<T: crate::tape::Write>(&self, tape: &mut T) -> Result<()> {
+        tape.give(&self.tents[..])
+    }
+}
+
+impl crate::graph::Write for Store {
+    fn write(&self, graph: &mut Graph) -> Result<usize> {
+        let regions = graph.give(&self.regions)?;
+        let records = graph.give_all(&self.records)?;
+        let mut object = Object::default();
+        object.give(&1u16)?;
+        object.link_long(regions);
+        object.give_count(records.len())?;
+        records
+            .into_iter()
+            .for_each(|index| object.link_long(index));
+        Ok(graph.add(object))
+    }
+}
@@ -0,0 +1,146 @@
+//! The [Web Open Font Format][1].
+//!
+//! [1]: https://www.w3.org/TR/WOFF/
+
+use std::io::{Cursor, Read};
+
+use truetype::Tag;
+
+use crate::options::Repair;
+use crate::{ReadOptions, Result};
+
+/// A file in the Web Open Font Format.
+pub struct File {
+    /// The header.
+    pub header: Header,
+    /// The table records.
+    pub records: Vec<Record>,
+    /// The decompressed tables in the order of the records.
+    pub tables: Vec<Vec<u8>>,
+    /// The decompressed extended metadata if present.
+    pub metadata: Option<Vec<u8>>,
+    /// The private data if present.
+    pub private: Option<Vec<u8>>,
+}
+
+table! {
+    /// The header of a file in the Web Open Font Format.
+    #[derive(Copy)]
+    pub Header { // WOFFHeader
+        signature              (Tag) = { Tag(*b"wOFF") }, // signature
+        flavor                 (Tag), // flavor
+        size                   (u32), // length
+        table_count            (u16), // numTables
+        reserved               (u16) = { 0 }, // reserved
+        font_size              (u32), // totalSfntSize
+        major_version          (u16), // majorVersion
+        minor_version          (u16), // minorVersion
+        metadata_offset        (u32), // metaOffset
+        metadata_size          (u32), // metaLength
+        metadata_original_size (u32), // metaOrigLength
+        private_offset         (u32), // privOffset
+        private_size           (u32), // privLength
+    }
+}
+
+table! {
+    /// A table record of a file in the Web Open Font Format.
+    #[derive(Copy)]
+    pub Record { // TableDirectoryEntry
+        tag             (Tag), // tag
+        offset          (u32), // offset
+        compressed_size (u32), // compLength
+        size            (u32), // origLength
+        checksum        (u32), // origChecksum
+    }
+}
+
+impl File {
+    /// Decode the font into a tape in the regular format.
+    ///
+    /// The tape can be read via [`crate::File::read`], and the resulting fonts can be used as
+    /// usual.
+    pub fn decode(&self) -> Result<Cursor<Vec<u8>>> {
+        let tables = self
+            .records
+            .iter()
+            .zip(self.tables.iter())
+            .map(|(record, data)| (record.tag, &data[..]))
+            .collect::<Vec<_>>();
+        let fonts = [(self.header.flavor, (0..tables.len()).collect())];
+        crate::file::assemble(&fonts, &tables, None).map(Cursor::new)
+    }
+}
+
+impl crate::value::Read for File {
+    #[inline]
+    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+        tape.take_given(&mut ReadOptions::strict())
+    }
+}
+
+impl<'l> crate::walue::Read<'l> for File {
+    type Parameter = &'l mut ReadOptions;
+
+    fn read<T: crate::tape::Read>(tape: &mut T, options: Self::Parameter) -> Result<Self> {
+        let position = tape.position()?;
+        let header = tape.take::<Header>()?;
+        match header.flavor {
+            flavor if truetype::accept(&flavor) => {}
+            _ => raise!("found an unknown font format in a web font"),
+        }
+        let records: Vec<Record> = tape.take_given(header.table_count as usize)?;
+        let mut tables = Vec::with_capacity(records.len());
+        for record in records.iter() {
+            tape.jump(position + record.offset as u64)?;
+            let data = tape.take_bytes(record.compressed_size as usize)?;
+            let data = inflate(data, record.size)?;
+            if crate::file::checksum(record.tag, &data) != record.checksum
+                && !options.repair(
+                    options.ignore_invalid_checksums,
+                    Repair::InvalidChecksum(record.tag),
+                )
+            {
+                raise!("found a malformed font table with {:?}", record.tag);
+            }
+            tables.push(data);
+        }
+        let metadata = if header.metadata_offset > 0 {
+            tape.jump(position + header.metadata_offset as u64)?;
+            let data = tape.take_bytes(header.metadata_size as usize)?;
+            Some(inflate(data, header.metadata_original_size)?)
+        } else {
+            None
+        };
+        let private = if header.private_offset > 0 {
+            tape.jump(position + header.private_offset as u64)?;
+            Some(tape.take_bytes(header.private_size as usize)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            header,
+            records,
+            tables,
+            metadata,
+            private,
+        })
+    }
+}
+
+fn inflate(data: Vec<u8>, size: u32) -> Result<Vec<u8>> {
+    if data.len() == size as usize {
+        return Ok(data);
+    }
+    if data.len() > size as usize {
+        raise!("found a malformed compressed table in a web font");
+    }
+    let mut buffer = vec![];
+    flate2::read::ZlibDecoder::new(&data[..])
+        .take(size as u64 + 1)
+        .read_to_end(&mut buffer)?;
+    if buffer.len() != size as usize {
+        raise!("found a malformed compressed table in a web font");
+    }
+    Ok(buffer)
+}
@@ -0,0 +1,293 @@
+//! The [Web Open Font Format 2.0][1].
+//!
+//! [1]: https://www.w3.org/TR/WOFF2/
+
+mod transform;
+
+use std::io::{Cursor, Read};
+
+use truetype::Tag;
+
+use crate::Result;
+
+/// A file in the Web Open Font Format 2.0.
+pub struct File {
+    /// The header.
+    pub header: Header,
+    /// The table records.
+    pub records: Vec<Record>,
+    /// The collection directory if the file contains a collection.
+    pub collection: Option<Collection>,
+    /// The reconstructed tables in the order of the records.
+    pub tables: Vec<Vec<u8>>,
+    /// The decompressed extended metadata if present.
+    pub metadata: Option<Vec<u8>>,
+    /// The private data if present.
+    pub private: Option<Vec<u8>>,
+}
+
+table! {
+    /// The header of a file in the Web Open Font Format 2.0.
+    #[derive(Copy)]
+    pub Header { // WOFF2Header
+        signature              (Tag) = { Tag(*b"wOF2") }, // signature
+        flavor                 (Tag), // flavor
+        size                   (u32), // length
+        table_count            (u16), // numTables
+        reserved               (u16) = { 0 }, // reserved
+        font_size              (u32), // totalSfntSize
+        compressed_size        (u32), // totalCompressedSize
+        major_version          (u16), // majorVersion
+        minor_version          (u16), // minorVersion
+        metadata_offset        (u32), // metaOffset
+        metadata_size          (u32), // metaLength
+        metadata_original_size (u32), // metaOrigLength
+        private_offset         (u32), // privOffset
+        private_size           (u32), // privLength
+    }
+}
+
+table! {
+    @define
+    /// A table record of a file in the Web Open Font Format 2.0.
+    #[derive(Copy)]
+    pub Record { // TableDirectoryEntry
+        flags            (u8         ), // flags
+        tag              (Tag        ), // tag
+        size             (u32        ), // origLength
+        transformed_size (Option<u32>), // transformLength
+    }
+}
+
+table! {
+    @define
+    /// A collection directory of a file in the Web Open Font Format 2.0.
+    pub Collection { // CollectionHeader
+        version    (u32                  ), // version
+        font_count (u16                  ), // numFonts
+        records    (Vec<CollectionRecord>), // CollectionFontEntry
+    }
+}
+
+table! {
+    @define
+    /// A font record of a collection directory.
+    pub CollectionRecord { // CollectionFontEntry
+        table_count   (u16     ), // numTables
+        flavor        (Tag     ), // flavor
+        table_indices (Vec<u16>), // index
+    }
+}
+
+impl File {
+    /// Decode the font into a tape in the regular format.
+    ///
+    /// The tape can be read via [`crate::File::read`], and the resulting fonts can be used as
+    /// usual.
+    pub fn decode(&self) -> Result<Cursor<Vec<u8>>> {
+        let tables = self
+            .records
+            .iter()
+            .zip(self.tables.iter())
+            .map(|(record, data)| (record.tag, &data[..]))
+            .collect::<Vec<_>>();
+        let collection = self.collection.as_ref().map(|collection| {
+            let version = collection.version;
+            ((version >> 16) as u16, version as u16)
+        });
+        crate::file::assemble(&self.fonts(), &tables, collection).map(Cursor::new)
+    }
+
+    fn fonts(&self) -> Vec<(Tag, Vec<usize>)> {
+        match self.collection {
+            Some(ref collection) => collection
+                .records
+                .iter()
+                .map(|record| {
+                    let indices = record.table_indices.iter().map(|&i| i as usize);
+                    (record.flavor, indices.collect())
+                })
+                .collect(),
+            _ => vec![(self.header.flavor, (0..self.records.len()).collect())],
+        }
+    }
+}
+
+impl Record {
+    /// Check if the table is transformed.
+    pub fn is_transformed(&self) -> bool {
+        match &self.tag.0 {
+            b"glyf" | b"loca" => self.flags >> 6 != 3,
+            _ => self.flags >> 6 != 0,
+        }
+    }
+}
+
+impl crate::value::Read for File {
+    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+        let position = tape.position()?;
+        let header = tape.take::<Header>()?;
+        let records: Vec<Record> = tape.take_given(header.table_count as usize)?;
+        let collection = match header.flavor {
+            flavor if &flavor.0 == b"ttcf" => {
+                let collection = tape.take::<Collection>()?;
+                for record in collection.records.iter() {
+                    for &i in record.table_indices.iter() {
+                        if i >= header.table_count {
+                            raise!("found a malformed collection directory in a web font");
+                        }
+                    }
+                }
+                Some(collection)
+            }
+            flavor if truetype::accept(&flavor) => None,
+            _ => raise!("found an unknown font format in a web font"),
+        };
+        let size = (records.iter())
+            .map(|record| record.transformed_size.unwrap_or(record.size) as u64)
+            .sum();
+        let data = decompress(tape.take_bytes(header.compressed_size as usize)?, size)?;
+        let mut tables = Vec::with_capacity(records.len());
+        let mut offset = 0;
+        for record in records.iter() {
+            let size = record.transformed_size.unwrap_or(record.size) as usize;
+            if offset + size > data.len() {
+                raise!("found a malformed compressed stream in a web font");
+            }
+            tables.push(data[offset..(offset + size)].to_vec());
+            offset += size;
+        }
+        let metadata = if header.metadata_offset > 0 {
+            tape.jump(position + header.metadata_offset as u64)?;
+            let size = header.metadata_original_size as u64;
+            let data = decompress(tape.take_bytes(header.metadata_size as usize)?, size)?;
+            if data.len() != header.metadata_original_size as usize {
+                raise!("found malformed metadata in a web font");
+            }
+            Some(data)
+        } else {
+            None
+        };
+        let private = if header.private_offset > 0 {
+            tape.jump(position + header.private_offset as u64)?;
+            Some(tape.take_bytes(header.private_size as usize)?)
+        } else {
+            None
+        };
+        let mut file = Self {
+            header,
+            records,
+            collection,
+            tables,
+            metadata,
+            private,
+        };
+        transform::reconstruct(&mut file)?;
+        Ok(file)
+    }
+}
+
+impl crate::value::Read for Record {
+    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+        let flags = tape.take::<u8>()?;
+        let tag = match flags & 0x3F {
+            0x3F => tape.take()?,
+            index => Tag(*TAGS[index as usize]),
+        };
+        let size = read_u32(tape)?;
+        let mut record = Self {
+            flags,
+            tag,
+            size,
+            transformed_size: None,
+        };
+        if record.is_transformed() {
+            record.transformed_size = Some(read_u32(tape)?);
+        }
+        Ok(record)
+    }
+}
+
+impl crate::value::Read for Collection {
+    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+        let version = tape.take()?;
+        match version {
+            0x00010000 | 0x00020000 => {}
+            _ => raise!("found an unknown version of the collection directory ({version:#x})"),
+        }
+        let font_count = read_u16(tape)?;
+        let records = (0..font_count)
+            .map(|_| tape.take())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            version,
+            font_count,
+            records,
+        })
+    }
+}
+
+impl crate::value::Read for CollectionRecord {
+    fn read<T: crate::tape::Read>(tape: &mut T) -> Result<Self> {
+        let table_count = read_u16(tape)?;
+        let flavor = tape.take()?;
+        let table_indices = (0..table_count)
+            .map(|_| read_u16(tape))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            table_count,
+            flavor,
+            table_indices,
+        })
+    }
+}
+
+const TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+fn decompress(data: Vec<u8>, size: u64) -> Result<Vec<u8>> {
+    let mut buffer = vec![];
+    brotli_decompressor::Decompressor::new(&data[..], 4096)
+        .take(size + 1)
+        .read_to_end(&mut buffer)?;
+    if buffer.len() as u64 > size {
+        raise!("found a malformed compressed stream in a web font");
+    }
+    Ok(buffer)
+}
+
+// UIntBase128
+fn read_u32<T: crate::tape::Read>(tape: &mut T) -> Result<u32> {
+    let mut value: u32 = 0;
+    for i in 0..5 {
+        let byte = tape.take::<u8>()?;
+        if i == 0 && byte == 0x80 {
+            break;
+        }
+        if value & 0xFE00_0000 != 0 {
+            break;
+        }
+        value = (value << 7) | (byte & 0x7F) as u32;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    raise!("found a malformed number in a web font")
+}
+
+// 255UInt16
+pub(crate) fn read_u16<T: crate::tape::Read>(tape: &mut T) -> Result<u16> {
+    Ok(match tape.take::<u8>()? {
+        253 => tape.take()?,
+        254 => tape.take::<u8>()? as u16 + 253 * 2,
+        255 => tape.take::<u8>()? as u16 + 253,
+        value => value as u16,
+    })
+}
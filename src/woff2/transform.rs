@@ -0,0 +1,409 @@
+use std::io::Cursor;
+
+use crate::tape::{Read, Write};
+use crate::woff2::{read_u16, File};
+use crate::Result;
+
+const ARE_ARGUMENTS_WORDS: u16 = 0x0001;
+const HAS_SCALAR_SCALE: u16 = 0x0008;
+const HAS_MORE_COMPONENTS: u16 = 0x0020;
+const HAS_VECTOR_SCALE: u16 = 0x0040;
+const HAS_MATRIX_SCALE: u16 = 0x0080;
+const HAS_INSTRUCTIONS: u16 = 0x0100;
+
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+const OVERLAP_SIMPLE: u8 = 0x40;
+
+macro_rules! reject(
+    () => (raise!("found a malformed transformed table in a web font"));
+);
+
+pub fn reconstruct(file: &mut File) -> Result<()> {
+    let fonts = file.fonts();
+    let mut done = vec![false; file.records.len()];
+    for (_, indices) in fonts.iter() {
+        let find = |tag: &[u8; 4]| {
+            indices
+                .iter()
+                .cloned()
+                .find(|&i| file.records[i].tag == tag)
+        };
+        let glyph_data = find(b"glyf");
+        let glyph_mapping = find(b"loca");
+        let mut x_mins = None;
+        if let (Some(i), Some(j)) = (glyph_data, glyph_mapping) {
+            if file.records[i].is_transformed() {
+                if !file.records[j].is_transformed() || file.records[j].transformed_size != Some(0)
+                {
+                    reject!();
+                }
+                if !done[i] {
+                    let mut values = vec![];
+                    let (data, mapping) = reconstruct_glyph_data(&file.tables[i], &mut values)?;
+                    if mapping.len() != file.records[j].size as usize {
+                        reject!();
+                    }
+                    file.tables[i] = data;
+                    file.tables[j] = mapping;
+                    done[i] = true;
+                    done[j] = true;
+                    x_mins = Some(values);
+                }
+            } else if file.records[j].is_transformed() {
+                reject!();
+            }
+        }
+        let horizontal_metrics = match find(b"hmtx") {
+            Some(i) if file.records[i].is_transformed() && !done[i] => i,
+            _ => continue,
+        };
+        if file.records[horizontal_metrics].flags >> 6 != 1 {
+            reject!();
+        }
+        let x_mins = match (x_mins, glyph_data, glyph_mapping, find(b"head")) {
+            (Some(values), _, _, _) => values,
+            (_, Some(i), Some(j), Some(k)) => {
+                extract_x_mins(&file.tables[i], &file.tables[j], &file.tables[k])?
+            }
+            _ => reject!(),
+        };
+        let metric_count = match find(b"hhea") {
+            Some(i) if file.tables[i].len() >= 36 => {
+                u16::from_be_bytes([file.tables[i][34], file.tables[i][35]])
+            }
+            _ => reject!(),
+        };
+        file.tables[horizontal_metrics] = reconstruct_horizontal_metrics(
+            &file.tables[horizontal_metrics],
+            metric_count,
+            &x_mins,
+        )?;
+        done[horizontal_metrics] = true;
+    }
+    for (i, record) in file.records.iter().enumerate() {
+        if record.is_transformed() && !done[i] {
+            reject!();
+        }
+        if !record.is_transformed() && file.tables[i].len() != record.size as usize {
+            reject!();
+        }
+    }
+    Ok(())
+}
+
+fn reconstruct_glyph_data(data: &[u8], x_mins: &mut Vec<i16>) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut tape = Cursor::new(data);
+    let _: u16 = tape.take()?;
+    let options: u16 = tape.take()?;
+    let glyph_count: u16 = tape.take()?;
+    let mapping_format: u16 = tape.take()?;
+    let mut streams = Vec::with_capacity(7);
+    let mut offset = 36;
+    for _ in 0..7 {
+        let size = tape.take::<u32>()? as usize;
+        if offset + size > data.len() {
+            reject!();
+        }
+        streams.push(Cursor::new(&data[offset..(offset + size)]));
+        offset += size;
+    }
+    let overlaps = if options & 1 != 0 {
+        let size = (glyph_count as usize + 7) >> 3;
+        if offset + size > data.len() {
+            reject!();
+        }
+        Some(&data[offset..(offset + size)])
+    } else {
+        None
+    };
+    let mut streams = streams.into_iter();
+    let mut contour_stream = streams.next().unwrap();
+    let mut point_stream = streams.next().unwrap();
+    let mut flag_stream = streams.next().unwrap();
+    let mut glyph_stream = streams.next().unwrap();
+    let mut component_stream = streams.next().unwrap();
+    let mut box_stream = streams.next().unwrap();
+    let mut instruction_stream = streams.next().unwrap();
+    let box_bitmap = box_stream.take_bytes(4 * ((glyph_count as usize + 31) >> 5))?;
+    let has_bit = |bitmap: &[u8], i: usize| bitmap[i >> 3] & (0x80 >> (i & 7)) != 0;
+
+    let mut glyph_data = vec![];
+    let mut offsets = Vec::with_capacity(glyph_count as usize + 1);
+    x_mins.reserve(glyph_count as usize);
+    for i in 0..(glyph_count as usize) {
+        offsets.push(glyph_data.len() as u32);
+        let contour_count: i16 = contour_stream.take()?;
+        let has_box = has_bit(&box_bitmap, i);
+        if contour_count == 0 {
+            if has_box {
+                reject!();
+            }
+            x_mins.push(0);
+            continue;
+        }
+        let mut glyph = vec![];
+        if contour_count > 0 {
+            let mut end_points = Vec::with_capacity(contour_count as usize);
+            let mut point_count = 0usize;
+            for _ in 0..contour_count {
+                point_count += read_u16(&mut point_stream)? as usize;
+                if point_count == 0 || point_count > 0xFFFF {
+                    reject!();
+                }
+                end_points.push((point_count - 1) as u16);
+            }
+            let flags = flag_stream.take_bytes(point_count)?;
+            let points = read_points(&mut glyph_stream, &flags)?;
+            let instruction_size = read_u16(&mut glyph_stream)?;
+            let instructions = instruction_stream.take_bytes(instruction_size as usize)?;
+            let bounds = if has_box {
+                read_bounds(&mut box_stream)?
+            } else {
+                compute_bounds(&points)
+            };
+            let overlap = overlaps.map(|bitmap| has_bit(bitmap, i)).unwrap_or(false);
+            glyph.give(&contour_count)?;
+            glyph.give(&[bounds.0, bounds.1, bounds.2, bounds.3][..])?;
+            glyph.give(&end_points[..])?;
+            glyph.give(&instruction_size)?;
+            glyph.give_bytes(&instructions)?;
+            write_points(&mut glyph, &points, overlap)?;
+            x_mins.push(bounds.0);
+        } else if contour_count == -1 {
+            if !has_box {
+                reject!();
+            }
+            let bounds = read_bounds(&mut box_stream)?;
+            glyph.give(&contour_count)?;
+            glyph.give(&[bounds.0, bounds.1, bounds.2, bounds.3][..])?;
+            let mut has_instructions = false;
+            loop {
+                let flags: u16 = component_stream.take()?;
+                let mut size = 2;
+                size += if flags & ARE_ARGUMENTS_WORDS != 0 {
+                    4
+                } else {
+                    2
+                };
+                if flags & HAS_SCALAR_SCALE != 0 {
+                    size += 2;
+                } else if flags & HAS_VECTOR_SCALE != 0 {
+                    size += 4;
+                } else if flags & HAS_MATRIX_SCALE != 0 {
+                    size += 8;
+                }
+                glyph.give(&flags)?;
+                glyph.give_bytes(&component_stream.take_bytes(size)?)?;
+                has_instructions |= flags & HAS_INSTRUCTIONS != 0;
+                if flags & HAS_MORE_COMPONENTS == 0 {
+                    break;
+                }
+            }
+            if has_instructions {
+                let instruction_size = read_u16(&mut glyph_stream)?;
+                glyph.give(&instruction_size)?;
+                glyph.give_bytes(&instruction_stream.take_bytes(instruction_size as usize)?)?;
+            }
+            x_mins.push(bounds.0);
+        } else {
+            reject!();
+        }
+        glyph.resize((glyph.len() + 3) & !3, 0);
+        glyph_data.extend(glyph);
+    }
+    offsets.push(glyph_data.len() as u32);
+    let mut glyph_mapping = vec![];
+    match mapping_format {
+        0 => {
+            for offset in offsets {
+                glyph_mapping.give(&((offset >> 1) as u16))?;
+            }
+        }
+        1 => glyph_mapping.give(&offsets[..])?,
+        _ => reject!(),
+    }
+    Ok((glyph_data, glyph_mapping))
+}
+
+fn reconstruct_horizontal_metrics(
+    data: &[u8],
+    metric_count: u16,
+    x_mins: &[i16],
+) -> Result<Vec<u8>> {
+    let mut tape = Cursor::new(data);
+    let flags: u8 = tape.take()?;
+    if flags & 0xFC != 0 || flags & 0x03 == 0 {
+        reject!();
+    }
+    let metric_count = metric_count as usize;
+    let glyph_count = x_mins.len();
+    if metric_count == 0 || metric_count > glyph_count {
+        reject!();
+    }
+    let advance_widths: Vec<u16> = tape.take_given(metric_count)?;
+    let left_side_bearings: Vec<i16> = if flags & 0x01 == 0 {
+        tape.take_given(metric_count)?
+    } else {
+        x_mins[..metric_count].to_vec()
+    };
+    let other_left_side_bearings: Vec<i16> = if flags & 0x02 == 0 {
+        tape.take_given(glyph_count - metric_count)?
+    } else {
+        x_mins[metric_count..].to_vec()
+    };
+    let mut table = Vec::with_capacity(4 * metric_count + 2 * (glyph_count - metric_count));
+    for (advance_width, left_side_bearing) in advance_widths.iter().zip(left_side_bearings) {
+        table.give(advance_width)?;
+        table.give(&left_side_bearing)?;
+    }
+    table.give(&other_left_side_bearings[..])?;
+    Ok(table)
+}
+
+fn extract_x_mins(glyph_data: &[u8], glyph_mapping: &[u8], header: &[u8]) -> Result<Vec<i16>> {
+    if header.len() < 52 {
+        reject!();
+    }
+    let offsets: Vec<usize> = match i16::from_be_bytes([header[50], header[51]]) {
+        0 => glyph_mapping
+            .chunks_exact(2)
+            .map(|chunk| 2 * u16::from_be_bytes([chunk[0], chunk[1]]) as usize)
+            .collect(),
+        1 => glyph_mapping
+            .chunks_exact(4)
+            .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as usize)
+            .collect(),
+        _ => reject!(),
+    };
+    let mut x_mins = Vec::with_capacity(offsets.len().saturating_sub(1));
+    for window in offsets.windows(2) {
+        if window[0] == window[1] {
+            x_mins.push(0);
+        } else if window[0] + 4 <= glyph_data.len() {
+            x_mins.push(i16::from_be_bytes([
+                glyph_data[window[0] + 2],
+                glyph_data[window[0] + 3],
+            ]));
+        } else {
+            reject!();
+        }
+    }
+    Ok(x_mins)
+}
+
+fn read_points<T: Read>(tape: &mut T, flags: &[u8]) -> Result<Vec<(i32, i32, bool)>> {
+    let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+    let (mut x, mut y) = (0i32, 0i32);
+    let mut points = Vec::with_capacity(flags.len());
+    for &flag in flags {
+        let on_curve = flag >> 7 == 0;
+        let flag = flag & 0x7F;
+        let (dx, dy) = if flag < 10 {
+            let b0 = tape.take::<u8>()? as i32;
+            (0, with_sign(flag, (((flag & 14) as i32) << 7) + b0))
+        } else if flag < 20 {
+            let b0 = tape.take::<u8>()? as i32;
+            (with_sign(flag, ((((flag - 10) & 14) as i32) << 7) + b0), 0)
+        } else if flag < 84 {
+            let b0 = (flag - 20) as i32;
+            let b1 = tape.take::<u8>()? as i32;
+            (
+                with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)),
+            )
+        } else if flag < 120 {
+            let b0 = (flag - 84) as i32;
+            let b1 = tape.take::<u8>()? as i32;
+            let b2 = tape.take::<u8>()? as i32;
+            (
+                with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+                with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+            )
+        } else if flag < 124 {
+            let b1 = tape.take::<u8>()? as i32;
+            let b2 = tape.take::<u8>()? as i32;
+            let b3 = tape.take::<u8>()? as i32;
+            (
+                with_sign(flag, (b1 << 4) + (b2 >> 4)),
+                with_sign(flag >> 1, ((b2 & 0x0F) << 8) + b3),
+            )
+        } else {
+            let b1 = tape.take::<u16>()? as i32;
+            let b2 = tape.take::<u16>()? as i32;
+            (with_sign(flag, b1), with_sign(flag >> 1, b2))
+        };
+        x += dx;
+        y += dy;
+        points.push((x, y, on_curve));
+    }
+    Ok(points)
+}
+
+fn write_points(tape: &mut Vec<u8>, points: &[(i32, i32, bool)], overlap: bool) -> Result<()> {
+    let mut flags = Vec::with_capacity(points.len());
+    let mut xs = vec![];
+    let mut ys = vec![];
+    let (mut x, mut y) = (0, 0);
+    for (i, &(point_x, point_y, on_curve)) in points.iter().enumerate() {
+        let (dx, dy) = (point_x - x, point_y - y);
+        let mut flag = if on_curve { ON_CURVE_POINT } else { 0 };
+        if i == 0 && overlap {
+            flag |= OVERLAP_SIMPLE;
+        }
+        if dx == 0 {
+            flag |= X_IS_SAME_OR_POSITIVE;
+        } else if (-255..=255).contains(&dx) {
+            flag |= X_SHORT_VECTOR;
+            if dx > 0 {
+                flag |= X_IS_SAME_OR_POSITIVE;
+            }
+            xs.push(dx.unsigned_abs() as u8);
+        } else {
+            xs.give(&(dx as i16))?;
+        }
+        if dy == 0 {
+            flag |= Y_IS_SAME_OR_POSITIVE;
+        } else if (-255..=255).contains(&dy) {
+            flag |= Y_SHORT_VECTOR;
+            if dy > 0 {
+                flag |= Y_IS_SAME_OR_POSITIVE;
+            }
+            ys.push(dy.unsigned_abs() as u8);
+        } else {
+            ys.give(&(dy as i16))?;
+        }
+        flags.push(flag);
+        x = point_x;
+        y = point_y;
+    }
+    tape.give_bytes(&flags)?;
+    tape.give_bytes(&xs)?;
+    tape.give_bytes(&ys)
+}
+
+fn read_bounds<T: Read>(tape: &mut T) -> Result<(i16, i16, i16, i16)> {
+    Ok((tape.take()?, tape.take()?, tape.take()?, tape.take()?))
+}
+
+fn compute_bounds(points: &[(i32, i32, bool)]) -> (i16, i16, i16, i16) {
+    if points.is_empty() {
+        return (0, 0, 0, 0);
+    }
+    let mut bounds = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+    for &(x, y, _) in points {
+        bounds.0 = bounds.0.min(x);
+        bounds.1 = bounds.1.min(y);
+        bounds.2 = bounds.2.max(x);
+        bounds.3 = bounds.3.max(y);
+    }
+    (
+        bounds.0 as i16,
+        bounds.1 as i16,
+        bounds.2 as i16,
+        bounds.3 as i16,
+    )
+}
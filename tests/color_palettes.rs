@@ -47,11 +47,27 @@ mod noto_color_emoji {
 }
 
 mod kalnia_glaze {
-    use opentype::tables::color_palettes::ColorPalettes;
+    use opentype::tables::color_palettes::{ColorPalettes, Header};
     use opentype::value::Read;
 
+    use crate::support;
+
     #[test]
     fn read() {
-        let _: ColorPalettes = ok!(Read::read(&mut setup!(KalniaGlaze, "CPAL")));
+        let table: ColorPalettes = ok!(Read::read(&mut setup!(KalniaGlaze, "CPAL")));
+        match table.header {
+            Header::Version1(ref header) => assert_eq!(header.entry_count, 9),
+            _ => unreachable!(),
+        }
+        assert_eq!(table.palette_flags.as_ref().map(Vec::len), Some(2));
+        assert!(table.palette_name_ids.is_none());
+        assert!(table.entry_name_ids.is_none());
+    }
+
+    #[test]
+    fn write() {
+        let table: ColorPalettes = ok!(Read::read(&mut setup!(KalniaGlaze, "CPAL")));
+        let data = support::round_trip(&table);
+        assert_eq!(data.len(), 28 + 18 * 4 + 2 * 4);
     }
 }
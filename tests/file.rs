@@ -2,6 +2,7 @@
 mod support;
 
 use opentype::File;
+use truetype::Tag;
 
 #[test]
 fn cff_regular() {
@@ -22,7 +23,35 @@ fn cff_variable() {
 }
 
 #[test]
-#[cfg_attr(not(feature = "ignore-invalid-checksums"), should_panic)]
+fn ttc_regular() {
+    use opentype::tables::GlyphPositioning;
+    use opentype::truetype::tables::FontHeader;
+
+    let mut tape = setup!(GinghamCollection);
+    let collection = ok!(ok!(File::collection(&mut tape)));
+    let file = ok!(File::read(&mut tape));
+    assert_eq!(collection.major_version, 2);
+    assert_eq!(collection.font_count, 2);
+    assert_eq!(collection.font_offsets, &[32, 348]);
+    assert!(collection.has_signature());
+    assert_eq!(collection.signature_size, 8);
+    assert_eq!(file.len(), 2);
+    assert_eq!(file[0].offsets.records.len(), 19);
+    assert_eq!(file[1].offsets.records.len(), 18);
+    for font in file.iter() {
+        let font_header = ok!(ok!(font.take::<_, FontHeader>(&mut tape)));
+        assert_eq!(font_header.units_per_em, 1000);
+    }
+    assert!(file[0].exists::<GlyphPositioning>());
+    assert!(!file[1].exists::<GlyphPositioning>());
+    assert_eq!(
+        file[0].offsets.records[11].offset,
+        file[1].offsets.records[10].offset,
+    );
+}
+
+#[test]
+#[should_panic]
 fn ttf_corrupted() {
     use opentype::truetype::tables::FontHeader;
 
@@ -31,6 +60,30 @@ fn ttf_corrupted() {
     let _ = ok!(ok!(file[0].take::<_, FontHeader>(&mut tape)));
 }
 
+#[test]
+fn ttf_corrupted_lenient() {
+    use opentype::truetype::tables::FontHeader;
+    use opentype::{ReadOptions, Repair};
+
+    let mut tape = setup!(KaushanScript);
+    let file = ok!(File::read(&mut tape));
+    let mut options = ReadOptions::strict();
+    assert!(file[0]
+        .position_with::<_, FontHeader>(&mut tape, &mut options)
+        .is_err());
+    assert!(options.repairs.is_empty());
+    let mut options = ReadOptions::lenient();
+    ok!(ok!(
+        file[0].position_with::<_, FontHeader>(&mut tape, &mut options)
+    ));
+    let _ = ok!(opentype::tape::Read::take::<FontHeader>(&mut tape));
+    assert_eq!(
+        options.repairs,
+        &[Repair::InvalidChecksum(truetype::Tag(*b"head"))],
+    );
+    assert!(file[0].take::<_, FontHeader>(&mut tape).is_err());
+}
+
 #[test]
 fn ttf_regular() {
     use opentype::truetype::tables::{FontHeader, GlyphData, GlyphMapping, MaximumProfile};
@@ -55,3 +108,226 @@ fn ttf_variable() {
     let file = ok!(File::read(&mut tape));
     let _ = ok!(ok!(file[0].take::<_, GlyphSubstitution>(&mut tape)));
 }
+
+#[test]
+fn woff() {
+    use opentype::tables::{FontVariations, GlyphPositioning, GlyphSubstitution};
+    use opentype::truetype::tables::FontHeader;
+    use opentype::value::Read;
+
+    let mut tape = setup!(GinghamWOFF);
+    assert!(File::read(&mut tape).is_err());
+    let file: opentype::woff::File = ok!(Read::read(&mut tape));
+    assert_eq!(file.header.flavor, Tag([0, 1, 0, 0]));
+    assert_eq!(file.header.table_count, 19);
+    assert_eq!(file.tables.len(), 19);
+    assert!(ok!(std::str::from_utf8(ok!(file.metadata.as_ref()))).contains("uniqueid"));
+    assert_eq!(ok!(file.private.as_ref()), b"private data");
+
+    let (file, mut tape) = ok!(File::decode(&mut setup!(GinghamWOFF)));
+    assert_eq!(tape.get_ref().len(), 20388);
+    let font_header = ok!(ok!(file[0].take::<_, FontHeader>(&mut tape)));
+    assert_eq!(font_header.units_per_em, 1000);
+    let _ = ok!(ok!(file[0].take::<_, GlyphSubstitution>(&mut tape)));
+    let _ = ok!(ok!(file[0].take::<_, GlyphPositioning>(&mut tape)));
+    let table = ok!(ok!(file[0].take::<_, FontVariations>(&mut tape)));
+    assert_eq!(table.axis_records[0].tag, Tag(*b"wght"));
+}
+
+#[test]
+fn woff_corrupted() {
+    use std::io::{Cursor, Write};
+
+    use opentype::value::Read;
+
+    let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::best());
+    ok!(encoder.write_all(&[0; 1 << 20]));
+    let table = ok!(encoder.finish());
+    let mut data = vec![];
+    data.extend_from_slice(b"wOFF");
+    data.extend_from_slice(&[0, 1, 0, 0]);
+    data.extend_from_slice(&((64 + table.len()) as u32).to_be_bytes());
+    data.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 28, 0, 1, 0, 0]);
+    data.extend_from_slice(&[0; 20]);
+    data.extend_from_slice(b"head");
+    data.extend_from_slice(&64u32.to_be_bytes());
+    data.extend_from_slice(&(table.len() as u32).to_be_bytes());
+    data.extend_from_slice(&16u32.to_be_bytes());
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data.extend_from_slice(&table);
+    let result: opentype::Result<opentype::woff::File> = Read::read(&mut Cursor::new(data));
+    assert!(result.is_err());
+}
+
+#[test]
+fn woff2() {
+    use opentype::tables::{GlyphPositioning, GlyphSubstitution};
+    use opentype::truetype::tables::{
+        glyph_data::Description, FontHeader, GlyphData, GlyphMapping, HorizontalHeader,
+        HorizontalMetrics, MaximumProfile,
+    };
+    use opentype::value::Read;
+
+    let mut tape = setup!(OpenSansWOFF2);
+    assert!(File::read(&mut tape).is_err());
+    let file: opentype::woff2::File = ok!(Read::read(&mut tape));
+    assert_eq!(file.header.flavor, Tag([0, 1, 0, 0]));
+    assert_eq!(file.header.table_count, 17);
+    assert!(file.collection.is_none());
+    assert!(file.records.iter().any(|record| record.is_transformed()));
+
+    let (file, mut tape) = ok!(File::decode(&mut setup!(OpenSansWOFF2)));
+    let font_header = ok!(ok!(file[0].take::<_, FontHeader>(&mut tape)));
+    assert_eq!(font_header.units_per_em, 2048);
+    let maximum_profile = ok!(ok!(file[0].take::<_, MaximumProfile>(&mut tape)));
+    let horizontal_header = ok!(ok!(file[0].take::<_, HorizontalHeader>(&mut tape)));
+    let horizontal_metrics = ok!(ok!(file[0].take_given::<_, HorizontalMetrics>(
+        &mut tape,
+        (&horizontal_header, &maximum_profile),
+    )));
+    let glyph_mapping = ok!(ok!(
+        file[0].take_given::<_, GlyphMapping>(&mut tape, (&font_header, &maximum_profile),)
+    ));
+    let glyph_data = ok!(ok!(
+        file[0].take_given::<_, GlyphData>(&mut tape, &glyph_mapping,)
+    ));
+    assert_eq!(glyph_data.len(), maximum_profile.glyph_count());
+    let mut simple_count = 0;
+    for glyph in glyph_data.iter().flatten() {
+        if let Description::Simple(ref description) = glyph.description {
+            let (mut x, mut y) = (0i16, 0i16);
+            let (mut min_x, mut max_x) = (i16::MAX, i16::MIN);
+            let (mut min_y, mut max_y) = (i16::MAX, i16::MIN);
+            for (dx, dy) in description.x.iter().zip(description.y.iter()) {
+                x += dx;
+                y += dy;
+                (min_x, max_x) = (min_x.min(x), max_x.max(x));
+                (min_y, max_y) = (min_y.min(y), max_y.max(y));
+            }
+            assert_eq!((glyph.min_x, glyph.max_x), (min_x, max_x));
+            assert_eq!((glyph.min_y, glyph.max_y), (min_y, max_y));
+            simple_count += 1;
+        }
+    }
+    assert!(simple_count > 100);
+    assert_eq!(
+        horizontal_metrics.records.len(),
+        horizontal_header.horizontal_metric_count as usize,
+    );
+    let _ = ok!(ok!(file[0].take::<_, GlyphSubstitution>(&mut tape)));
+    let _ = ok!(ok!(file[0].take::<_, GlyphPositioning>(&mut tape)));
+}
+
+#[test]
+fn write_collection() {
+    use opentype::tables::GlyphPositioning;
+    use opentype::truetype::tables::FontHeader;
+    use opentype::Builder;
+
+    let mut tape = setup!(GinghamCollection);
+    let file = ok!(File::read(&mut tape));
+    let fonts = file
+        .iter()
+        .map(|font| ok!(Builder::from_font(font, &mut tape)))
+        .collect::<Vec<_>>();
+    let mut data = vec![];
+    ok!(File::write(&mut data, &fonts));
+
+    let mut other_tape = std::io::Cursor::new(data);
+    let collection = ok!(ok!(File::collection(&mut other_tape)));
+    let other = ok!(File::read(&mut other_tape));
+    assert_eq!(collection.major_version, 1);
+    assert_eq!(collection.font_count, 2);
+    assert!(!collection.has_signature());
+    assert_eq!(other[0].offsets.records.len(), 19);
+    assert_eq!(other[1].offsets.records.len(), 18);
+    for font in other.iter() {
+        let font_header = ok!(ok!(font.take::<_, FontHeader>(&mut other_tape)));
+        assert_eq!(font_header.units_per_em, 1000);
+    }
+    assert!(other[0].exists::<GlyphPositioning>());
+    assert!(!other[1].exists::<GlyphPositioning>());
+    let shared = other[1]
+        .offsets
+        .records
+        .iter()
+        .filter(|record| {
+            other[0]
+                .offsets
+                .records
+                .iter()
+                .any(|other| (other.tag, other.offset) == (record.tag, record.offset))
+        })
+        .count();
+    assert_eq!(shared, 18);
+    for (font, builder) in other.iter().zip(fonts.iter()) {
+        assert_eq!(
+            ok!(Builder::from_font(font, &mut other_tape)).get(Tag(*b"cmap")),
+            builder.get(Tag(*b"cmap"))
+        );
+    }
+}
+
+#[test]
+fn write_regular() {
+    use opentype::truetype::tables::FontHeader;
+    use opentype::Builder;
+
+    let mut tape = setup!(SourceSerifPro);
+    let file = ok!(File::read(&mut tape));
+    let mut builder = ok!(Builder::from_font(&file[0], &mut tape));
+    let mut font_header = ok!(ok!(file[0].take::<_, FontHeader>(&mut tape)));
+    font_header.modified += 1;
+    ok!(builder.add(&font_header));
+    let mut data = vec![];
+    ok!(builder.write(&mut data));
+    assert_eq!(data.len() % 4, 0);
+    let total = data
+        .chunks(4)
+        .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .fold(0u32, |total, value| total.wrapping_add(value));
+    assert_eq!(total, FontHeader::CHECKSUM_ADJUSTMENT);
+
+    let mut other_tape = std::io::Cursor::new(data);
+    assert!(ok!(File::collection(&mut other_tape)).is_none());
+    let other = ok!(File::read(&mut other_tape));
+    let header = &other[0].offsets.header;
+    assert_eq!(header.version, file[0].offsets.header.version);
+    assert_eq!(header.table_count, 12);
+    assert_eq!(header.search_range, 128);
+    assert_eq!(header.entry_selector, 3);
+    assert_eq!(header.range_shift, 64);
+    let tags = other[0]
+        .offsets
+        .records
+        .iter()
+        .map(|record| record.tag)
+        .collect::<Vec<_>>();
+    assert_eq!(tags, builder.tags().collect::<Vec<_>>());
+    let other_builder = ok!(Builder::from_font(&other[0], &mut other_tape));
+    for tag in tags.into_iter().filter(|tag| tag != b"head") {
+        assert_eq!(other_builder.get(tag), builder.get(tag));
+    }
+    let other_font_header = ok!(ok!(other[0].take::<_, FontHeader>(&mut other_tape)));
+    assert_eq!(other_font_header.modified, font_header.modified);
+}
+
+#[test]
+fn write_too_many_tables() {
+    use opentype::Builder;
+
+    let mut builder = Builder::new(Tag(*b"OTTO"));
+    for i in 0..4096u32 {
+        builder.add_bytes(Tag(i.to_be_bytes()), vec![]);
+    }
+    assert!(builder.write(&mut vec![]).is_err());
+    builder.remove(Tag(0u32.to_be_bytes()));
+    let mut data = vec![];
+    ok!(builder.write(&mut data));
+    let file = ok!(File::read(&mut std::io::Cursor::new(data)));
+    let header = &file[0].offsets.header;
+    assert_eq!(header.table_count, 4095);
+    assert_eq!(header.search_range, 32768);
+    assert_eq!(header.entry_selector, 11);
+    assert_eq!(header.range_shift, 32752);
+}
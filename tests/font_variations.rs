@@ -15,3 +15,57 @@ fn table() {
     assert_eq!(f32::from(table.axis_records[0].min_value), 200.0);
     assert_eq!(f32::from(table.axis_records[0].max_value), 900.0);
 }
+
+#[test]
+fn write() {
+    let mut table: FontVariations = ok!(Read::read(&mut setup!(AdobeVFPrototypeTTF, "fvar")));
+    support::round_trip(&table);
+
+    let has_postscript_name_id = table.header.instance_size == 4 + 4 * 2 + 2;
+    for record in table.instance_records.iter() {
+        assert_eq!(record.postscript_name_id.is_some(), has_postscript_name_id);
+    }
+    table.header.instance_size = 0;
+    for record in table.instance_records.iter_mut() {
+        record.postscript_name_id = None;
+    }
+    let data = support::write(&table);
+    let other: FontVariations = ok!(Read::read(&mut std::io::Cursor::new(&data)));
+    assert_eq!(other.header.instance_size, 4 + 4 * 2);
+    assert_eq!(
+        data.len(),
+        16 + 2 * 20 + table.instance_records.len() * (4 + 4 * 2)
+    );
+    assert!(other
+        .instance_records
+        .iter()
+        .all(|record| record.postscript_name_id.is_none()));
+}
+
+#[test]
+fn write_many_axes() {
+    let mut table: FontVariations = ok!(Read::read(&mut setup!(AdobeVFPrototypeTTF, "fvar")));
+    let axis_record = table.axis_records[0];
+    table.axis_records = vec![axis_record; 16382];
+    table.instance_records.truncate(1);
+    table.instance_records[0].coordinates = vec![Default::default(); 16382];
+    table.instance_records[0].postscript_name_id = Some(Default::default());
+    table.header.instance_size = 0;
+    let data = support::write(&table);
+    let other: FontVariations = ok!(Read::read(&mut std::io::Cursor::new(&data)));
+    assert_eq!(other.header.instance_size, 6 + 4 * 16382);
+    assert!(other.instance_records[0].postscript_name_id.is_some());
+
+    table.axis_records.push(axis_record);
+    table.instance_records[0]
+        .coordinates
+        .push(Default::default());
+    table.instance_records[0].postscript_name_id = None;
+    assert!(opentype::value::Write::write(&table, &mut vec![]).is_err());
+
+    let mut data = vec![0, 1, 0, 0, 0, 16, 0, 2, 0x3F, 0xFF, 0, 20, 0, 1, 0xFF, 0xFF];
+    data.resize(16 + 16383 * 20 + 4 + 4 * 16383, 0);
+    let other: FontVariations = ok!(Read::read(&mut std::io::Cursor::new(&data)));
+    assert_eq!(other.instance_records[0].coordinates.len(), 16383);
+    assert!(other.instance_records[0].postscript_name_id.is_none());
+}
@@ -1,9 +1,13 @@
 #[macro_use]
 mod support;
 
+use opentype::layout::coverage::{Coverage, Coverage1};
 use opentype::layout::Class;
-use opentype::tables::glyph_definition::{GlyphDefinition, Header};
+use opentype::tables::glyph_definition::{
+    Caret, Caret1, Caret2, GlyphDefinition, Header, Header12, Ligature, Ligatures, Marks,
+};
 use opentype::value::Read;
+use truetype::q16;
 
 #[test]
 fn table() {
@@ -22,7 +26,106 @@ fn table() {
     }
     assert!(table.attachments.is_none());
     match &table.ligatures {
-        &Some(ref table) => assert_eq!(table.count, 0),
+        Some(table) => assert_eq!(table.count, 0),
         _ => unreachable!(),
     }
 }
+
+#[test]
+fn write() {
+    let table: GlyphDefinition = ok!(Read::read(&mut setup!(OpenSans, "GDEF")));
+    support::round_trip(&table);
+}
+
+#[test]
+fn write_marks() {
+    let coverage = |glyph_ids: Vec<u16>| {
+        Coverage::Format1(Coverage1 {
+            format: 1,
+            glyph_count: glyph_ids.len() as u16,
+            glyph_ids,
+        })
+    };
+    let table = GlyphDefinition {
+        header: Header::Version12(Header12::default()),
+        ligatures: Some(Ligatures {
+            coverage: coverage(vec![42]),
+            records: vec![Ligature {
+                carets: vec![
+                    Caret::Format1(Caret1 {
+                        format: 1,
+                        coordinate: -120,
+                    }),
+                    Caret::Format2(Caret2 {
+                        format: 2,
+                        index: 7,
+                    }),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        marks: Some(Marks {
+            coverages: vec![coverage(vec![1, 2]), coverage(vec![3])],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let data = support::write(&table);
+    let other: GlyphDefinition = ok!(Read::read(&mut std::io::Cursor::new(&data)));
+    match &other.header {
+        Header::Version12(header) => assert_eq!(header.minor_version, 2),
+        _ => unreachable!(),
+    }
+    let ligatures = other.ligatures.as_ref().unwrap();
+    assert_eq!(ligatures.count, 1);
+    match &ligatures.records[0].carets[..] {
+        [Caret::Format1(first), Caret::Format2(second)] => {
+            assert_eq!(first.coordinate, -120);
+            assert_eq!(second.index, 7);
+        }
+        _ => unreachable!(),
+    }
+    let marks = other.marks.as_ref().unwrap();
+    assert_eq!(marks.count, 2);
+    let glyph_ids = |coverages: &[Coverage]| {
+        (coverages.iter())
+            .map(|coverage| coverage.iter().collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        glyph_ids(&marks.coverages),
+        glyph_ids(&table.marks.unwrap().coverages),
+    );
+}
+
+#[test]
+fn variations() {
+    let table: GlyphDefinition = ok!(Read::read(&mut setup!(KalniaGlaze, "GDEF")));
+    match &table.header {
+        Header::Version13(header) => assert_eq!(header.variation_offset, 402),
+        _ => unreachable!(),
+    }
+    let store = table.variations.as_ref().unwrap();
+    assert_eq!(store.records.len(), 7);
+    let carets = table.ligature_carets(&[]);
+    assert_eq!(carets.len(), 22);
+    assert_eq!(carets[0], &[Some(486.0)]);
+    assert_eq!(carets[1], &[Some(342.0)]);
+    let coordinates = [q16(0x4000), q16(0x4000)];
+    let delta = ok!(store.evaluate(5, 34, &coordinates).ok_or(()));
+    assert!(delta != 0.0);
+    let carets = table.ligature_carets(&coordinates);
+    assert_eq!(carets[0], &[Some(486.0 + delta)]);
+}
+
+#[test]
+fn write_variations() {
+    for mut tape in [
+        setup!(AdobeVFPrototypeTTF, "GDEF"),
+        setup!(KalniaGlaze, "GDEF"),
+    ] {
+        let table: GlyphDefinition = ok!(Read::read(&mut tape));
+        support::round_trip(&table);
+    }
+}
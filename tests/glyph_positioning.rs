@@ -22,6 +22,8 @@ mod crimson_text {
     use opentype::tables::glyph_positioning::GlyphPositioning;
     use opentype::value::Read;
 
+    use crate::support::{self, take, Fixture};
+
     #[test]
     fn features() {
         let GlyphPositioning { features, .. } = ok!(Read::read(&mut setup!(CrimsonText, "GPOS")));
@@ -32,14 +34,35 @@ mod crimson_text {
             .collect::<Vec<_>>();
         assert_eq!(tags, tags![b"kern", b"mark", b"mkmk"]);
     }
+
+    #[test]
+    fn write() {
+        let table = take::<GlyphPositioning>(Fixture::CrimsonText);
+        support::round_trip(&table);
+    }
+}
+
+mod kalnia_glaze {
+    use opentype::tables::glyph_positioning::GlyphPositioning;
+
+    use crate::support::{self, take, Fixture};
+
+    #[test]
+    fn write() {
+        let table = take::<GlyphPositioning>(Fixture::KalniaGlaze);
+        support::round_trip(&table);
+    }
 }
 
 mod source_serif {
+    use opentype::layout::feature::Parameters;
     use opentype::layout::Language;
     use opentype::layout::Script;
     use opentype::tables::glyph_positioning::{GlyphPositioning, PairAdjustment, Type};
     use opentype::value::Read;
 
+    use crate::support;
+
     #[test]
     fn features() {
         let GlyphPositioning { features, .. } =
@@ -62,6 +85,15 @@ mod source_serif {
             .map(|record| record.lookup_index_count)
             .collect::<Vec<_>>();
         assert_eq!(lookups, &[1, 1, 1, 1, 1, 0, 0, 0, 0, 0]);
+        for record in &features.records[5..] {
+            match &record.parameters {
+                Some(Parameters::OpticalSize(parameters)) => {
+                    assert_eq!(parameters.design_size, 100);
+                    assert_eq!(parameters.subfamily_id, 0);
+                }
+                _ => unreachable!(),
+            }
+        }
     }
 
     #[test]
@@ -116,4 +148,231 @@ mod source_serif {
         assert_eq!(record.language_count, 3);
         assert!(record.get(Language::Turkish).is_some());
     }
+
+    #[test]
+    fn view() {
+        use opentype::layout::view::{Class, Directory};
+
+        let table: GlyphPositioning = ok!(Read::read(&mut setup!(SourceSerifPro, "GPOS")));
+        let data = support::read(support::Fixture::SourceSerifPro, "GPOS");
+        let view = ok!(Directory::<Type>::new(&data));
+        assert_eq!(view.major_version(), table.major_version);
+        assert_eq!(view.minor_version(), table.minor_version);
+        assert_eq!(view.lookup_offset(), table.lookup_offset);
+        assert_eq!(view.variation_offset(), table.variation_offset);
+        assert_eq!(
+            format!("{:?}", ok!(view.scripts())),
+            format!("{:?}", table.scripts),
+        );
+        assert_eq!(
+            format!("{:?}", ok!(view.features())),
+            format!("{:?}", table.features),
+        );
+        let lookups = ok!(view.lookups());
+        assert_eq!(lookups.count(), table.lookups.count);
+        assert_eq!(lookups.offsets().collect::<Vec<_>>(), table.lookups.offsets);
+        for (record, other) in lookups.records().zip(&table.lookups.records) {
+            let record = ok!(record);
+            assert_eq!(record.r#type(), other.r#type);
+            assert_eq!(record.flags(), other.flags);
+            assert_eq!(record.table_count(), other.table_count);
+            assert_eq!(
+                record.table_offsets().collect::<Vec<_>>(),
+                other.table_offsets
+            );
+            assert_eq!(record.mark_filtering_set(), other.mark_filtering_set);
+            let tables = ok!(record.tables().collect::<Result<Vec<_>, _>>());
+            assert_eq!(format!("{tables:?}"), format!("{:?}", other.tables));
+            for (i, other) in other.tables.iter().enumerate() {
+                let table = ok!(record.table(i));
+                assert_eq!(table.r#type(), 2);
+                let (format, coverage, classes) = match other {
+                    Type::PairAdjustment(PairAdjustment::Format1(other)) => {
+                        (1, &other.coverage, None)
+                    }
+                    Type::PairAdjustment(PairAdjustment::Format2(other)) => {
+                        let classes = (other.class1_offset, other.class2_offset);
+                        (
+                            2,
+                            &other.coverage,
+                            Some((classes, &other.class1, &other.class2)),
+                        )
+                    }
+                    _ => unreachable!(),
+                };
+                assert_eq!(table.format(), format);
+                let view = ok!(table.coverage());
+                for glyph_id in 0..2000 {
+                    assert_eq!(ok!(view.index_of(glyph_id)), coverage.index_of(glyph_id));
+                }
+                if let Some(((offset1, offset2), class1, class2)) = classes {
+                    let view1 = ok!(Class::new(&table.data()[offset1 as usize..]));
+                    let view2 = ok!(Class::new(&table.data()[offset2 as usize..]));
+                    for glyph_id in 0..2000 {
+                        assert_eq!(ok!(view1.class_of(glyph_id)), class1.class_of(glyph_id));
+                        assert_eq!(ok!(view2.class_of(glyph_id)), class2.class_of(glyph_id));
+                    }
+                }
+            }
+        }
+        let record = ok!(lookups.get(0));
+        assert_eq!(record.r#type(), 2);
+        assert!(matches!(ok!(record.get(0)), Type::PairAdjustment(_)));
+        assert!(lookups.get(lookups.count() as usize).is_err());
+        assert!(record.table(record.table_count() as usize).is_err());
+        assert!(Directory::<Type>::new(&data[..8]).is_err());
+    }
+
+    #[test]
+    fn write() {
+        let table: GlyphPositioning = ok!(Read::read(&mut setup!(SourceSerifPro, "GPOS")));
+        support::round_trip(&table);
+    }
+}
+
+mod synthetic {
+    use opentype::layout::coverage::{Coverage, Coverage1};
+    use opentype::layout::lookup::{Flags, Lookups, Record};
+    use opentype::layout::{Features, Scripts};
+    use opentype::tables::glyph_positioning::{
+        GlyphPositioning, Pair1, Pair1s, PairAdjustment, PairAdjustment1, Single, SingleAdjustment,
+        Type,
+    };
+    use opentype::value::Read;
+
+    use crate::support;
+
+    #[test]
+    fn extension() {
+        #[rustfmt::skip]
+        let data: Vec<u8> = vec![
+            0, 1, 0, 0, 0, 10, 0, 12, 0, 14, // GPOS
+            0, 0, // ScriptList
+            0, 0, // FeatureList
+            0, 1, 0, 4, // LookupList
+            0, 9, 0, 0, 0, 1, 0, 8, // Lookup
+            0, 1, 0, 1, 0, 0, 0, 8, // ExtensionPosFormat1
+            0, 1, 0, 8, 0, 4, 0xFF, 0xCE, // SinglePosFormat1
+            0, 1, 0, 1, 0, 5, // CoverageFormat1
+        ];
+        let table: GlyphPositioning = ok!(Read::read(&mut std::io::Cursor::new(&data)));
+        let record = &table.lookups.records[0];
+        assert_eq!(record.r#type, 9);
+        assert_eq!(record.effective_type(), 1);
+        match &record.tables[0] {
+            Type::SingleAdjustment(SingleAdjustment::Format1(table)) => {
+                assert_eq!(table.value.x_advance, Some(-50));
+            }
+            _ => unreachable!(),
+        }
+        let other: GlyphPositioning = ok!(Read::read(&mut std::io::Cursor::new(support::write(
+            &table
+        ))));
+        assert_eq!(other.lookups.records[0].r#type, 1);
+    }
+
+    #[test]
+    fn failure() {
+        use opentype::{Failure, Segment};
+
+        #[rustfmt::skip]
+        let data: Vec<u8> = vec![
+            0, 1, 0, 0, 0, 10, 0, 12, 0, 14, // GPOS
+            0, 0, // ScriptList
+            0, 0, // FeatureList
+            0, 1, 0, 4, // LookupList
+            0, 2, 0, 0, 0, 1, 0, 8, // Lookup
+            0, 2, 0, 16, 0, 0, 0, 0, 0, 22, 0, 30, 0, 1, 0, 1, // PairPosFormat2
+            0, 1, 0, 1, 0, 5, // CoverageFormat1
+            0, 1, 0, 5, 0, 1, 0, 0, // ClassDefFormat1
+            0, 3, // ClassDefFormat3
+        ];
+        let error = Read::read(&mut std::io::Cursor::new(&data))
+            .map(|_: GlyphPositioning| ())
+            .unwrap_err();
+        let failure = ok!(Failure::find(&error));
+        assert_eq!(failure.kind, std::io::ErrorKind::Other);
+        assert_eq!(
+            failure.message,
+            "found an unknown format of the glyph class (3)"
+        );
+        assert_eq!(failure.offset, 56);
+        assert_eq!(
+            failure.path.0,
+            &[
+                Segment::Name("lookups"),
+                Segment::Index(0),
+                Segment::Name("tables"),
+                Segment::Index(0),
+                Segment::Name("PairAdjustment2"),
+                Segment::Name("class2"),
+            ],
+        );
+        assert_eq!(
+            failure.path.to_string(),
+            "lookups[0]/tables[0]/PairAdjustment2/class2",
+        );
+    }
+
+    #[test]
+    fn write() {
+        let records = (0..3u16)
+            .map(|i| Pair1s {
+                count: 1,
+                records: vec![Pair1 {
+                    glyph2_id: i + 10,
+                    value1: Some(Single {
+                        x_advance: Some(-10 * i as i16),
+                        ..Default::default()
+                    }),
+                    value2: None,
+                }],
+            })
+            .collect();
+        let table = PairAdjustment1 {
+            records,
+            coverage: Coverage::Format1(Coverage1 {
+                format: 1,
+                glyph_count: 3,
+                glyph_ids: vec![1, 2, 3],
+            }),
+            ..Default::default()
+        };
+        let table = GlyphPositioning {
+            major_version: 1,
+            minor_version: 0,
+            script_offset: 0,
+            feature_offset: 0,
+            lookup_offset: 0,
+            variation_offset: 0,
+            scripts: Scripts::default(),
+            features: Features::default(),
+            lookups: Lookups {
+                count: 1,
+                offsets: vec![],
+                records: vec![Record {
+                    r#type: 2,
+                    flags: Flags(0),
+                    table_count: 1,
+                    table_offsets: vec![],
+                    mark_filtering_set: None,
+                    tables: vec![Type::PairAdjustment(PairAdjustment::Format1(table))],
+                }],
+            },
+            variations: None,
+        };
+        let data = support::write(&table);
+        let other: GlyphPositioning = ok!(Read::read(&mut std::io::Cursor::new(&data)));
+        match &other.lookups.records[0].tables[0] {
+            Type::PairAdjustment(PairAdjustment::Format1(table)) => {
+                assert_eq!(table.value1_flags.0, 0b100);
+                assert_eq!(table.value2_flags.0, 0);
+                assert_eq!(table.record_count, 3);
+                let value = table.records[2].records[0].value1.as_ref().unwrap();
+                assert_eq!(value.x_advance, Some(-20));
+                assert!(value.x_placement.is_none());
+            }
+            _ => unreachable!(),
+        }
+    }
 }
@@ -1,16 +1,112 @@
 #[macro_use]
 mod support;
 
+mod adobe_vf_prototype {
+    use opentype::layout::feature::Condition;
+    use opentype::tables::glyph_substitution::GlyphSubstitution;
+    use truetype::q16;
+
+    use crate::support::{self, take, Fixture};
+
+    #[test]
+    fn variations() {
+        let table = take::<GlyphSubstitution>(Fixture::AdobeVFPrototypeTTF);
+        let variations = table.variations.as_ref().unwrap();
+        assert_eq!(variations.records.len(), 1);
+        let record = &variations.records[0];
+        let conditions = record.conditions.as_ref().unwrap();
+        match &conditions.records[1] {
+            Condition::Format1(condition) => {
+                assert_eq!(condition.axis_index, 0);
+                assert_eq!(condition.min_value, q16(11199));
+                assert_eq!(condition.max_value, q16(16384));
+            }
+        }
+        let substitutions = record.substitutions.as_ref().unwrap();
+        assert_eq!(substitutions.headers[0].feature_index, 6);
+        assert_eq!(substitutions.records[0].lookup_indices, &[9]);
+
+        let features = table.effective_features(&[]);
+        assert_eq!(features.len(), 9);
+        assert!(features[6].lookup_indices.is_empty());
+        let features = table.effective_features(&[q16(11198), q16(0)]);
+        assert!(features[6].lookup_indices.is_empty());
+        let features = table.effective_features(&[q16(11199), q16(0)]);
+        assert_eq!(features[6].lookup_indices, &[9]);
+        assert_eq!(
+            features[5].lookup_indices,
+            table.features.records[5].lookup_indices
+        );
+        let features = table.effective_features(&[q16(0x4000), q16(-0x4000)]);
+        assert!(features[6].lookup_indices.is_empty());
+    }
+
+    #[test]
+    fn write() {
+        let table = take::<GlyphSubstitution>(Fixture::AdobeVFPrototypeTTF);
+        support::round_trip(&table);
+    }
+}
+
+mod crimson_text {
+    use opentype::tables::glyph_substitution::GlyphSubstitution;
+
+    use crate::support::{self, take, Fixture};
+
+    #[test]
+    fn write() {
+        let table = take::<GlyphSubstitution>(Fixture::CrimsonText);
+        support::round_trip(&table);
+    }
+}
+
+mod kalnia_glaze {
+    use opentype::tables::glyph_substitution::GlyphSubstitution;
+
+    use crate::support::{self, take, Fixture};
+
+    #[test]
+    fn write() {
+        let table = take::<GlyphSubstitution>(Fixture::KalniaGlaze);
+        support::round_trip(&table);
+    }
+}
+
 mod londrina_solid {
     use opentype::tables::glyph_substitution::GlyphSubstitution;
     use opentype::value::Read;
+    use opentype::{walue, File, ReadOptions, Repair};
 
     #[test]
-    #[cfg_attr(not(feature = "ignore-incomplete-directories"), should_panic)]
+    #[should_panic]
     fn scripts() {
         let GlyphSubstitution { scripts, .. } = ok!(Read::read(&mut setup!(LondrinaSolid, "GSUB")));
         assert!(scripts.headers.is_empty());
     }
+
+    #[test]
+    fn scripts_lenient() {
+        let mut tape = setup!(LondrinaSolid);
+        let file = ok!(File::read(&mut tape));
+        let mut options = ReadOptions::lenient();
+        let GlyphSubstitution { scripts, .. } =
+            ok!(ok!(file[0].take_with(&mut tape, &mut options)));
+        assert!(scripts.headers.is_empty());
+        assert_eq!(options.repairs, &[Repair::IncompleteDirectory]);
+    }
+
+    #[test]
+    fn scripts_lenient_thread() {
+        let mut options = ReadOptions::lenient();
+        let handle = std::thread::spawn(move || {
+            let mut tape = setup!(LondrinaSolid, "GSUB");
+            let table: GlyphSubstitution = ok!(walue::Read::read(&mut tape, &mut options));
+            (table, options)
+        });
+        let (GlyphSubstitution { scripts, .. }, options) = ok!(handle.join());
+        assert!(scripts.headers.is_empty());
+        assert_eq!(options.repairs, &[Repair::IncompleteDirectory]);
+    }
 }
 
 mod source_serif {
@@ -18,6 +114,8 @@ mod source_serif {
     use opentype::tables::glyph_substitution::{GlyphSubstitution, SingleSubstitution, Type};
     use opentype::value::Read;
 
+    use crate::support;
+
     #[test]
     fn features() {
         let GlyphSubstitution { features, .. } =
@@ -102,7 +200,7 @@ mod source_serif {
         let record = &lookups.records[17];
         assert_eq!(record.tables.len(), 1);
         match &record.tables[0] {
-            &Type::LigatureSubstitution(ref table) => {
+            Type::LigatureSubstitution(table) => {
                 assert_eq!(table.record_count, 1);
                 let table = &table.records[0];
                 assert_eq!(table.count, 3);
@@ -144,4 +242,92 @@ mod source_serif {
         assert_eq!(record.language_count, 3);
         assert!(record.get(Language::Turkish).is_some());
     }
+
+    #[test]
+    fn write() {
+        let table: GlyphSubstitution = ok!(Read::read(&mut setup!(SourceSerifPro, "GSUB")));
+        support::round_trip(&table);
+    }
+}
+
+mod synthetic {
+    use std::io::Cursor;
+
+    use opentype::layout::coverage::{Coverage, Coverage1};
+    use opentype::layout::lookup::{Flags, Lookups, Record};
+    use opentype::layout::{Features, Scripts};
+    use opentype::tables::glyph_substitution::{
+        GlyphSubstitution, SingleSubstitution, SingleSubstitution2, Type,
+    };
+    use opentype::value::Read;
+
+    use crate::support;
+
+    #[test]
+    fn write() {
+        let records = (0..10u16)
+            .map(|i| {
+                let glyph_ids = (0..5000).map(|j| 5000 * i + j).collect::<Vec<_>>();
+                let table = SingleSubstitution2 {
+                    format: 2,
+                    glyph_count: 5000,
+                    glyph_ids: glyph_ids.iter().map(|glyph_id| glyph_id + 1).collect(),
+                    coverage: Coverage::Format1(Coverage1 {
+                        format: 1,
+                        glyph_count: 5000,
+                        glyph_ids,
+                    }),
+                    ..Default::default()
+                };
+                Record {
+                    r#type: 1,
+                    flags: Flags(0),
+                    table_count: 1,
+                    table_offsets: vec![],
+                    mark_filtering_set: None,
+                    tables: vec![Type::SingleSubstitution(SingleSubstitution::Format2(table))],
+                }
+            })
+            .collect::<Vec<_>>();
+        let table = GlyphSubstitution {
+            major_version: 1,
+            minor_version: 0,
+            script_offset: 0,
+            feature_offset: 0,
+            lookup_offset: 0,
+            variation_offset: 0,
+            scripts: Scripts::default(),
+            features: Features::default(),
+            lookups: Lookups {
+                count: 10,
+                offsets: vec![],
+                records,
+            },
+            variations: None,
+        };
+        let data = support::write(&table);
+        assert!(data.len() > 10 * 20000);
+        let other: GlyphSubstitution = ok!(Read::read(&mut Cursor::new(&data)));
+        assert_eq!(other.lookups.records.len(), 10);
+        let mut count = 0;
+        for (i, record) in other.lookups.records.iter().enumerate() {
+            assert_eq!(record.effective_type(), 1);
+            if record.r#type == 7 {
+                count += 1;
+            }
+            let table = match &record.tables[0] {
+                Type::SingleSubstitution(SingleSubstitution::Format2(table)) => table,
+                _ => unreachable!(),
+            };
+            assert_eq!(table.glyph_ids[0], 5000 * i as u16 + 1);
+            match table.coverage {
+                Coverage::Format1(ref coverage) => {
+                    assert_eq!(coverage.glyph_ids[4999], 5000 * i as u16 + 4999);
+                }
+                _ => unreachable!(),
+            }
+        }
+        assert!(count > 0);
+        assert_eq!(support::write(&other), data);
+    }
 }
@@ -0,0 +1,281 @@
+#[macro_use]
+mod support;
+
+mod crimson_text {
+    use opentype::layout::Class;
+    use opentype::tables::glyph_positioning::{GlyphPositioning, PairAdjustment, Type};
+    use opentype::value::Read;
+
+    #[test]
+    fn lookups() {
+        let GlyphPositioning { lookups, .. } = ok!(Read::read(&mut setup!(CrimsonText, "GPOS")));
+        let mut count = 0;
+        for record in &lookups.records {
+            for table in &record.tables {
+                let table = match table {
+                    Type::PairAdjustment(PairAdjustment::Format2(table)) => table,
+                    _ => continue,
+                };
+                for (i, glyph_id) in table.coverage.iter().enumerate() {
+                    assert_eq!(table.coverage.index_of(glyph_id), Some(i as u16));
+                }
+                for class in [&table.class1, &table.class2] {
+                    let last = match class {
+                        Class::Format1(class) => class.indices.iter().max().cloned(),
+                        Class::Format2(class) => {
+                            class.records.iter().map(|record| record.index).max()
+                        }
+                    };
+                    for index in 1..=last.unwrap_or(0) {
+                        for glyph_id in class.glyphs(index) {
+                            assert_eq!(class.class_of(glyph_id), index);
+                        }
+                    }
+                }
+                count += 1;
+            }
+        }
+        assert!(count > 0);
+    }
+}
+
+mod synthetic {
+    use std::io::Cursor;
+
+    use opentype::layout::class::{self, Class, Class1, Class2};
+    use opentype::layout::coverage::{self, Coverage, Coverage1, Coverage2};
+    use opentype::tape::Read;
+
+    use crate::support::write;
+
+    #[test]
+    fn class1() {
+        let class = Class::Format1(Class1 {
+            format: 1,
+            start_glyph_id: 10,
+            glyph_count: 4,
+            indices: vec![1, 0, 2, 1],
+        });
+        assert_eq!(class.class_of(9), 0);
+        assert_eq!(class.class_of(10), 1);
+        assert_eq!(class.class_of(11), 0);
+        assert_eq!(class.class_of(12), 2);
+        assert_eq!(class.class_of(14), 0);
+        assert_eq!(class.glyphs(1).collect::<Vec<_>>(), &[10, 13]);
+        assert_eq!(class.glyphs(2).collect::<Vec<_>>(), &[12]);
+        assert_eq!(class.glyphs(3).count(), 0);
+    }
+
+    #[test]
+    fn class2() {
+        let class = Class::Format2(Class2 {
+            format: 2,
+            record_count: 3,
+            records: vec![
+                record(20, 22, 1),
+                record(5, 6, 2),
+                record(21, 25, 3), // overlapping
+            ],
+        });
+        let class: Class = ok!(Cursor::new(write(&class)).take());
+        match &class {
+            Class::Format2(table) => assert_eq!(table.record_count, 3),
+            _ => unreachable!(),
+        }
+        assert_eq!(class.class_of(4), 0);
+        assert_eq!(class.class_of(5), 2);
+        assert_eq!(class.class_of(21), 1);
+        assert_eq!(class.class_of(23), 3);
+        assert_eq!(class.glyphs(1).collect::<Vec<_>>(), &[20, 21, 22]);
+        assert_eq!(class.glyphs(3).collect::<Vec<_>>(), &[23, 24, 25]);
+
+        fn record(start_glyph_id: u16, end_glyph_id: u16, index: u16) -> class::Record {
+            class::Record {
+                start_glyph_id,
+                end_glyph_id,
+                index,
+            }
+        }
+    }
+
+    #[test]
+    fn coverage1() {
+        let coverage = Coverage::Format1(Coverage1 {
+            format: 1,
+            glyph_count: 4,
+            glyph_ids: vec![3, 7, 8, 42],
+        });
+        assert_eq!(coverage.index_of(7), Some(1));
+        assert_eq!(coverage.index_of(42), Some(3));
+        assert_eq!(coverage.index_of(9), None);
+        assert_eq!(coverage.iter().collect::<Vec<_>>(), &[3, 7, 8, 42]);
+
+        let coverage = Coverage::Format1(Coverage1 {
+            format: 1,
+            glyph_count: 4,
+            glyph_ids: vec![42, 7, 3, 7], // unsorted
+        });
+        let coverage: Coverage = ok!(Cursor::new(write(&coverage)).take());
+        match &coverage {
+            Coverage::Format1(table) => assert_eq!(table.glyph_ids, &[42, 7, 3, 7]),
+            _ => unreachable!(),
+        }
+        assert_eq!(coverage.index_of(3), Some(2));
+        assert_eq!(coverage.index_of(7), Some(1));
+        assert_eq!(coverage.index_of(8), None);
+        assert_eq!(coverage.index_of(42), Some(0));
+
+        let coverage = Coverage::Format1(Coverage1 {
+            format: 1,
+            glyph_count: 3,
+            glyph_ids: vec![5, 3, 5], // duplicated
+        });
+        let data = write(&coverage);
+        let coverage: Coverage = ok!(Cursor::new(&data).take());
+        assert_eq!(write(&coverage), data);
+        assert_eq!(coverage.index_of(3), Some(1));
+        assert_eq!(coverage.index_of(5), Some(0));
+        assert_eq!(coverage.iter().collect::<Vec<_>>(), &[5, 3, 5]);
+    }
+
+    #[test]
+    fn coverage2() {
+        let coverage = Coverage::Format2(Coverage2 {
+            format: 2,
+            record_count: 2,
+            records: vec![record(10, 12, 0), record(20, 21, 3)],
+        });
+        assert_eq!(coverage.index_of(9), None);
+        assert_eq!(coverage.index_of(11), Some(1));
+        assert_eq!(coverage.index_of(13), None);
+        assert_eq!(coverage.index_of(21), Some(4));
+        assert_eq!(coverage.iter().collect::<Vec<_>>(), &[10, 11, 12, 20, 21]);
+
+        let coverage = Coverage::Format2(Coverage2 {
+            format: 2,
+            record_count: 3,
+            records: vec![record(20, 21, 3), record(10, 12, 0), record(11, 11, 5)],
+        });
+        let coverage: Coverage = ok!(Cursor::new(write(&coverage)).take());
+        assert_eq!(coverage.index_of(11), Some(1));
+        match &coverage {
+            Coverage::Format2(table) => assert_eq!(table.record_count, 3),
+            _ => unreachable!(),
+        }
+        assert_eq!(coverage.index_of(20), Some(3));
+        assert_eq!(
+            coverage.iter().collect::<Vec<_>>(),
+            &[10, 11, 12, 20, 21, 11]
+        );
+
+        fn record(start_glyph_id: u16, end_glyph_id: u16, index: u16) -> coverage::Record {
+            coverage::Record {
+                start_glyph_id,
+                end_glyph_id,
+                index,
+            }
+        }
+    }
+    #[test]
+    fn view_class() {
+        use opentype::layout::view::Class as View;
+
+        let class = Class::Format1(Class1 {
+            format: 1,
+            start_glyph_id: 10,
+            glyph_count: 4,
+            indices: vec![1, 0, 2, 1],
+        });
+        let data = write(&class);
+        let view = ok!(View::new(&data));
+        for glyph_id in 0..20 {
+            assert_eq!(ok!(view.class_of(glyph_id)), class.class_of(glyph_id));
+        }
+
+        // The last value is cut off, which is noticed only when it is accessed.
+        let view = ok!(View::new(&data[..data.len() - 1]));
+        assert_eq!(ok!(view.class_of(9)), 0);
+        assert_eq!(ok!(view.class_of(12)), 2);
+        assert_eq!(ok!(view.class_of(14)), 0);
+        assert!(view.class_of(13).is_err());
+
+        let class = Class::Format2(Class2 {
+            format: 2,
+            record_count: 3,
+            records: vec![record(20, 22, 1), record(5, 6, 2), record(21, 25, 3)],
+        });
+        let data = write(&class);
+        let view = ok!(View::new(&data));
+        for glyph_id in 0..30 {
+            assert_eq!(ok!(view.class_of(glyph_id)), class.class_of(glyph_id));
+        }
+        let view = ok!(View::new(&data[..data.len() - 2]));
+        assert!(view.class_of(23).is_err());
+
+        assert!(View::new(&data[..3]).is_err());
+        assert!(View::new(&[0, 3, 0, 0]).is_err());
+
+        fn record(start_glyph_id: u16, end_glyph_id: u16, index: u16) -> class::Record {
+            class::Record {
+                start_glyph_id,
+                end_glyph_id,
+                index,
+            }
+        }
+    }
+
+    #[test]
+    fn view_coverage() {
+        use opentype::layout::view::Coverage as View;
+
+        let coverage = Coverage::Format1(Coverage1 {
+            format: 1,
+            glyph_count: 4,
+            glyph_ids: vec![3, 7, 42, 50],
+        });
+        let data = write(&coverage);
+        let view = ok!(View::new(&data));
+        assert_eq!((view.format(), view.count()), (1, 4));
+        for glyph_id in 0..60 {
+            assert_eq!(ok!(view.index_of(glyph_id)), coverage.index_of(glyph_id));
+        }
+
+        let coverage = Coverage::Format1(Coverage1 {
+            format: 1,
+            glyph_count: 3,
+            glyph_ids: vec![5, 3, 5],
+        });
+        let data = write(&coverage);
+        let view = ok!(View::new(&data));
+        assert_eq!(ok!(view.index_of(3)), Some(1));
+        assert_eq!(ok!(view.index_of(5)), Some(0));
+        assert_eq!(ok!(view.index_of(4)), None);
+
+        let coverage = Coverage::Format2(Coverage2 {
+            format: 2,
+            record_count: 3,
+            records: vec![record(10, 12, 0), record(20, 21, 3), record(30, 30, 5)],
+        });
+        let data = write(&coverage);
+        let view = ok!(View::new(&data));
+        for glyph_id in 0..40 {
+            assert_eq!(ok!(view.index_of(glyph_id)), coverage.index_of(glyph_id));
+        }
+
+        // The last record is cut off, which is noticed only when the entries are searched.
+        let view = ok!(View::new(&data[..data.len() - 4]));
+        assert_eq!(view.count(), 3);
+        assert!(view.index_of(11).is_err());
+
+        assert!(View::new(&data[..2]).is_err());
+        assert!(View::new(&[0, 3, 0, 0]).is_err());
+
+        fn record(start_glyph_id: u16, end_glyph_id: u16, index: u16) -> coverage::Record {
+            coverage::Record {
+                start_glyph_id,
+                end_glyph_id,
+                index,
+            }
+        }
+    }
+}
@@ -0,0 +1,1147 @@
+#[macro_use]
+mod support;
+
+mod adobe_vf_prototype {
+    use opentype::layout::{Feature, Script};
+    use opentype::shaping::{Planner, Setting};
+    use opentype::tables::glyph_substitution::GlyphSubstitution;
+    use truetype::q16;
+
+    use crate::support::{take, Fixture};
+
+    #[test]
+    fn plan() {
+        let table = take::<GlyphSubstitution>(Fixture::AdobeVFPrototypeTTF);
+        let mut planner = Planner::new(Script::Latin, None);
+        planner.substitution = vec![
+            vec![Setting::on(Feature::RequiredVariationAlternates)],
+            vec![
+                Setting::on(Feature::StandardLigatures),
+                Setting::off(Feature::OldstyleFigures),
+                Setting::on(Feature::TabularFigures),
+            ],
+        ];
+        let plan = planner.plan(Some(&table), None);
+        assert_eq!(plan.substitution_indices().collect::<Vec<_>>(), &[2, 8]);
+        assert!(plan.positioning.is_empty());
+
+        let coordinates = [q16(11199), q16(0)];
+        planner.coordinates = &coordinates;
+        let plan = planner.plan(Some(&table), None);
+        assert_eq!(plan.substitution_indices().collect::<Vec<_>>(), &[9, 2, 8]);
+        let lookup = &plan.substitution[0].lookups[0];
+        assert_eq!(
+            lookup.settings,
+            &[Setting::on(Feature::RequiredVariationAlternates)],
+        );
+    }
+}
+
+mod crimson_text {
+    use opentype::layout::Script;
+    use opentype::shaping::{Options, Shaper};
+    use opentype::File;
+
+    #[test]
+    fn plan() {
+        let mut tape = setup!(CrimsonText);
+        let file = ok!(File::read(&mut tape));
+        let shaper = ok!(Shaper::read(&file[0], &mut tape));
+        let plan = shaper.plan(&Options::new(Script::DevanagariV2));
+        assert_eq!(plan.script, Script::Devanagari);
+        let plan = shaper.plan(&Options::new(Script::Latin));
+        assert_eq!(plan.script, Script::Latin);
+    }
+
+    #[test]
+    fn shape() {
+        let mut tape = setup!(CrimsonText);
+        let file = ok!(File::read(&mut tape));
+        let shaper = ok!(Shaper::read(&file[0], &mut tape));
+        let glyph_ids = ['e', '\u{301}', 'x']
+            .iter()
+            .map(|&character| shaper.mapping[&(character as u32)])
+            .collect::<Vec<_>>();
+        let advance = |glyph_id| shaper.metrics.get(glyph_id).0 as i32;
+
+        let mut options = Options::new(Script::Latin);
+        let buffer = ok!(shaper.shape("e\u{301}x", &options));
+        assert_eq!(buffer.glyph_ids(), glyph_ids);
+        let clusters = buffer
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .collect::<Vec<_>>();
+        assert_eq!(clusters, &[0, 1, 3]);
+        assert_eq!(buffer.glyphs[1].x_advance, 0);
+        let x_offset = buffer.glyphs[1].x_offset + advance(glyph_ids[0]);
+        assert!(x_offset > 0);
+
+        options.right_to_left = true;
+        let buffer = ok!(shaper.shape("e\u{301}x", &options));
+        assert_eq!(
+            buffer.glyph_ids(),
+            &[glyph_ids[2], glyph_ids[1], glyph_ids[0]]
+        );
+        assert_eq!(buffer.glyphs[1].x_offset, x_offset);
+    }
+}
+
+mod source_serif {
+    use opentype::layout::{Feature, Language, Script};
+    use opentype::shaping::{Buffer, Options, Planner, Positioning, Setting, Substitution};
+    use opentype::tables::glyph_positioning::{self, GlyphPositioning, PairAdjustment};
+    use opentype::tables::glyph_substitution::{GlyphSubstitution, SingleSubstitution, Type};
+    use opentype::value::Read;
+    use opentype::File;
+    use truetype::Tag;
+
+    #[test]
+    fn plan() {
+        let table: GlyphSubstitution = ok!(Read::read(&mut setup!(SourceSerifPro, "GSUB")));
+        let stages = vec![
+            vec![Setting::on(Feature::LocalizedForms)],
+            vec![
+                Setting::on(Feature::Fractions),
+                Setting::on(Feature::StandardLigatures),
+                Setting::on(Feature::Numerators),
+                Setting::off(Feature::Numerators),
+            ],
+        ];
+        let indices = |script, language| {
+            let mut planner = Planner::new(script, language);
+            planner.substitution = stages.clone();
+            let plan = planner.plan(Some(&table), None);
+            plan.substitution_indices().collect::<Vec<_>>()
+        };
+        assert_eq!(
+            indices(Script::Latin, Some(Language::Turkish)),
+            &[2, 4, 6, 7, 17],
+        );
+        assert_eq!(
+            indices(Script::Latin, Some(Language::Other(Tag(*b"XYZ ")))),
+            &[4, 6, 7, 17],
+        );
+        assert_eq!(indices(Script::Cyrillic, None), &[4, 6, 7, 17]);
+
+        let mut planner = Planner::new(Script::Latin, None);
+        planner.substitution = stages.clone();
+        let plan = planner.plan(Some(&table), None);
+        let lookup = &plan.substitution[1].lookups[0];
+        assert_eq!(lookup.index, 4);
+        assert_eq!(lookup.settings, &[Setting::on(Feature::Fractions)]);
+    }
+
+    #[test]
+    fn positioning() {
+        let table: GlyphPositioning = ok!(Read::read(&mut setup!(SourceSerifPro, "GPOS")));
+        let engine = Positioning::new(&table, None);
+
+        let (first, pair) = match &table.lookups.records[0].tables[0] {
+            glyph_positioning::Type::PairAdjustment(PairAdjustment::Format1(table)) => (
+                ok!(table.coverage.iter().next()),
+                &table.records[0].records[0],
+            ),
+            _ => unreachable!(),
+        };
+        let mut buffer = Buffer::new([first, pair.glyph2_id]);
+        for glyph in buffer.glyphs.iter_mut() {
+            glyph.x_advance = 500;
+        }
+        ok!(engine.apply(&mut buffer, 0));
+        engine.resolve(&mut buffer);
+        let value = ok!(pair.value1.as_ref());
+        assert_ne!(ok!(value.x_advance), 0);
+        assert_eq!(
+            buffer.glyphs[0].x_advance,
+            500 + ok!(value.x_advance) as i32,
+        );
+        assert_eq!(buffer.glyphs[1].x_advance, 500);
+
+        assert!(engine.apply(&mut buffer, 42).is_err());
+    }
+
+    #[test]
+    fn shape() {
+        let mut tape = setup!(SourceSerifPro);
+        let file = ok!(File::read(&mut tape));
+        let mut options = Options::new(Script::Latin);
+        let buffer = ok!(opentype::shaping::shape(
+            &file[0],
+            &mut tape,
+            "office AV",
+            &options,
+        ));
+        assert_eq!(buffer.glyph_ids(), &[42, 33, 211, 30, 32, 1, 2, 23]);
+        let clusters = buffer
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .collect::<Vec<_>>();
+        assert_eq!(clusters, &[0, 1, 2, 4, 5, 6, 7, 8]);
+        let advances = buffer
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.x_advance)
+            .collect::<Vec<_>>();
+        assert_eq!(advances, &[549, 354, 617, 488, 510, 233, 535, 674]);
+
+        options.features = vec![
+            Setting::off(Feature::StandardLigatures),
+            Setting::off(Feature::Kerning),
+        ];
+        let buffer = ok!(opentype::shaping::shape(
+            &file[0],
+            &mut tape,
+            "office AV",
+            &options,
+        ));
+        assert_eq!(buffer.glyph_ids(), &[42, 33, 33, 36, 30, 32, 1, 2, 23]);
+        assert_eq!(buffer.glyphs[7].x_advance, 535 + 129);
+    }
+
+    #[test]
+    fn substitution() {
+        let table: GlyphSubstitution = ok!(Read::read(&mut setup!(SourceSerifPro, "GSUB")));
+        let engine = Substitution::new(&table, None);
+
+        let (glyph_ids, coverage) = match &table.lookups.records[0].tables[0] {
+            Type::SingleSubstitution(SingleSubstitution::Format2(table)) => {
+                (&table.glyph_ids, &table.coverage)
+            }
+            _ => unreachable!(),
+        };
+        let glyph_id = ok!(coverage.iter().nth(3));
+        let mut buffer = Buffer::new([0, glyph_id, 0]);
+        ok!(engine.apply(&mut buffer, 0));
+        assert_eq!(buffer.glyph_ids(), &[0, glyph_ids[3], 0]);
+
+        let (first, ligature) = match &table.lookups.records[17].tables[0] {
+            Type::LigatureSubstitution(table) => (
+                ok!(table.coverage.iter().next()),
+                &table.records[0].records[0],
+            ),
+            _ => unreachable!(),
+        };
+        let mut buffer = Buffer::new([first, ligature.glyph_ids[0], 0]);
+        ok!(engine.apply(&mut buffer, 17));
+        assert_eq!(buffer.glyph_ids(), &[ligature.glyph_id, 0]);
+        assert_eq!(buffer.glyphs[1].cluster, 2);
+
+        assert!(engine.apply(&mut buffer, 42).is_err());
+    }
+}
+
+mod synthetic {
+    use opentype::layout::context::{Action, ChainedContext, ChainedContext3};
+    use opentype::layout::coverage::{Coverage, Coverage1};
+    use opentype::layout::lookup::{Flags, Lookups, Record, Subtable};
+    use opentype::layout::{feature, language, script};
+    use opentype::layout::{Class, Directory, Feature, Features, Language, Script, Scripts};
+    use opentype::shaping::{Buffer, Options, Planner, Positioning, Setting, Shaper, Substitution};
+    use opentype::tables::glyph_definition::{GlyphDefinition, Header, Header1};
+    use opentype::tables::glyph_positioning::{
+        self, Anchor, Anchor1, Base, Bases, Component, Connection, CursiveAttachment, Mark1,
+        Mark1s, Mark2, Mark2s, MarkToBaseAttachment, MarkToLigatureAttachment,
+        MarkToMarkAttachment, Pair1, Pair1s, PairAdjustment, PairAdjustment1, Single,
+        SingleAdjustment, SingleAdjustment1,
+    };
+    use opentype::tables::glyph_substitution::{
+        self, AlternateSubstitution, Alternates, Ligature, LigatureSubstitution, Ligatures,
+        MultipleSubstitution, ReverseChainedContextualSubstibution, Sequence, SingleSubstitution,
+        SingleSubstitution1, Type,
+    };
+    use truetype::tables::horizontal_metrics::{self as metrics, HorizontalMetrics};
+    use truetype::Tag;
+
+    #[test]
+    fn alternate() {
+        let table = table(vec![record(
+            Flags(0),
+            Type::AlternateSubstitution(AlternateSubstitution {
+                coverage: coverage(&[1]),
+                records: vec![Alternates {
+                    glyph_count: 2,
+                    glyph_ids: vec![10, 11],
+                }],
+                ..Default::default()
+            }),
+        )]);
+        let mut engine = Substitution::new(&table, None);
+        let mut buffer = Buffer::new([1, 2, 1]);
+        ok!(engine.apply(&mut buffer, 0));
+        assert_eq!(buffer.glyph_ids(), &[10, 2, 10]);
+        engine.alternate = 1;
+        let mut buffer = Buffer::new([1]);
+        ok!(engine.apply(&mut buffer, 0));
+        assert_eq!(buffer.glyph_ids(), &[11]);
+        engine.alternate = 2;
+        let mut buffer = Buffer::new([1]);
+        ok!(engine.apply(&mut buffer, 0));
+        assert_eq!(buffer.glyph_ids(), &[1]);
+    }
+
+    #[test]
+    fn arabic() {
+        let mut table = table(vec![
+            record(Flags(0), single(&[1, 2, 3, 4, 8], 10)),
+            record(Flags(0), single(&[1, 2, 3, 4, 8], 20)),
+            record(Flags(0), single(&[1, 2, 3, 4, 8], 30)),
+            record(Flags(0), single(&[1, 2, 3, 4, 8], 40)),
+            record(
+                Flags(0),
+                glyph_substitution::Type::MultipleSubstitution(MultipleSubstitution {
+                    coverage: coverage(&[60]),
+                    records: vec![Sequence {
+                        glyph_count: 3,
+                        glyph_ids: vec![70, 71, 72],
+                    }],
+                    ..Default::default()
+                }),
+            ),
+        ]);
+        directory(
+            &mut table,
+            b"DFLT",
+            &[
+                (b"isol", &[0]),
+                (b"fina", &[1]),
+                (b"medi", &[2]),
+                (b"init", &[3]),
+                (b"stch", &[4]),
+            ],
+        );
+        let mapping = [
+            ('\u{0628}', 1),
+            ('\u{062A}', 2),
+            ('\u{0627}', 3),
+            ('\u{0621}', 4),
+            ('\u{064E}', 5),
+            ('\u{200C}', 6),
+            ('\u{0654}', 7),
+            ('\u{0712}', 8),
+            ('\u{070F}', 60),
+        ];
+        let shaper = shaper(table, &mapping, |glyph_id| match glyph_id {
+            70..=72 => 100,
+            _ => 500,
+        });
+        let mut options = Options::new(Script::Arabic);
+
+        let text = "\u{0628}\u{064E}\u{062A}\u{0628}\u{0627}\u{0621}\u{0628}\u{200C}\u{0628}";
+        let buffer = ok!(shaper.shape(text, &options));
+        assert_eq!(buffer.glyph_ids(), &[41, 5, 32, 31, 23, 4, 11, 6, 11]);
+
+        let buffer = ok!(shaper.shape("\u{0628}\u{064E}\u{0654}\u{0628}", &options));
+        assert_eq!(buffer.glyph_ids(), &[41, 7, 5, 21]);
+        let clusters = buffer
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .collect::<Vec<_>>();
+        assert_eq!(clusters, &[0, 2, 2, 6]);
+
+        options.script = Script::Syriac;
+        let text = "\u{070F}\u{0712}\u{0712}\u{0712}";
+        let buffer = ok!(shaper.shape(text, &options));
+        let mut glyph_ids = vec![70];
+        glyph_ids.extend([71; 13]);
+        glyph_ids.extend([72, 48, 38, 28]);
+        assert_eq!(buffer.glyph_ids(), glyph_ids);
+        let offsets = buffer.glyphs[..15]
+            .iter()
+            .map(|glyph| glyph.x_offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, (1..16).map(|i| -100 * i).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn chained_context() {
+        let table = table(vec![
+            record(
+                Flags(0),
+                Type::ChainedContextualSubstitution(ChainedContext::Format3(ChainedContext3 {
+                    format: 3,
+                    backward_coverages: vec![coverage(&[1])],
+                    coverages: vec![coverage(&[2]), coverage(&[3])],
+                    forward_coverages: vec![coverage(&[4])],
+                    actions: vec![
+                        Action {
+                            position_index: 1,
+                            lookup_index: 2,
+                        },
+                        Action {
+                            position_index: 0,
+                            lookup_index: 1,
+                        },
+                    ],
+                    ..Default::default()
+                })),
+            ),
+            record(Flags(0), single(&[2], 10)),
+            record(
+                Flags(0),
+                Type::MultipleSubstitution(MultipleSubstitution {
+                    coverage: coverage(&[3]),
+                    records: vec![Sequence {
+                        glyph_count: 2,
+                        glyph_ids: vec![30, 31],
+                    }],
+                    ..Default::default()
+                }),
+            ),
+        ]);
+        let engine = Substitution::new(&table, None);
+        let mut buffer = Buffer::new([1, 2, 3, 4, 2, 3, 4]);
+        ok!(engine.apply(&mut buffer, 0));
+        assert_eq!(buffer.glyph_ids(), &[1, 12, 30, 31, 4, 2, 3, 4]);
+        let clusters = buffer
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .collect::<Vec<_>>();
+        assert_eq!(clusters, &[0, 1, 2, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn closure() {
+        let mut table = table(vec![
+            record(Flags(0), single(&[1], 10)),
+            record(Flags(0), ligatures(&[1, 2], 20)),
+            record(
+                Flags(0),
+                Type::AlternateSubstitution(AlternateSubstitution {
+                    coverage: coverage(&[3]),
+                    records: vec![Alternates {
+                        glyph_count: 2,
+                        glyph_ids: vec![30, 31],
+                    }],
+                    ..Default::default()
+                }),
+            ),
+            record(
+                Flags(0),
+                Type::ChainedContextualSubstitution(ChainedContext::Format3(ChainedContext3 {
+                    format: 3,
+                    backward_coverages: vec![coverage(&[5])],
+                    coverages: vec![coverage(&[1])],
+                    actions: vec![Action {
+                        position_index: 0,
+                        lookup_index: 4,
+                    }],
+                    ..Default::default()
+                })),
+            ),
+            record(Flags(0), single(&[1], 40)),
+            record(
+                Flags(0),
+                Type::MultipleSubstitution(MultipleSubstitution {
+                    coverage: coverage(&[11]),
+                    records: vec![Sequence {
+                        glyph_count: 2,
+                        glyph_ids: vec![50, 51],
+                    }],
+                    ..Default::default()
+                }),
+            ),
+        ]);
+        directory(
+            &mut table,
+            b"latn",
+            &[
+                (b"ccmp", &[0, 5]),
+                (b"liga", &[1]),
+                (b"salt", &[2]),
+                (b"calt", &[3]),
+            ],
+        );
+        let mut planner = Planner::new(Script::Latin, None);
+        planner.substitution = vec![vec![
+            Setting::on(Feature::GlyphCompositionDecomposition),
+            Setting::on(Feature::StandardLigatures),
+            Setting::on(Feature::StylisticAlternates),
+            Setting::on(Feature::ContextualAlternates),
+        ]];
+        let plan = planner.plan(Some(&table), None);
+        let closure = |glyph_ids: &[u16]| {
+            opentype::shaping::closure(&table, &plan, glyph_ids.iter().copied())
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(closure(&[1]), &[1, 11, 50, 51]);
+        assert_eq!(closure(&[1, 2]), &[1, 2, 11, 20, 50, 51]);
+        assert_eq!(closure(&[1, 5]), &[1, 5, 11, 41, 50, 51]);
+        assert_eq!(closure(&[3]), &[3, 30, 31]);
+
+        let mapping = [('a', 1), ('b', 2), ('c', 3)];
+        let shaper = shaper(table, &mapping, |_| 500);
+        let mut options = Options::new(Script::Latin);
+        let closure = |options: &Options, text| {
+            let glyph_ids = shaper.closure(text, options);
+            glyph_ids.into_iter().collect::<Vec<_>>()
+        };
+        assert_eq!(closure(&options, "ab"), &[1, 2, 11, 20, 50, 51]);
+        assert_eq!(closure(&options, "cd"), &[0, 3]);
+        options.features = vec![Setting::on(Feature::StylisticAlternates)];
+        assert_eq!(closure(&options, "c"), &[3, 30, 31]);
+    }
+
+    #[test]
+    fn cursive() {
+        let table = table(vec![record(
+            Flags(0),
+            glyph_positioning::Type::CursiveAttachment(CursiveAttachment {
+                coverage: coverage(&[1, 2]),
+                connection_count: 2,
+                connections: vec![
+                    Connection {
+                        end_anchor: Some(anchor(450, 100)),
+                        ..Default::default()
+                    },
+                    Connection {
+                        start_anchor: Some(anchor(50, 0)),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+        )]);
+        let engine = Positioning::new(&table, None);
+        let mut buffer = advance(Buffer::new([1, 2]), &[600, 600]);
+        ok!(engine.apply(&mut buffer, 0));
+        engine.resolve(&mut buffer);
+        assert_eq!(positions(&buffer), &[(450, 0, 0), (550, -50, 100)]);
+    }
+
+    #[test]
+    fn hangul() {
+        let mut table = table(vec![
+            record(Flags(0), single(&[1], 20)),
+            record(Flags(0), single(&[2], 20)),
+            record(Flags(0), single(&[3], 20)),
+        ]);
+        directory(
+            &mut table,
+            b"hang",
+            &[(b"ljmo", &[0]), (b"vjmo", &[1]), (b"tjmo", &[2])],
+        );
+        let mapping = [
+            ('\u{1100}', 1),
+            ('\u{1161}', 2),
+            ('\u{11A8}', 3),
+            ('\u{AC00}', 10),
+        ];
+        let shaper = shaper(table, &mapping, |_| 500);
+        let options = Options::new(Script::Hangul);
+        let shape = |text| ok!(shaper.shape(text, &options)).glyph_ids();
+        assert_eq!(shape("\u{1100}\u{1161}"), &[10]);
+        assert_eq!(shape("\u{AC01}"), &[21, 22, 23]);
+        assert_eq!(shape("\u{AC00}\u{11A8}"), &[21, 22, 23]);
+        assert_eq!(shape("\u{1100}\u{1161}\u{11A8}"), &[21, 22, 23]);
+        assert_eq!(shape("\u{1100}"), &[1]);
+    }
+
+    #[test]
+    fn hebrew() {
+        let mapping = [
+            ('\u{05B8}', 7),
+            ('\u{05BC}', 2),
+            ('\u{05C1}', 3),
+            ('\u{05D0}', 6),
+            ('\u{05E9}', 1),
+            ('\u{FB2C}', 5),
+            ('\u{FB49}', 4),
+        ];
+        let shaper = shaper(table(vec![]), &mapping, |_| 500);
+        let options = Options::new(Script::Hebrew);
+        let shape = |text| ok!(shaper.shape(text, &options)).glyph_ids();
+        assert_eq!(shape("\u{05E9}\u{05BC}\u{05C1}"), &[5]);
+        assert_eq!(shape("\u{05E9}\u{05BC}"), &[4]);
+        assert_eq!(shape("\u{05E9}\u{05C1}"), &[1, 3]);
+        assert_eq!(shape("\u{05D0}\u{05B8}"), &[6, 7]);
+    }
+
+    #[test]
+    fn indic() {
+        let shaper = devanagari(b"dev2");
+        let options = Options::new(Script::Devanagari);
+        let shape = |text| ok!(shaper.shape(text, &options)).glyph_ids();
+        assert_eq!(shape("\u{0915}\u{093F}"), &[4, 1]);
+        assert_eq!(shape("\u{0930}\u{094D}\u{0915}"), &[1, 20]);
+        assert_eq!(shape("\u{0915}\u{094D}\u{0937}\u{093F}"), &[4, 21, 5]);
+        assert_eq!(shape("\u{092F}\u{094D}\u{0915}\u{093F}"), &[8, 3, 4, 1]);
+        assert_eq!(shape("\u{0915}\u{094D}\u{092F}"), &[1, 22]);
+        assert_eq!(shape("\u{093F}"), &[4, 7]);
+
+        let buffer = ok!(shaper.shape("\u{0915}\u{093F} \u{0915}", &options));
+        assert_eq!(buffer.glyph_ids(), &[4, 1, 0, 1]);
+        let clusters = buffer
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .collect::<Vec<_>>();
+        assert_eq!(clusters, &[0, 0, 6, 7]);
+
+        let shaper = devanagari(b"deva");
+        let options = Options::new(Script::DevanagariV2);
+        let buffer = ok!(shaper.shape("\u{0915}\u{094D}\u{092F}", &options));
+        assert_eq!(buffer.glyph_ids(), &[1, 8, 3]);
+    }
+
+    #[test]
+    fn ligature() {
+        let table = table(vec![record(
+            Flags(0b1000),
+            Type::LigatureSubstitution(LigatureSubstitution {
+                coverage: coverage(&[1]),
+                records: vec![Ligatures {
+                    count: 2,
+                    records: vec![
+                        Ligature {
+                            glyph_id: 8,
+                            glyph_count: 3,
+                            glyph_ids: vec![2, 3],
+                        },
+                        Ligature {
+                            glyph_id: 9,
+                            glyph_count: 2,
+                            glyph_ids: vec![2],
+                        },
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+        )]);
+        let definition = definition(&[1, 1, 1, 3]);
+        let mut buffer = Buffer::new([1, 4, 2, 4, 1, 2]);
+        ok!(Substitution::new(&table, Some(&definition)).apply(&mut buffer, 0));
+        assert_eq!(buffer.glyph_ids(), &[9, 4, 4, 9]);
+        assert_eq!(buffer.glyphs[0].ligature_id, buffer.glyphs[1].ligature_id);
+        assert_eq!(buffer.glyphs[1].component, 0);
+        assert_eq!(buffer.glyphs[2].ligature_id, 0);
+        assert_ne!(buffer.glyphs[3].ligature_id, buffer.glyphs[0].ligature_id);
+        assert_eq!(buffer.glyphs[3].cluster, 4);
+
+        let mut buffer = Buffer::new([1, 4, 2, 4, 1, 2]);
+        ok!(Substitution::new(&table, None).apply(&mut buffer, 0));
+        assert_eq!(buffer.glyph_ids(), &[1, 4, 2, 4, 9]);
+    }
+
+    #[test]
+    fn mark_to_base() {
+        let table = table(vec![
+            record(
+                Flags(0),
+                glyph_positioning::Type::MarkToBaseAttachment(MarkToBaseAttachment {
+                    mark_class_count: 1,
+                    mark_coverage: coverage(&[2]),
+                    base_coverage: coverage(&[1]),
+                    marks: marks(&[(0, 50, 0)]),
+                    bases: Bases {
+                        count: 1,
+                        records: vec![Base {
+                            anchors: vec![Some(anchor(250, 600))],
+                            ..Default::default()
+                        }],
+                    },
+                    ..Default::default()
+                }),
+            ),
+            record(
+                Flags(0),
+                glyph_positioning::Type::MarkToMarkAttachment(MarkToMarkAttachment {
+                    mark_class_count: 1,
+                    mark1_coverage: coverage(&[3]),
+                    mark2_coverage: coverage(&[2]),
+                    mark1s: marks(&[(0, 10, 0)]),
+                    mark2s: Mark2s {
+                        count: 1,
+                        records: vec![Mark2 {
+                            anchors: vec![Some(anchor(20, 100))],
+                            ..Default::default()
+                        }],
+                    },
+                    ..Default::default()
+                }),
+            ),
+        ]);
+        let definition = definition(&[1, 3, 3]);
+        let engine = Positioning::new(&table, Some(&definition));
+        let mut buffer = advance(Buffer::new([1, 2, 3]), &[500, 0, 0]);
+        ok!(engine.apply(&mut buffer, 0));
+        ok!(engine.apply(&mut buffer, 1));
+        engine.resolve(&mut buffer);
+        assert_eq!(
+            positions(&buffer),
+            &[(500, 0, 0), (0, -300, 600), (0, -290, 700)],
+        );
+    }
+
+    #[test]
+    fn mark_to_ligature() {
+        let table = table(vec![record(
+            Flags(0),
+            glyph_positioning::Type::MarkToLigatureAttachment(MarkToLigatureAttachment {
+                mark_class_count: 1,
+                mark_coverage: coverage(&[2]),
+                ligature_coverage: coverage(&[1]),
+                marks: marks(&[(0, 0, 0)]),
+                ligatures: glyph_positioning::Ligatures {
+                    count: 1,
+                    records: vec![glyph_positioning::Ligature {
+                        count: 2,
+                        components: vec![
+                            Component {
+                                anchors: vec![Some(anchor(100, 500))],
+                                ..Default::default()
+                            },
+                            Component {
+                                anchors: vec![Some(anchor(400, 500))],
+                                ..Default::default()
+                            },
+                        ],
+                    }],
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )]);
+        let definition = definition(&[2, 3]);
+        let engine = Positioning::new(&table, Some(&definition));
+        let mut buffer = advance(Buffer::new([1, 2, 2]), &[600, 0, 0]);
+        buffer.glyphs[0].ligature_id = 1;
+        buffer.glyphs[1].ligature_id = 1;
+        ok!(engine.apply(&mut buffer, 0));
+        engine.resolve(&mut buffer);
+        assert_eq!(
+            positions(&buffer),
+            &[(600, 0, 0), (0, -500, 500), (0, -200, 500)],
+        );
+    }
+
+    #[test]
+    fn pair() {
+        let table = table(vec![record(
+            Flags(0),
+            glyph_positioning::Type::PairAdjustment(PairAdjustment::Format1(PairAdjustment1 {
+                format: 1,
+                record_count: 1,
+                coverage: coverage(&[1]),
+                records: vec![Pair1s {
+                    count: 1,
+                    records: vec![Pair1 {
+                        glyph2_id: 2,
+                        value1: Some(Single {
+                            x_advance: Some(-20),
+                            ..Default::default()
+                        }),
+                        value2: Some(Single {
+                            x_placement: Some(5),
+                            ..Default::default()
+                        }),
+                    }],
+                }],
+                ..Default::default()
+            })),
+        )]);
+        let engine = Positioning::new(&table, None);
+        let mut buffer = advance(Buffer::new([1, 2, 1, 3]), &[500, 500, 500, 500]);
+        ok!(engine.apply(&mut buffer, 0));
+        engine.resolve(&mut buffer);
+        assert_eq!(
+            positions(&buffer),
+            &[(480, 0, 0), (500, 5, 0), (500, 0, 0), (500, 0, 0)],
+        );
+    }
+
+    #[test]
+    fn positioning_context() {
+        let table = table(vec![
+            record(
+                Flags(0),
+                glyph_positioning::Type::ChainedContextualPositioning(ChainedContext::Format3(
+                    ChainedContext3 {
+                        format: 3,
+                        backward_coverages: vec![coverage(&[1])],
+                        coverages: vec![coverage(&[2])],
+                        actions: vec![Action {
+                            position_index: 0,
+                            lookup_index: 1,
+                        }],
+                        ..Default::default()
+                    },
+                )),
+            ),
+            record(
+                Flags(0),
+                glyph_positioning::Type::SingleAdjustment(SingleAdjustment::Format1(
+                    SingleAdjustment1 {
+                        format: 1,
+                        coverage: coverage(&[2]),
+                        value: Single {
+                            x_advance: Some(10),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                )),
+            ),
+        ]);
+        let engine = Positioning::new(&table, None);
+        let mut buffer = advance(Buffer::new([1, 2, 2]), &[500, 500, 500]);
+        ok!(engine.apply(&mut buffer, 0));
+        assert_eq!(positions(&buffer), &[(500, 0, 0), (510, 0, 0), (500, 0, 0)],);
+    }
+
+    #[test]
+    fn plan() {
+        let mut table = table::<glyph_substitution::Type>(vec![
+            record(Flags(0), single(&[1], 1)),
+            record(Flags(0), single(&[1], 2)),
+        ]);
+        table.scripts = Scripts {
+            count: 1,
+            headers: vec![script::Header {
+                tag: Tag(*b"latn"),
+                offset: 0,
+            }],
+            records: vec![script::Record {
+                default_language: Some(language::Record {
+                    required_feature_index: 1,
+                    feature_index_count: 1,
+                    feature_indices: vec![0],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+        };
+        table.features = Features {
+            count: 2,
+            headers: vec![
+                feature::Header {
+                    tag: Tag(*b"liga"),
+                    offset: 0,
+                },
+                feature::Header {
+                    tag: Tag(*b"ccmp"),
+                    offset: 0,
+                },
+            ],
+            records: vec![
+                feature::Record {
+                    lookup_index_count: 2,
+                    lookup_indices: vec![1, 0],
+                    ..Default::default()
+                },
+                feature::Record {
+                    lookup_index_count: 2,
+                    lookup_indices: vec![1, 42],
+                    ..Default::default()
+                },
+            ],
+        };
+        let mut planner = Planner::new(Script::Arabic, Some(Language::Arabic));
+        planner.substitution = vec![vec![], vec![Setting::on(Feature::StandardLigatures)]];
+        let plan = planner.plan(Some(&table), None);
+        assert_eq!(plan.substitution.len(), 2);
+        let lookups = &plan.substitution[0].lookups;
+        assert_eq!(lookups.len(), 1);
+        assert_eq!(lookups[0].index, 1);
+        assert_eq!(
+            lookups[0].settings,
+            &[Setting::on(Feature::GlyphCompositionDecomposition)],
+        );
+        assert_eq!(plan.substitution_indices().collect::<Vec<_>>(), &[1, 0, 1]);
+    }
+
+    #[test]
+    fn reverse_chained_context() {
+        let table = table(vec![record(
+            Flags(0),
+            Type::ReverseChainedContextualSubstibution(ReverseChainedContextualSubstibution {
+                format: 1,
+                coverage: coverage(&[1]),
+                forward_glyph_count: 1,
+                forward_coverages: vec![coverage(&[2, 7])],
+                glyph_count: 1,
+                glyph_ids: vec![7],
+                ..Default::default()
+            }),
+        )]);
+        let mut buffer = Buffer::new([1, 1, 1, 2, 1]);
+        ok!(Substitution::new(&table, None).apply(&mut buffer, 0));
+        assert_eq!(buffer.glyph_ids(), &[7, 7, 7, 2, 1]);
+    }
+
+    #[test]
+    fn thai() {
+        let mapping = [
+            ('\u{0E01}', 1),
+            ('\u{0E48}', 2),
+            ('\u{0E4D}', 3),
+            ('\u{0E32}', 4),
+            ('\u{0E1B}', 5),
+            ('\u{0E34}', 6),
+            ('\u{F713}', 7),
+            ('\u{F701}', 8),
+            ('\u{F70A}', 9),
+        ];
+        let shaper = shaper(table(vec![]), &mapping, |_| 500);
+        let options = Options::new(Script::Thai);
+        let shape = |text| ok!(shaper.shape(text, &options)).glyph_ids();
+        assert_eq!(shape("\u{0E01}\u{0E33}"), &[1, 3, 4]);
+        assert_eq!(shape("\u{0E01}\u{0E48}"), &[1, 9]);
+        assert_eq!(shape("\u{0E1B}\u{0E34}\u{0E48}"), &[5, 8, 7]);
+
+        let buffer = ok!(shaper.shape("\u{0E01}\u{0E48}\u{0E33}", &options));
+        assert_eq!(buffer.glyph_ids(), &[1, 3, 2, 4]);
+        let clusters = buffer
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .collect::<Vec<_>>();
+        assert_eq!(clusters, &[0, 3, 3, 3]);
+
+        let mut table = table(vec![]);
+        directory(&mut table, b"lao ", &[]);
+        let other = self::shaper(table, &mapping, |_| 500);
+        let shape = |text, script| ok!(other.shape(text, &Options::new(script))).glyph_ids();
+        assert_eq!(shape("\u{0E01}\u{0E48}", Script::Thai), &[1, 9]);
+        assert_eq!(shape("\u{0E01}\u{0E48}", Script::Lao), &[1, 2]);
+    }
+
+    #[test]
+    fn universal() {
+        let mut table = table(vec![
+            record(Flags(0), ligatures(&[2, 3], 20)),
+            record(Flags(0), ligatures(&[3, 2], 21)),
+        ]);
+        directory(&mut table, b"khmr", &[(b"rphf", &[0]), (b"pref", &[1])]);
+        let mapping = [
+            ('\u{1780}', 1),
+            ('\u{179A}', 2),
+            ('\u{17D2}', 3),
+            ('\u{17C1}', 4),
+            ('\u{17B6}', 5),
+            ('\u{25CC}', 7),
+        ];
+        let shaper = shaper(table, &mapping, |_| 500);
+        let options = Options::new(Script::Khmer);
+        let shape = |text| ok!(shaper.shape(text, &options)).glyph_ids();
+        assert_eq!(shape("\u{1780}\u{17C1}"), &[4, 1]);
+        assert_eq!(shape("\u{17C1}"), &[4, 7]);
+        assert_eq!(shape("\u{1780}\u{17D2}\u{179A}"), &[21, 1]);
+        assert_eq!(shape("\u{1780}\u{17C1} \u{1780}"), &[4, 1, 0, 1]);
+
+        let buffer = ok!(shaper.shape("\u{179A}\u{17D2}\u{1780}\u{17B6}", &options));
+        assert_eq!(buffer.glyph_ids(), &[1, 20, 5]);
+        let clusters = buffer
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .collect::<Vec<_>>();
+        assert_eq!(clusters, &[0, 0, 9]);
+    }
+
+    fn advance(mut buffer: Buffer, advances: &[i32]) -> Buffer {
+        for (glyph, &advance) in buffer.glyphs.iter_mut().zip(advances) {
+            glyph.x_advance = advance;
+        }
+        buffer
+    }
+
+    fn anchor(x: i16, y: i16) -> Anchor {
+        Anchor::Format1(Anchor1 { format: 1, x, y })
+    }
+
+    fn class(indices: &[u16]) -> Class {
+        Class::Format1(opentype::layout::class::Class1 {
+            format: 1,
+            start_glyph_id: 1,
+            glyph_count: indices.len() as u16,
+            indices: indices.to_vec(),
+        })
+    }
+
+    fn coverage(glyph_ids: &[u16]) -> Coverage {
+        Coverage::Format1(Coverage1 {
+            format: 1,
+            glyph_count: glyph_ids.len() as u16,
+            glyph_ids: glyph_ids.to_vec(),
+        })
+    }
+
+    fn definition(indices: &[u16]) -> GlyphDefinition {
+        GlyphDefinition {
+            header: Header::Version1(Header1::default()),
+            glyph_class: Some(class(indices)),
+            ..Default::default()
+        }
+    }
+
+    fn devanagari(tag: &[u8; 4]) -> Shaper {
+        let mut table = table(vec![
+            record(Flags(0), ligatures(&[2, 3], 20)),
+            record(Flags(0), ligatures(&[1, 3], 21)),
+            record(Flags(0), ligatures(&[3, 8], 22)),
+        ]);
+        directory(
+            &mut table,
+            tag,
+            &[(b"rphf", &[0]), (b"half", &[1]), (b"blwf", &[2])],
+        );
+        let mapping = [
+            ('\u{0915}', 1),
+            ('\u{0930}', 2),
+            ('\u{094D}', 3),
+            ('\u{093F}', 4),
+            ('\u{0937}', 5),
+            ('\u{25CC}', 7),
+            ('\u{092F}', 8),
+        ];
+        shaper(table, &mapping, |_| 500)
+    }
+
+    fn directory<T>(table: &mut Directory<T>, tag: &[u8; 4], features: &[(&[u8; 4], &[u16])]) {
+        table.scripts = Scripts {
+            count: 1,
+            headers: vec![script::Header {
+                tag: Tag(*tag),
+                offset: 0,
+            }],
+            records: vec![script::Record {
+                default_language: Some(language::Record {
+                    required_feature_index: 0xFFFF,
+                    feature_index_count: features.len() as u16,
+                    feature_indices: (0..features.len() as u16).collect(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+        };
+        table.features = Features {
+            count: features.len() as u16,
+            headers: features
+                .iter()
+                .map(|(tag, _)| feature::Header {
+                    tag: Tag(**tag),
+                    offset: 0,
+                })
+                .collect(),
+            records: features
+                .iter()
+                .map(|(_, lookup_indices)| feature::Record {
+                    lookup_index_count: lookup_indices.len() as u16,
+                    lookup_indices: lookup_indices.to_vec(),
+                    ..Default::default()
+                })
+                .collect(),
+        };
+    }
+
+    fn ligatures(glyph_ids: &[u16], glyph_id: u16) -> Type {
+        Type::LigatureSubstitution(LigatureSubstitution {
+            coverage: coverage(&glyph_ids[..1]),
+            records: vec![Ligatures {
+                count: 1,
+                records: vec![Ligature {
+                    glyph_id,
+                    glyph_count: glyph_ids.len() as u16,
+                    glyph_ids: glyph_ids[1..].to_vec(),
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+    }
+
+    fn marks(values: &[(u16, i16, i16)]) -> Mark1s {
+        Mark1s {
+            count: values.len() as u16,
+            records: values
+                .iter()
+                .map(|&(class_id, x, y)| Mark1 {
+                    class_id,
+                    anchor: anchor(x, y),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    fn positions(buffer: &Buffer) -> Vec<(i32, i32, i32)> {
+        buffer
+            .glyphs
+            .iter()
+            .map(|glyph| (glyph.x_advance, glyph.x_offset, glyph.y_offset))
+            .collect()
+    }
+
+    fn record<T: Subtable>(flags: Flags, table: T) -> Record<T> {
+        Record {
+            r#type: table.r#type(),
+            flags,
+            table_count: 1,
+            table_offsets: vec![],
+            mark_filtering_set: None,
+            tables: vec![table],
+        }
+    }
+
+    fn shaper<F>(
+        table: glyph_substitution::GlyphSubstitution,
+        mapping: &[(char, u16)],
+        advance: F,
+    ) -> Shaper
+    where
+        F: Fn(u16) -> u16,
+    {
+        Shaper {
+            mapping: mapping
+                .iter()
+                .map(|&(character, glyph_id)| (character as u32, glyph_id))
+                .collect(),
+            metrics: HorizontalMetrics {
+                records: (0..100)
+                    .map(|glyph_id| metrics::Record {
+                        advance_width: advance(glyph_id),
+                        left_side_bearing: 0,
+                    })
+                    .collect(),
+                left_side_bearings: vec![],
+            },
+            definition: None,
+            substitution: Some(table),
+            positioning: None,
+        }
+    }
+
+    fn single(glyph_ids: &[u16], delta_glyph_id: i16) -> Type {
+        Type::SingleSubstitution(SingleSubstitution::Format1(SingleSubstitution1 {
+            format: 1,
+            coverage: coverage(glyph_ids),
+            delta_glyph_id,
+            ..Default::default()
+        }))
+    }
+
+    fn table<T>(records: Vec<Record<T>>) -> Directory<T> {
+        Directory {
+            major_version: 1,
+            minor_version: 0,
+            script_offset: 0,
+            feature_offset: 0,
+            lookup_offset: 0,
+            variation_offset: 0,
+            scripts: Scripts::default(),
+            features: Features::default(),
+            lookups: Lookups {
+                count: records.len() as u16,
+                offsets: vec![],
+                records,
+            },
+            variations: None,
+        }
+    }
+}
@@ -0,0 +1,149 @@
+#[macro_use]
+mod support;
+
+mod adobe_vf_prototype {
+    use opentype::subsetting::{self, Options};
+    use opentype::File;
+
+    #[test]
+    fn subset() {
+        let mut tape = setup!(AdobeVFPrototypeCFF);
+        let file = ok!(File::read(&mut tape));
+        let options = Options {
+            characters: "Hello".chars().collect(),
+            ..Default::default()
+        };
+        assert!(subsetting::subset(&file[0], &mut tape, &options).is_err());
+    }
+}
+
+mod crimson_text {
+    use std::io::Cursor;
+
+    use opentype::layout::{Feature, Script};
+    use opentype::shaping::{Options as ShapingOptions, Shaper};
+    use opentype::subsetting::{self, Options};
+    use opentype::truetype::tables::{FontHeader, GlyphData, GlyphMapping, MaximumProfile};
+    use opentype::truetype::Tag;
+    use opentype::File;
+
+    #[test]
+    fn subset() {
+        let mut tape = setup!(CrimsonText);
+        let file = ok!(File::read(&mut tape));
+        let shaper = ok!(Shaper::read(&file[0], &mut tape));
+        let options = Options {
+            characters: "e\u{301}x".chars().collect(),
+            features: Some(vec![Feature::Kerning, Feature::MarkPositioning]),
+            ..Default::default()
+        };
+        let (builder, mapping) = ok!(subsetting::subset(&file[0], &mut tape, &options));
+        assert_eq!(mapping.get(0), Some(0));
+        assert_eq!(mapping.get(shaper.mapping[&('e' as u32)]), Some(1));
+        let metrics = ok!(builder.get(Tag(*b"OS/2")));
+        assert_eq!(&metrics[64..68], &[0x00, 0x65, 0x03, 0x01]);
+
+        let mut data = vec![];
+        ok!(builder.write(&mut data));
+        let mut tape = Cursor::new(data);
+        let file = ok!(File::read(&mut tape));
+        let header = ok!(ok!(file[0].take::<_, FontHeader>(&mut tape)));
+        let profile = ok!(ok!(file[0].take::<_, MaximumProfile>(&mut tape)));
+        assert_eq!(profile.glyph_count(), mapping.len());
+        let offsets: GlyphMapping = ok!(ok!(file[0].take_given(&mut tape, (&header, &profile))));
+        let glyphs: GlyphData = ok!(ok!(file[0].take_given(&mut tape, &offsets)));
+        assert_eq!(glyphs.len(), mapping.len());
+
+        let other = ok!(Shaper::read(&file[0], &mut tape));
+        let options = ShapingOptions::new(Script::Latin);
+        let buffer = ok!(shaper.shape("e\u{301}x", &options));
+        let other_buffer = ok!(other.shape("e\u{301}x", &options));
+        let glyph_ids = (buffer.glyph_ids().iter())
+            .map(|&glyph_id| ok!(mapping.get(glyph_id)))
+            .collect::<Vec<_>>();
+        assert_eq!(other_buffer.glyph_ids(), glyph_ids);
+        for (glyph, other) in buffer.glyphs.iter().zip(&other_buffer.glyphs) {
+            assert_eq!(glyph.x_advance, other.x_advance);
+            assert_eq!(glyph.x_offset, other.x_offset);
+            assert_eq!(glyph.y_offset, other.y_offset);
+        }
+    }
+}
+
+mod source_serif {
+    use std::io::Cursor;
+
+    use opentype::layout::feature::Parameters;
+    use opentype::layout::{Feature, Script};
+    use opentype::postscript::compact1::FontSet;
+    use opentype::shaping::{Options as ShapingOptions, Shaper};
+    use opentype::subsetting::{self, Options};
+    use opentype::tables::glyph_positioning::GlyphPositioning;
+    use opentype::File;
+
+    #[test]
+    fn subset() {
+        let mut tape = setup!(SourceSerifPro);
+        let file = ok!(File::read(&mut tape));
+        let shaper = ok!(Shaper::read(&file[0], &mut tape));
+        let ligature = ok!(shaper.shape("office", &ShapingOptions::new(Script::Latin)));
+        assert!(ligature.glyphs.len() < 6);
+        let options = Options {
+            characters: "cefio".chars().collect(),
+            features: Some(vec![Feature::StandardLigatures]),
+            ..Default::default()
+        };
+        let (builder, mapping) = ok!(subsetting::subset(&file[0], &mut tape, &options));
+        for &glyph_id in ligature.glyph_ids().iter() {
+            assert!(mapping.get(glyph_id).is_some());
+        }
+
+        let mut data = vec![];
+        ok!(builder.write(&mut data));
+        let mut tape = Cursor::new(data);
+        let file = ok!(File::read(&mut tape));
+        let font_set = ok!(ok!(file[0].take::<_, FontSet>(&mut tape)));
+        assert_eq!(font_set.character_strings[0].count as usize, mapping.len());
+
+        let other = ok!(Shaper::read(&file[0], &mut tape));
+        let options = ShapingOptions::new(Script::Latin);
+        let buffer = ok!(other.shape("office", &options));
+        let glyph_ids = (ligature.glyph_ids().iter())
+            .map(|&glyph_id| ok!(mapping.get(glyph_id)))
+            .collect::<Vec<_>>();
+        assert_eq!(buffer.glyph_ids(), glyph_ids);
+        for (glyph, other) in ligature.glyphs.iter().zip(&buffer.glyphs) {
+            assert_eq!(glyph.x_advance, other.x_advance);
+        }
+    }
+    #[test]
+    fn subset_optical_size() {
+        let mut tape = setup!(SourceSerifPro);
+        let file = ok!(File::read(&mut tape));
+        let options = Options {
+            characters: "office".chars().collect(),
+            features: Some(vec![Feature::OpticalSize]),
+            ..Default::default()
+        };
+        let (builder, _) = ok!(subsetting::subset(&file[0], &mut tape, &options));
+
+        let mut data = vec![];
+        ok!(builder.write(&mut data));
+        let mut tape = Cursor::new(data);
+        let file = ok!(File::read(&mut tape));
+        let table = ok!(ok!(file[0].take::<_, GlyphPositioning>(&mut tape)));
+        let tags = (table.features.headers.iter())
+            .map(|header| header.tag)
+            .collect::<Vec<_>>();
+        assert_eq!(tags, tags![b"size", b"size", b"size", b"size", b"size"]);
+        for record in &table.features.records {
+            assert!(record.lookup_indices.is_empty());
+            match &record.parameters {
+                Some(Parameters::OpticalSize(parameters)) => {
+                    assert_eq!(parameters.design_size, 100);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+}
@@ -24,11 +24,14 @@ pub enum Fixture {
     AdobeVFPrototypeCFF,
     AdobeVFPrototypeTTF,
     CrimsonText,
+    GinghamCollection,
+    GinghamWOFF,
     KalniaGlaze,
     KaushanScript,
     LondrinaSolid,
     NotoColorEmoji,
     OpenSans,
+    OpenSansWOFF2,
     SourceSerifPro,
 }
 
@@ -38,54 +41,33 @@ impl Fixture {
             Fixture::AdobeVFPrototypeCFF => "AdobeVFPrototype.otf",
             Fixture::AdobeVFPrototypeTTF => "AdobeVFPrototype.ttf",
             Fixture::CrimsonText => "CrimsonText-Regular.ttf",
+            Fixture::GinghamCollection => "Gingham.ttc",
+            Fixture::GinghamWOFF => "Gingham.woff",
             Fixture::KalniaGlaze => "KalniaGlaze[wdth,wght].ttf",
             Fixture::KaushanScript => "KaushanScript-Regular.ttf",
             Fixture::LondrinaSolid => "LondrinaSolid-Regular.ttf",
             Fixture::NotoColorEmoji => "NotoColorEmoji-Regular.ttf",
             Fixture::OpenSans => "OpenSans-Italic.ttf",
+            Fixture::OpenSansWOFF2 => "OpenSans-Regular.woff2",
             Fixture::SourceSerifPro => "SourceSerifPro-Regular.otf",
         };
         PathBuf::from("tests").join("fixtures").join(file_name)
     }
 
     pub fn offset(&self, table: &str) -> u64 {
-        match *self {
-            Fixture::AdobeVFPrototypeCFF => match table {
-                _ => unreachable!(),
-            },
-            Fixture::AdobeVFPrototypeTTF => match table {
-                "GPOS" => 137912,
-                "fvar" => 41556,
-                _ => unreachable!(),
-            },
-            Fixture::CrimsonText => match table {
-                "GPOS" => 94952,
-                _ => unreachable!(),
-            },
-            Fixture::KalniaGlaze => match table {
-                "CPAL" => 148600,
-                _ => unreachable!(),
-            },
-            Fixture::KaushanScript => match table {
-                _ => unreachable!(),
-            },
-            Fixture::LondrinaSolid => match table {
-                "GSUB" => 12236,
-                _ => unreachable!(),
-            },
-            Fixture::NotoColorEmoji => match table {
-                "CPAL" => 10976,
-                _ => unreachable!(),
-            },
-            Fixture::OpenSans => match table {
-                "GDEF" => 206348,
-                _ => unreachable!(),
-            },
-            Fixture::SourceSerifPro => match table {
-                "GPOS" => 60412,
-                "GSUB" => 57648,
-                _ => unreachable!(),
-            },
+        match (self, table) {
+            (Fixture::AdobeVFPrototypeTTF, "GDEF") => 183148,
+            (Fixture::AdobeVFPrototypeTTF, "GPOS") => 137912,
+            (Fixture::AdobeVFPrototypeTTF, "fvar") => 41556,
+            (Fixture::CrimsonText, "GPOS") => 94952,
+            (Fixture::KalniaGlaze, "CPAL") => 148600,
+            (Fixture::KalniaGlaze, "GDEF") => 148708,
+            (Fixture::LondrinaSolid, "GSUB") => 12236,
+            (Fixture::NotoColorEmoji, "CPAL") => 10976,
+            (Fixture::OpenSans, "GDEF") => 206348,
+            (Fixture::SourceSerifPro, "GPOS") => 60412,
+            (Fixture::SourceSerifPro, "GSUB") => 57648,
+            _ => unreachable!(),
         }
     }
 }
@@ -97,3 +79,66 @@ pub fn setup(fixture: Fixture, table: Option<&str>) -> File {
     )));
     file
 }
+
+pub fn read(fixture: Fixture, table: &str) -> Vec<u8> {
+    let data = ok!(std::fs::read(fixture.path()));
+    data[fixture.offset(table) as usize..].to_vec()
+}
+
+pub fn take<T>(fixture: Fixture) -> T
+where
+    T: opentype::Table + opentype::value::Read,
+{
+    let mut tape = setup(fixture, None);
+    let file = ok!(opentype::File::read(&mut tape));
+    ok!(ok!(file[0].take(&mut tape)))
+}
+
+pub fn write<T: opentype::value::Write>(value: &T) -> Vec<u8> {
+    let mut data = vec![];
+    ok!(opentype::tape::Write::give(&mut data, value));
+    data
+}
+
+/// Write a value, read it back, and check that the structure is the same except for offsets and
+/// that writing it again yields the same data, which is returned.
+pub fn round_trip<T>(value: &T) -> Vec<u8>
+where
+    T: opentype::value::Read + opentype::value::Write + std::fmt::Debug,
+{
+    let data = write(value);
+    let other: T = ok!(opentype::value::Read::read(&mut std::io::Cursor::new(
+        &data
+    )));
+    assert_eq!(strip(&other), strip(value));
+    assert_eq!(write(&other), data);
+    data
+}
+
+fn strip<T: std::fmt::Debug>(value: &T) -> String {
+    let value = format!("{value:#?}");
+    let mut lines = vec![];
+    let mut skipping: Option<usize> = None;
+    for line in value.lines() {
+        let trimmed = line.trim_start();
+        let indentation = line.len() - trimmed.len();
+        if let Some(level) = skipping {
+            if indentation > level {
+                continue;
+            }
+            skipping = None;
+            if trimmed.starts_with(')') || trimmed.starts_with(']') {
+                continue;
+            }
+        }
+        let name = trimmed.split(':').next().unwrap();
+        if name.ends_with("offset") || name.ends_with("offsets") {
+            if trimmed.ends_with('[') || trimmed.ends_with('(') {
+                skipping = Some(indentation);
+            }
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,100 @@
+#[macro_use]
+mod support;
+
+mod kalnia_glaze {
+    use std::io::{Seek, SeekFrom};
+
+    use opentype::tables::glyph_definition::Header;
+    use opentype::value::Read;
+    use opentype::variations::item::Store;
+    use truetype::q16;
+
+    #[test]
+    fn store() {
+        let mut tape = setup!(KalniaGlaze, "GDEF");
+        let offset = match ok!(Header::read(&mut tape)) {
+            Header::Version13(header) => header.variation_offset,
+            _ => unreachable!(),
+        };
+        let position = crate::support::Fixture::KalniaGlaze.offset("GDEF");
+        ok!(tape.seek(SeekFrom::Start(position + offset as u64)));
+        let table: Store = ok!(Read::read(&mut tape));
+        assert_eq!(table.regions.axis_count, 2);
+        assert_eq!(table.regions.records.len(), 3);
+        let tent = &table.regions.records[2].tents[1];
+        assert_eq!(
+            (tent.start, tent.peak, tent.end),
+            (q16(0), q16(0x4000), q16(0x4000))
+        );
+        assert_eq!(table.records.len(), 7);
+        let record = &table.records[3];
+        assert_eq!(record.region_indices, &[2, 1]);
+        assert_eq!(record.deltas.len(), 9);
+        assert_eq!(record.deltas[2], &[430, 0]);
+        let record = &table.records[6];
+        assert_eq!(record.short_delta_count, 3);
+        assert_eq!(record.deltas[0], &[-10, 150, 310]);
+
+        let full = [q16(0x4000), q16(0x4000)];
+        let half = [q16(0x2000), q16(0x2000)];
+        assert_eq!(table.evaluate(3, 2, &full), Some(430.0));
+        assert_eq!(table.evaluate(3, 2, &half), Some(107.5));
+        assert_eq!(table.evaluate(3, 0, &half), Some(-95.0));
+        assert_eq!(table.evaluate(3, 0, &[]), Some(0.0));
+        assert_eq!(table.evaluate(3, 9, &full), None);
+        assert_eq!(table.evaluate(7, 0, &full), None);
+    }
+}
+
+mod synthetic {
+    use opentype::value::Read;
+    use opentype::variations::item::{Record, Tent};
+    use truetype::q16;
+
+    use crate::support;
+
+    #[test]
+    fn record() {
+        for (short_delta_count, size) in [(0x8001, 6 + 4 + 4 + 2), (0x0001, 6 + 4 + 2 + 1)] {
+            let record = Record {
+                item_count: 1,
+                short_delta_count,
+                region_count: 2,
+                region_indices: vec![0, 1],
+                deltas: vec![vec![if short_delta_count > 1 { 100000 } else { 1000 }, -5]],
+            };
+            let data = support::write(&record);
+            assert_eq!(data.len(), size);
+            let other: Record = ok!(Read::read(&mut std::io::Cursor::new(&data)));
+            assert_eq!(other.deltas, record.deltas);
+        }
+        let record = Record {
+            item_count: 1,
+            short_delta_count: 0,
+            region_count: 1,
+            region_indices: vec![0],
+            deltas: vec![vec![1000]],
+        };
+        assert!(opentype::tape::Write::give(&mut vec![], &record).is_err());
+    }
+
+    #[test]
+    fn tent() {
+        let tent = Tent {
+            start: q16(0),
+            peak: q16(0x2000),
+            end: q16(0x4000),
+        };
+        assert_eq!(tent.scalar(q16(-0x1000)), 0.0);
+        assert_eq!(tent.scalar(q16(0x1000)), 0.5);
+        assert_eq!(tent.scalar(q16(0x2000)), 1.0);
+        assert_eq!(tent.scalar(q16(0x3000)), 0.5);
+        assert_eq!(tent.scalar(q16(0x4000)), 0.0);
+        let tent = Tent {
+            start: q16(0),
+            peak: q16(0),
+            end: q16(0),
+        };
+        assert_eq!(tent.scalar(q16(0x3000)), 1.0);
+    }
+}